@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+use crate::{GameState, RunStats, SaveData, Score, ShowToast};
+
+pub enum Condition {
+    Score(u64),
+    TotalRocks(u64),
+    SurviveSeconds(f32),
+    CrashWithinSeconds(f32),
+}
+
+pub struct Achievement {
+    // Stored in the save file, so never change these once released
+    pub id: &'static str,
+    pub name: &'static str,
+    pub condition: Condition,
+}
+
+// Names are drawn with the bitmap font, so stick to letters, digits and spaces
+pub const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "score_10",
+        name: "Score 10",
+        condition: Condition::Score(10),
+    },
+    Achievement {
+        id: "total_rocks_100",
+        name: "Pass 100 rocks",
+        condition: Condition::TotalRocks(100),
+    },
+    Achievement {
+        id: "survive_60",
+        name: "Survive 60 seconds",
+        condition: Condition::SurviveSeconds(60.0),
+    },
+    Achievement {
+        id: "crash_within_1",
+        name: "Crash within 1 second",
+        condition: Condition::CrashWithinSeconds(1.0),
+    },
+];
+
+impl Condition {
+    pub fn is_met(&self, run: &RunStats, score: &Score, save: &SaveData, crashed: bool) -> bool {
+        match *self {
+            Condition::Score(points) => score.0 >= points,
+            Condition::TotalRocks(rocks) => save.stats.rocks_passed >= rocks,
+            Condition::SurviveSeconds(seconds) => run.time >= seconds,
+            Condition::CrashWithinSeconds(seconds) => crashed && run.time < seconds,
+        }
+    }
+}
+
+pub fn achievement_system(
+    mut save: ResMut<SaveData>,
+    run: Res<RunStats>,
+    score: Res<Score>,
+    state: Res<State<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    let crashed = state.current() == &GameState::GameOver;
+
+    for achievement in ACHIEVEMENTS {
+        if save.achievements.contains(achievement.id)
+            || !achievement.condition.is_met(&run, &score, &save, crashed)
+        {
+            continue;
+        }
+
+        save.achievements.insert(achievement.id.to_string());
+        toasts.send(ShowToast(format!("Unlocked {}", achievement.name)));
+    }
+}
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    spawn_floating_text, BaseColor, BitmapText, BitmapTextBundle, CollisionPolygon, DemoMode,
+    DespawnOffscreen, FlapInput, GameAssets, GameMode, GameRng, GameSpeed, HorizontalVelocity,
+    HudVisibility, Interpolated, Layer, Lifetime, Particle, Player, PowerUp, PowerUpCollected,
+    RemoveAfterState, Rock, RunMode, SaveData, Score, ScreenAnchor, SCORE_POPUP_SCALE,
+};
+
+const START_AMMO: u32 = 5;
+const MAX_AMMO: u32 = 10;
+const PICKUP_AMMO: u32 = 3;
+
+// Worth as much as a gate, the rock is gone before it can be flown past
+const ROCK_BREAK_SCORE: u64 = 1;
+
+// Negative, since horizontal movement is towards the left
+const BULLET_VELOCITY: f32 = -600.0;
+const BULLET_SIZE: Vec2 = Vec2::new(12.0, 4.0);
+const BULLET_MARGIN: f32 = 20.0;
+
+const CRUMBLE_TIME: f32 = 0.5;
+const CRUMBLE_GRAVITY: f32 = 800.0;
+const CRUMBLE_PUFFS: usize = 4;
+
+// Bullets left in armed mode
+#[derive(Default)]
+pub struct Ammo(pub u32);
+
+#[derive(Component)]
+pub struct Bullet;
+
+#[derive(Component)]
+pub struct AmmoText;
+
+pub struct ArmedMode;
+
+impl GameMode for ArmedMode {
+    fn run_mode(&self) -> RunMode {
+        RunMode::Armed
+    }
+
+    fn select_key(&self) -> Option<KeyCode> {
+        Some(KeyCode::A)
+    }
+
+    fn label(&self, _save: &SaveData) -> String {
+        "Armed fire with X or the right mouse button".to_string()
+    }
+
+    // Broken rocks are worth points, so armed scores can't be compared to the other modes and keep no records
+    fn setup(&self, commands: &mut Commands, world: &World) {
+        if world.resource::<DemoMode>().active {
+            return;
+        }
+
+        commands.insert_resource(Ammo(START_AMMO));
+
+        let mut bundle = BitmapTextBundle::at(ScreenAnchor::TopLeft.offset(15.0, -75.0))
+            .with_text(ammo_text(START_AMMO));
+        bundle.transform.scale = Vec3::new(0.5, 0.5, 1.0);
+
+        commands
+            .spawn_bundle(bundle)
+            .insert(AmmoText)
+            .insert(RemoveAfterState);
+    }
+}
+
+// Queued shots are always used up, so a shot from another mode never carries over
+pub fn fire_system(
+    mut commands: Commands,
+    mode: Res<RunMode>,
+    mut ammo: ResMut<Ammo>,
+    mut query: Query<(&Transform, &mut FlapInput), With<Player>>,
+) {
+    for (transform, mut input) in query.iter_mut() {
+        if !input.fire {
+            continue;
+        }
+        input.fire = false;
+
+        if *mode != RunMode::Armed || ammo.0 == 0 {
+            continue;
+        }
+        ammo.0 -= 1;
+
+        let translation = transform.translation;
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(1.0, 0.85, 0.3),
+                    custom_size: Some(BULLET_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_xyz(translation.x, translation.y, Layer::Bullets.z()),
+                ..default()
+            })
+            .insert(Bullet)
+            .insert(HorizontalVelocity(BULLET_VELOCITY))
+            .insert(DespawnOffscreen {
+                margin: BULLET_MARGIN,
+            })
+            .insert(Interpolated::default())
+            .insert(RemoveAfterState);
+    }
+}
+
+pub fn bullet_system(
+    mut commands: Commands,
+    bullets: Query<(Entity, &Transform), With<Bullet>>,
+    mut rocks: Query<
+        (
+            Entity,
+            &Transform,
+            &CollisionPolygon,
+            &HorizontalVelocity,
+            &mut Sprite,
+        ),
+        With<Rock>,
+    >,
+    mut score: ResMut<Score>,
+    mut rng: ResMut<GameRng>,
+    game_speed: Res<GameSpeed>,
+    assets: Res<GameAssets>,
+) {
+    // Removing Rock only happens at the end of the step, so two bullets could otherwise break the same rock
+    let mut broken: Vec<Entity> = vec![];
+
+    for (bullet, bullet_transform) in bullets.iter() {
+        let point = bullet_transform.translation;
+
+        for (rock, rock_transform, polygon, velocity, mut sprite) in rocks.iter_mut() {
+            if broken.contains(&rock) || !polygon.contains(rock_transform.translation, point) {
+                continue;
+            }
+
+            broken.push(rock);
+            commands.entity(bullet).despawn_recursive();
+            score.0 += ROCK_BREAK_SCORE;
+            spawn_floating_text(
+                &mut commands,
+                point,
+                format!("+{}", ROCK_BREAK_SCORE),
+                SCORE_POPUP_SCALE,
+            );
+
+            // There's no broken rock sprite, so it darkens and falls out of the picture as a particle instead.
+            // Without Rock and its polygon it can't be hit, scored or shot again.
+            sprite.color = Color::rgb(0.55, 0.5, 0.45);
+            commands
+                .entity(rock)
+                .remove::<Rock>()
+                .remove::<CollisionPolygon>()
+                .remove::<HorizontalVelocity>()
+                .remove::<Interpolated>()
+                .remove::<BaseColor>()
+                .insert(Particle::new(
+                    Vec2::new(-velocity.0 * game_speed.0, 0.0),
+                    CRUMBLE_GRAVITY,
+                ))
+                .insert(Lifetime::from_seconds(CRUMBLE_TIME));
+
+            for _ in 0..CRUMBLE_PUFFS {
+                let puff_velocity = Vec2::new(
+                    rng.cosmetic.gen_range(-150.0..50.0),
+                    rng.cosmetic.gen_range(-50.0..150.0),
+                );
+
+                commands
+                    .spawn_bundle(SpriteBundle {
+                        texture: assets.puff.clone(),
+                        transform: Transform::from_xyz(point.x, point.y, Layer::Bullets.z())
+                            .with_scale(Vec3::new(0.5, 0.5, 1.0)),
+                        ..default()
+                    })
+                    .insert(Particle::new(puff_velocity, CRUMBLE_GRAVITY / 2.0))
+                    .insert(Lifetime::from_seconds(CRUMBLE_TIME));
+            }
+
+            break;
+        }
+    }
+}
+
+// Ammo pickups are spawned from the power-up table, see power_up_table
+pub fn ammo_pickup_system(
+    mut ammo: ResMut<Ammo>,
+    mut collected_events: EventReader<PowerUpCollected>,
+) {
+    for event in collected_events.iter() {
+        if event.power_up == PowerUp::Ammo {
+            ammo.0 = (ammo.0 + PICKUP_AMMO).min(MAX_AMMO);
+        }
+    }
+}
+
+pub fn ammo_text_system(
+    ammo: Res<Ammo>,
+    hud: Res<HudVisibility>,
+    mut query: Query<&mut BitmapText, With<AmmoText>>,
+) {
+    if !ammo.is_changed() && !hud.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.text = hud.text(ammo_text(ammo.0));
+    }
+}
+
+fn ammo_text(ammo: u32) -> String {
+    format!("Ammo {}", ammo)
+}
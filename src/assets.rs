@@ -0,0 +1,90 @@
+use bevy::asset::HandleId;
+use bevy::prelude::*;
+
+use crate::{create_bitmap_font, AssetPack, BitmapFont};
+
+// Paths in the assets folder. Replacing one of these files, or remapping it in an asset pack, is all it takes to
+// change a sprite everywhere.
+const PLAYER_TEXTURE: &str = "Planes/planeBlue1.png";
+const SECOND_PLAYER_TEXTURE: &str = "Planes/planeRed1.png";
+const THIRD_PLAYER_TEXTURE: &str = "Planes/planeYellow1.png";
+const ENEMY_TEXTURE: &str = "Planes/planeGreen1.png";
+const PUFF_TEXTURE: &str = "puffSmall.png";
+const STAR_GOLD_TEXTURE: &str = "starGold.png";
+const STAR_SILVER_TEXTURE: &str = "starSilver.png";
+const MENU_BUTTON_TEXTURE: &str = "UI/buttonLarge.png";
+const GET_READY_TEXTURE: &str = "UI/textGetReady.png";
+const GAME_OVER_TEXTURE: &str = "UI/textGameOver.png";
+const TAP_LEFT_TEXTURE: &str = "UI/tapLeft.png";
+const TAP_RIGHT_TEXTURE: &str = "UI/tapRight.png";
+const UI_FONT: &str = "Roboto-Regular.ttf";
+
+// Every texture and font that isn't part of a theme, loaded once when the game starts. Systems take their
+// handles from here instead of loading by path when they spawn something, so Loading can wait for all of them
+// and nothing pops in the first time it shows up. Theme textures are in ThemeState.
+pub struct GameAssets {
+    // The ghost is the same plane, tinted
+    pub player: Handle<Image>,
+    pub second_player: Handle<Image>,
+    pub third_player: Handle<Image>,
+    pub enemy: Handle<Image>,
+    pub puff: Handle<Image>,
+    pub star_gold: Handle<Image>,
+    pub star_silver: Handle<Image>,
+    pub menu_button: Handle<Image>,
+    pub get_ready: Handle<Image>,
+    pub game_over: Handle<Image>,
+    pub tap_left: Handle<Image>,
+    pub tap_right: Handle<Image>,
+    pub bitmap_font: BitmapFont,
+    // For the debug overlay and the speedrun timer, which are bevy UI text
+    pub ui_font: Handle<Font>,
+}
+
+impl FromWorld for GameAssets {
+    fn from_world(world: &mut World) -> Self {
+        let asset_server = world.resource::<AssetServer>();
+        let pack = world.resource::<AssetPack>();
+
+        Self {
+            player: pack.load(asset_server, PLAYER_TEXTURE),
+            second_player: pack.load(asset_server, SECOND_PLAYER_TEXTURE),
+            third_player: pack.load(asset_server, THIRD_PLAYER_TEXTURE),
+            enemy: pack.load(asset_server, ENEMY_TEXTURE),
+            puff: pack.load(asset_server, PUFF_TEXTURE),
+            star_gold: pack.load(asset_server, STAR_GOLD_TEXTURE),
+            star_silver: pack.load(asset_server, STAR_SILVER_TEXTURE),
+            menu_button: pack.load(asset_server, MENU_BUTTON_TEXTURE),
+            get_ready: pack.load(asset_server, GET_READY_TEXTURE),
+            game_over: pack.load(asset_server, GAME_OVER_TEXTURE),
+            tap_left: pack.load(asset_server, TAP_LEFT_TEXTURE),
+            tap_right: pack.load(asset_server, TAP_RIGHT_TEXTURE),
+            bitmap_font: create_bitmap_font(asset_server, pack),
+            ui_font: pack.load(asset_server, UI_FONT),
+        }
+    }
+}
+
+impl GameAssets {
+    // Everything Loading waits for before the menu shows up
+    pub fn ids(&self) -> impl Iterator<Item = HandleId> + '_ {
+        [
+            &self.player,
+            &self.second_player,
+            &self.third_player,
+            &self.enemy,
+            &self.puff,
+            &self.star_gold,
+            &self.star_silver,
+            &self.menu_button,
+            &self.get_ready,
+            &self.game_over,
+            &self.tap_left,
+            &self.tap_right,
+        ]
+        .into_iter()
+        .chain(self.bitmap_font.textures())
+        .map(|handle| handle.id)
+        .chain(std::iter::once(self.ui_font.id))
+    }
+}
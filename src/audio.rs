@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+
+#[cfg(feature = "audio")]
+use bevy::utils::HashMap;
+#[cfg(feature = "audio")]
+use rand::prelude::*;
+#[cfg(feature = "audio")]
+use serde::Deserialize;
+
+#[cfg(feature = "audio")]
+use crate::{
+    read_file, CrashEvent, DemoMode, FlapEvent, GameRng, GatePassedEvent, PowerUp,
+    PowerUpCollected, SaveData,
+};
+
+#[cfg(feature = "audio")]
+const SOUNDS: &str = include_str!("../assets/sounds.ron");
+// Next to the executable, for mods
+#[cfg(feature = "audio")]
+const MOD_SOUNDS_PATH: &str = "sounds.ron";
+
+// Plays the sound effect registered for a cue in assets/sounds.ron, at the player's SFX volume. Can always be
+// sent, builds without the audio feature just don't play anything.
+pub struct SoundCue(pub &'static str);
+
+// See assets/sounds.ron
+#[cfg(feature = "audio")]
+#[derive(Deserialize)]
+struct SoundDefinition {
+    file: String,
+    #[serde(default = "unvaried")]
+    volume: (f32, f32),
+    #[serde(default = "unvaried")]
+    pitch: (f32, f32),
+}
+
+#[cfg(feature = "audio")]
+fn unvaried() -> (f32, f32) {
+    (1.0, 1.0)
+}
+
+#[cfg(feature = "audio")]
+struct CueSound {
+    handle: Handle<AudioSource>,
+    volume: (f32, f32),
+    pitch: (f32, f32),
+}
+
+// Every cue with the sound it plays, loaded when the app is built
+#[cfg(feature = "audio")]
+pub struct SoundCues(HashMap<String, CueSound>);
+
+#[cfg(feature = "audio")]
+impl FromWorld for SoundCues {
+    fn from_world(world: &mut World) -> Self {
+        let mut definitions: HashMap<String, SoundDefinition> =
+            ron::from_str(SOUNDS).expect("assets/sounds.ron is invalid");
+
+        // A broken mod file shouldn't keep the game from starting, the built in sounds still play
+        if let Ok(contents) = read_file(MOD_SOUNDS_PATH) {
+            match ron::from_str::<HashMap<String, SoundDefinition>>(&contents) {
+                Ok(replaced) => definitions.extend(replaced),
+                Err(err) => warn!("Could not read {}: {}", MOD_SOUNDS_PATH, err),
+            }
+        }
+
+        let asset_server = world.resource::<AssetServer>();
+        let sounds = definitions
+            .into_iter()
+            .map(|(cue, definition)| {
+                let sound = CueSound {
+                    handle: asset_server.load(definition.file.as_str()),
+                    volume: definition.volume,
+                    pitch: definition.pitch,
+                };
+                (cue, sound)
+            })
+            .collect();
+
+        Self(sounds)
+    }
+}
+
+#[cfg(feature = "audio")]
+pub struct SfxPlugin;
+
+#[cfg(feature = "audio")]
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SoundCues>()
+            .add_system(gameplay_cue_system.before(sound_cue_system))
+            .add_system(sound_cue_system);
+    }
+}
+
+// Gameplay events that make a sound, so the systems sending them don't have to know about audio. The demo
+// plays without them, it would only be noise on the menu.
+#[cfg(feature = "audio")]
+pub fn gameplay_cue_system(
+    mut flap_events: EventReader<FlapEvent>,
+    mut gate_events: EventReader<GatePassedEvent>,
+    mut crash_events: EventReader<CrashEvent>,
+    mut collected_events: EventReader<PowerUpCollected>,
+    demo: Res<DemoMode>,
+    mut cues: EventWriter<SoundCue>,
+) {
+    let mut played = vec![];
+    played.extend(flap_events.iter().map(|_| "flap"));
+    played.extend(gate_events.iter().map(|_| "score"));
+    played.extend(crash_events.iter().map(|_| "crash"));
+    played.extend(collected_events.iter().map(|event| match event.power_up {
+        PowerUp::Repair => "repair",
+        _ => "pickup",
+    }));
+
+    if demo.active {
+        return;
+    }
+
+    for cue in played {
+        cues.send(SoundCue(cue));
+    }
+}
+
+#[cfg(feature = "audio")]
+pub fn sound_cue_system(
+    mut cues: EventReader<SoundCue>,
+    sounds: Res<SoundCues>,
+    audio: Res<Audio>,
+    save: Res<SaveData>,
+    mut rng: ResMut<GameRng>,
+) {
+    for cue in cues.iter() {
+        let sound = match sounds.0.get(cue.0) {
+            Some(sound) => sound,
+            None => {
+                warn!("There's no sound for the {} cue", cue.0);
+                continue;
+            }
+        };
+
+        let volume = vary(&mut rng.cosmetic, sound.volume) * save.settings.sfx_volume;
+        let speed = vary(&mut rng.cosmetic, sound.pitch);
+        audio.play_with_settings(
+            sound.handle.clone(),
+            PlaybackSettings::ONCE.with_volume(volume).with_speed(speed),
+        );
+    }
+}
+
+#[cfg(feature = "audio")]
+fn vary<R: Rng>(rng: &mut R, (min, max): (f32, f32)) -> f32 {
+    if max > min {
+        rng.gen_range(min..max)
+    } else {
+        min
+    }
+}
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{AssetPack, BaseColor, Score, SpriteAlpha, Tween, TweenCompleted};
+
+// Embedded like the obstacle patterns, so the first sky is there from the first frame
+const BACKGROUNDS: &str = include_str!("../assets/backgrounds.ron");
+
+const SKY_FADE_TIME: f32 = 2.0;
+
+#[derive(Deserialize)]
+pub struct BackgroundTheme {
+    // Reaching this score fades the sky to this theme
+    pub score: u64,
+    pub texture: String,
+    pub tint: (f32, f32, f32),
+}
+
+impl BackgroundTheme {
+    pub fn color(&self) -> Color {
+        Color::rgb(self.tint.0, self.tint.1, self.tint.2)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BackgroundThemes {
+    pub themes: Vec<BackgroundTheme>,
+    // Index of the theme on screen
+    #[serde(skip)]
+    applied: usize,
+}
+
+impl BackgroundThemes {
+    pub fn first(&self) -> &BackgroundTheme {
+        &self.themes[0]
+    }
+
+    fn for_score(&self, score: u64) -> usize {
+        self.themes
+            .iter()
+            .rposition(|theme| theme.score <= score)
+            .unwrap_or(0)
+    }
+}
+
+pub fn load_background_themes() -> BackgroundThemes {
+    let themes: BackgroundThemes =
+        ron::from_str(BACKGROUNDS).expect("assets/backgrounds.ron is invalid");
+    assert!(
+        !themes.themes.is_empty(),
+        "assets/backgrounds.ron has no themes"
+    );
+
+    themes
+}
+
+// The looping sky, as opposed to the ground and ceiling strips
+#[derive(Component)]
+pub struct Sky;
+
+// A child of a sky sprite that fades in on top of it, then replaces its texture and tint. Being a child keeps
+// it lined up with its parent when loop_background moves it.
+#[derive(Component)]
+pub struct SkyFade {
+    color: Color,
+}
+
+pub fn sky_theme_system(
+    mut commands: Commands,
+    mut themes: ResMut<BackgroundThemes>,
+    score: Res<Score>,
+    asset_server: Res<AssetServer>,
+    pack: Res<AssetPack>,
+    skies: Query<Entity, With<Sky>>,
+    fades: Query<(), With<SkyFade>>,
+) {
+    // A new run fades back to the first sky the same way
+    let wanted = themes.for_score(score.0);
+    if wanted == themes.applied || !fades.is_empty() {
+        return;
+    }
+
+    let theme = &themes.themes[wanted];
+    let texture: Handle<Image> = pack.load(&asset_server, &theme.texture);
+    let color = theme.color();
+    let mut hidden = color;
+    hidden.set_a(0.0);
+
+    for sky in skies.iter() {
+        let fade = commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: hidden,
+                    ..default()
+                },
+                texture: texture.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, 0.01),
+                ..default()
+            })
+            .insert(Tween::new(SpriteAlpha, 0.0, 1.0, SKY_FADE_TIME))
+            .insert(SkyFade { color })
+            .insert(BaseColor(color))
+            .id();
+
+        commands.entity(sky).add_child(fade);
+    }
+
+    themes.applied = wanted;
+}
+
+pub fn sky_fade_system(
+    mut commands: Commands,
+    mut completed: EventReader<TweenCompleted>,
+    fades: Query<(&Parent, &SkyFade, &Handle<Image>), Without<Sky>>,
+    mut skies: Query<(&mut BaseColor, &mut Handle<Image>), With<Sky>>,
+) {
+    for event in completed.iter() {
+        if let Ok((parent, fade, texture)) = fades.get(event.entity) {
+            if let Ok((mut sky_color, mut sky_texture)) = skies.get_mut(parent.get()) {
+                sky_color.0 = fade.color;
+                *sky_texture = texture.clone();
+            }
+
+            commands.entity(event.entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{HorizontalVelocity, Rock, PHYSICS_STEP};
+
+/// Motion an obstacle pattern gives its rocks, written in patterns.ron as the name of the behavior followed by
+/// its settings, e.g. `"oscillate amplitude=40 period=2"`. Settings that are left out keep their defaults.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Behavior {
+    /// Up and down around where it spawned, period is in seconds for a full cycle
+    Oscillate { amplitude: f32, period: f32 },
+    /// Scrolls factor times as fast once it's been on the course for after seconds
+    Accelerate { after: f32, factor: f32 },
+    /// In degrees per second, counterclockwise
+    Rotate { speed: f32 },
+}
+
+impl TryFrom<String> for Behavior {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        let mut words = source.split_whitespace();
+        let name = words.next().ok_or("a behavior can't be empty")?;
+        let mut behavior = match name {
+            "oscillate" => Behavior::Oscillate {
+                amplitude: 30.0,
+                period: 2.0,
+            },
+            "accelerate" => Behavior::Accelerate {
+                after: 1.0,
+                factor: 2.0,
+            },
+            "rotate" => Behavior::Rotate { speed: 90.0 },
+            _ => return Err(format!("there's no behavior called {}", name)),
+        };
+
+        for setting in words {
+            let (key, value) = setting
+                .split_once('=')
+                .ok_or_else(|| format!("{} should be written as setting=value", setting))?;
+            let value: f32 = value
+                .parse()
+                .map_err(|_| format!("{} isn't a number in {}", value, source))?;
+
+            let field = match (&mut behavior, key) {
+                (Behavior::Oscillate { amplitude, .. }, "amplitude") => amplitude,
+                (Behavior::Oscillate { period, .. }, "period") => period,
+                (Behavior::Accelerate { after, .. }, "after") => after,
+                (Behavior::Accelerate { factor, .. }, "factor") => factor,
+                (Behavior::Rotate { speed }, "speed") => speed,
+                _ => return Err(format!("{} has no setting called {}", name, key)),
+            };
+            *field = value;
+        }
+
+        if let Behavior::Oscillate { period, .. } = behavior {
+            if period <= 0.0 {
+                return Err(format!("the period in {} has to be more than 0", source));
+            }
+        }
+
+        Ok(behavior)
+    }
+}
+
+// The behaviors of an obstacle, on every one of its rocks. They're all run by behavior_system, so a new kind of
+// motion only needs a variant and a match arm rather than a system and a component of its own.
+#[derive(Component, Clone)]
+pub struct Behaviors {
+    behaviors: Vec<Behavior>,
+    // Simulated seconds since the rock was spawned
+    time: f32,
+    base_y: f32,
+    base_gap_center: f32,
+}
+
+impl Behaviors {
+    pub fn new(behaviors: Vec<Behavior>, base_y: f32, base_gap_center: f32) -> Self {
+        Self {
+            behaviors,
+            time: 0.0,
+            base_y,
+            base_gap_center,
+        }
+    }
+}
+
+pub fn behavior_system(
+    mut query: Query<(
+        &mut Behaviors,
+        &mut Transform,
+        &mut HorizontalVelocity,
+        &mut Rock,
+    )>,
+) {
+    for (mut behaviors, mut transform, mut velocity, mut rock) in query.iter_mut() {
+        let before = behaviors.time;
+        behaviors.time += PHYSICS_STEP;
+        let time = behaviors.time;
+
+        for behavior in behaviors.behaviors.iter() {
+            match *behavior {
+                Behavior::Oscillate { amplitude, period } => {
+                    let offset = amplitude * (time / period * TAU).sin();
+                    transform.translation.y = behaviors.base_y + offset;
+                    rock.gap_center = behaviors.base_gap_center + offset;
+                }
+                // Only on the step it gets past after, so it doesn't keep speeding up
+                Behavior::Accelerate { after, factor } => {
+                    if before < after && time >= after {
+                        velocity.0 *= factor;
+                    }
+                }
+                Behavior::Rotate { speed } => {
+                    transform.rotate(Quat::from_rotation_z(speed.to_radians() * PHYSICS_STEP));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::f32::consts::PI;
+
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    add_collision_polygon, tick_spawn_timer, DespawnOffscreen, GameResult, GameRng, Hazard,
+    HazardKind, HazardSpawn, HorizontalVelocity, Interpolated, Layer, Score, ScreenAnchor,
+    SpawnHooks, COURSE_VELOCITY, PHYSICS_STEP,
+};
+
+const BLADE_MIN_SCORE: u64 = 40;
+const BLADE_LENGTH: f32 = 150.0;
+const BLADE_WIDTH: f32 = 14.0;
+const HUB_RADIUS: f32 = 10.0;
+// Far enough for the tips to be off-screen whichever way the blade points
+const BLADE_MARGIN: f32 = BLADE_LENGTH / 2.0;
+
+// A bar spinning around its middle. Its polygon turns with it, collision_system takes the rotation of the
+// transform into account.
+#[derive(Component, Clone)]
+pub struct Blade {
+    // In radians per second, negative spins clockwise
+    speed: f32,
+}
+
+pub struct BladeTimer(pub Timer);
+
+impl Default for BladeTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.0, false))
+    }
+}
+
+pub fn reset_blades(mut timer: ResMut<BladeTimer>) {
+    *timer = BladeTimer::default();
+}
+
+pub fn blade_spawn_system(
+    mut commands: Commands,
+    mut timer: ResMut<BladeTimer>,
+    score: Res<Score>,
+    mut rng: ResMut<GameRng>,
+    hooks: Res<SpawnHooks>,
+) -> GameResult {
+    if score.0 < BLADE_MIN_SCORE {
+        return Ok(());
+    }
+
+    if !tick_spawn_timer(&mut timer.0, || rng.course.gen_range(8.0..13.0)) {
+        return Ok(());
+    }
+
+    let direction = if rng.course.gen_bool(0.5) { 1.0 } else { -1.0 };
+    let blade = Blade {
+        speed: direction * rng.course.gen_range(PI / 2.0..PI),
+    };
+    let angle = rng.course.gen_range(0.0..PI);
+    let y = rng.course.gen_range(-80.0..80.0);
+
+    let mut spawn = HazardSpawn::new(
+        HazardKind::Blade,
+        ScreenAnchor::Right.offset(BLADE_MARGIN, y),
+        score.0,
+    );
+    if !hooks.hazard(&mut spawn) {
+        return Ok(());
+    }
+
+    let transform = Transform::from_translation(spawn.position.extend(Layer::Course.z()))
+        .with_rotation(Quat::from_rotation_z(angle));
+    let mut entity = spawn_blade(&mut commands, transform, blade)?;
+    spawn.apply_attached(&mut entity);
+
+    Ok(())
+}
+
+// The collision outline is a child, so it turns along with the blade
+pub fn spawn_blade<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    transform: Transform,
+    blade: Blade,
+) -> GameResult<EntityCommands<'w, 's, 'a>> {
+    let bar = shapes::Rectangle {
+        extents: Vec2::new(BLADE_LENGTH, BLADE_WIDTH),
+        origin: RectangleOrigin::Center,
+    };
+    let hub = shapes::Circle {
+        radius: HUB_RADIUS,
+        center: Vec2::ZERO,
+    };
+
+    let mut entity = commands.spawn_bundle(GeometryBuilder::build_as(
+        &bar,
+        DrawMode::Outlined {
+            fill_mode: FillMode::color(Color::rgb(0.75, 0.78, 0.8)),
+            outline_mode: StrokeMode::new(Color::rgb(0.35, 0.37, 0.4), 2.0),
+        },
+        transform,
+    ));
+
+    let (x, y) = (BLADE_LENGTH / 2.0, BLADE_WIDTH / 2.0);
+    add_collision_polygon(&mut entity, &vec![(-x, -y), (x, -y), (x, y), (-x, y)], 1.0)?;
+
+    entity
+        .insert(HorizontalVelocity(COURSE_VELOCITY))
+        .insert(DespawnOffscreen {
+            margin: BLADE_MARGIN,
+        })
+        .insert(Interpolated::default())
+        .insert(Hazard)
+        .insert(blade)
+        .with_children(|parent| {
+            parent.spawn_bundle(GeometryBuilder::build_as(
+                &hub,
+                DrawMode::Fill(FillMode::color(Color::rgb(0.3, 0.3, 0.32))),
+                Transform::from_xyz(0.0, 0.0, 0.1),
+            ));
+        });
+
+    Ok(entity)
+}
+
+pub fn blade_system(mut query: Query<(&Blade, &mut Transform)>) {
+    for (blade, mut transform) in query.iter_mut() {
+        transform.rotate(Quat::from_rotation_z(blade.speed * PHYSICS_STEP));
+    }
+}
+
+// For savestates, spawns a blade exactly like the one captured
+#[cfg(feature = "debug-tools")]
+pub fn respawn_blade(commands: &mut Commands, transform: &Transform, blade: &Blade) -> GameResult {
+    spawn_blade(commands, *transform, blade.clone())?;
+
+    Ok(())
+}
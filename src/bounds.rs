@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{GameConfig, Player, HEIGHT, PHYSICS_STEP};
+
+// Half the height of the hull, the plane is drawn at half size
+const PLANE_HALF_HEIGHT: f32 = 15.0;
+
+// What happens to a plane that flies off the top or bottom of the screen, see assets/config.ron
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+pub enum BoundsRule {
+    Clamp,
+    Crash(f32),
+}
+
+impl BoundsRule {
+    // Checked by collision_system, which crashes the plane as if it hit a rock
+    pub fn is_out(self, player: &Player) -> bool {
+        match self {
+            BoundsRule::Clamp => false,
+            BoundsRule::Crash(grace) => player.outside > grace,
+        }
+    }
+}
+
+// Runs after the planes have moved, so collision_system sees where they ended up
+pub fn bounds_system(mut query: Query<(&mut Player, &mut Transform)>, config: Res<GameConfig>) {
+    for (mut player, mut transform) in query.iter_mut() {
+        let y = transform.translation.y;
+
+        match config.bounds {
+            // Bonks against the edge, whatever speed it had going out of the screen is gone
+            BoundsRule::Clamp => {
+                let edge = HEIGHT / 2.0 - PLANE_HALF_HEIGHT;
+                if y > edge {
+                    transform.translation.y = edge;
+                    player.velocity = player.velocity.min(0.0);
+                } else if y < -edge {
+                    transform.translation.y = -edge;
+                    player.velocity = player.velocity.max(0.0);
+                }
+            }
+            // Only counts once the plane is out of sight entirely
+            BoundsRule::Crash(_) => {
+                if y.abs() > HEIGHT / 2.0 + PLANE_HALF_HEIGHT {
+                    player.outside += PHYSICS_STEP;
+                } else {
+                    player.outside = 0.0;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    CrashEvent, Ease, Eased, GameRng, GameSpeed, GameState, Player, SaveData, SimulationTime,
+    HEIGHT, WIDTH,
+};
+
+// Seconds the camera takes to ease to a new zoom or focus. Targets that keep moving, like the zoom that
+// follows the speed, restart it every frame and get followed with a little lag.
+const ZOOM_TIME: f32 = 0.3;
+
+// The camera pulls back as the course speeds up, fully by SPEED_ZOOM_FULL. It's kept this small because
+// everything spawns just off screen, any further and rocks would be seen popping in at the edges.
+const SPEED_ZOOM_OUT: f32 = 0.015;
+const SPEED_ZOOM_FULL: f32 = 2.0;
+
+const CRASH_ZOOM: f32 = 0.92;
+const CRASH_ZOOM_TIME: f32 = 0.25;
+
+const CRASH_SHAKE: Shake = Shake {
+    amplitude: 12.0,
+    duration: 0.4,
+    decay: 2.0,
+};
+// Long enough to feel the hit, short enough not to look like a stutter
+const HIT_STOP_TIME: f32 = 0.08;
+
+#[derive(Component)]
+pub struct MainCamera;
+
+// Lets effects zoom the camera in on a point for a little while, on top of the zoom that follows the speed
+pub struct CameraZoom {
+    zoom: f32,
+    focus: Vec2,
+    timer: Timer,
+    scale: Eased<f32>,
+    // Where the camera has eased to, without any shake
+    position: Eased<Vec2>,
+}
+
+impl Default for CameraZoom {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            focus: Vec2::ZERO,
+            timer: Timer::from_seconds(0.0, false),
+            scale: Eased::new(1.0),
+            position: Eased::new(Vec2::ZERO),
+        }
+    }
+}
+
+// Sent to shake the camera. The shake dies down from amplitude to nothing over duration, decay is the
+// exponent of that curve so higher values die down sooner.
+#[derive(Clone, Copy)]
+pub struct Shake {
+    pub amplitude: f32,
+    pub duration: f32,
+    pub decay: f32,
+}
+
+#[derive(Default)]
+pub struct CameraShake {
+    shake: Option<Shake>,
+    elapsed: f32,
+    offset: Vec2,
+}
+
+impl CameraShake {
+    fn strength(&self) -> f32 {
+        match self.shake {
+            Some(shake) => {
+                shake.amplitude * (1.0 - self.elapsed / shake.duration).powf(shake.decay)
+            }
+            None => 0.0,
+        }
+    }
+}
+
+impl CameraZoom {
+    // A zoom below 1.0 zooms in
+    pub fn zoom_to(&mut self, zoom: f32, focus: Vec2, seconds: f32) {
+        self.zoom = zoom;
+        self.focus = focus;
+        self.timer = Timer::from_seconds(seconds, false);
+    }
+
+    fn reset(&mut self) {
+        self.zoom = 1.0;
+        self.focus = Vec2::ZERO;
+        self.timer = Timer::from_seconds(0.0, false);
+    }
+}
+
+fn speed_zoom(game_speed: f32) -> f32 {
+    let t = ((game_speed - 1.0) / (SPEED_ZOOM_FULL - 1.0)).clamp(0.0, 1.0);
+    1.0 + SPEED_ZOOM_OUT * Ease::QuadOut.apply(t)
+}
+
+// Effects that are still going when the state changes are cut short, the camera eases back from wherever they
+// left it. The crash that ends a run gets to finish its punch-in on the continue or game over screen.
+pub fn camera_zoom_system(
+    mut zoom: ResMut<CameraZoom>,
+    shake: Res<CameraShake>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+    game_speed: Res<GameSpeed>,
+    state: Res<State<GameState>>,
+    save: Res<SaveData>,
+    time: Res<Time>,
+) {
+    let state_changed =
+        state.is_changed() && !matches!(state.current(), GameState::Continue | GameState::GameOver);
+    if zoom.timer.tick(time.delta()).just_finished() || state_changed {
+        zoom.reset();
+    }
+
+    let mut target_scale = zoom.zoom;
+    if !save.settings.reduced_motion {
+        target_scale *= speed_zoom(game_speed.0);
+    }
+
+    if (zoom.scale.target() - target_scale).abs() > f32::EPSILON {
+        zoom.scale.set(target_scale, ZOOM_TIME, Ease::CubicOut);
+    }
+
+    let (mut transform, mut projection) = match camera_query.get_single_mut() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+    let scale = zoom.scale.tick(time.delta_seconds());
+    if (projection.scale - scale).abs() > f32::EPSILON {
+        projection.scale = scale;
+    }
+
+    // Never show anything outside of the playfield
+    let max_offset = ((1.0 - scale) * Vec2::new(WIDTH, HEIGHT) / 2.0).max(Vec2::ZERO);
+    let target = zoom.focus.clamp(-max_offset, max_offset);
+
+    if zoom.position.target() != target {
+        zoom.position.set(target, ZOOM_TIME, Ease::CubicOut);
+    }
+    let position = zoom.position.tick(time.delta_seconds());
+
+    transform.translation.x = position.x + shake.offset.x;
+    transform.translation.y = position.y + shake.offset.y;
+}
+
+// Every crash shakes the camera, punches in on the plane and briefly freezes the simulation, so it lands
+// with a thud
+pub fn crash_effects_system(
+    mut crashes: EventReader<CrashEvent>,
+    mut shakes: EventWriter<Shake>,
+    mut simulation_time: ResMut<SimulationTime>,
+    mut zoom: ResMut<CameraZoom>,
+    save: Res<SaveData>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    if crashes.iter().count() == 0 {
+        return;
+    }
+
+    shakes.send(CRASH_SHAKE);
+    simulation_time.hit_stop(HIT_STOP_TIME);
+
+    if !save.settings.reduced_motion {
+        let focus = player_query
+            .iter()
+            .next()
+            .map_or(Vec2::ZERO, |transform| transform.translation.truncate());
+        zoom.zoom_to(CRASH_ZOOM, focus, CRASH_ZOOM_TIME);
+    }
+}
+
+// Runs on real time, so the shake carries on through a hit-stop
+pub fn camera_shake_system(
+    mut camera_shake: ResMut<CameraShake>,
+    mut shakes: EventReader<Shake>,
+    save: Res<SaveData>,
+    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
+) {
+    for shake in shakes.iter() {
+        // A weak shake doesn't cut a stronger one short
+        if shake.amplitude >= camera_shake.strength() {
+            camera_shake.shake = Some(*shake);
+            camera_shake.elapsed = 0.0;
+        }
+    }
+
+    let duration = match camera_shake.shake {
+        Some(shake) => shake.duration,
+        None => return,
+    };
+
+    camera_shake.elapsed += time.delta_seconds();
+    let disabled = !save.settings.screen_shake || save.settings.reduced_motion;
+    if camera_shake.elapsed >= duration || disabled {
+        camera_shake.shake = None;
+        camera_shake.offset = Vec2::ZERO;
+        return;
+    }
+
+    let direction = Vec2::new(
+        rng.cosmetic.gen_range(-1.0..1.0),
+        rng.cosmetic.gen_range(-1.0..1.0),
+    );
+    camera_shake.offset = direction * camera_shake.strength();
+}
@@ -0,0 +1,92 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    BitmapTextBundle, GameModes, GameRng, Layer, Lifetime, Particle, RemoveAfterState, RunMode,
+    SaveData, Score, ScreenAnchor, TextAnchor, TextEffect, WIDTH,
+};
+
+// Between the game over sign and the panel
+const BANNER_Y: f32 = 30.0;
+const BANNER_SCALE: f32 = 0.7;
+pub const NEW_BEST_COLOR: Color = Color::rgb(1.0, 0.8, 0.2);
+
+const CONFETTI_PIECES: f32 = 80.0;
+const CONFETTI_GRAVITY: f32 = 90.0;
+
+// The run that just beat the best score of its mode. Kept until the next run starts, so the menu can point out
+// its row on the leaderboard.
+pub struct NewBest {
+    pub mode: RunMode,
+    pub score: u64,
+}
+
+// Has to run before finish_run_stats records the score, after that there's nothing left to beat. The first run
+// of a mode has no best yet, so it isn't celebrated.
+pub fn celebrate_new_best(
+    mut commands: Commands,
+    score: Res<Score>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    save: Res<SaveData>,
+    mut rng: ResMut<GameRng>,
+) {
+    let previous = modes.get(*mode).best_score(&save);
+    if previous == 0 || score.0 <= previous {
+        return;
+    }
+
+    commands.insert_resource(NewBest {
+        mode: *mode,
+        score: score.0,
+    });
+
+    // The font has no exclamation mark
+    commands
+        .spawn_bundle(
+            BitmapTextBundle::new(0.0, BANNER_Y)
+                .with_anchor(TextAnchor::Center)
+                .with_scale(BANNER_SCALE)
+                .with_color(NEW_BEST_COLOR)
+                .with_text("New best".to_string()),
+        )
+        .insert(
+            TextEffect::default()
+                .with_typewriter(12.0)
+                .with_bounce(10.0)
+                .with_pulse(0.08),
+        )
+        .insert(RemoveAfterState);
+
+    let pieces = (CONFETTI_PIECES * save.settings.effects_intensity.factor()).round() as u32;
+    let rng = &mut rng.cosmetic;
+
+    // Falls from just above the top edge, drifting sideways as it goes
+    for _ in 0..pieces {
+        let x = rng.gen_range(WIDTH / -2.0..WIDTH / 2.0);
+        let position = ScreenAnchor::Top.offset(x, rng.gen_range(0.0..60.0));
+        let velocity = Vec2::new(rng.gen_range(-60.0..60.0), rng.gen_range(-120.0..-20.0));
+        let color = Color::hsl(rng.gen_range(0.0..360.0), 0.9, 0.6);
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::new(5.0, 9.0)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(Layer::Particles.z()))
+                    .with_rotation(Quat::from_rotation_z(rng.gen_range(0.0..PI))),
+                ..default()
+            })
+            .insert(Particle::new(velocity, CONFETTI_GRAVITY))
+            .insert(Lifetime::from_seconds(rng.gen_range(3.0..5.0)))
+            .insert(RemoveAfterState);
+    }
+}
+
+pub fn clear_new_best(mut commands: Commands) {
+    commands.remove_resource::<NewBest>();
+}
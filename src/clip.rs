@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use bevy::render::texture::BevyDefault;
+
+use crate::{
+    report_error, unix_seconds, AddGameOverSection, DemoMode, GameError, GameOverSection,
+    GameResult, GameState, MainCamera, Recorder, ShowToast, HEIGHT, WIDTH,
+};
+
+const CLIP_DIRECTORY: &str = "clips";
+// Half the playfield at 15 frames a second keeps the buffer at around 35 MB
+const CLIP_SECONDS: usize = 6;
+const CLIP_RATE: usize = 15;
+const CLIP_FRAMES: usize = CLIP_SECONDS * CLIP_RATE;
+const CLIP_DOWNSCALE: usize = 2;
+const CLIP_WIDTH: usize = WIDTH as usize / CLIP_DOWNSCALE;
+const CLIP_HEIGHT: usize = HEIGHT as usize / CLIP_DOWNSCALE;
+// Frames come in once per rendered frame, so at 60 frames a second only every fourth one is kept
+const FRAME_SKIP: usize = 60 / CLIP_RATE;
+// Frames that can wait for the buffer thread before the recorder starts skipping them
+const QUEUED_FRAMES: usize = 4;
+// 1 is best and slowest, 30 is worst and fastest
+const QUANTIZE_SPEED: i32 = 10;
+
+// The last seconds of the run, downscaled to rgba, oldest first. K on the game over screen saves them as a gif.
+#[derive(Default)]
+pub struct ClipBuffer {
+    frames: Arc<Mutex<VecDeque<Vec<u8>>>>,
+    tapped: bool,
+    saving: Option<Mutex<Receiver<io::Result<String>>>>,
+}
+
+// Nothing left over from the previous run
+pub fn clear_clip(clip: Res<ClipBuffer>) {
+    clip.frames.lock().expect("only locked briefly").clear();
+}
+
+// Only runs are kept, not the demo or whatever is on screen while paused
+pub fn clip_capture_system(
+    mut commands: Commands,
+    mut clip: ResMut<ClipBuffer>,
+    mut recorder: ResMut<Recorder>,
+    mut images: ResMut<Assets<Image>>,
+    cameras: Query<Entity, With<MainCamera>>,
+    state: Res<State<GameState>>,
+    demo: Res<DemoMode>,
+) {
+    let wanted = state.current() == &GameState::Playing && !demo.active;
+    if wanted == clip.tapped {
+        return;
+    }
+
+    if !wanted {
+        recorder.stop_frame_tap(&mut commands, &mut images);
+        clip.tapped = false;
+        return;
+    }
+
+    // The thread ends once the recorder drops its end of the channel
+    let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(QUEUED_FRAMES);
+    if !recorder.start_frame_tap(sender, &mut commands, &mut images, &cameras) {
+        return;
+    }
+    clip.tapped = true;
+
+    let frames = clip.frames.clone();
+    thread::spawn(move || {
+        for (i, frame) in receiver.into_iter().enumerate() {
+            if i % FRAME_SKIP != 0 {
+                continue;
+            }
+
+            let frame = downscale(&frame);
+            let mut frames = frames.lock().expect("only locked briefly");
+            if frames.len() == CLIP_FRAMES {
+                frames.pop_front();
+            }
+            frames.push_back(frame);
+        }
+    });
+}
+
+pub fn clip_section(clip: Res<ClipBuffer>, mut sections: EventWriter<GameOverSection>) {
+    if !clip.frames.lock().expect("only locked briefly").is_empty() {
+        sections.send(GameOverSection::line(
+            300,
+            "Press K to save a clip".to_string(),
+        ));
+    }
+}
+
+// A key rather than a button, clicking anywhere already leaves the game over screen. The clip stays in the buffer
+// until the next run, so it can be saved more than once.
+pub fn save_clip_system(
+    keys: Res<Input<KeyCode>>,
+    mut clip: ResMut<ClipBuffer>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !keys.just_pressed(KeyCode::K) || clip.saving.is_some() {
+        return;
+    }
+
+    let frames: Vec<Vec<u8>> = clip
+        .frames
+        .lock()
+        .expect("only locked briefly")
+        .iter()
+        .cloned()
+        .collect();
+    if frames.is_empty() {
+        return;
+    }
+
+    let (sender, saved) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(write_gif(frames));
+    });
+
+    toasts.send(ShowToast("Saving clip".to_string()));
+    clip.saving = Some(Mutex::new(saved));
+}
+
+pub fn clip_saved_system(
+    mut clip: ResMut<ClipBuffer>,
+    mut toasts: EventWriter<ShowToast>,
+) -> GameResult {
+    let saved = match &clip.saving {
+        Some(saving) => match saving.lock().expect("only locked here").try_recv() {
+            Ok(saved) => saved,
+            Err(TryRecvError::Empty) => return Ok(()),
+            Err(TryRecvError::Disconnected) => Err(io::ErrorKind::Interrupted.into()),
+        },
+        None => return Ok(()),
+    };
+    clip.saving = None;
+
+    let path = saved.map_err(|source| GameError::Io {
+        path: CLIP_DIRECTORY,
+        source,
+    })?;
+
+    info!("Saved a clip to {}", path);
+    toasts.send(ShowToast("Clip saved".to_string()));
+    Ok(())
+}
+
+// Every pixel is the average of a square of the captured frame, in rgba whichever way round the frame was
+fn downscale(frame: &[u8]) -> Vec<u8> {
+    let bgra = matches!(
+        TextureFormat::bevy_default(),
+        TextureFormat::Bgra8UnormSrgb | TextureFormat::Bgra8Unorm
+    );
+    let frame_width = CLIP_WIDTH * CLIP_DOWNSCALE;
+    let samples = (CLIP_DOWNSCALE * CLIP_DOWNSCALE) as u32;
+
+    let mut pixels = Vec::with_capacity(CLIP_WIDTH * CLIP_HEIGHT * 4);
+    for y in 0..CLIP_HEIGHT {
+        for x in 0..CLIP_WIDTH {
+            let mut sum = [0u32; 4];
+            for dy in 0..CLIP_DOWNSCALE {
+                for dx in 0..CLIP_DOWNSCALE {
+                    let i = ((y * CLIP_DOWNSCALE + dy) * frame_width + x * CLIP_DOWNSCALE + dx) * 4;
+                    for (channel, total) in sum.iter_mut().enumerate() {
+                        *total += frame[i + channel] as u32;
+                    }
+                }
+            }
+
+            let [first, g, third, a] = sum.map(|total| (total / samples) as u8);
+            if bgra {
+                pixels.extend([third, g, first, a]);
+            } else {
+                pixels.extend([first, g, third, a]);
+            }
+        }
+    }
+
+    pixels
+}
+
+fn write_gif(frames: Vec<Vec<u8>>) -> io::Result<String> {
+    fs::create_dir_all(CLIP_DIRECTORY)?;
+    let path = format!("{}/clip-{}.gif", CLIP_DIRECTORY, unix_seconds());
+
+    let file = File::create(&path)?;
+    let mut encoder =
+        gif::Encoder::new(file, CLIP_WIDTH as u16, CLIP_HEIGHT as u16, &[]).map_err(gif_error)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(gif_error)?;
+
+    for mut pixels in frames {
+        let mut frame = gif::Frame::from_rgba_speed(
+            CLIP_WIDTH as u16,
+            CLIP_HEIGHT as u16,
+            &mut pixels,
+            QUANTIZE_SPEED,
+        );
+        // In hundredths of a second
+        frame.delay = (100 / CLIP_RATE) as u16;
+        encoder.write_frame(&frame).map_err(gif_error)?;
+    }
+
+    Ok(path)
+}
+
+fn gif_error(err: gif::EncodingError) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+// Runs alongside RecorderPlugin, which does the capturing
+pub struct ClipPlugin;
+
+impl Plugin for ClipPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClipBuffer>()
+            .add_system(clip_capture_system)
+            .add_system(clip_saved_system.chain(report_error))
+            .add_system_set(SystemSet::on_enter(GameState::Start).with_system(clear_clip))
+            .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(save_clip_system))
+            .add_game_over_section(clip_section);
+    }
+}
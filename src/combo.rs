@@ -0,0 +1,57 @@
+use bevy::prelude::*;
+
+use crate::{ComboConfig, FlapEvent, Invincible, NearMissEvent};
+
+// Gates passed in a row with few flaps and no grazes. The streak raises the points every gate is worth.
+#[derive(Default, Clone)]
+pub struct Combo {
+    streak: u32,
+    flaps_since_gate: u32,
+    grazed: bool,
+}
+
+impl Combo {
+    pub fn multiplier(&self, config: &ComboConfig) -> u64 {
+        let level = self.streak / config.gates_per_level.max(1);
+        (1 + level as u64).min(config.max_multiplier)
+    }
+
+    // Called by rock_system for every gate, before the gate is scored
+    pub fn pass_gate(&mut self, config: &ComboConfig) {
+        if self.grazed || self.flaps_since_gate > config.max_flaps {
+            self.streak = 0;
+        } else {
+            self.streak += 1;
+        }
+
+        self.flaps_since_gate = 0;
+        self.grazed = false;
+    }
+
+    fn reset(&mut self) {
+        *self = Combo::default();
+    }
+}
+
+pub fn reset_combo(mut combo: ResMut<Combo>) {
+    combo.reset();
+}
+
+// Grazing a rock or losing a life ends the streak straight away
+pub fn combo_system(
+    mut combo: ResMut<Combo>,
+    mut flaps: EventReader<FlapEvent>,
+    mut near_misses: EventReader<NearMissEvent>,
+    respawned: Query<(), Added<Invincible>>,
+) {
+    combo.flaps_since_gate += flaps.iter().count() as u32;
+
+    if near_misses.iter().count() > 0 {
+        combo.streak = 0;
+        combo.grazed = true;
+    }
+
+    if !respawned.is_empty() {
+        combo.reset();
+    }
+}
@@ -0,0 +1,85 @@
+use serde::Deserialize;
+
+use crate::{BoundsRule, DifficultyPresets, SpeedrunRules};
+
+// Embedded like the obstacle patterns, so tuning never depends on files next to the executable
+const CONFIG: &str = include_str!("../assets/config.ron");
+
+/// Tuning values for the game, see assets/config.ron. Embedded in the game, it's the same in every build.
+#[derive(Deserialize)]
+pub struct GameConfig {
+    pub physics: PhysicsConfig,
+    pub fuel: FuelConfig,
+    pub lives: LivesConfig,
+    pub continues: ContinueConfig,
+    pub combo: ComboConfig,
+    pub rocks: RockTypesConfig,
+    pub speedrun: SpeedrunRules,
+    pub bounds: BoundsRule,
+    pub difficulty: DifficultyPresets,
+}
+
+#[derive(Deserialize)]
+pub struct PhysicsConfig {
+    pub max_steps_per_frame: u32,
+    pub collision_substeps: u32,
+}
+
+#[derive(Deserialize)]
+pub struct FuelConfig {
+    pub capacity: f32,
+    pub can_refill: f32,
+    pub gates_per_can: u32,
+}
+
+#[derive(Deserialize)]
+pub struct LivesConfig {
+    pub start: u32,
+    // 0 for no extra lives
+    pub points_per_extra: u64,
+    pub invincibility: f32,
+}
+
+#[derive(Deserialize)]
+pub struct ContinueConfig {
+    pub seconds: f32,
+    pub free: u32,
+    // 0 for none
+    pub points_per_token: u64,
+}
+
+#[derive(Deserialize)]
+pub struct ComboConfig {
+    pub max_flaps: u32,
+    pub gates_per_level: u32,
+    pub max_multiplier: u64,
+}
+
+// The random rocks between patterns, by which side they're on
+#[derive(Deserialize)]
+pub struct RockTypesConfig {
+    pub down: RockTypeConfig,
+    pub up: RockTypeConfig,
+    pub both: RockTypeConfig,
+}
+
+impl RockTypesConfig {
+    // By the rock_type of a GateSpawn
+    pub fn get(&self, rock_type: u8) -> &RockTypeConfig {
+        match rock_type {
+            0 => &self.down,
+            1 => &self.up,
+            _ => &self.both,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RockTypeConfig {
+    pub weight: u32,
+    pub points: u64,
+}
+
+pub fn load_game_config() -> GameConfig {
+    ron::from_str(CONFIG).expect("assets/config.ron is invalid")
+}
@@ -0,0 +1,226 @@
+use std::str::FromStr;
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use crate::{
+    flap_input_system, fullscreen_toggle_system, BitmapText, BitmapTextBundle, GameRng, GameSpeed,
+    Invincible, Obstacle, ObstacleQueue, Player, RockKind, RockTimer, Score, ScreenAnchor,
+    ShowToast,
+};
+
+const CONSOLE_POSITION: Vec2 = ScreenAnchor::BottomLeft.offset(15.0, 40.0);
+const CONSOLE_SCALE: f32 = 0.5;
+const CONSOLE_COLOR: Color = Color::rgb(0.6, 1.0, 0.6);
+
+// Opened with the backtick key. While it's open every key goes to the console and nothing else sees it.
+#[derive(Default)]
+pub struct DevConsole {
+    open: bool,
+    input: String,
+    // Keeps the planes invincible, even after a respawn or a gravity flip replaces their invincibility
+    god: bool,
+}
+
+// A line entered in the console, run by console_command_system
+pub struct ConsoleLine(pub String);
+
+#[derive(Component)]
+pub struct ConsoleText;
+
+enum ConsoleCommand {
+    Score(u64),
+    Speed(f32),
+    SpawnRock(RockKind, f32),
+    God(bool),
+    Seed(u64),
+}
+
+impl ConsoleCommand {
+    // The messages end up in a toast, so they stick to letters, digits and spaces
+    fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        match words.as_slice() {
+            ["score", score] => Ok(ConsoleCommand::Score(parse_number(score)?)),
+            ["speed", speed] => Ok(ConsoleCommand::Speed(parse_number(speed)?)),
+            ["spawn", "rock", kind] => Ok(ConsoleCommand::SpawnRock(parse_rock(kind)?, 1.0)),
+            ["spawn", "rock", kind, scale] => Ok(ConsoleCommand::SpawnRock(
+                parse_rock(kind)?,
+                parse_number(scale)?,
+            )),
+            ["god", "on"] => Ok(ConsoleCommand::God(true)),
+            ["god", "off"] => Ok(ConsoleCommand::God(false)),
+            ["seed", seed] => Ok(ConsoleCommand::Seed(parse_number(seed)?)),
+            [] => Err("Nothing to run".to_string()),
+            _ => Err(format!("Unknown command {}", words[0])),
+        }
+    }
+}
+
+fn parse_number<T: FromStr>(arg: &str) -> Result<T, String> {
+    arg.parse().map_err(|_| "Not a number".to_string())
+}
+
+// Named after the way the rock points, like the obstacle patterns
+fn parse_rock(kind: &str) -> Result<RockKind, String> {
+    match kind {
+        "up" => Ok(RockKind::Up),
+        "down" => Ok(RockKind::Down),
+        "both" => Ok(RockKind::Both),
+        _ => Err(format!("Unknown rock {}", kind)),
+    }
+}
+
+pub fn setup_console(mut commands: Commands) {
+    commands
+        .spawn_bundle(
+            BitmapTextBundle::at(CONSOLE_POSITION)
+                .with_scale(CONSOLE_SCALE)
+                .with_color(CONSOLE_COLOR),
+        )
+        .insert(Visibility { is_visible: false })
+        .insert(ConsoleText);
+}
+
+// Runs right after bevy reads the input, so the keys can be taken away before anything else sees them
+pub fn console_input_system(
+    mut console: ResMut<DevConsole>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut characters: ResMut<Events<ReceivedCharacter>>,
+    mut lines: EventWriter<ConsoleLine>,
+) {
+    if keys.just_pressed(KeyCode::Grave) {
+        console.open = !console.open;
+        console.input.clear();
+    } else if !console.open {
+        return;
+    }
+
+    // Drained rather than read, so name entry doesn't pick up the letters too
+    for event in characters.drain() {
+        if console.open && event.char != '`' && !event.char.is_control() {
+            console.input.push(event.char.to_ascii_lowercase());
+        }
+    }
+
+    if keys.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if keys.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        lines.send(ConsoleLine(line));
+    }
+
+    // Held keys too, or a flap held while opening the console would keep going
+    let pressed: Vec<KeyCode> = keys.get_pressed().copied().collect();
+    for key in pressed {
+        keys.reset(key);
+    }
+    keys.clear();
+}
+
+pub fn console_command_system(
+    mut lines: EventReader<ConsoleLine>,
+    mut console: ResMut<DevConsole>,
+    mut score: ResMut<Score>,
+    mut game_speed: ResMut<GameSpeed>,
+    mut queue: ResMut<ObstacleQueue>,
+    mut rock_timer: ResMut<RockTimer>,
+    mut rng: ResMut<GameRng>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    for line in lines.iter() {
+        let message = match ConsoleCommand::parse(&line.0) {
+            Ok(ConsoleCommand::Score(points)) => {
+                score.0 = points;
+                format!("Score {}", points)
+            }
+            Ok(ConsoleCommand::Speed(speed)) => {
+                game_speed.0 = speed.max(0.1);
+                "Speed changed".to_string()
+            }
+            Ok(ConsoleCommand::SpawnRock(kind, scale)) => {
+                queue.0.push_front(Obstacle {
+                    rocks: Some(kind),
+                    scale: (scale, scale),
+                    ..default()
+                });
+                // Spawned on the next physics step instead of when the current delay runs out
+                let duration = rock_timer.0.duration();
+                rock_timer.0.set_elapsed(duration);
+                "Rock spawned".to_string()
+            }
+            Ok(ConsoleCommand::God(god)) => {
+                console.god = god;
+                if god { "God mode on" } else { "God mode off" }.to_string()
+            }
+            Ok(ConsoleCommand::Seed(seed)) => {
+                // Picked up by seed_run_rng when the next run starts
+                rng.seed = Some(seed);
+                format!("Seed {} from the next run", seed)
+            }
+            Err(message) => message,
+        };
+
+        info!("Console {:?}: {}", line.0, message);
+        toasts.send(ShowToast(message));
+    }
+}
+
+// Characters the font has no glyph for, like the point in 0.8, are left out of what's shown
+pub fn console_text_system(
+    console: Res<DevConsole>,
+    mut query: Query<(&mut BitmapText, &mut Visibility), With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+
+    for (mut text, mut visibility) in query.iter_mut() {
+        visibility.is_visible = console.open;
+        text.text = if console.input.is_empty() {
+            "Type a command".to_string()
+        } else {
+            console.input.clone()
+        };
+    }
+}
+
+pub fn god_mode_system(
+    mut commands: Commands,
+    console: Res<DevConsole>,
+    mut planes: Query<(Entity, Option<&Invincible>, &mut Visibility), With<Player>>,
+) {
+    for (entity, invincible, mut visibility) in planes.iter_mut() {
+        let permanent = invincible.map_or(false, Invincible::is_permanent);
+
+        if console.god && !permanent {
+            commands.entity(entity).insert(Invincible::permanent());
+            visibility.is_visible = true;
+        } else if !console.god && permanent {
+            commands.entity(entity).remove::<Invincible>();
+        }
+    }
+}
+
+// Only in builds with the dev-console feature
+pub struct DevConsolePlugin;
+
+impl Plugin for DevConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DevConsole>()
+            .add_event::<ConsoleLine>()
+            .add_startup_system(setup_console)
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                console_input_system
+                    .after(InputSystem)
+                    .before(flap_input_system)
+                    .before(fullscreen_toggle_system),
+            )
+            .add_system(console_command_system)
+            .add_system(console_text_system.after(console_command_system))
+            .add_system(god_mode_system.after(console_command_system));
+    }
+}
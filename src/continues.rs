@@ -0,0 +1,236 @@
+use bevy::prelude::*;
+
+use crate::{
+    despawn_or_pool, Action, ActionInput, BitmapText, BitmapTextBundle, DemoMode, Fuel, GameConfig,
+    GameModes, GameResult, GameState, Hazard, Interpolated, Invincible, Player, PooledRock, Rock,
+    RockPool, RunMode, SaveData, Score, ScreenAnchor, ShowToast, TextAnchor, TextEffect, BUMP,
+    HEIGHT,
+};
+
+// Rocks and hazards this close to the plane, left or right, are cleared when it flies on. Otherwise it would be
+// revived inside whatever it hit.
+const CLEAR_DISTANCE: f32 = 250.0;
+// A revived plane is moved back into view if it crashed at the edge of the screen
+const REVIVE_MARGIN: f32 = 40.0;
+// Flaps still coming in from just before the crash shouldn't be taken for continuing
+const INPUT_GRACE: f32 = 0.4;
+
+const TITLE_POSITION: Vec2 = ScreenAnchor::Center.offset(0.0, 140.0);
+const COUNT_POSITION: Vec2 = ScreenAnchor::Center.offset(0.0, 60.0);
+const HINT_POSITION: Vec2 = ScreenAnchor::Center.offset(0.0, -60.0);
+
+// What's left of the current run's second chances. Each run gets a few for free, after those a continue spends
+// one of the tokens in the save.
+#[derive(Clone, Default)]
+pub struct Continues {
+    allowed: bool,
+    free: u32,
+    next_token: u64,
+    // Seconds left on the Continue screen
+    remaining: f32,
+}
+
+impl Continues {
+    // Whether a crash that ends the run goes to the Continue screen rather than straight to the game over one
+    pub fn can_offer(&self, save: &SaveData) -> bool {
+        self.allowed && (self.free > 0 || save.continue_tokens > 0)
+    }
+
+    fn hint(&self, save: &SaveData) -> String {
+        if self.free > 0 {
+            "Click to fly on".to_string()
+        } else {
+            let tokens = if save.continue_tokens == 1 {
+                "token"
+            } else {
+                "tokens"
+            };
+            format!(
+                "Click to spend a token  {} {} left",
+                save.continue_tokens, tokens
+            )
+        }
+    }
+}
+
+// Everything on the Continue screen, it's pushed on top of Playing so the run stays as it was underneath
+#[derive(Component)]
+pub struct ContinueText;
+
+#[derive(Component)]
+pub struct ContinueCount;
+
+// Practice never ends on a crash, and the demo and modes that don't allow them never get a continue
+pub fn setup_continues(
+    mut continues: ResMut<Continues>,
+    config: Res<GameConfig>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    demo: Res<DemoMode>,
+) {
+    let mode = modes.get(*mode);
+    *continues = Continues {
+        allowed: !demo.active && mode.allows_continues() && !mode.forgives_crashes(),
+        free: config.continues.free,
+        next_token: config.continues.points_per_token,
+        remaining: 0.0,
+    };
+}
+
+// Like extra lives, only runs that could spend them earn tokens
+pub fn continue_token_system(
+    mut continues: ResMut<Continues>,
+    score: Res<Score>,
+    config: Res<GameConfig>,
+    mut save: ResMut<SaveData>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !continues.allowed || config.continues.points_per_token == 0 {
+        return;
+    }
+
+    while score.0 >= continues.next_token {
+        save.continue_tokens += 1;
+        continues.next_token += config.continues.points_per_token;
+        toasts.send(ShowToast("Continue token earned".to_string()));
+    }
+}
+
+pub fn setup_continue(
+    mut commands: Commands,
+    mut continues: ResMut<Continues>,
+    config: Res<GameConfig>,
+    save: Res<SaveData>,
+) {
+    continues.remaining = config.continues.seconds;
+
+    commands
+        .spawn_bundle(
+            BitmapTextBundle::at(TITLE_POSITION)
+                .with_anchor(TextAnchor::Center)
+                .with_scale(0.6)
+                .with_text("Continue".to_string()),
+        )
+        .insert(ContinueText);
+
+    commands
+        .spawn_bundle(
+            BitmapTextBundle::at(COUNT_POSITION)
+                .with_anchor(TextAnchor::Center)
+                .with_scale(1.2)
+                .with_text(countdown_text(continues.remaining)),
+        )
+        .insert(TextEffect::default().with_pulse(0.1))
+        .insert(ContinueCount)
+        .insert(ContinueText);
+
+    commands
+        .spawn_bundle(
+            BitmapTextBundle::at(HINT_POSITION)
+                .with_anchor(TextAnchor::Center)
+                .with_scale(0.35)
+                .with_line_height(100.0)
+                .with_text(format!("{}\nEsc to give up", continues.hint(&save))),
+        )
+        .insert(ContinueText);
+}
+
+// Revives the plane where it crashed, with the rocks around it gone and the same blinking grace period a
+// respawn gets. Falls through to the game over screen once the countdown runs out, as if the run had ended on
+// the crash.
+pub fn continue_system(
+    mut commands: Commands,
+    mut input: ActionInput,
+    mut continues: ResMut<Continues>,
+    mut save: ResMut<SaveData>,
+    mut fuel: ResMut<Fuel>,
+    config: Res<GameConfig>,
+    mut player_query: Query<(Entity, &mut Player, &mut Transform, &mut Interpolated)>,
+    obstacles: Query<
+        (Entity, &Transform, Option<&PooledRock>),
+        (Or<(With<Rock>, With<Hazard>)>, Without<Player>),
+    >,
+    mut rock_pool: ResMut<RockPool>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    mut state: ResMut<State<GameState>>,
+    time: Res<Time>,
+) -> GameResult {
+    continues.remaining -= time.delta_seconds();
+    if input.keys.just_pressed(KeyCode::Escape) {
+        continues.remaining = 0.0;
+    }
+
+    if continues.remaining <= 0.0 {
+        modes.get(*mode).end_run(&mut commands, &[]);
+        // Unwinds Continue and Playing before the game over screen shows up
+        state.replace(GameState::GameOver)?;
+        return Ok(());
+    }
+
+    let bindings = &save.settings.bindings;
+    let waiting = continues.remaining > config.continues.seconds - INPUT_GRACE;
+    if waiting || !(input.just_pressed(bindings, Action::Confirm) || input.tap().is_some()) {
+        return Ok(());
+    }
+
+    // Otherwise the same click would flap straight away
+    input.consume(bindings, Action::Confirm);
+    input.consume(bindings, Action::Flap);
+    input.consume_tap();
+
+    if continues.free > 0 {
+        continues.free -= 1;
+    } else {
+        save.continue_tokens -= 1;
+    }
+    fuel.0 = config.fuel.capacity;
+
+    for (entity, mut player, mut transform, mut interpolated) in player_query.iter_mut() {
+        let edge = HEIGHT / 2.0 - REVIVE_MARGIN;
+        let y = transform.translation.y.clamp(-edge, edge);
+        interpolated.shift(Vec3::new(0.0, y - transform.translation.y, 0.0));
+        transform.translation.y = y;
+        transform.rotation = Quat::IDENTITY;
+        player.velocity = BUMP;
+        player.outside = 0.0;
+
+        for (obstacle, obstacle_transform, pooled) in obstacles.iter() {
+            let distance = (obstacle_transform.translation.x - transform.translation.x).abs();
+            if distance < CLEAR_DISTANCE {
+                despawn_or_pool(&mut commands, &mut rock_pool, obstacle, pooled);
+            }
+        }
+
+        commands
+            .entity(entity)
+            .insert(Invincible::new(config.lives.invincibility));
+    }
+
+    state.pop()?;
+
+    Ok(())
+}
+
+pub fn continue_count_system(
+    continues: Res<Continues>,
+    mut query: Query<&mut BitmapText, With<ContinueCount>>,
+) {
+    let text = countdown_text(continues.remaining);
+    for mut count in query.iter_mut() {
+        if count.text != text {
+            count.text = text.clone();
+        }
+    }
+}
+
+pub fn cleanup_continue(mut commands: Commands, query: Query<Entity, With<ContinueText>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Whole seconds left, rounded up so it never shows 0 while there's still time
+fn countdown_text(remaining: f32) -> String {
+    (remaining.ceil() as u64).to_string()
+}
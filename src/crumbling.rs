@@ -0,0 +1,117 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    BaseColor, CollisionPolygon, GameRng, GameSpeed, Lifetime, Particle, Player, Rock,
+    COURSE_VELOCITY, PHYSICS_STEP, ROCK_HEIGHT,
+};
+
+// Only single rocks crumble, past the point where rocks start moving too
+pub const CRUMBLING_MIN_SCORE: u64 = 30;
+pub const CRUMBLING_CHANCE: f64 = 0.15;
+
+const CRACK_TIME: f32 = 1.0;
+// There's no cracked rock sprite, so a cracking rock flickers between its own color and this one
+const CRACKED_COLOR: Color = Color::rgb(0.7, 0.6, 0.55);
+// Color changes per second when it starts cracking, speeding up to twice as many just before it crumbles
+const CRACK_FLICKER_RATE: f32 = 6.0;
+
+const FRAGMENTS: usize = 7;
+const FRAGMENT_SCALE: f32 = 0.22;
+const FRAGMENT_GRAVITY: f32 = 700.0;
+const FRAGMENT_TIME: f32 = 1.2;
+
+// A rock that starts cracking once it's the next one the planes have to get past, and crumbles away CRACK_TIME
+// later. Waiting for it opens the path, flying past before that is quicker.
+#[derive(Component, Clone, Copy, Default)]
+pub enum Crumbling {
+    #[default]
+    Solid,
+    // With the seconds left until it crumbles
+    Cracking(f32),
+}
+
+pub fn crumbling_system(
+    mut commands: Commands,
+    mut crumbling: Query<(
+        Entity,
+        &Transform,
+        &Rock,
+        &mut Crumbling,
+        &mut BaseColor,
+        &Handle<Image>,
+    )>,
+    rocks: Query<(&Transform, &Rock)>,
+    planes: Query<&Transform, With<Player>>,
+    game_speed: Res<GameSpeed>,
+    mut rng: ResMut<GameRng>,
+) {
+    if crumbling.is_empty() {
+        return;
+    }
+
+    // All planes fly at the same x, the nearest obstacle is the closest one that hasn't been passed yet
+    let nearest_gate = planes.iter().next().and_then(|plane| {
+        rocks
+            .iter()
+            .filter(|(transform, _)| transform.translation.x >= plane.translation.x)
+            .min_by(|(a, _), (b, _)| a.translation.x.total_cmp(&b.translation.x))
+            .map(|(_, rock)| rock.gate())
+    });
+
+    for (entity, transform, rock, mut state, mut base_color, texture) in crumbling.iter_mut() {
+        let left = match *state {
+            Crumbling::Solid if Some(rock.gate()) == nearest_gate => CRACK_TIME,
+            Crumbling::Solid => continue,
+            Crumbling::Cracking(left) => left - PHYSICS_STEP,
+        };
+
+        if left > 0.0 {
+            let elapsed = CRACK_TIME - left;
+            let flicker =
+                (elapsed * CRACK_FLICKER_RATE * (1.0 + elapsed / CRACK_TIME)) as u32 % 2 == 0;
+            base_color.0 = if flicker { CRACKED_COLOR } else { Color::WHITE };
+            *state = Crumbling::Cracking(left);
+            continue;
+        }
+
+        // Stays a Rock, so the gate it was part of still scores once it's passed
+        commands
+            .entity(entity)
+            .remove::<CollisionPolygon>()
+            .remove::<Crumbling>()
+            .insert(Visibility { is_visible: false });
+
+        let height = ROCK_HEIGHT * transform.scale.y;
+        let rng = &mut rng.cosmetic;
+        for _ in 0..FRAGMENTS {
+            let position = transform.translation
+                + Vec3::new(
+                    rng.gen_range(-20.0..20.0),
+                    rng.gen_range(height / -2.0..height / 2.0),
+                    0.0,
+                );
+            let velocity = Vec2::new(
+                -COURSE_VELOCITY * game_speed.0 + rng.gen_range(-60.0..60.0),
+                rng.gen_range(-40.0..120.0),
+            );
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: CRACKED_COLOR,
+                        ..default()
+                    },
+                    texture: texture.clone(),
+                    transform: Transform::from_translation(position)
+                        .with_scale(Vec3::splat(FRAGMENT_SCALE))
+                        .with_rotation(Quat::from_rotation_z(rng.gen_range(0.0..TAU))),
+                    ..default()
+                })
+                .insert(Particle::new(velocity, FRAGMENT_GRAVITY))
+                .insert(Lifetime::from_seconds(FRAGMENT_TIME));
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    cursor_world_position, read_file, Action, ActionInput, BitmapText, BitmapTextBundle,
+    GameAssets, GameResult, GameState, Layer, MainCamera, RemoveAfterState, SaveData, ScreenAnchor,
+    ViewportSize,
+};
+
+const CUTSCENES: &str = include_str!("../assets/cutscenes.ron");
+// Next to the executable, for mods
+const MOD_CUTSCENES_PATH: &str = "cutscenes.ron";
+
+pub const INTRO_CUTSCENE: &str = "intro";
+
+const LETTERS_PER_SECOND: f32 = 20.0;
+const TEXT_SCALE: f32 = 0.4;
+// Long lines wrap before they reach the skip button
+const TEXT_WIDTH: f32 = 600.0;
+
+const SKIP_SCALE: f32 = 0.4;
+const SKIP_SIZE: Vec2 = Vec2::new(196.0 * SKIP_SCALE, 70.0 * SKIP_SCALE);
+
+// See assets/cutscenes.ron
+#[derive(Deserialize, Clone)]
+pub struct Cutscene {
+    shots: Vec<CutsceneShot>,
+}
+
+#[derive(Deserialize, Clone)]
+struct CutsceneShot {
+    sprites: Vec<CutsceneSprite>,
+    text: String,
+    duration: f32,
+}
+
+#[derive(Deserialize, Clone)]
+struct CutsceneSprite {
+    image: String,
+    from: (f32, f32),
+    #[serde(default)]
+    to: Option<(f32, f32)>,
+    #[serde(default = "default_sprite_scale")]
+    scale: f32,
+}
+
+fn default_sprite_scale() -> f32 {
+    1.0
+}
+
+impl CutsceneSprite {
+    // Progress is from 0 at the start of the shot to 1 at the end
+    fn position(&self, progress: f32) -> Vec2 {
+        let from = Vec2::from(self.from);
+        let to = self.to.map_or(from, Vec2::from);
+        from.lerp(to, progress)
+    }
+}
+
+impl CutsceneShot {
+    // Typing the text out counts towards the duration, so long lines don't get cut short
+    fn length(&self) -> f32 {
+        self.typing_time() + self.duration
+    }
+
+    fn typing_time(&self) -> f32 {
+        self.text.chars().count() as f32 / LETTERS_PER_SECOND
+    }
+
+    fn typed_text(&self, elapsed: f32) -> String {
+        let letters = (elapsed * LETTERS_PER_SECOND) as usize;
+        self.text.chars().take(letters).collect()
+    }
+}
+
+pub struct Cutscenes(HashMap<String, Cutscene>);
+
+pub fn load_cutscenes() -> Cutscenes {
+    let mut cutscenes: HashMap<String, Cutscene> =
+        ron::from_str(CUTSCENES).expect("assets/cutscenes.ron is invalid");
+
+    // A broken mod file shouldn't stop the game from starting, the built in ones still play
+    if let Ok(contents) = read_file(MOD_CUTSCENES_PATH) {
+        match ron::from_str::<HashMap<String, Cutscene>>(&contents) {
+            Ok(added) => cutscenes.extend(added),
+            Err(err) => warn!("Could not read {}: {}", MOD_CUTSCENES_PATH, err),
+        }
+    }
+
+    Cutscenes(cutscenes)
+}
+
+// Set before switching to GameState::Cutscene, which goes to the given state once the cutscene is over
+#[derive(Default)]
+pub struct NextCutscene(Option<(String, GameState)>);
+
+impl NextCutscene {
+    pub fn play(&mut self, name: &str, then: GameState) {
+        self.0 = Some((name.to_string(), then));
+    }
+}
+
+// The cutscene on screen, only exists in GameState::Cutscene
+pub struct PlayingCutscene {
+    name: String,
+    cutscene: Cutscene,
+    then: GameState,
+    shot: usize,
+    elapsed: f32,
+}
+
+#[derive(Component)]
+pub struct CutsceneText;
+
+// Which sprite of which shot it is, despawned when the next shot starts
+#[derive(Component)]
+pub struct CutsceneSpriteIndex {
+    shot: usize,
+    sprite: usize,
+}
+
+#[derive(Component)]
+pub struct SkipButton;
+
+pub fn setup_cutscene(
+    mut commands: Commands,
+    mut next: ResMut<NextCutscene>,
+    cutscenes: Res<Cutscenes>,
+    assets: Res<GameAssets>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    let (name, then) = match next.0.take() {
+        Some(next) => next,
+        None => {
+            state.overwrite_set(GameState::Menu)?;
+            return Ok(());
+        }
+    };
+
+    // Mods can leave out any of them, the game carries on as if it had played
+    let cutscene = match cutscenes.0.get(&name) {
+        Some(cutscene) if !cutscene.shots.is_empty() => cutscene.clone(),
+        _ => {
+            state.overwrite_set(then)?;
+            return Ok(());
+        }
+    };
+
+    let mut text = BitmapTextBundle::at(ScreenAnchor::Bottom.offset(-300.0, 60.0))
+        .with_max_width(TEXT_WIDTH / TEXT_SCALE);
+    text.transform.scale = Vec3::new(TEXT_SCALE, TEXT_SCALE, 1.0);
+    commands
+        .spawn_bundle(text)
+        .insert(CutsceneText)
+        .insert(RemoveAfterState);
+
+    let mut label = BitmapTextBundle::new(SKIP_SIZE.x / -2.0 / SKIP_SCALE + 25.0, 0.0)
+        .with_text("Skip".to_string());
+    label.transform.scale = Vec3::splat(0.35 / SKIP_SCALE);
+    commands
+        .spawn_bundle(SpriteBundle {
+            texture: assets.menu_button.clone(),
+            transform: Transform::from_translation(skip_position().extend(Layer::Ui.z()))
+                .with_scale(Vec3::splat(SKIP_SCALE)),
+            ..default()
+        })
+        .insert(SkipButton)
+        .insert(RemoveAfterState)
+        .with_children(|parent| {
+            parent.spawn_bundle(label);
+        });
+
+    commands.insert_resource(PlayingCutscene {
+        name,
+        cutscene,
+        then,
+        shot: 0,
+        elapsed: 0.0,
+    });
+
+    Ok(())
+}
+
+// Confirm finishes typing the text and then moves to the next shot, Escape or the skip button ends it
+pub fn cutscene_system(
+    mut commands: Commands,
+    playing: Option<ResMut<PlayingCutscene>>,
+    mut state: ResMut<State<GameState>>,
+    mut save: ResMut<SaveData>,
+    mut input: ActionInput,
+    windows: Res<Windows>,
+    viewport: Res<ViewportSize>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    time: Res<Time>,
+) -> GameResult {
+    let mut playing = match playing {
+        Some(playing) => playing,
+        None => return Ok(()),
+    };
+
+    let bindings = &save.settings.bindings;
+    let clicked = input.mouse.just_pressed(MouseButton::Left);
+    let on_skip =
+        cursor_world_position(&windows, &viewport, &camera_query).map_or(false, |cursor| {
+            (cursor - skip_position())
+                .abs()
+                .cmple(SKIP_SIZE / 2.0)
+                .all()
+        });
+    let skip = input.keys.just_pressed(KeyCode::Escape) || (clicked && on_skip);
+    let advance = input.just_pressed(bindings, Action::Confirm)
+        || (clicked && !on_skip)
+        || input.tap().is_some();
+
+    // Whatever ends the cutscene shouldn't also flap or press a button on the next screen
+    input.consume(bindings, Action::Confirm);
+    input.mouse.reset(MouseButton::Left);
+    input.consume_tap();
+
+    let shot = &playing.cutscene.shots[playing.shot];
+    let (typing_time, length) = (shot.typing_time(), shot.length());
+
+    if advance && playing.elapsed < typing_time {
+        playing.elapsed = typing_time;
+    } else if advance || playing.elapsed >= length {
+        playing.shot += 1;
+        playing.elapsed = 0.0;
+    } else {
+        playing.elapsed += time.delta_seconds();
+    }
+
+    if skip || playing.shot >= playing.cutscene.shots.len() {
+        save.seen_cutscenes.insert(playing.name.clone());
+        commands.remove_resource::<PlayingCutscene>();
+        state.set(playing.then.clone())?;
+    }
+
+    Ok(())
+}
+
+pub fn cutscene_shot_system(
+    mut commands: Commands,
+    playing: Option<Res<PlayingCutscene>>,
+    asset_server: Res<AssetServer>,
+    mut sprites: Query<(Entity, &CutsceneSpriteIndex, &mut Transform)>,
+    mut texts: Query<&mut BitmapText, With<CutsceneText>>,
+) {
+    let playing = match playing {
+        Some(playing) => playing,
+        None => return,
+    };
+    let shot = match playing.cutscene.shots.get(playing.shot) {
+        Some(shot) => shot,
+        None => return,
+    };
+
+    let progress = (playing.elapsed / shot.length()).min(1.0);
+    let mut spawned = false;
+
+    for (entity, index, mut transform) in sprites.iter_mut() {
+        if index.shot != playing.shot {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        spawned = true;
+        if let Some(sprite) = shot.sprites.get(index.sprite) {
+            let position = sprite.position(progress);
+            transform.translation = position.extend(transform.translation.z);
+        }
+    }
+
+    if !spawned {
+        for (i, sprite) in shot.sprites.iter().enumerate() {
+            let z = Layer::Ui.z() - 0.1 + i as f32 * 0.001;
+            let position = sprite.position(0.0).extend(z);
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    texture: asset_server.load(&sprite.image),
+                    transform: Transform::from_translation(position).with_scale(Vec3::new(
+                        sprite.scale,
+                        sprite.scale,
+                        1.0,
+                    )),
+                    ..default()
+                })
+                .insert(CutsceneSpriteIndex {
+                    shot: playing.shot,
+                    sprite: i,
+                })
+                .insert(RemoveAfterState);
+        }
+    }
+
+    let text = shot.typed_text(playing.elapsed);
+    for mut cutscene_text in texts.iter_mut() {
+        if cutscene_text.text != text {
+            cutscene_text.text = text.clone();
+        }
+    }
+}
+
+fn skip_position() -> Vec2 {
+    ScreenAnchor::Bottom.offset(340.0, 30.0)
+}
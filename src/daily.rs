@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{GameMode, RecordKey, RunMode, SaveData};
+
+pub const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+// Best score of the latest daily challenge, from saves before records were kept per mode
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyBest {
+    pub day: u64,
+    pub score: u64,
+}
+
+// Days since the unix epoch, in UTC so everyone switches to the next course at the same time
+pub fn today() -> u64 {
+    unix_seconds() / SECONDS_PER_DAY
+}
+
+pub struct DailyMode;
+
+impl GameMode for DailyMode {
+    fn run_mode(&self) -> RunMode {
+        RunMode::Daily
+    }
+
+    fn select_key(&self) -> Option<KeyCode> {
+        Some(KeyCode::D)
+    }
+
+    fn label(&self, save: &SaveData) -> String {
+        format!("Daily challenge best {}", self.best_score(save))
+    }
+
+    fn course_seed(&self, _seed: Option<u64>) -> Option<u64> {
+        Some(today())
+    }
+
+    fn record_key(&self) -> Option<RecordKey> {
+        Some(RecordKey::new(RunMode::Daily, vec![], today()))
+    }
+
+    fn shares_course(&self) -> bool {
+        true
+    }
+
+    fn uses_difficulty(&self) -> bool {
+        false
+    }
+
+    fn allows_continues(&self) -> bool {
+        false
+    }
+}
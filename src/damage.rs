@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    GameAssets, GameRng, GameState, Lifetime, Particle, SaveData, SimulationTime, SkinTint,
+};
+
+// Smoke puffs per second for each damage tier
+const SMOKE_RATES: [f32; 3] = [0.0, 3.0, 12.0];
+const SMOKE_LIFETIME: f32 = 0.9;
+
+// How beaten up a plane looks. Only planes that lose a life and fly on ever get damaged, so this only
+// shows up in modes with more than one life.
+#[derive(Component, Clone, Default)]
+pub struct Damage {
+    hits: u32,
+    pending_smoke: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DamageTier {
+    Clean,
+    Scratched,
+    Smoking,
+}
+
+impl Damage {
+    pub fn hit(&mut self) {
+        self.hits += 1;
+    }
+
+    // Back to a clean plane
+    pub fn repair(&mut self) {
+        self.hits = 0;
+    }
+
+    // One tier less, for repair kits
+    pub fn mend(&mut self) {
+        self.hits = self.hits.saturating_sub(1);
+    }
+
+    pub fn tier(&self) -> DamageTier {
+        match self.hits {
+            0 => DamageTier::Clean,
+            1 => DamageTier::Scratched,
+            _ => DamageTier::Smoking,
+        }
+    }
+}
+
+impl DamageTier {
+    // There are no damaged plane sprites, so the plane is tinted darker instead
+    fn tint(&self) -> Color {
+        match self {
+            DamageTier::Clean => Color::WHITE,
+            DamageTier::Scratched => Color::rgb(0.85, 0.8, 0.75),
+            DamageTier::Smoking => Color::rgb(0.6, 0.55, 0.5),
+        }
+    }
+
+    fn smoke_rate(&self) -> f32 {
+        SMOKE_RATES[*self as usize]
+    }
+}
+
+// Every run starts with a clean plane
+pub fn reset_damage(mut query: Query<&mut Damage>) {
+    for mut damage in query.iter_mut() {
+        damage.repair();
+    }
+}
+
+pub fn damage_tint_system(mut query: Query<(&Damage, Option<&SkinTint>, &mut Sprite)>) {
+    for (damage, skin_tint, mut sprite) in query.iter_mut() {
+        let mut tint = damage.tier().tint();
+        if let Some(skin_tint) = skin_tint {
+            tint = tint * Vec4::from(skin_tint.0.as_rgba_f32());
+        }
+        if sprite.color != tint {
+            sprite.color = tint;
+        }
+    }
+}
+
+pub fn smoke_trail_system(
+    mut commands: Commands,
+    mut query: Query<(&mut Damage, &GlobalTransform)>,
+    mut rng: ResMut<GameRng>,
+    assets: Res<GameAssets>,
+    save: Res<SaveData>,
+    state: Res<State<GameState>>,
+    simulation_time: Res<SimulationTime>,
+) {
+    if state.current() != &GameState::Playing {
+        return;
+    }
+
+    let intensity = save.settings.effects_intensity.factor();
+
+    for (mut damage, transform) in query.iter_mut() {
+        let rate = damage.tier().smoke_rate() * intensity;
+        if rate == 0.0 {
+            continue;
+        }
+
+        damage.pending_smoke += rate * simulation_time.delta_seconds();
+        let position = transform.translation();
+
+        while damage.pending_smoke >= 1.0 {
+            damage.pending_smoke -= 1.0;
+
+            let velocity = Vec2::new(
+                rng.cosmetic.gen_range(-120.0..-80.0),
+                rng.cosmetic.gen_range(10.0..40.0),
+            );
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(0.3, 0.3, 0.3, 0.8),
+                        ..default()
+                    },
+                    texture: assets.puff.clone(),
+                    // Behind the plane, out of its tail
+                    transform: Transform::from_xyz(
+                        position.x - 20.0,
+                        position.y,
+                        position.z - 0.05,
+                    )
+                    .with_scale(Vec3::new(0.4, 0.4, 1.0)),
+                    ..default()
+                })
+                .insert(Particle::new(velocity, 0.0))
+                .insert(Lifetime::from_seconds(SMOKE_LIFETIME));
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{GameAssets, GameRng, GameState, Hazard, Layer, Rock, SaveData, HEIGHT, WIDTH};
+
+// Seconds of play for a whole day, from noon through the night and back
+const DAY_LENGTH: f32 = 120.0;
+
+// Where in the day each tint is reached, with the stars' opacity at that point. The tint is blended linearly in
+// between, so the sky darkens over a few seconds instead of switching.
+const KEYFRAMES: &[(f32, Vec3, f32)] = &[
+    (0.0, Vec3::new(1.0, 1.0, 1.0), 0.0),
+    (0.35, Vec3::new(1.0, 1.0, 1.0), 0.0),
+    (0.45, Vec3::new(1.0, 0.75, 0.6), 0.0),
+    (0.55, Vec3::new(0.35, 0.4, 0.65), 1.0),
+    (0.8, Vec3::new(0.35, 0.4, 0.65), 1.0),
+    (0.9, Vec3::new(1.0, 0.8, 0.75), 0.0),
+    (1.0, Vec3::new(1.0, 1.0, 1.0), 0.0),
+];
+
+// How quickly the tint follows the time of day. Slow enough that going back to noon on the menu is
+// a fade rather than a jump.
+const TINT_SMOOTHING: f32 = 1.5;
+
+const STAR_COUNT: usize = 30;
+
+// With high contrast on, these take the place of the time of day. Obstacles stay bright and warm while everything
+// behind them goes dark and cool, yellow against blue stays apart with every common kind of color blindness.
+const OBSTACLE_TINT: Vec3 = Vec3::new(1.0, 0.9, 0.45);
+const BACKGROUND_TINT: Vec3 = Vec3::new(0.35, 0.4, 0.55);
+
+// Cosmetic only, it advances with the frame time and never touches the simulation
+#[derive(Default)]
+pub struct TimeOfDay {
+    time: f32,
+    tint: Option<Vec3>,
+    stars: f32,
+}
+
+// The color of a sprite before the time of day tints it. Whatever wants to recolor a tinted sprite changes this
+// instead of its Sprite.
+#[derive(Component, Clone, Copy)]
+pub struct BaseColor(pub Color);
+
+#[derive(Component)]
+pub struct Star;
+
+pub fn setup_stars(mut commands: Commands, mut rng: ResMut<GameRng>, assets: Res<GameAssets>) {
+    for _ in 0..STAR_COUNT {
+        let x = rng.cosmetic.gen_range(WIDTH / -2.0..WIDTH / 2.0);
+        let y = rng.cosmetic.gen_range(-40.0..HEIGHT / 2.0);
+        let scale = rng.cosmetic.gen_range(0.1..0.25);
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+                    ..default()
+                },
+                texture: assets.star_silver.clone(),
+                transform: Transform::from_xyz(x, y, Layer::Stars.z())
+                    .with_scale(Vec3::new(scale, scale, 1.0)),
+                ..default()
+            })
+            .insert(Star);
+    }
+}
+
+// The day only moves on while playing. Back on the menu it heads for noon again, which the smoothing
+// turns into a fade.
+pub fn time_of_day_system(
+    mut time_of_day: ResMut<TimeOfDay>,
+    state: Res<State<GameState>>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+
+    match state.current() {
+        GameState::Playing => time_of_day.time = (time_of_day.time + dt) % DAY_LENGTH,
+        GameState::Menu | GameState::Start => time_of_day.time = 0.0,
+        _ => return,
+    }
+
+    let (target, stars) = keyframe_at(time_of_day.time / DAY_LENGTH);
+    let blend = 1.0 - (-TINT_SMOOTHING * dt).exp();
+
+    let tint = time_of_day.tint.unwrap_or(target);
+    time_of_day.tint = Some(tint.lerp(target, blend));
+    time_of_day.stars += (stars - time_of_day.stars) * blend;
+}
+
+pub fn day_night_tint_system(
+    time_of_day: Res<TimeOfDay>,
+    save: Res<SaveData>,
+    mut sprites: Query<(&BaseColor, &mut Sprite, Option<&Rock>, Option<&Hazard>), Without<Star>>,
+    mut stars_query: Query<&mut Sprite, With<Star>>,
+) {
+    let time_tint = time_of_day.tint.unwrap_or(Vec3::ONE);
+
+    for (base, mut sprite, rock, hazard) in sprites.iter_mut() {
+        let tint = if !save.settings.high_contrast {
+            time_tint
+        } else if rock.is_some() || hazard.is_some() {
+            OBSTACLE_TINT
+        } else {
+            BACKGROUND_TINT
+        };
+
+        // The alpha belongs to whatever is fading the sprite
+        let color = Color::rgba(
+            base.0.r() * tint.x,
+            base.0.g() * tint.y,
+            base.0.b() * tint.z,
+            sprite.color.a(),
+        );
+
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+
+    // Nothing but clutter behind the course with high contrast on
+    let stars = if save.settings.high_contrast {
+        0.0
+    } else {
+        time_of_day.stars
+    };
+
+    for mut sprite in stars_query.iter_mut() {
+        if sprite.color.a() != stars {
+            sprite.color.set_a(stars);
+        }
+    }
+}
+
+fn keyframe_at(phase: f32) -> (Vec3, f32) {
+    for pair in KEYFRAMES.windows(2) {
+        let (start, start_tint, start_stars) = pair[0];
+        let (end, end_tint, end_stars) = pair[1];
+
+        if phase <= end {
+            let t = ((phase - start) / (end - start)).clamp(0.0, 1.0);
+            return (
+                start_tint.lerp(end_tint, t),
+                start_stars + (end_stars - start_stars) * t,
+            );
+        }
+    }
+
+    (Vec3::ONE, 0.0)
+}
@@ -0,0 +1,109 @@
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+use crate::{CollisionStats, GameAssets, GameSpeed, HudVisibility, RockTimer, TextureMemory};
+
+// Runtime developer options, available in release builds too
+#[derive(Default)]
+pub struct DebugSettings {
+    // Collider outlines and the stats text, toggled with F3
+    pub show_overlay: bool,
+}
+
+// Drawn on top of everything that has a collision shape
+#[derive(Component)]
+pub struct CollisionOutline;
+
+#[derive(Component)]
+pub struct DebugText;
+
+pub fn setup_debug_overlay(mut commands: Commands, assets: Res<GameAssets>) {
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: assets.ui_font.clone(),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(5.0),
+                    left: Val::Px(5.0),
+                    ..default()
+                },
+                display: Display::None,
+                ..default()
+            }),
+        )
+        .insert(DebugText);
+}
+
+pub fn debug_toggle_system(
+    keys: Res<Input<KeyCode>>,
+    mut debug: ResMut<DebugSettings>,
+    hud: Res<HudVisibility>,
+    mut outlines: Query<&mut Visibility, With<CollisionOutline>>,
+    mut text_query: Query<&mut Style, With<DebugText>>,
+) {
+    if keys.just_pressed(KeyCode::F3) {
+        debug.show_overlay = !debug.show_overlay;
+    }
+
+    // A clean HUD hides the overlay without turning it off
+    let show = debug.show_overlay && hud.shows_extras();
+
+    if debug.is_changed() || hud.is_changed() {
+        for mut style in text_query.iter_mut() {
+            style.display = if show { Display::Flex } else { Display::None };
+        }
+    }
+
+    // Also catches outlines of rocks that were just spawned
+    for mut visibility in outlines.iter_mut() {
+        if visibility.is_visible != show {
+            visibility.is_visible = show;
+        }
+    }
+}
+
+pub fn debug_text_system(
+    debug: Res<DebugSettings>,
+    diagnostics: Res<Diagnostics>,
+    game_speed: Res<GameSpeed>,
+    rock_timer: Res<RockTimer>,
+    texture_memory: Res<TextureMemory>,
+    collisions: Res<CollisionStats>,
+    entities: Query<Entity>,
+    mut text_query: Query<&mut Text, With<DebugText>>,
+) {
+    if !debug.show_overlay {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.average())
+        .unwrap_or(0.0);
+
+    let rock_timer = &rock_timer.0;
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!(
+            "FPS: {:.0}\nEntities: {}\nGame speed: {:.3}\nNext rocks: {:.2}s / {:.2}s\nTextures: {} (~{:.1} MB estimated / {:.0} MB)\nCollision checks: {} / {}",
+            fps,
+            entities.iter().count(),
+            game_speed.0,
+            rock_timer.elapsed_secs(),
+            rock_timer.duration().as_secs_f32(),
+            texture_memory.count,
+            texture_memory.megabytes(),
+            texture_memory.budget_megabytes(),
+            collisions.checked,
+            collisions.pairs,
+        );
+    }
+}
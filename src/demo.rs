@@ -0,0 +1,126 @@
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+
+use crate::{
+    BitmapTextBundle, Controlled, FlapInput, GameResult, GameState, Player, RemoveAfterState, Rock,
+    ScreenAnchor,
+};
+
+// Seconds without input on the menu before the demo starts
+const DEMO_IDLE_TIME: f32 = 10.0;
+
+// How far below the middle of the gap the autopilot lets the plane drop before flapping
+const AUTOPILOT_SLACK: f32 = 20.0;
+
+// Rocks whose middle is this far behind the plane are still in the way
+const AUTOPILOT_LOOK_BEHIND: f32 = 60.0;
+
+#[derive(Default)]
+pub struct DemoMode {
+    pub active: bool,
+}
+
+#[derive(Component)]
+pub struct DemoLabel;
+
+pub fn demo_active(demo: Res<DemoMode>) -> ShouldRun {
+    if demo.active {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+// For everything that would otherwise count the demo as one of the player's runs
+pub fn demo_inactive(demo: Res<DemoMode>) -> ShouldRun {
+    if demo.active {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
+
+pub fn demo_idle_system(
+    mut idle: Local<f32>,
+    mut demo: ResMut<DemoMode>,
+    mut state: ResMut<State<GameState>>,
+    time: Res<Time>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<MouseButton>>,
+) -> GameResult {
+    if keys.get_just_pressed().next().is_some() || buttons.get_just_pressed().next().is_some() {
+        *idle = 0.0;
+        return Ok(());
+    }
+
+    *idle += time.delta_seconds();
+    if *idle < DEMO_IDLE_TIME {
+        return Ok(());
+    }
+
+    *idle = 0.0;
+    demo.active = true;
+    state.set(GameState::Playing)?;
+
+    Ok(())
+}
+
+pub fn setup_demo_label(mut commands: Commands) {
+    commands
+        .spawn_bundle(
+            BitmapTextBundle::at(ScreenAnchor::TopLeft.offset(15.0, -75.0))
+                .with_text("DEMO".to_string()),
+        )
+        .insert(DemoLabel)
+        .insert(RemoveAfterState);
+}
+
+// Any click or key ends the demo
+pub fn demo_exit_system(
+    mut buttons: ResMut<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    if buttons.get_just_pressed().next().is_none() && keys.get_just_pressed().next().is_none() {
+        return Ok(());
+    }
+
+    // Overwritten because crashing in the same frame also ends the demo
+    state.overwrite_set(GameState::Menu)?;
+    buttons.reset(MouseButton::Left);
+
+    Ok(())
+}
+
+// Runs when the menu comes back, so cleaning up after the demo can still tell it was one
+pub fn end_demo(mut demo: ResMut<DemoMode>) {
+    demo.active = false;
+}
+
+// Aims for the middle of the next gap, flapping whenever the plane is falling below it
+pub fn autopilot_system(
+    demo: Res<DemoMode>,
+    mut player_query: Query<(&Player, &Transform, &mut FlapInput), With<Controlled>>,
+    rock_query: Query<(&Rock, &Transform)>,
+) {
+    if !demo.active {
+        return;
+    }
+
+    let (player, player_transform, mut input) = match player_query.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+    let player_x = player_transform.translation.x;
+
+    let target = rock_query
+        .iter()
+        .filter(|(_, transform)| transform.translation.x > player_x - AUTOPILOT_LOOK_BEHIND)
+        .min_by(|(_, a), (_, b)| a.translation.x.total_cmp(&b.translation.x))
+        .map_or(0.0, |(rock, _)| rock.gap_center);
+
+    let flap = player.velocity < 0.0 && player_transform.translation.y < target - AUTOPILOT_SLACK;
+
+    input.just_pressed |= flap && !input.pressed;
+    input.pressed = flap;
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{GameConfig, GameModes, GameSpeed, RunMode, SaveData, ShowToast};
+
+// Picked on the menu with F and kept in the settings of the profile. Records carry it, so easy scores never
+// mix with hard ones.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+}
+
+// Tuning of a single difficulty, see assets/config.ron. Every value is a factor, the default changes nothing.
+#[derive(Clone, Copy, Deserialize)]
+pub struct DifficultyPreset {
+    pub gravity: f32,
+    pub rock_scale: f32,
+    pub spawn_delay: f32,
+    pub base_speed: f32,
+}
+
+impl Default for DifficultyPreset {
+    fn default() -> Self {
+        Self {
+            gravity: 1.0,
+            rock_scale: 1.0,
+            spawn_delay: 1.0,
+            base_speed: 1.0,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct DifficultyPresets {
+    pub easy: DifficultyPreset,
+    pub normal: DifficultyPreset,
+    pub hard: DifficultyPreset,
+}
+
+impl DifficultyPresets {
+    pub fn get(&self, difficulty: Difficulty) -> DifficultyPreset {
+        match difficulty {
+            Difficulty::Easy => self.easy,
+            Difficulty::Normal => self.normal,
+            Difficulty::Hard => self.hard,
+        }
+    }
+}
+
+// The difficulty of the current run, set when it starts
+#[derive(Default)]
+pub struct ActiveDifficulty {
+    pub difficulty: Difficulty,
+    pub preset: DifficultyPreset,
+}
+
+// Runs before setup_tutorial, which slows the first gates down from wherever this puts the speed
+pub fn setup_difficulty(
+    mut active: ResMut<ActiveDifficulty>,
+    mut game_speed: ResMut<GameSpeed>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    save: Res<SaveData>,
+    config: Res<GameConfig>,
+) {
+    let difficulty = modes.get(*mode).difficulty(&save);
+    *active = ActiveDifficulty {
+        difficulty,
+        preset: config.difficulty.get(difficulty),
+    };
+    game_speed.0 = active.preset.base_speed;
+}
+
+pub fn difficulty_select_system(
+    keys: Res<Input<KeyCode>>,
+    mut save: ResMut<SaveData>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if keys.just_pressed(KeyCode::F) {
+        save.settings.difficulty = save.settings.difficulty.next();
+        toasts.send(ShowToast(format!(
+            "{} difficulty",
+            save.settings.difficulty.name()
+        )));
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+// How a value gets from where it was to where it's going, t runs from 0 to 1
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ease {
+    Linear,
+    QuadIn,
+    QuadOut,
+    CubicOut,
+    SmoothStep,
+}
+
+impl Ease {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::SmoothStep => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+// Anything that can be eased between two values
+pub trait Interpolate: Copy {
+    fn interpolate(self, to: Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Interpolate for Vec2 {
+    fn interpolate(self, to: Self, t: f32) -> Self {
+        self.lerp(to, t)
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn interpolate(self, to: Self, t: f32) -> Self {
+        self.lerp(to, t)
+    }
+}
+
+// Takes the short way round
+impl Interpolate for Quat {
+    fn interpolate(self, to: Self, t: f32) -> Self {
+        self.slerp(to, t)
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(self, to: Self, t: f32) -> Self {
+        let from = Vec4::from(self.as_rgba_f32());
+        let to = Vec4::from(to.as_rgba_f32());
+        Color::from(from.lerp(to, t))
+    }
+}
+
+// A value that eases towards whatever it was last set to. Setting it again halfway starts from where it is,
+// so targets can change every frame without jumps.
+#[derive(Clone, Copy, Debug)]
+pub struct Eased<T: Interpolate> {
+    from: T,
+    to: T,
+    elapsed: f32,
+    duration: f32,
+    ease: Ease,
+}
+
+impl<T: Interpolate> Eased<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            from: value,
+            to: value,
+            elapsed: 0.0,
+            duration: 0.0,
+            ease: Ease::Linear,
+        }
+    }
+
+    pub fn set(&mut self, to: T, seconds: f32, ease: Ease) {
+        self.from = self.value();
+        self.to = to;
+        self.elapsed = 0.0;
+        self.duration = seconds;
+        self.ease = ease;
+    }
+
+    pub fn tick(&mut self, seconds: f32) -> T {
+        self.elapsed = (self.elapsed + seconds).min(self.duration);
+        self.value()
+    }
+
+    pub fn value(&self) -> T {
+        if self.duration <= 0.0 {
+            return self.to;
+        }
+
+        let t = self.ease.apply(self.elapsed / self.duration);
+        self.from.interpolate(self.to, t)
+    }
+
+    pub fn target(&self) -> T {
+        self.to
+    }
+}
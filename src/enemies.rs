@@ -0,0 +1,180 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use rand::prelude::*;
+
+use crate::{
+    add_collision_outline, scaled_polygon, spawn_floating_text, DespawnOffscreen, GameAssets,
+    GameError, GameModes, GameResult, GameRng, Hazard, HazardKind, HazardSpawn, HorizontalVelocity,
+    Interpolated, Layer, PlaneHulls, PlaneSprite, Player, RunMode, Score, ScreenAnchor, SpawnHooks,
+    Telegraph, TelegraphStyle, PHYSICS_STEP, SCORE_POPUP_SCALE,
+};
+
+// Enemies only show up once the player can handle rocks
+const ENEMY_MIN_SCORE: u64 = 25;
+const ENEMY_SCORE: u64 = 2;
+
+// Faster than the rocks, since it flies towards the player instead of just scrolling past
+const ENEMY_VELOCITY: f32 = 400.0;
+const ENEMY_MARGIN: f32 = 60.0;
+
+const ENEMY_BOB_AMPLITUDE: f32 = 10.0;
+const ENEMY_BOB_FREQUENCY: f32 = 1.5;
+
+// Seconds an enemy waits just off-screen while its marker blinks
+const ENEMY_WARNING: f32 = 1.0;
+
+pub struct EnemyTimer(pub Timer);
+
+impl Default for EnemyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.0, false))
+    }
+}
+
+// Ticks the timer of something that keeps showing up on the course by a physics step. Once it finishes it's set
+// to the next delay and true means it's time to spawn. A fresh timer finishes straight away, that only starts the
+// countdown, so nothing shows up the moment its threshold is reached.
+pub fn tick_spawn_timer(timer: &mut Timer, next_delay: impl FnOnce() -> f32) -> bool {
+    if !timer.tick(Duration::from_secs_f32(PHYSICS_STEP)).finished() {
+        return false;
+    }
+
+    let first = timer.duration().is_zero();
+    timer.set_duration(Duration::from_secs_f32(next_delay()));
+    timer.reset();
+
+    !first
+}
+
+#[derive(Component, Clone)]
+pub struct Enemy {
+    has_scored: bool,
+    base_y: f32,
+    time: f32,
+}
+
+pub fn enemy_spawn_system(
+    mut commands: Commands,
+    mut timer: ResMut<EnemyTimer>,
+    score: Res<Score>,
+    mut rng: ResMut<GameRng>,
+    assets: Res<GameAssets>,
+    hulls: Res<PlaneHulls>,
+    hooks: Res<SpawnHooks>,
+) -> GameResult {
+    if score.0 < ENEMY_MIN_SCORE {
+        return Ok(());
+    }
+
+    // Drawn along with the delay, so seeded courses stay the same
+    let mut y = 0.0;
+    let spawn = tick_spawn_timer(&mut timer.0, || {
+        let (next_y, delay) = next_enemy(&mut rng.course);
+        y = next_y;
+        delay
+    });
+    if !spawn {
+        return Ok(());
+    }
+
+    let position = ScreenAnchor::Right.offset(60.0, y);
+    let mut spawn = HazardSpawn::new(HazardKind::Enemy, position, score.0);
+    if !hooks.hazard(&mut spawn) {
+        return Ok(());
+    }
+    let Vec2 { x, y } = spawn.position;
+
+    // Mirrored, since enemies fly the other way. Sized with custom_size so the outline doesn't need scaling.
+    let hull: Vec<(f32, f32)> = hulls
+        .get(PlaneSprite::Green)
+        .iter()
+        .map(|(x, y)| (-x / 2.0, y / 2.0))
+        .collect();
+
+    let collider = scaled_polygon(&hull, 1.0).ok_or(GameError::InvalidPolygon)?;
+
+    let mut entity = commands.spawn_bundle(SpriteBundle {
+        sprite: Sprite {
+            flip_x: true,
+            custom_size: Some(Vec2::new(44.0, 36.5)),
+            ..default()
+        },
+        texture: assets.enemy.clone(),
+        transform: Transform::from_xyz(x, y, Layer::Course.z()),
+        ..default()
+    });
+
+    // The collider comes once the telegraph is over, so an enemy waiting off-screen can't be hit or shot
+    add_collision_outline(&mut entity, &hull, Vec3::ONE);
+
+    entity
+        .insert(HorizontalVelocity(ENEMY_VELOCITY))
+        .insert(DespawnOffscreen {
+            margin: ENEMY_MARGIN,
+        })
+        .insert(Interpolated::default())
+        .insert(Hazard)
+        .insert(Enemy {
+            has_scored: false,
+            base_y: y,
+            time: 0.0,
+        })
+        .insert(Telegraph::new(ENEMY_WARNING, TelegraphStyle::Edge).arming(collider));
+
+    spawn.apply_attached(&mut entity);
+
+    Ok(())
+}
+
+// The altitude of the next enemy and the delay before the one after it.
+// Drawn from the course rng like rocks, so seeded courses stay the same.
+pub fn next_enemy<R: Rng>(rng: &mut R) -> (f32, f32) {
+    let y = rng.gen_range(-120.0..120.0);
+    let delay = rng.gen_range(4.0..8.0);
+
+    (y, delay)
+}
+
+pub fn enemy_system(mut query: Query<(&mut Transform, &mut Enemy)>) {
+    for (mut transform, mut enemy) in query.iter_mut() {
+        enemy.time += PHYSICS_STEP;
+        transform.translation.y =
+            enemy.base_y + ENEMY_BOB_AMPLITUDE * (enemy.time * ENEMY_BOB_FREQUENCY * TAU).sin();
+    }
+}
+
+// Worth more than a gate, since dodging something that moves is harder
+pub fn enemy_score_system(
+    mut commands: Commands,
+    mut query: Query<(&Transform, &mut Enemy)>,
+    player_query: Query<&Transform, With<Player>>,
+    mut score: ResMut<Score>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+) {
+    let only_gates = modes.get(*mode).counts_only_gates();
+
+    let player_translation = match player_query.iter().next() {
+        Some(transform) => transform.translation,
+        None => return,
+    };
+
+    for (transform, mut enemy) in query.iter_mut() {
+        if !enemy.has_scored && transform.translation.x < player_translation.x {
+            enemy.has_scored = true;
+            if only_gates {
+                continue;
+            }
+
+            score.0 += ENEMY_SCORE;
+            spawn_floating_text(
+                &mut commands,
+                player_translation + Vec3::new(0.0, 30.0, 0.0),
+                format!("+{}", ENEMY_SCORE),
+                SCORE_POPUP_SCALE,
+            );
+        }
+    }
+}
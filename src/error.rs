@@ -0,0 +1,91 @@
+use std::fmt;
+use std::io;
+
+use bevy::ecs::schedule::StateError;
+use bevy::prelude::*;
+
+use crate::ShowToast;
+
+pub type GameResult<T = ()> = Result<T, GameError>;
+
+// Failures the game can carry on from. Systems that can fail return a GameResult and are chained into
+// report_error, so nothing panics in the middle of a run.
+#[derive(Debug)]
+pub enum GameError {
+    // Usually a different state that was already asked for in the same frame
+    State(StateError),
+    // Collision shapes are typed in by hand, nothing but this stops them from being concave
+    InvalidPolygon,
+    Io {
+        path: &'static str,
+        source: io::Error,
+    },
+    Unreadable {
+        path: &'static str,
+        source: ron::error::SpannedError,
+    },
+    // Reading it could lose whatever this version doesn't know about
+    NewerVersion {
+        path: &'static str,
+    },
+    Serialize(ron::Error),
+}
+
+impl GameError {
+    // What the player gets told, None for errors that only matter to whoever reads the log
+    pub fn toast(&self) -> Option<String> {
+        match self {
+            GameError::State(_) | GameError::InvalidPolygon => None,
+            GameError::Io { path, source } if source.kind() == io::ErrorKind::NotFound => {
+                Some(format!("No {} found", path))
+            }
+            GameError::Io { path, .. } => Some(format!("Could not access {}", path)),
+            GameError::Unreadable { path, .. } => Some(format!("{} is unreadable", path)),
+            GameError::NewerVersion { path } => Some(format!("{} is from a newer version", path)),
+            GameError::Serialize(_) => Some("Saving failed".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::State(err) => write!(f, "Could not change state: {}", err),
+            GameError::InvalidPolygon => write!(f, "Collision shape is not convex"),
+            GameError::Io { path, source } => write!(f, "Could not access {}: {}", path, source),
+            GameError::Unreadable { path, source } => {
+                write!(f, "Could not read {}: {}", path, source)
+            }
+            GameError::NewerVersion { path } => {
+                write!(f, "{} was written by a newer version of the game", path)
+            }
+            GameError::Serialize(err) => write!(f, "Could not serialize: {}", err),
+        }
+    }
+}
+
+impl From<StateError> for GameError {
+    fn from(err: StateError) -> Self {
+        GameError::State(err)
+    }
+}
+
+impl From<ron::Error> for GameError {
+    fn from(err: ron::Error) -> Self {
+        GameError::Serialize(err)
+    }
+}
+
+// Chained after every system that returns a GameResult
+pub fn report_error(In(result): In<GameResult>, mut toasts: EventWriter<ShowToast>) {
+    let err = match result {
+        Ok(()) => return,
+        Err(err) => err,
+    };
+
+    error!("{}", err);
+
+    if let Some(message) = err.toast() {
+        toasts.send(ShowToast(message));
+    }
+}
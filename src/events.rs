@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{RunMode, RunSpeed};
+
+// Gameplay events, so other modules can react to what happens in a run without
+// having to hook into the core systems themselves
+
+// Sent when the player starts a flap. Carries the vertical velocity from right before the flap.
+pub struct FlapEvent {
+    pub velocity: f32,
+}
+
+// Sent once for every rock pair the player flies past
+pub struct GatePassedEvent {
+    // Vertical distance between the player and the middle of the gap
+    pub offset: f32,
+}
+
+// Sent the first time the player comes close to a rock without touching it
+pub struct NearMissEvent {
+    pub position: Vec3,
+    // Whether the rock's gate was still ahead, only those close calls are worth a point
+    pub before_gate: bool,
+}
+
+// Sent when a plane hits something, whether it has a life to spare or not. Once for every plane that crashed.
+pub struct CrashEvent {
+    pub position: Vec3,
+    pub cause: CrashCause,
+}
+
+// What a plane crashed into
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CrashCause {
+    Rock,
+    // Anything else on the course that crashes a plane, like lasers and enemies
+    Hazard,
+    // Out of sight for too long
+    Bounds,
+}
+
+// Sent when a run's score goes past the best score, so the moment can be kept as a screenshot
+pub struct PhotoFinishEvent;
+
+// Sent when a top score has been given a name and added to the local leaderboard
+pub struct TopScoreEntered {
+    pub mode: RunMode,
+    pub name: String,
+    pub score: u64,
+    pub speed: RunSpeed,
+}
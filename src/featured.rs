@@ -0,0 +1,220 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Deserialize;
+
+use crate::{
+    Action, ActionInput, BitmapText, BitmapTextBundle, GameMode, GameResult, GameRng, GameState,
+    RemoveAfterState, RunMode, SaveData, TextAnchor,
+};
+
+const FEATURED: &str = include_str!("../assets/featured.ron");
+
+const TITLE_Y: f32 = 190.0;
+const RUN_TITLE_Y: f32 = 110.0;
+const DESCRIPTION_Y: f32 = 50.0;
+const DESCRIPTION_SCALE: f32 = 0.35;
+const DESCRIPTION_WIDTH: f32 = 560.0;
+const DETAILS_Y: f32 = -120.0;
+const DETAILS_SCALE: f32 = 0.3;
+
+// See assets/featured.ron
+#[derive(Deserialize, Clone)]
+pub struct FeaturedRun {
+    pub seed: u64,
+    pub title: String,
+    pub description: String,
+}
+
+// The bundled runs, followed by any the online server adds
+pub struct FeaturedRuns {
+    runs: Vec<FeaturedRun>,
+    // The one picked last, which Play flies again while the featured mode is selected
+    picked: usize,
+}
+
+impl FeaturedRuns {
+    // Runs with a seed that's already in the list are left out
+    pub fn extend(&mut self, runs: Vec<FeaturedRun>) {
+        for run in runs {
+            if self.runs.iter().all(|existing| existing.seed != run.seed) {
+                self.runs.push(run);
+            }
+        }
+    }
+
+    fn picked(&self) -> Option<&FeaturedRun> {
+        self.runs.get(self.picked)
+    }
+}
+
+pub fn load_featured_runs() -> FeaturedRuns {
+    FeaturedRuns {
+        runs: ron::from_str(FEATURED).expect("assets/featured.ron is invalid"),
+        picked: 0,
+    }
+}
+
+pub struct FeaturedMode;
+
+impl GameMode for FeaturedMode {
+    fn run_mode(&self) -> RunMode {
+        RunMode::Featured
+    }
+
+    // Picked in the gallery instead of with a key, see featured_system
+    fn label(&self, _save: &SaveData) -> String {
+        "Featured run  Press G to pick another".to_string()
+    }
+
+    // Everyone flies the same course, but it's there to be explored rather than to compete on
+    fn shares_course(&self) -> bool {
+        true
+    }
+}
+
+// Runs after seed_run_rng, which doesn't know which run was picked
+pub fn seed_featured_rng(
+    featured: Res<FeaturedRuns>,
+    mode: Res<RunMode>,
+    mut rng: ResMut<GameRng>,
+) {
+    let seed = match featured.picked() {
+        Some(run) if *mode == RunMode::Featured => run.seed,
+        _ => return,
+    };
+
+    rng.course = StdRng::seed_from_u64(seed);
+}
+
+// G on the menu
+pub fn featured_shortcut_system(
+    keys: Res<Input<KeyCode>>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    if keys.just_pressed(KeyCode::G) {
+        state.set(GameState::Featured)?;
+    }
+
+    Ok(())
+}
+
+// The run on screen, only exists in GameState::Featured
+pub struct FeaturedSelection(usize);
+
+#[derive(Component)]
+pub enum FeaturedText {
+    Title,
+    Description,
+    Details,
+}
+
+pub fn setup_featured(mut commands: Commands, featured: Res<FeaturedRuns>) {
+    commands.insert_resource(FeaturedSelection(featured.picked));
+
+    let title = BitmapTextBundle::new(0.0, TITLE_Y)
+        .with_anchor(TextAnchor::Center)
+        .with_scale(0.6)
+        .with_text("Featured runs".to_string());
+    commands.spawn_bundle(title).insert(RemoveAfterState);
+
+    let texts = [
+        (
+            FeaturedText::Title,
+            BitmapTextBundle::new(0.0, RUN_TITLE_Y).with_scale(0.5),
+        ),
+        (
+            FeaturedText::Description,
+            BitmapTextBundle::new(0.0, DESCRIPTION_Y)
+                .with_scale(DESCRIPTION_SCALE)
+                .with_max_width(DESCRIPTION_WIDTH / DESCRIPTION_SCALE),
+        ),
+        (
+            FeaturedText::Details,
+            BitmapTextBundle::new(0.0, DETAILS_Y).with_scale(DETAILS_SCALE),
+        ),
+    ];
+
+    for (kind, bundle) in texts {
+        commands
+            .spawn_bundle(bundle.with_anchor(TextAnchor::Center))
+            .insert(kind)
+            .insert(RemoveAfterState);
+    }
+}
+
+// Left and right browse, confirming flies the run on screen. Escape goes back without picking one.
+pub fn featured_system(
+    mut selection: ResMut<FeaturedSelection>,
+    mut featured: ResMut<FeaturedRuns>,
+    mut mode: ResMut<RunMode>,
+    mut state: ResMut<State<GameState>>,
+    mut input: ActionInput,
+    save: Res<SaveData>,
+) -> GameResult {
+    if input.keys.just_pressed(KeyCode::Escape) {
+        state.set(GameState::Menu)?;
+        return Ok(());
+    }
+
+    let count = featured.runs.len();
+    if count == 0 {
+        return Ok(());
+    }
+
+    if input.arrow_just_pressed(KeyCode::Left) {
+        selection.0 = (selection.0 + count - 1) % count;
+    }
+    if input.arrow_just_pressed(KeyCode::Right) {
+        selection.0 = (selection.0 + 1) % count;
+    }
+
+    let bindings = &save.settings.bindings;
+    let confirmed = input.just_pressed(bindings, Action::Confirm)
+        || input.mouse.just_pressed(MouseButton::Left)
+        || input.tap().is_some();
+    if !confirmed {
+        return Ok(());
+    }
+
+    // The same press shouldn't also skip the countdown
+    input.consume(bindings, Action::Confirm);
+    input.mouse.reset(MouseButton::Left);
+    input.consume_tap();
+
+    featured.picked = selection.0;
+    *mode = RunMode::Featured;
+    state.set(GameState::Start)?;
+
+    Ok(())
+}
+
+pub fn featured_text_system(
+    selection: Res<FeaturedSelection>,
+    featured: Res<FeaturedRuns>,
+    mut texts: Query<(&FeaturedText, &mut BitmapText)>,
+) {
+    if !selection.is_changed() && !featured.is_changed() {
+        return;
+    }
+
+    let run = featured.runs.get(selection.0);
+    for (kind, mut text) in texts.iter_mut() {
+        let label = match (kind, run) {
+            (FeaturedText::Title, Some(run)) => run.title.clone(),
+            (FeaturedText::Description, Some(run)) => run.description.clone(),
+            (FeaturedText::Details, Some(run)) => format!(
+                "Seed {}  {} of {}",
+                run.seed,
+                selection.0 + 1,
+                featured.runs.len()
+            ),
+            (FeaturedText::Title, None) => "Nothing featured yet".to_string(),
+            _ => String::new(),
+        };
+
+        if text.text != label {
+            text.text = label;
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    BatchedParticle, GameRng, Layer, Lifetime, Particle, SaveData, Score, ScreenAnchor,
+    SimulationTime, SoundCue, WIDTH,
+};
+
+const FIREWORK_MILESTONE: u64 = 25;
+const FIREWORK_ROCKETS: f32 = 5.0;
+const FIREWORK_SPARKS: f32 = 30.0;
+const FIREWORK_GRAVITY: f32 = 300.0;
+const SPARK_GRAVITY: f32 = 120.0;
+
+#[derive(Component)]
+pub struct Firework {
+    fuse: Timer,
+    color: Color,
+}
+
+pub fn firework_launch_system(
+    mut commands: Commands,
+    mut previous_score: Local<u64>,
+    score: Res<Score>,
+    save: Res<SaveData>,
+    mut rng: ResMut<GameRng>,
+) {
+    // The score can go up by several points in one frame, e.g. 24 to 27 with a combo
+    let previous = std::mem::replace(&mut *previous_score, score.0);
+    if previous / FIREWORK_MILESTONE >= score.0 / FIREWORK_MILESTONE {
+        return;
+    }
+
+    let rockets = (FIREWORK_ROCKETS * save.settings.effects_intensity.factor()).round() as u32;
+    let rng = &mut rng.cosmetic;
+
+    for _ in 0..rockets {
+        let x = rng.gen_range(WIDTH / -2.0 + 50.0..WIDTH / 2.0 - 50.0);
+        let position = ScreenAnchor::Bottom.offset(x, 0.0);
+        let velocity = Vec2::new(rng.gen_range(-30.0..30.0), rng.gen_range(350.0..450.0));
+        let color = Color::hsl(rng.gen_range(0.0..360.0), 1.0, 0.6);
+
+        let mut rocket = Particle::new(velocity, FIREWORK_GRAVITY);
+        rocket.fade = false;
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(Vec2::new(3.0, 8.0)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(Layer::Particles.z())),
+                ..default()
+            })
+            .insert(rocket)
+            .insert(Lifetime::from_seconds(2.0))
+            .insert(Firework {
+                fuse: Timer::from_seconds(rng.gen_range(0.6..1.0), false),
+                color,
+            });
+    }
+}
+
+pub fn firework_burst_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Firework, &Transform)>,
+    simulation_time: Res<SimulationTime>,
+    save: Res<SaveData>,
+    mut rng: ResMut<GameRng>,
+    mut cues: EventWriter<SoundCue>,
+) {
+    let sparks = (FIREWORK_SPARKS * save.settings.effects_intensity.factor()).round() as u32;
+    let rng = &mut rng.cosmetic;
+
+    for (entity, mut firework, transform) in query.iter_mut() {
+        if !firework.fuse.tick(simulation_time.delta()).finished() {
+            continue;
+        }
+
+        for _ in 0..sparks {
+            let angle = rng.gen_range(0.0..PI * 2.0);
+            let speed = rng.gen_range(80.0..200.0);
+
+            commands
+                .spawn()
+                .insert(Transform::from_translation(transform.translation))
+                .insert(BatchedParticle {
+                    color: firework.color,
+                    size: Vec2::new(4.0, 4.0),
+                })
+                .insert(Particle::new(
+                    Vec2::new(angle.cos(), angle.sin()) * speed,
+                    SPARK_GRAVITY,
+                ))
+                .insert(Lifetime::from_seconds(rng.gen_range(0.8..1.4)));
+        }
+
+        cues.send(SoundCue("firework"));
+        commands.entity(entity).despawn_recursive();
+    }
+}
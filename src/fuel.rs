@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::{
+    DemoMode, DespawnOffscreen, GameConfig, HorizontalVelocity, HudVisibility, Interpolated, Layer,
+    Player, RemoveAfterState, Rock, ScreenAnchor, SoundCue, PHYSICS_STEP,
+};
+
+const CAN_SIZE: Vec2 = Vec2::new(18.0, 26.0);
+const CAN_MARGIN: f32 = 40.0;
+const CAN_RADIUS: f32 = 30.0;
+
+const GAUGE_SIZE: Vec2 = Vec2::new(160.0, 12.0);
+const GAUGE_Y: f32 = ScreenAnchor::Top.offset(0.0, -75.0).y;
+// Below this fraction of a full tank the gauge turns red
+const GAUGE_LOW: f32 = 0.25;
+
+// Seconds of flying left, shared by both planes in versus. Flapping stops working once it runs out.
+#[derive(Default)]
+pub struct Fuel(pub f32);
+
+impl Fuel {
+    pub fn is_empty(&self) -> bool {
+        self.0 <= 0.0
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct FuelCanSpawner {
+    gates_until_can: u32,
+}
+
+#[derive(Component)]
+pub struct FuelCan;
+
+// The part of the gauge that shrinks as the tank empties
+#[derive(Component)]
+pub struct FuelGauge;
+
+// The dark bar behind it
+#[derive(Component)]
+pub struct FuelGaugeFrame;
+
+pub fn setup_fuel(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut fuel: ResMut<Fuel>,
+    mut spawner: ResMut<FuelCanSpawner>,
+    demo: Res<DemoMode>,
+) {
+    fuel.0 = config.fuel.capacity;
+    spawner.gates_until_can = config.fuel.gates_per_can;
+
+    // The demo never runs out, so it needs no gauge
+    if demo.active {
+        return;
+    }
+
+    let left = -GAUGE_SIZE.x / 2.0;
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.0, 0.0, 0.0, 0.5),
+                custom_size: Some(GAUGE_SIZE),
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            transform: Transform::from_xyz(left, GAUGE_Y, Layer::Overlay.z()),
+            ..default()
+        })
+        .insert(FuelGaugeFrame)
+        .insert(RemoveAfterState);
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(GAUGE_SIZE),
+                anchor: Anchor::CenterLeft,
+                ..default()
+            },
+            transform: Transform::from_xyz(left, GAUGE_Y, Layer::Overlay.z() + 0.1),
+            ..default()
+        })
+        .insert(FuelGauge)
+        .insert(RemoveAfterState);
+}
+
+pub fn fuel_system(mut fuel: ResMut<Fuel>, demo: Res<DemoMode>) {
+    if !demo.active {
+        fuel.0 = (fuel.0 - PHYSICS_STEP).max(0.0);
+    }
+}
+
+// Puts a can in the gap of every few rock pairs. Counting pairs instead of drawing from the rng keeps
+// seeded courses the same.
+pub fn fuel_can_spawn_system(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut spawner: ResMut<FuelCanSpawner>,
+    new_rocks: Query<(&Transform, &HorizontalVelocity, &Rock), Added<Rock>>,
+) {
+    // Both rocks of a pair spawn at the same x
+    let mut gaps: Vec<(f32, f32, f32)> = vec![];
+    for (transform, velocity, rock) in new_rocks.iter() {
+        let x = transform.translation.x;
+        if !gaps.iter().any(|(gap_x, _, _)| *gap_x == x) {
+            gaps.push((x, rock.gap_center, velocity.0));
+        }
+    }
+
+    for (x, y, velocity) in gaps {
+        spawner.gates_until_can = spawner.gates_until_can.saturating_sub(1);
+        if spawner.gates_until_can > 0 {
+            continue;
+        }
+        spawner.gates_until_can = config.fuel.gates_per_can;
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.85, 0.2, 0.15),
+                    custom_size: Some(CAN_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_xyz(x, y, Layer::Course.z()),
+                ..default()
+            })
+            .insert(FuelCan)
+            .insert(HorizontalVelocity(velocity))
+            .insert(DespawnOffscreen { margin: CAN_MARGIN })
+            .insert(Interpolated::default())
+            .insert(RemoveAfterState);
+    }
+}
+
+pub fn fuel_can_system(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    mut fuel: ResMut<Fuel>,
+    cans: Query<(Entity, &Transform), With<FuelCan>>,
+    player_query: Query<&Transform, With<Player>>,
+    mut cues: EventWriter<SoundCue>,
+) {
+    for (can, transform) in cans.iter() {
+        let collected = player_query.iter().any(|player| {
+            player
+                .translation
+                .truncate()
+                .distance(transform.translation.truncate())
+                < CAN_RADIUS
+        });
+
+        if collected {
+            fuel.0 = (fuel.0 + config.fuel.can_refill).min(config.fuel.capacity);
+            commands.entity(can).despawn_recursive();
+            cues.send(SoundCue("pickup"));
+        }
+    }
+}
+
+pub fn fuel_gauge_system(
+    fuel: Res<Fuel>,
+    config: Res<GameConfig>,
+    hud: Res<HudVisibility>,
+    mut query: Query<&mut Sprite, With<FuelGauge>>,
+    mut parts: Query<&mut Visibility, Or<(With<FuelGauge>, With<FuelGaugeFrame>)>>,
+) {
+    if !fuel.is_changed() && !hud.is_changed() {
+        return;
+    }
+
+    for mut visibility in parts.iter_mut() {
+        visibility.is_visible = hud.shows_extras();
+    }
+
+    let fraction = (fuel.0 / config.fuel.capacity).clamp(0.0, 1.0);
+
+    for mut sprite in query.iter_mut() {
+        sprite.custom_size = Some(Vec2::new(GAUGE_SIZE.x * fraction, GAUGE_SIZE.y));
+        sprite.color = if fraction < GAUGE_LOW {
+            Color::rgb(0.9, 0.25, 0.2)
+        } else {
+            Color::rgb(0.3, 0.8, 0.3)
+        };
+    }
+}
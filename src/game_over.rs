@@ -0,0 +1,107 @@
+use bevy::ecs::schedule::IntoSystemDescriptor;
+use bevy::prelude::*;
+
+use crate::{
+    BitmapTextBundle, Ease, GameState, RemoveAfterState, SaveData, ScreenAnchor, Translation, Tween,
+};
+
+const PANEL_TOP: f32 = -40.0;
+const LINE_SPACING: f32 = 55.0;
+
+// Lines come in from the left while the course winds down, one after the other
+const SLIDE_DISTANCE: f32 = 600.0;
+const SLIDE_TIME: f32 = 0.5;
+const SLIDE_STAGGER: f32 = 0.1;
+
+// Part of the panel under the game over text, sent by a section system when the run ends. A feature that wants to
+// show something there registers its own section with add_game_over_section, nothing else has to change.
+#[derive(Clone)]
+pub struct GameOverSection {
+    // Lower is higher up. The score is at 100, sections pick their place relative to that.
+    pub order: i32,
+    pub lines: Vec<String>,
+}
+
+impl GameOverSection {
+    pub fn line(order: i32, text: String) -> Self {
+        Self {
+            order,
+            lines: vec![text],
+        }
+    }
+}
+
+// Every section sent since the run ended, in the order they're shown
+#[derive(Default)]
+pub struct GameOverPanel(Vec<GameOverSection>);
+
+#[derive(Component)]
+pub struct GameOverLine;
+
+pub trait AddGameOverSection {
+    fn add_game_over_section<Params>(
+        &mut self,
+        system: impl IntoSystemDescriptor<Params>,
+    ) -> &mut Self;
+}
+
+impl AddGameOverSection for App {
+    // The system runs when the game over screen shows up and sends GameOverSection events
+    fn add_game_over_section<Params>(
+        &mut self,
+        system: impl IntoSystemDescriptor<Params>,
+    ) -> &mut Self {
+        self.add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(system))
+    }
+}
+
+// Rebuilt whenever a section comes in, so one that takes a while (like a rank from a server) still ends up in its
+// place instead of at the bottom. Only the first lines slide in, later ones show up where they belong.
+pub fn game_over_panel_system(
+    mut commands: Commands,
+    mut sections: EventReader<GameOverSection>,
+    mut panel: ResMut<GameOverPanel>,
+    lines: Query<Entity, With<GameOverLine>>,
+    save: Res<SaveData>,
+) {
+    let count = panel.0.len();
+    panel.0.extend(sections.iter().cloned());
+    if panel.0.len() == count {
+        return;
+    }
+
+    // Stable, so sections with the same order stay in the order they were sent
+    panel.0.sort_by_key(|section| section.order);
+
+    for line in lines.iter() {
+        commands.entity(line).despawn_recursive();
+    }
+
+    let slide = count == 0 && !save.settings.reduced_motion;
+    let texts = panel.0.iter().flat_map(|section| section.lines.iter());
+    for (i, text) in texts.enumerate() {
+        let y = PANEL_TOP - i as f32 * LINE_SPACING;
+        let mut bundle =
+            BitmapTextBundle::at(ScreenAnchor::Left.offset(100.0, y)).with_text(text.clone());
+        let to = bundle.transform.translation;
+        let from = to - Vec3::X * SLIDE_DISTANCE;
+        if slide {
+            bundle.transform.translation = from;
+        }
+
+        let mut line = commands.spawn_bundle(bundle);
+        line.insert(GameOverLine).insert(RemoveAfterState);
+
+        if slide {
+            line.insert(
+                Tween::new(Translation, from, to, SLIDE_TIME)
+                    .with_ease(Ease::CubicOut)
+                    .with_delay(i as f32 * SLIDE_STAGGER),
+            );
+        }
+    }
+}
+
+pub fn reset_game_over_panel(mut panel: ResMut<GameOverPanel>) {
+    panel.0.clear();
+}
@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    z_angle, Controlled, GameAssets, Layer, RemoveAfterState, RunMode, RunStats, SaveData, Score,
+};
+
+// Seconds between recorded positions, playback interpolates in between
+const GHOST_SAMPLE_INTERVAL: f32 = 0.05;
+
+const GHOST_ALPHA: f32 = 0.4;
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct GhostSample {
+    // Seconds since the start of the run
+    pub time: f32,
+    pub y: f32,
+    pub angle: f32,
+}
+
+// The flight path of a run, stored in the save file for the endless personal best
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GhostRun {
+    pub score: u64,
+    pub samples: Vec<GhostSample>,
+}
+
+impl GhostRun {
+    // None once the recorded run has crashed
+    pub fn sample_at(&self, time: f32) -> Option<(f32, Quat)> {
+        let next = self.samples.iter().position(|sample| sample.time >= time)?;
+        let b = self.samples[next];
+        let a = if next > 0 { self.samples[next - 1] } else { b };
+
+        let t = if b.time > a.time {
+            (time - a.time) / (b.time - a.time)
+        } else {
+            1.0
+        };
+
+        // Slerp so the plane takes the short way round when its angle wraps
+        let rotation = Quat::from_rotation_z(a.angle).slerp(Quat::from_rotation_z(b.angle), t);
+        Some((a.y + (b.y - a.y) * t, rotation))
+    }
+}
+
+// The path of the current run, saved as the new ghost if it beats the old one
+#[derive(Default)]
+pub struct GhostRecorder {
+    run: GhostRun,
+    since_sample: f32,
+}
+
+impl GhostRecorder {
+    // The path of the last run, until the next one starts
+    pub fn run(&self) -> &GhostRun {
+        &self.run
+    }
+}
+
+// Only a sprite, it has no Player or collision shape so collision_system never sees it
+#[derive(Component)]
+pub struct Ghost;
+
+pub fn setup_ghost(
+    mut commands: Commands,
+    mut recorder: ResMut<GhostRecorder>,
+    mode: Res<RunMode>,
+    save: Res<SaveData>,
+    assets: Res<GameAssets>,
+) {
+    *recorder = GhostRecorder::default();
+
+    // Other modes have a different course every day or week, so racing an old run makes little sense
+    if *mode != RunMode::Endless || save.ghost.samples.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, GHOST_ALPHA),
+                ..default()
+            },
+            texture: assets.player.clone(),
+            transform: Transform::from_xyz(-200.0, 0.0, Layer::Ghost.z())
+                .with_scale(Vec3::new(0.5, 0.5, 1.0)),
+            ..default()
+        })
+        .insert(Ghost)
+        .insert(RemoveAfterState);
+}
+
+pub fn ghost_record_system(
+    mut recorder: ResMut<GhostRecorder>,
+    run: Res<RunStats>,
+    time: Res<Time>,
+    player_query: Query<&Transform, With<Controlled>>,
+) {
+    recorder.since_sample += time.delta_seconds();
+    if recorder.since_sample < GHOST_SAMPLE_INTERVAL && !recorder.run.samples.is_empty() {
+        return;
+    }
+
+    // Versus planes never record a ghost
+    if let Ok(transform) = player_query.get_single() {
+        recorder.since_sample = 0.0;
+        record_sample(&mut recorder.run, run.time, transform);
+    }
+}
+
+// Follows the recorded path using the same clock as the current run
+pub fn ghost_playback_system(
+    run: Res<RunStats>,
+    save: Res<SaveData>,
+    mut ghost_query: Query<(&mut Transform, &mut Visibility), With<Ghost>>,
+) {
+    for (mut transform, mut visibility) in ghost_query.iter_mut() {
+        match save.ghost.sample_at(run.time) {
+            Some((y, rotation)) => {
+                transform.translation.y = y;
+                transform.rotation = rotation;
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+}
+
+pub fn save_ghost(
+    mut recorder: ResMut<GhostRecorder>,
+    mut save: ResMut<SaveData>,
+    mode: Res<RunMode>,
+    score: Res<Score>,
+    run: Res<RunStats>,
+    player_query: Query<&Transform, With<Controlled>>,
+) {
+    if *mode != RunMode::Endless || score.0 <= save.ghost.score {
+        return;
+    }
+
+    // The crash itself, so the ghost disappears where it hit the rock
+    if let Ok(transform) = player_query.get_single() {
+        record_sample(&mut recorder.run, run.time, transform);
+    }
+
+    recorder.run.score = score.0;
+    save.ghost = recorder.run.clone();
+}
+
+fn record_sample(run: &mut GhostRun, time: f32, transform: &Transform) {
+    run.samples.push(GhostSample {
+        time,
+        y: transform.translation.y,
+        angle: z_angle(transform.rotation),
+    });
+}
@@ -0,0 +1,146 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    GameAssets, HudVisibility, Invincible, Layer, Player, PowerUp, PowerUpCollected,
+    RemoveAfterState, ShowToast, PHYSICS_STEP,
+};
+
+const FLIP_DURATION: f32 = 5.0;
+// Rocks that came in while the plane was flipped may have their tips right where it ends up, so it flies
+// through everything for a moment after flipping back
+const FLIP_BACK_GRACE: f32 = 0.75;
+
+// One dot per half second left
+const RING_DOTS: usize = 10;
+const RING_RADIUS: f32 = 42.0;
+// The ring blinks for the last second, so the flip back doesn't come as a surprise
+const RING_BLINK_TIME: f32 = 1.0;
+const RING_BLINK_RATE: f32 = 8.0;
+
+// Gravity pulls up and flapping pushes down while the plane has this, see player_system
+#[derive(Component, Clone)]
+pub struct GravityFlip {
+    remaining: f32,
+}
+
+impl GravityFlip {
+    // 1 when picked up, 0 when the plane flips back
+    pub fn fraction_left(&self) -> f32 {
+        (self.remaining / FLIP_DURATION).clamp(0.0, 1.0)
+    }
+}
+
+// Follows the plane around like a livery tag, so it doesn't turn with it
+#[derive(Component)]
+pub struct GravityFlipDot {
+    plane: Entity,
+    index: usize,
+}
+
+// 1 the right way up, -1 flipped
+pub fn gravity_direction(flip: Option<&GravityFlip>) -> f32 {
+    if flip.is_some() {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+// Picking up another one while flipped starts the five seconds over
+pub fn gravity_flip_pickup_system(
+    mut commands: Commands,
+    mut collected_events: EventReader<PowerUpCollected>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    for event in collected_events.iter() {
+        if event.power_up != PowerUp::GravityFlip {
+            continue;
+        }
+
+        commands.entity(event.player).insert(GravityFlip {
+            remaining: FLIP_DURATION,
+        });
+        toasts.send(ShowToast("Gravity flipped".to_string()));
+    }
+}
+
+// Runs before player_system, so the flip back happens at the start of a step
+pub fn gravity_flip_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut GravityFlip, &mut Player)>,
+) {
+    for (entity, mut flip, mut player) in query.iter_mut() {
+        flip.remaining -= PHYSICS_STEP;
+        if flip.remaining > 0.0 {
+            continue;
+        }
+
+        // Whatever speed it built up would take it straight into the ground or the ceiling
+        player.velocity = 0.0;
+
+        commands
+            .entity(entity)
+            .remove::<GravityFlip>()
+            .insert(Invincible::new(FLIP_BACK_GRACE));
+    }
+}
+
+pub fn gravity_flip_ring_system(
+    mut commands: Commands,
+    planes: Query<(Entity, &Transform, Option<&GravityFlip>), With<Player>>,
+    mut dots: Query<(Entity, &GravityFlipDot, &mut Transform, &mut Visibility), Without<Player>>,
+    assets: Res<GameAssets>,
+    hud: Res<HudVisibility>,
+    time: Res<Time>,
+) {
+    for (dot_entity, dot, mut transform, mut visibility) in dots.iter_mut() {
+        let (plane, flip) = match planes.get(dot.plane) {
+            Ok((_, plane, Some(flip))) => (plane, flip),
+            _ => {
+                commands.entity(dot_entity).despawn_recursive();
+                continue;
+            }
+        };
+
+        // Dots go out one by one, the one at the top last
+        let left = (flip.remaining / FLIP_DURATION * RING_DOTS as f32).ceil() as usize;
+        let blink = flip.remaining < RING_BLINK_TIME
+            && (time.seconds_since_startup() as f32 * RING_BLINK_RATE) as u32 % 2 == 0;
+        visibility.is_visible = dot.index < left && !blink && hud.shows_extras();
+
+        let position = plane.translation.truncate() + dot_offset(dot.index);
+        transform.translation = position.extend(transform.translation.z);
+    }
+
+    for (plane, transform, flip) in planes.iter() {
+        let has_ring = dots.iter().any(|(_, dot, _, _)| dot.plane == plane);
+        if flip.is_none() || has_ring {
+            continue;
+        }
+
+        for index in 0..RING_DOTS {
+            let position = transform.translation.truncate() + dot_offset(index);
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(0.7, 0.5, 1.0, 0.8),
+                        ..default()
+                    },
+                    texture: assets.puff.clone(),
+                    transform: Transform::from_translation(position.extend(Layer::Overlay.z()))
+                        .with_scale(Vec3::new(0.15, 0.15, 1.0)),
+                    ..default()
+                })
+                .insert(GravityFlipDot { plane, index })
+                .insert(RemoveAfterState);
+        }
+    }
+}
+
+fn dot_offset(index: usize) -> Vec2 {
+    let angle = TAU / 4.0 - index as f32 / RING_DOTS as f32 * TAU;
+    Vec2::new(angle.cos(), angle.sin()) * RING_RADIUS
+}
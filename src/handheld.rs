@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::utils::Instant;
+use serde::{Deserialize, Serialize};
+
+use crate::{is_handheld, sleep, EffectsIntensity, SaveData, Settings};
+
+// A bundle of settings that suit a kind of device, so a Steam Deck plays well without going through the
+// settings screen first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DevicePreset {
+    Desktop,
+    // Small screen, gamepad controls and a battery
+    Handheld,
+}
+
+impl DevicePreset {
+    pub fn detect() -> Self {
+        if is_handheld() {
+            DevicePreset::Handheld
+        } else {
+            DevicePreset::Desktop
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DevicePreset::Desktop => "Desktop",
+            DevicePreset::Handheld => "Handheld",
+        }
+    }
+
+    // Only touches the settings the presets disagree on
+    fn apply(&self, settings: &mut Settings) {
+        let defaults = Settings::default();
+
+        match self {
+            DevicePreset::Desktop => {
+                settings.ui_scale = defaults.ui_scale;
+                settings.gamepad_menus = defaults.gamepad_menus;
+                settings.frame_cap = defaults.frame_cap;
+                settings.effects_intensity = defaults.effects_intensity;
+            }
+            DevicePreset::Handheld => {
+                settings.ui_scale = 1.25;
+                settings.gamepad_menus = true;
+                settings.frame_cap = FrameCap::Fps40;
+                settings.effects_intensity = EffectsIntensity::Low;
+            }
+        }
+    }
+}
+
+// Applied when the save is loaded and when the preset is picked in the settings. Nothing happens when the preset
+// is the one that was applied last, so changes made on top of it are kept between launches.
+pub fn apply_device_preset(settings: &mut Settings) {
+    let preset = settings.device_preset.unwrap_or_else(DevicePreset::detect);
+    if settings.applied_preset == Some(preset) {
+        return;
+    }
+
+    // Desktop is what the settings default to, so a save from before there were presets keeps what it has
+    if settings.applied_preset.is_some() || preset != DevicePreset::Desktop {
+        preset.apply(settings);
+    }
+    settings.applied_preset = Some(preset);
+}
+
+// Steam Deck screens can run at 40Hz, which saves a lot of battery and still plays smoothly
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameCap {
+    Off,
+    Fps40,
+    Fps60,
+}
+
+impl FrameCap {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FrameCap::Off => "Off",
+            FrameCap::Fps40 => "40",
+            FrameCap::Fps60 => "60",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            FrameCap::Off => FrameCap::Fps40,
+            FrameCap::Fps40 => FrameCap::Fps60,
+            FrameCap::Fps60 => FrameCap::Off,
+        }
+    }
+
+    fn frame_time(&self) -> Option<Duration> {
+        match self {
+            FrameCap::Off => None,
+            FrameCap::Fps40 => Some(Duration::from_secs_f64(1.0 / 40.0)),
+            FrameCap::Fps60 => Some(Duration::from_secs_f64(1.0 / 60.0)),
+        }
+    }
+}
+
+// Sleeps away the rest of the frame at the end of the update. The physics runs on fixed steps, so a lower frame
+// rate doesn't change how the game plays.
+pub fn frame_cap_system(save: Res<SaveData>, time: Res<Time>) {
+    let frame_time = match save.settings.frame_cap.frame_time() {
+        Some(frame_time) => frame_time,
+        None => return,
+    };
+    let frame_start = match time.last_update() {
+        Some(frame_start) => frame_start,
+        None => return,
+    };
+
+    let elapsed = Instant::now() - frame_start;
+    if elapsed < frame_time {
+        sleep(frame_time - elapsed);
+    }
+}
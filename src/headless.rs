@@ -0,0 +1,349 @@
+use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+
+// Marks an app that runs without a window, a GPU or the save on disk, see GamePlugin
+pub struct Headless;
+
+/// Runs the game without a window, a GPU or the save on disk, for tests and tools. Added before [`GamePlugin`].
+/// Nothing is drawn and the game doesn't run itself, whoever drives it calls `App::update` for every frame.
+///
+/// [`GamePlugin`]: crate::GamePlugin
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        // Without backends the renderer doesn't start, everything that draws is still there but never runs
+        app.insert_resource(Headless).insert_resource(WgpuSettings {
+            backends: None,
+            ..default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use bevy::input::keyboard::KeyboardInput;
+    use bevy::input::ButtonState;
+    use bevy::prelude::*;
+
+    use super::HeadlessPlugin;
+    use crate::{
+        GameConfig, GamePlugin, GameRng, GameState, GateSpawn, GravityFlip, PhysicsClock, Pilot,
+        Player, PowerUp, PowerUpCollected, Rock, RunMode, SaveData, Score, SpawnDecision,
+        SpawnHooks, PHYSICS_STEP,
+    };
+
+    // Long enough for a gate to come in from the right and go off the left
+    const MAX_STEPS: u32 = (8.0 / PHYSICS_STEP) as u32;
+    // Well within the five seconds a flip lasts, so picking it up again keeps the plane flipped
+    const FLIP_STEPS: u32 = (1.0 / PHYSICS_STEP) as u32;
+
+    // The whole game with one physics step per frame and input sent as key events
+    struct HeadlessGame {
+        app: App,
+        flapping: bool,
+    }
+
+    impl HeadlessGame {
+        fn new() -> Self {
+            let mut app = App::new();
+            app.add_plugin(HeadlessPlugin).add_plugin(GamePlugin);
+            // Startup systems
+            app.update();
+
+            app.world.resource_mut::<PhysicsClock>().set_frozen(true);
+            app.world.resource_mut::<SaveData>().settings.tutorial_done = Some(true);
+
+            // Nothing but the gates a test asks for
+            app.world
+                .resource_mut::<SpawnHooks>()
+                .on_hazard_spawn(|_| SpawnDecision::Veto);
+
+            Self {
+                app,
+                flapping: false,
+            }
+        }
+
+        // Only the first gate is spawned, shaped by the hook
+        fn single_gate(&mut self, shape: fn(&mut GateSpawn)) {
+            let spawned = AtomicU64::new(0);
+            self.app
+                .world
+                .resource_mut::<SpawnHooks>()
+                .on_gate_spawn(move |gate| {
+                    if spawned.fetch_add(1, Ordering::Relaxed) > 0 {
+                        return SpawnDecision::Veto;
+                    }
+
+                    gate.oscillation = None;
+                    shape(gate);
+                    SpawnDecision::Spawn
+                });
+        }
+
+        // Straight past the menu and the countdown
+        fn start_run(&mut self, mode: RunMode) {
+            self.app.insert_resource(mode);
+            self.set_state(GameState::Start);
+            self.set_state(GameState::Playing);
+        }
+
+        fn set_state(&mut self, state: GameState) {
+            self.app
+                .world
+                .resource_mut::<State<GameState>>()
+                .overwrite_set(state)
+                .expect("the state can be changed");
+            self.app.update();
+        }
+
+        fn state(&self) -> GameState {
+            self.app
+                .world
+                .resource::<State<GameState>>()
+                .current()
+                .clone()
+        }
+
+        fn score(&self) -> u64 {
+            self.app.world.resource::<Score>().0
+        }
+
+        fn rocks(&mut self) -> usize {
+            self.app
+                .world
+                .query_filtered::<(), With<Rock>>()
+                .iter(&self.app.world)
+                .count()
+        }
+
+        fn rock_translations(&mut self) -> Vec<Vec3> {
+            self.app
+                .world
+                .query_filtered::<&Transform, With<Rock>>()
+                .iter(&self.app.world)
+                .map(|transform| transform.translation)
+                .collect()
+        }
+
+        // Height, vertical velocity and gravity direction of the first plane
+        fn plane(&mut self) -> (f32, f32, f32) {
+            self.app
+                .world
+                .query::<(&Transform, &Player, &Pilot, Option<&GravityFlip>)>()
+                .iter(&self.app.world)
+                .find(|(_, _, pilot, _)| **pilot == Pilot::One)
+                .map(|(transform, player, _, flip)| {
+                    let direction = if flip.is_some() { -1.0 } else { 1.0 };
+                    (transform.translation.y, player.velocity, direction)
+                })
+                .expect("there is a plane")
+        }
+
+        // As if the first plane had flown into a gravity flip power-up
+        fn flip_gravity(&mut self) {
+            let player = self
+                .app
+                .world
+                .query::<(Entity, &Pilot)>()
+                .iter(&self.app.world)
+                .find(|(_, pilot)| **pilot == Pilot::One)
+                .map(|(entity, _)| entity)
+                .expect("there is a plane");
+
+            self.app
+                .world
+                .resource_mut::<Events<PowerUpCollected>>()
+                .send(PowerUpCollected {
+                    power_up: PowerUp::GravityFlip,
+                    player,
+                });
+        }
+
+        fn key(&mut self, key: KeyCode, state: ButtonState) {
+            self.app
+                .world
+                .resource_mut::<Events<KeyboardInput>>()
+                .send(KeyboardInput {
+                    scan_code: 0,
+                    key_code: Some(key),
+                    state,
+                });
+        }
+
+        // Held for a single frame
+        fn tap(&mut self, key: KeyCode) {
+            self.key(key, ButtonState::Pressed);
+            self.app.update();
+            self.key(key, ButtonState::Released);
+            self.app.update();
+        }
+
+        fn step(&mut self) {
+            self.app.world.resource_mut::<PhysicsClock>().request_step();
+            self.app.update();
+        }
+
+        // Flaps whenever the plane falls below the height, the way the demo's autopilot does. While gravity is
+        // flipped it falls up and flapping pushes it down instead.
+        fn hold_height(&mut self, height: f32) {
+            let (y, velocity, direction) = self.plane();
+            let flap = (y - height) * direction < 0.0 && velocity * direction < 0.0;
+
+            if flap != self.flapping {
+                let state = if flap {
+                    ButtonState::Pressed
+                } else {
+                    ButtonState::Released
+                };
+                self.key(KeyCode::Space, state);
+                self.flapping = flap;
+            }
+
+            self.step();
+        }
+    }
+
+    // A gap from about -120 to 120, around the height the plane is kept at
+    fn wide_gap(gate: &mut GateSpawn) {
+        gate.rock_type = 2;
+        gate.scale = 0.7;
+        gate.offset = 0.0;
+    }
+
+    // The bottom rock reaches well above the middle
+    fn high_gap(gate: &mut GateSpawn) {
+        gate.rock_type = 2;
+        gate.scale = 1.2;
+        gate.offset = 150.0;
+    }
+
+    #[test]
+    fn flying_through_a_gate_scores_its_points() {
+        let mut game = HeadlessGame::new();
+        game.single_gate(wide_gap);
+        game.start_run(RunMode::Endless);
+
+        for _ in 0..MAX_STEPS {
+            game.hold_height(0.0);
+            if game.score() > 0 {
+                break;
+            }
+        }
+
+        // A pair with rocks on both sides
+        let points = game.app.world.resource::<GameConfig>().rocks.both.points;
+        assert_eq!(game.score(), points);
+        assert_eq!(game.state(), GameState::Playing);
+    }
+
+    #[test]
+    fn flying_through_a_gate_upside_down_scores_its_points() {
+        let mut game = HeadlessGame::new();
+        game.single_gate(wide_gap);
+        game.start_run(RunMode::Endless);
+
+        for step in 0..MAX_STEPS {
+            if step % FLIP_STEPS == 0 {
+                game.flip_gravity();
+            }
+            game.hold_height(0.0);
+            if game.score() > 0 {
+                break;
+            }
+        }
+
+        // The plane tilts the other way while flipped, so would a hull turned the wrong way
+        let (_, _, direction) = game.plane();
+        assert_eq!(direction, -1.0, "the plane flipped back before the gate");
+        let points = game.app.world.resource::<GameConfig>().rocks.both.points;
+        assert_eq!(game.score(), points);
+        assert_eq!(game.state(), GameState::Playing);
+    }
+
+    // Practice flies on through crashes, so the course keeps coming however the plane does
+    fn seeded_course(seed: u64) -> Vec<Vec3> {
+        let mut game = HeadlessGame::new();
+        game.app.insert_resource(GameRng::new(Some(seed)));
+        game.start_run(RunMode::Practice);
+
+        for _ in 0..MAX_STEPS {
+            game.hold_height(0.0);
+        }
+
+        game.rock_translations()
+    }
+
+    #[test]
+    fn same_seed_spawns_the_same_course() {
+        let course = seeded_course(42);
+
+        assert!(!course.is_empty(), "no rocks were spawned");
+        assert_eq!(course, seeded_course(42));
+        assert_ne!(course, seeded_course(43));
+    }
+
+    #[test]
+    fn rocks_are_despawned_once_they_are_off_screen() {
+        let mut game = HeadlessGame::new();
+        game.single_gate(wide_gap);
+        game.start_run(RunMode::Endless);
+
+        let mut spawned = false;
+        for _ in 0..MAX_STEPS {
+            game.hold_height(0.0);
+            let rocks = game.rocks();
+            spawned |= rocks > 0;
+
+            if spawned && rocks == 0 {
+                break;
+            }
+        }
+
+        assert!(spawned, "the gate was never spawned");
+        assert_eq!(game.rocks(), 0);
+    }
+
+    #[test]
+    fn hitting_a_rock_ends_the_run() {
+        let mut game = HeadlessGame::new();
+        game.single_gate(high_gap);
+        game.start_run(RunMode::Endless);
+
+        for _ in 0..MAX_STEPS {
+            game.hold_height(0.0);
+            if game.state() != GameState::Playing {
+                break;
+            }
+        }
+
+        // Giving up on the continue falls through to the game over screen
+        assert_eq!(game.state(), GameState::Continue);
+        game.tap(KeyCode::Escape);
+        assert_eq!(game.state(), GameState::GameOver);
+        assert_eq!(game.score(), 0);
+    }
+
+    #[test]
+    fn confirming_the_game_over_screen_goes_back_to_the_menu() {
+        let mut game = HeadlessGame::new();
+        game.single_gate(high_gap);
+        game.start_run(RunMode::Endless);
+
+        for _ in 0..MAX_STEPS {
+            game.hold_height(0.0);
+            if game.state() != GameState::Playing {
+                break;
+            }
+        }
+        game.tap(KeyCode::Escape);
+        assert_eq!(game.state(), GameState::GameOver);
+
+        // Without a score there's no name to enter
+        game.tap(KeyCode::Return);
+        assert_eq!(game.state(), GameState::Menu);
+    }
+}
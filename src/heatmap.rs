@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    BitmapTextBundle, CrashCause, CrashEvent, DemoMode, Layer, RemoveAfterState, SaveData,
+    TextAnchor, GROUND_HEIGHT, HEIGHT, WIDTH,
+};
+
+// Only the latest crashes are kept, so the save doesn't keep growing and the map shows how the player flies now
+const MAX_CRASH_SITES: usize = 200;
+
+// The playfield in miniature, to the right of the stats
+const MAP_CENTER: Vec2 = Vec2::new(305.0, 40.0);
+const MAP_SCALE: f32 = 0.2;
+const MAP_COLOR: Color = Color::rgba(0.1, 0.15, 0.25, 0.8);
+const MAP_GROUND_COLOR: Color = Color::rgba(0.45, 0.35, 0.25, 0.8);
+
+const DOT_SIZE: f32 = 6.0;
+// Crashes closer together than this on the real playfield heat each other up
+const HEAT_RADIUS: f32 = 30.0;
+// A dot on its own, the hottest spot is fully opaque
+const MIN_HEAT_ALPHA: f32 = 0.25;
+
+const LEGEND_TOP: f32 = MAP_CENTER.y - HEIGHT * MAP_SCALE / 2.0 - 20.0;
+const LEGEND_SPACING: f32 = 18.0;
+const LEGEND_SCALE: f32 = 0.22;
+
+// Where a plane crashed on the screen, kept in the player's stats
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct CrashSite {
+    pub x: f32,
+    pub y: f32,
+    pub cause: CrashCause,
+}
+
+impl CrashCause {
+    fn color(self) -> Color {
+        match self {
+            CrashCause::Rock => Color::rgb(1.0, 0.6, 0.1),
+            CrashCause::Hazard => Color::rgb(1.0, 0.15, 0.2),
+            CrashCause::Bounds => Color::rgb(0.5, 0.7, 1.0),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            CrashCause::Rock => "Rocks",
+            CrashCause::Hazard => "Hazards",
+            CrashCause::Bounds => "Out of sight",
+        }
+    }
+}
+
+// Runs right after collisions, a crash that ends the run leaves Playing before the next frame's systems would
+// see its event
+pub fn record_crash_sites(
+    mut crashes: EventReader<CrashEvent>,
+    mut save: ResMut<SaveData>,
+    demo: Res<DemoMode>,
+) {
+    if demo.active {
+        return;
+    }
+
+    for crash in crashes.iter() {
+        let sites = &mut save.stats.crash_sites;
+        sites.push(CrashSite {
+            x: crash.position.x,
+            y: crash.position.y,
+            cause: crash.cause,
+        });
+        if sites.len() > MAX_CRASH_SITES {
+            sites.remove(0);
+        }
+    }
+}
+
+// A dot for every crash on a miniature of the playfield. Dots in a crowded spot are drawn more opaque, so the
+// places the player keeps crashing at stand out.
+pub fn setup_crash_heatmap(mut commands: Commands, save: Res<SaveData>) {
+    let size = Vec2::new(WIDTH, HEIGHT) * MAP_SCALE;
+    spawn_rect(&mut commands, MAP_CENTER, size, MAP_COLOR, 0.0);
+
+    let ground = Vec2::new(WIDTH, GROUND_HEIGHT) * MAP_SCALE;
+    let ground_offset = Vec2::new(0.0, (size.y - ground.y) / 2.0);
+    for offset in [ground_offset, -ground_offset] {
+        spawn_rect(
+            &mut commands,
+            MAP_CENTER + offset,
+            ground,
+            MAP_GROUND_COLOR,
+            0.1,
+        );
+    }
+
+    let sites = &save.stats.crash_sites;
+    if sites.is_empty() {
+        commands
+            .spawn_bundle(
+                BitmapTextBundle::at(MAP_CENTER)
+                    .with_anchor(TextAnchor::Center)
+                    .with_scale(LEGEND_SCALE)
+                    .with_text("No crashes yet".to_string()),
+            )
+            .insert(RemoveAfterState);
+        return;
+    }
+
+    let heat: Vec<usize> = sites
+        .iter()
+        .map(|site| {
+            let position = Vec2::new(site.x, site.y);
+            sites
+                .iter()
+                .filter(|other| position.distance(Vec2::new(other.x, other.y)) < HEAT_RADIUS)
+                .count()
+        })
+        .collect();
+    let hottest = heat.iter().copied().max().unwrap_or(1) as f32;
+
+    // Crashes off the top or bottom of the screen are drawn at its edge
+    let edge = (size - Vec2::splat(DOT_SIZE)) / 2.0;
+    for (site, heat) in sites.iter().zip(heat) {
+        let offset = (Vec2::new(site.x, site.y) * MAP_SCALE).clamp(-edge, edge);
+        let mut color = site.cause.color();
+        color.set_a(MIN_HEAT_ALPHA + (1.0 - MIN_HEAT_ALPHA) * heat as f32 / hottest);
+        spawn_rect(
+            &mut commands,
+            MAP_CENTER + offset,
+            Vec2::splat(DOT_SIZE),
+            color,
+            0.2,
+        );
+    }
+
+    let left = MAP_CENTER.x - size.x / 2.0;
+    let causes = [CrashCause::Rock, CrashCause::Hazard, CrashCause::Bounds];
+    for (i, cause) in causes.into_iter().enumerate() {
+        let y = LEGEND_TOP - i as f32 * LEGEND_SPACING;
+        commands
+            .spawn_bundle(
+                BitmapTextBundle::new(left, y)
+                    .with_scale(LEGEND_SCALE)
+                    .with_color(cause.color())
+                    .with_text(cause.label().to_string()),
+            )
+            .insert(RemoveAfterState);
+    }
+}
+
+// Offset a fraction in z so the dots stay in front of the map
+fn spawn_rect(commands: &mut Commands, position: Vec2, size: Vec2, color: Color, z: f32) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_translation(position.extend(Layer::Ui.z() + z)),
+            ..default()
+        })
+        .insert(RemoveAfterState);
+}
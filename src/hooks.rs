@@ -0,0 +1,146 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+use crate::Behavior;
+
+/// What a hook wants done with the spawn it was handed
+pub enum SpawnDecision {
+    Spawn,
+    Veto,
+}
+
+/// A rock pair that's about to be spawned
+pub struct GateSpawn {
+    pub x: f32,
+    pub scale: f32,
+    // 0 for only a bottom rock, 1 for only a top rock, 2 for both
+    pub rock_type: u8,
+    // Moves both rocks up or down
+    pub offset: f32,
+    // Amplitude and frequency, if the pair moves up and down
+    pub oscillation: Option<(f32, f32)>,
+    // Motion from the obstacle pattern, run on both rocks alike
+    pub behaviors: Vec<Behavior>,
+    // Pairs of rocks chained into a cave, 1 for a single pair. A cave is scored once, at its exit.
+    pub segments: u32,
+    // Cracks and crumbles away once it's the next obstacle, see Crumbling
+    pub crumbling: bool,
+    pub score: u64,
+    attach: Vec<fn(&mut EntityCommands)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HazardKind {
+    Enemy,
+    Stalactite,
+    Pendulum,
+    LaserGate,
+    Blade,
+}
+
+/// Anything that crashes a plane other than rocks, about to be spawned
+pub struct HazardSpawn {
+    pub kind: HazardKind,
+    // Where it spawns. For pendulums this is the pivot, which always hangs from the ceiling so only x is used.
+    pub position: Vec2,
+    pub score: u64,
+    attach: Vec<fn(&mut EntityCommands)>,
+}
+
+impl GateSpawn {
+    pub fn new(
+        scale: f32,
+        rock_type: u8,
+        offset: f32,
+        oscillation: Option<(f32, f32)>,
+        score: u64,
+    ) -> Self {
+        Self {
+            x: 0.0,
+            scale,
+            rock_type,
+            offset,
+            oscillation,
+            behaviors: vec![],
+            segments: 1,
+            crumbling: false,
+            score,
+            attach: vec![],
+        }
+    }
+
+    // Runs on every rock of the pair once it's spawned, e.g. to insert extra components
+    pub fn attach(&mut self, f: fn(&mut EntityCommands)) {
+        self.attach.push(f);
+    }
+
+    pub fn apply_attached(&self, entity: &mut EntityCommands) {
+        for f in self.attach.iter() {
+            f(entity);
+        }
+    }
+}
+
+impl HazardSpawn {
+    pub fn new(kind: HazardKind, position: Vec2, score: u64) -> Self {
+        Self {
+            kind,
+            position,
+            score,
+            attach: vec![],
+        }
+    }
+
+    // Runs on the hazard once it's spawned, e.g. to insert extra components
+    pub fn attach(&mut self, f: fn(&mut EntityCommands)) {
+        self.attach.push(f);
+    }
+
+    pub fn apply_attached(&self, entity: &mut EntityCommands) {
+        for f in self.attach.iter() {
+            f(entity);
+        }
+    }
+}
+
+type GateHook = Box<dyn Fn(&mut GateSpawn) -> SpawnDecision + Send + Sync>;
+type HazardHook = Box<dyn Fn(&mut HazardSpawn) -> SpawnDecision + Send + Sync>;
+
+/// Spawn-time hooks for mods. A mod registers its hooks on this resource while the app is built,
+/// e.g. `app.world.resource_mut::<SpawnHooks>().on_gate_spawn(...)`, and the spawners run them in the order they
+/// were registered. Hooks run after everything has been drawn from the course rng, so changing or vetoing a spawn
+/// never shifts the rest of a seeded course.
+#[derive(Default)]
+pub struct SpawnHooks {
+    gate_hooks: Vec<GateHook>,
+    hazard_hooks: Vec<HazardHook>,
+}
+
+impl SpawnHooks {
+    pub fn on_gate_spawn(
+        &mut self,
+        hook: impl Fn(&mut GateSpawn) -> SpawnDecision + Send + Sync + 'static,
+    ) {
+        self.gate_hooks.push(Box::new(hook));
+    }
+
+    pub fn on_hazard_spawn(
+        &mut self,
+        hook: impl Fn(&mut HazardSpawn) -> SpawnDecision + Send + Sync + 'static,
+    ) {
+        self.hazard_hooks.push(Box::new(hook));
+    }
+
+    // False if a hook vetoed the spawn, the hooks after it don't run then
+    pub fn gate(&self, spawn: &mut GateSpawn) -> bool {
+        self.gate_hooks
+            .iter()
+            .all(|hook| matches!(hook(spawn), SpawnDecision::Spawn))
+    }
+
+    pub fn hazard(&self, spawn: &mut HazardSpawn) -> bool {
+        self.hazard_hooks
+            .iter()
+            .all(|hook| matches!(hook(spawn), SpawnDecision::Spawn))
+    }
+}
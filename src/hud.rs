@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    ammo_text_system, format_number, fuel_gauge_system, lives_text_system, speedrun_text_system,
+    style_text_system, time_trial_text_system, BitmapText, BitmapTextBundle, Combo, GameAssets,
+    GameConfig, GameState, GravityFlip, Layer, NumberFormat, Pilot, RunMode, SaveData, Score,
+    ScreenAnchor, TextAnchor,
+};
+
+// Everything is placed relative to the edges of the playfield, which keeps its size however the window is
+// resized, see ViewportSize
+const SCORE_POSITION: Vec2 = ScreenAnchor::TopRight.offset(-15.0, -75.0);
+const BEST_POSITION: Vec2 = ScreenAnchor::TopRight.offset(-15.0, -150.0);
+const BEST_SCALE: f32 = 0.3;
+// Down and to the right of the score, with high contrast on
+const SHADOW_OFFSET: Vec2 = Vec2::new(4.0, -4.0);
+const SHADOW_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.85);
+
+// Below the lives, further ones go to the right
+const POWER_UP_POSITION: Vec2 = ScreenAnchor::TopLeft.offset(35.0, -150.0);
+const POWER_UP_SPACING: f32 = 55.0;
+const POWER_UP_ICON_SCALE: f32 = 0.5;
+// One dot per slice of the time left, going out clockwise from the top
+const TIMER_DOTS: usize = 12;
+const TIMER_RADIUS: f32 = 22.0;
+
+// The score, the best of the session, power-ups that are running out and the text of every mode that adds to
+// the HUD. Modules spawn their own parts, the systems that keep them up to date are all added here.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HudVisibility>()
+            .init_resource::<SessionBest>()
+            .add_startup_system(setup_hud)
+            .add_system_to_stage(CoreStage::PreUpdate, hud_visibility_system)
+            .add_system(clean_hud_toggle_system)
+            .add_system(score_text_system)
+            .add_system(score_shadow_system.after(score_text_system))
+            .add_system(best_text_system)
+            .add_system(power_up_indicator_system)
+            .add_system(style_text_system)
+            .add_system(ammo_text_system)
+            .add_system(time_trial_text_system)
+            .add_system(fuel_gauge_system)
+            .add_system(speedrun_text_system)
+            .add_system(lives_text_system)
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(session_best_system),
+            );
+    }
+}
+
+// What the HUD shows while playing. A clean HUD keeps only the score, for recording footage without the clutter.
+// Every system that draws part of the HUD checks it, and redraws when it changes.
+#[derive(Default)]
+pub struct HudVisibility {
+    clean: bool,
+}
+
+impl HudVisibility {
+    // Everything but the score
+    pub fn shows_extras(&self) -> bool {
+        !self.clean
+    }
+
+    // For HUD text, an empty BitmapText hides all its glyphs
+    pub fn text(&self, text: String) -> String {
+        if self.shows_extras() {
+            text
+        } else {
+            String::new()
+        }
+    }
+}
+
+// F7, in any state. Kept in the settings, so every profile remembers its own.
+pub fn clean_hud_toggle_system(keys: Res<Input<KeyCode>>, mut save: ResMut<SaveData>) {
+    if keys.just_pressed(KeyCode::F7) {
+        save.settings.clean_hud = !save.settings.clean_hud;
+    }
+}
+
+// Follows the settings instead of being toggled directly, so an imported profile brings its choice along
+pub fn hud_visibility_system(save: Res<SaveData>, mut hud: ResMut<HudVisibility>) {
+    if hud.clean != save.settings.clean_hud {
+        hud.clean = save.settings.clean_hud;
+    }
+}
+
+#[derive(Component)]
+pub struct ScoreText;
+
+// The same glyphs as the score in black right behind it, so the score stands out against any sky
+#[derive(Component)]
+pub struct ScoreShadow;
+
+#[derive(Component)]
+pub struct BestText;
+
+// The best score of each mode since the game was started, not kept in the save file
+#[derive(Default)]
+pub struct SessionBest(HashMap<RunMode, u64>);
+
+// Timed power-ups of the first plane, with what's left of them from 1 down to 0
+#[derive(Component, Clone, Copy, PartialEq)]
+pub enum PowerUpIndicator {
+    GravityFlip,
+}
+
+impl PowerUpIndicator {
+    const ALL: [PowerUpIndicator; 1] = [PowerUpIndicator::GravityFlip];
+
+    fn color(&self) -> Color {
+        match self {
+            PowerUpIndicator::GravityFlip => Color::rgb(0.7, 0.5, 1.0),
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct PowerUpTimerDot {
+    indicator: PowerUpIndicator,
+    index: usize,
+}
+
+pub fn setup_hud(mut commands: Commands, assets: Res<GameAssets>) {
+    commands
+        .spawn_bundle(
+            BitmapTextBundle::at(SCORE_POSITION)
+                .with_anchor(TextAnchor::Right)
+                .with_number_format(NumberFormat::Compact),
+        )
+        .insert(ScoreText);
+
+    let mut shadow = BitmapTextBundle::at(SCORE_POSITION + SHADOW_OFFSET)
+        .with_anchor(TextAnchor::Right)
+        .with_color(SHADOW_COLOR);
+    shadow.transform.translation.z -= 0.5;
+    commands.spawn_bundle(shadow).insert(ScoreShadow);
+
+    commands
+        .spawn_bundle(
+            BitmapTextBundle::at(BEST_POSITION)
+                .with_anchor(TextAnchor::Right)
+                .with_scale(BEST_SCALE)
+                .with_number_format(NumberFormat::Compact),
+        )
+        .insert(BestText);
+
+    // Hidden until the power-up is picked up, they stay around between runs
+    for (slot, indicator) in PowerUpIndicator::ALL.iter().enumerate() {
+        let center = POWER_UP_POSITION + Vec2::new(slot as f32 * POWER_UP_SPACING, 0.0);
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: indicator.color(),
+                    ..default()
+                },
+                texture: assets.star_silver.clone(),
+                transform: Transform::from_translation(center.extend(Layer::Overlay.z()))
+                    .with_scale(Vec3::new(POWER_UP_ICON_SCALE, POWER_UP_ICON_SCALE, 1.0)),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            })
+            .insert(*indicator);
+
+        for index in 0..TIMER_DOTS {
+            let angle = TAU / 4.0 - index as f32 / TIMER_DOTS as f32 * TAU;
+            let position = center + Vec2::new(angle.cos(), angle.sin()) * TIMER_RADIUS;
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: indicator.color(),
+                        ..default()
+                    },
+                    texture: assets.puff.clone(),
+                    transform: Transform::from_translation(position.extend(Layer::Overlay.z()))
+                        .with_scale(Vec3::new(0.1, 0.1, 1.0)),
+                    visibility: Visibility { is_visible: false },
+                    ..default()
+                })
+                .insert(PowerUpTimerDot {
+                    indicator: *indicator,
+                    index,
+                });
+        }
+    }
+}
+
+// The combo multiplier is shown next to the score once there is one
+pub fn score_text_system(
+    score: Res<Score>,
+    combo: Res<Combo>,
+    config: Res<GameConfig>,
+    assets: Res<GameAssets>,
+    mut text_query: Query<&mut BitmapText, With<ScoreText>>,
+) {
+    if !score.is_changed() && !combo.is_changed() {
+        return;
+    }
+
+    let multiplier = combo.multiplier(&config.combo);
+
+    // None before the HUD is set up
+    for mut text in text_query.iter_mut() {
+        let points = format_number(score.0, text.number_format, &assets.bitmap_font);
+        let score_text = if multiplier > 1 {
+            format!("x{} {}", multiplier, points)
+        } else {
+            points
+        };
+
+        if text.text != score_text {
+            text.text = score_text;
+        }
+    }
+}
+
+// Left empty with high contrast off, which hides it like HUD text that isn't shown
+pub fn score_shadow_system(
+    save: Res<SaveData>,
+    score_query: Query<&BitmapText, With<ScoreText>>,
+    mut shadow_query: Query<&mut BitmapText, (With<ScoreShadow>, Without<ScoreText>)>,
+) {
+    let score = match score_query.get_single() {
+        Ok(score) => score,
+        Err(_) => return,
+    };
+
+    let wanted = if save.settings.high_contrast {
+        score.text.as_str()
+    } else {
+        ""
+    };
+
+    for mut shadow in shadow_query.iter_mut() {
+        if shadow.text != wanted {
+            shadow.text = wanted.to_string();
+        }
+    }
+}
+
+pub fn session_best_system(score: Res<Score>, mode: Res<RunMode>, mut best: ResMut<SessionBest>) {
+    let best = best.0.entry(*mode).or_default();
+    *best = (*best).max(score.0);
+}
+
+// Only once there's a run to beat, the current score takes over when it gets past it
+pub fn best_text_system(
+    best: Res<SessionBest>,
+    score: Res<Score>,
+    mode: Res<RunMode>,
+    hud: Res<HudVisibility>,
+    assets: Res<GameAssets>,
+    mut text_query: Query<&mut BitmapText, With<BestText>>,
+) {
+    if !best.is_changed() && !score.is_changed() && !mode.is_changed() && !hud.is_changed() {
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        let label = match best.0.get(&*mode) {
+            Some(&best) if best > 0 => {
+                let best =
+                    format_number(best.max(score.0), text.number_format, &assets.bitmap_font);
+                hud.text(format!("Best {}", best))
+            }
+            _ => String::new(),
+        };
+
+        if text.text != label {
+            text.text = label;
+        }
+    }
+}
+
+pub fn power_up_indicator_system(
+    planes: Query<(&Pilot, Option<&GravityFlip>)>,
+    hud: Res<HudVisibility>,
+    mut icons: Query<(&PowerUpIndicator, &mut Visibility)>,
+    mut dots: Query<(&PowerUpTimerDot, &mut Visibility), Without<PowerUpIndicator>>,
+) {
+    let left = |indicator: PowerUpIndicator| {
+        let (_, flip) = planes.iter().find(|(pilot, _)| **pilot == Pilot::One)?;
+        match indicator {
+            PowerUpIndicator::GravityFlip => flip.map(|flip| flip.fraction_left()),
+        }
+    };
+
+    for (indicator, mut visibility) in icons.iter_mut() {
+        let shown = hud.shows_extras() && left(*indicator).is_some();
+        if visibility.is_visible != shown {
+            visibility.is_visible = shown;
+        }
+    }
+
+    for (dot, mut visibility) in dots.iter_mut() {
+        let dots_left =
+            left(dot.indicator).map_or(0, |left| (left * TIMER_DOTS as f32).ceil() as usize);
+        let shown = hud.shows_extras() && dot.index < dots_left;
+        if visibility.is_visible != shown {
+            visibility.is_visible = shown;
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use ncollide2d::na::Point2;
+use ncollide2d::shape::ConvexPolygon;
+use serde::Deserialize;
+
+use crate::GameAssets;
+
+// Embedded like the obstacle patterns, the planes need their hulls from the first frame
+const HULLS: &str = include_str!("../assets/Planes/hulls.ron");
+
+// The plane sprites, players fly one of them depending on their skin or livery and enemies fly the green one
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+pub enum PlaneSprite {
+    Blue,
+    Red,
+    Green,
+    Yellow,
+}
+
+const PLANE_SPRITES: [PlaneSprite; 4] = [
+    PlaneSprite::Blue,
+    PlaneSprite::Red,
+    PlaneSprite::Green,
+    PlaneSprite::Yellow,
+];
+
+impl PlaneSprite {
+    pub fn texture(&self, assets: &GameAssets) -> Handle<Image> {
+        match self {
+            PlaneSprite::Blue => assets.player.clone(),
+            PlaneSprite::Red => assets.second_player.clone(),
+            PlaneSprite::Green => assets.enemy.clone(),
+            PlaneSprite::Yellow => assets.third_player.clone(),
+        }
+    }
+}
+
+// See assets/Planes/hulls.ron
+pub struct PlaneHulls(HashMap<PlaneSprite, Vec<(f32, f32)>>);
+
+impl PlaneHulls {
+    // Every sprite has one, load_plane_hulls made sure of it
+    pub fn get(&self, plane: PlaneSprite) -> &[(f32, f32)] {
+        &self.0[&plane]
+    }
+}
+
+// Collisions only work with convex hulls, so a broken one is caught here rather than the first time a plane
+// spawns
+pub fn load_plane_hulls() -> PlaneHulls {
+    let hulls: HashMap<PlaneSprite, Vec<(f32, f32)>> =
+        ron::from_str(HULLS).expect("assets/Planes/hulls.ron is invalid");
+
+    for plane in PLANE_SPRITES {
+        let hull = hulls
+            .get(&plane)
+            .unwrap_or_else(|| panic!("assets/Planes/hulls.ron has no hull for {:?}", plane));
+        let points: Vec<Point2<f32>> = hull.iter().map(|(x, y)| Point2::new(*x, *y)).collect();
+        if ConvexPolygon::try_from_points(&points).is_none() {
+            panic!(
+                "The {:?} hull in assets/Planes/hulls.ron is not convex",
+                plane
+            );
+        }
+    }
+
+    PlaneHulls(hulls)
+}
@@ -0,0 +1,182 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+use crate::{
+    BitmapTextBundle, GameAssets, GameState, HudVisibility, Layer, Lifetime, Particle, PlaneSprite,
+    Player, RemoveAfterState, SimulationTime,
+};
+
+// Relative to the plane, which is drawn at half size
+const DECAL_OFFSET: Vec3 = Vec3::new(-24.0, 8.0, 0.1);
+const DECAL_SCALE: f32 = 0.6;
+
+const TAG_OFFSET: Vec2 = Vec2::new(0.0, 45.0);
+const TAG_SIZE: Vec2 = Vec2::new(64.0, 20.0);
+const TAG_TEXT_SCALE: f32 = 0.25;
+
+const TRAIL_INTERVAL: f32 = 0.05;
+const TRAIL_LIFETIME: f32 = 0.6;
+const TRAIL_ALPHA: f32 = 0.35;
+// Falls behind at about the speed of the course
+const TRAIL_VELOCITY: Vec2 = Vec2::new(-150.0, 0.0);
+
+// How a plane looks, so players sharing a screen or a race can tell each other apart. Everyone gets one, but the
+// tags and trails only show up when there's more than one plane.
+#[derive(Component, Clone, Copy)]
+pub struct Livery {
+    // Players are called by it, there are no player names in multiplayer
+    pub name: &'static str,
+    plane: PlaneSprite,
+    // For the decal, the tag and the trail
+    pub color: Color,
+}
+
+// Green is left out, it's what the enemies fly
+const LIVERIES: [Livery; 3] = [
+    Livery {
+        name: "Blue",
+        plane: PlaneSprite::Blue,
+        color: Color::rgb(0.35, 0.6, 1.0),
+    },
+    Livery {
+        name: "Red",
+        plane: PlaneSprite::Red,
+        color: Color::rgb(1.0, 0.35, 0.3),
+    },
+    Livery {
+        name: "Yellow",
+        plane: PlaneSprite::Yellow,
+        color: Color::rgb(1.0, 0.85, 0.25),
+    },
+];
+
+impl Livery {
+    // The slot is the order players joined in, so everyone in a game gets a different one. They only repeat
+    // with more players than there are liveries.
+    pub fn for_slot(slot: usize) -> Self {
+        LIVERIES[slot % LIVERIES.len()]
+    }
+
+    pub fn plane(&self) -> PlaneSprite {
+        self.plane
+    }
+
+    pub fn texture(&self, assets: &GameAssets) -> Handle<Image> {
+        self.plane.texture(assets)
+    }
+}
+
+// Points at the plane a tag floats above
+#[derive(Component)]
+pub struct LiveryTag(Entity);
+
+// The plane's sprite should already use the livery's texture
+pub fn add_livery(entity: &mut EntityCommands, livery: Livery, assets: &GameAssets) {
+    entity.insert(livery).with_children(|parent| {
+        parent.spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: livery.color,
+                ..default()
+            },
+            texture: assets.star_silver.clone(),
+            transform: Transform::from_translation(DECAL_OFFSET)
+                .with_scale(Vec3::splat(DECAL_SCALE)),
+            ..default()
+        });
+    });
+}
+
+// Tags don't turn with the plane, so they're kept apart from it and follow it around instead
+pub fn livery_tag_system(
+    mut commands: Commands,
+    planes: Query<(Entity, &Livery, &Transform), With<Player>>,
+    mut tags: Query<(Entity, &LiveryTag, &mut Transform), Without<Player>>,
+    hud: Res<HudVisibility>,
+) {
+    let multiplayer = planes.iter().count() > 1 && hud.shows_extras();
+
+    for (tag_entity, tag, mut transform) in tags.iter_mut() {
+        match planes.get(tag.0) {
+            Ok((_, _, plane)) if multiplayer => {
+                let position = plane.translation.truncate() + TAG_OFFSET;
+                transform.translation = position.extend(transform.translation.z);
+            }
+            _ => commands.entity(tag_entity).despawn_recursive(),
+        }
+    }
+
+    if !multiplayer {
+        return;
+    }
+
+    for (plane, livery, transform) in planes.iter() {
+        if tags.iter().any(|(_, tag, _)| tag.0 == plane) {
+            continue;
+        }
+
+        let position = transform.translation.truncate() + TAG_OFFSET;
+
+        // The label is a child, so its scale is relative to the plate
+        let mut label =
+            BitmapTextBundle::new(TAG_SIZE.x / -2.0 + 6.0, 0.0).with_text(livery.name.to_string());
+        label.transform.scale = Vec3::new(TAG_TEXT_SCALE, TAG_TEXT_SCALE, 1.0);
+        label.transform.translation.z = 0.1;
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: livery.color,
+                    custom_size: Some(TAG_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_translation(position.extend(Layer::Overlay.z())),
+                ..default()
+            })
+            .insert(LiveryTag(plane))
+            .insert(RemoveAfterState)
+            .with_children(|parent| {
+                parent.spawn_bundle(label);
+            });
+    }
+}
+
+pub fn livery_trail_system(
+    mut commands: Commands,
+    mut timer: Local<Option<Timer>>,
+    planes: Query<(&Livery, &GlobalTransform), With<Player>>,
+    assets: Res<GameAssets>,
+    state: Res<State<GameState>>,
+    simulation_time: Res<SimulationTime>,
+) {
+    if state.current() != &GameState::Playing || planes.iter().count() < 2 {
+        return;
+    }
+
+    let timer = timer.get_or_insert_with(|| Timer::from_seconds(TRAIL_INTERVAL, true));
+    if !timer.tick(simulation_time.delta()).just_finished() {
+        return;
+    }
+
+    for (livery, transform) in planes.iter() {
+        let position = transform.translation();
+
+        let mut color = livery.color;
+        color.set_a(TRAIL_ALPHA);
+
+        // Faint the whole time instead of fading out, so the trails stay out of the way
+        let mut particle = Particle::new(TRAIL_VELOCITY, 0.0);
+        particle.fade = false;
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite { color, ..default() },
+                texture: assets.puff.clone(),
+                // Behind the plane, out of its tail
+                transform: Transform::from_xyz(position.x - 20.0, position.y, position.z - 0.05)
+                    .with_scale(Vec3::new(0.3, 0.3, 1.0)),
+                ..default()
+            })
+            .insert(particle)
+            .insert(Lifetime::from_seconds(TRAIL_LIFETIME));
+    }
+}
@@ -0,0 +1,340 @@
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
+use bevy::input::touch::Touch;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{in_pause_corner, DemoMode, GameState, Livery, SaveData, ViewportSize};
+
+// Who flies a plane. Only versus has a second pilot.
+#[derive(Component, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Pilot {
+    // Whatever is bound to Flap and Shoot
+    One,
+    // Up arrow
+    Two,
+}
+
+impl Pilot {
+    // The order the pilots joined in, which picks their livery
+    pub fn slot(&self) -> usize {
+        match self {
+            Pilot::One => 0,
+            Pilot::Two => 1,
+        }
+    }
+
+    pub fn livery(&self) -> Livery {
+        Livery::for_slot(self.slot())
+    }
+
+    // Named after the color of their plane
+    pub fn name(&self) -> &'static str {
+        self.livery().name
+    }
+}
+
+// What the game asks for instead of specific keys, so the controls can be rebound in the settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Flap,
+    Shoot,
+    Pause,
+    // Menus and the screens that wait for a click. Clicking a menu button goes by where the mouse is instead.
+    Confirm,
+}
+
+impl Action {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Flap => "Flap",
+            Action::Shoot => "Shoot",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    // Any connected gamepad
+    Gamepad(GamepadButtonType),
+}
+
+impl Binding {
+    // Short enough for the settings screen. The bitmap font has no punctuation.
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Mouse(MouseButton::Left) => "Click".to_string(),
+            Binding::Mouse(MouseButton::Right) => "Right click".to_string(),
+            Binding::Mouse(MouseButton::Middle) => "Middle click".to_string(),
+            Binding::Mouse(MouseButton::Other(button)) => format!("Mouse {}", button),
+            Binding::Gamepad(button) => {
+                let name = match button {
+                    GamepadButtonType::South => "A".to_string(),
+                    GamepadButtonType::East => "B".to_string(),
+                    GamepadButtonType::West => "X".to_string(),
+                    GamepadButtonType::North => "Y".to_string(),
+                    GamepadButtonType::LeftTrigger => "LB".to_string(),
+                    GamepadButtonType::RightTrigger => "RB".to_string(),
+                    GamepadButtonType::LeftTrigger2 => "LT".to_string(),
+                    GamepadButtonType::RightTrigger2 => "RT".to_string(),
+                    button => format!("{:?}", button),
+                };
+                format!("Pad {}", name)
+            }
+        }
+    }
+
+    fn is_same_device(&self, other: &Binding) -> bool {
+        matches!(
+            (self, other),
+            (Binding::Key(_), Binding::Key(_))
+                | (Binding::Mouse(_), Binding::Mouse(_))
+                | (Binding::Gamepad(_), Binding::Gamepad(_))
+        )
+    }
+}
+
+// Which inputs trigger each action. Part of the settings, so it's saved along with them.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputBindings {
+    pub flap: Vec<Binding>,
+    pub shoot: Vec<Binding>,
+    pub pause: Vec<Binding>,
+    pub confirm: Vec<Binding>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            flap: vec![
+                Binding::Mouse(MouseButton::Left),
+                Binding::Key(KeyCode::Space),
+                Binding::Gamepad(GamepadButtonType::South),
+            ],
+            shoot: vec![
+                Binding::Mouse(MouseButton::Right),
+                Binding::Key(KeyCode::X),
+                Binding::Gamepad(GamepadButtonType::West),
+            ],
+            pause: vec![
+                Binding::Key(KeyCode::Escape),
+                Binding::Gamepad(GamepadButtonType::Start),
+            ],
+            confirm: vec![
+                Binding::Mouse(MouseButton::Left),
+                Binding::Key(KeyCode::Return),
+                Binding::Key(KeyCode::Space),
+                Binding::Gamepad(GamepadButtonType::South),
+            ],
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        match action {
+            Action::Flap => &self.flap,
+            Action::Shoot => &self.shoot,
+            Action::Pause => &self.pause,
+            Action::Confirm => &self.confirm,
+        }
+    }
+
+    fn get_mut(&mut self, action: Action) -> &mut Vec<Binding> {
+        match action {
+            Action::Flap => &mut self.flap,
+            Action::Shoot => &mut self.shoot,
+            Action::Pause => &mut self.pause,
+            Action::Confirm => &mut self.confirm,
+        }
+    }
+
+    // Replaces whatever the action had on the same device, so binding a key keeps the mouse and gamepad bindings
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.get_mut(action);
+        bindings.retain(|existing| !existing.is_same_device(&binding));
+        bindings.push(binding);
+    }
+
+    pub fn reset(&mut self, action: Action) {
+        *self.get_mut(action) = InputBindings::default().get(action).to_vec();
+    }
+}
+
+// Keyboard, mouse, gamepad and touch together, for asking about actions. Mutable so a press can be used up,
+// which keeps a click that leaves a screen from also counting on the next one.
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+    pub keys: ResMut<'w, Input<KeyCode>>,
+    pub mouse: ResMut<'w, Input<MouseButton>>,
+    gamepad_buttons: ResMut<'w, Input<GamepadButton>>,
+    gamepads: Res<'w, Gamepads>,
+    // Not bindable, a finger anywhere on the screen flaps
+    pub touches: Res<'w, Touches>,
+    used_taps: ResMut<'w, UsedTaps>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> ActionInput<'w, 's> {
+    pub fn pressed(&self, bindings: &InputBindings, action: Action) -> bool {
+        bindings.get(action).iter().any(|binding| match binding {
+            Binding::Key(key) => self.keys.pressed(*key),
+            Binding::Mouse(button) => self.mouse.pressed(*button),
+            Binding::Gamepad(button) => self
+                .gamepad_buttons(*button)
+                .any(|button| self.gamepad_buttons.pressed(button)),
+        })
+    }
+
+    pub fn just_pressed(&self, bindings: &InputBindings, action: Action) -> bool {
+        bindings.get(action).iter().any(|binding| match binding {
+            Binding::Key(key) => self.keys.just_pressed(*key),
+            Binding::Mouse(button) => self.mouse.just_pressed(*button),
+            Binding::Gamepad(button) => self
+                .gamepad_buttons(*button)
+                .any(|button| self.gamepad_buttons.just_pressed(button)),
+        })
+    }
+
+    pub fn consume(&mut self, bindings: &InputBindings, action: Action) {
+        for binding in bindings.get(action) {
+            match binding {
+                Binding::Key(key) => self.keys.reset(*key),
+                Binding::Mouse(button) => self.mouse.reset(*button),
+                Binding::Gamepad(button) => {
+                    let buttons: Vec<GamepadButton> = self.gamepad_buttons(*button).collect();
+                    for button in buttons {
+                        self.gamepad_buttons.reset(button);
+                    }
+                }
+            }
+        }
+    }
+
+    // Menus are moved around with the arrow keys or the d-pad on any gamepad
+    pub fn arrow_just_pressed(&self, key: KeyCode) -> bool {
+        let button_type = match key {
+            KeyCode::Up => GamepadButtonType::DPadUp,
+            KeyCode::Down => GamepadButtonType::DPadDown,
+            KeyCode::Left => GamepadButtonType::DPadLeft,
+            KeyCode::Right => GamepadButtonType::DPadRight,
+            _ => return self.keys.just_pressed(key),
+        };
+
+        self.keys.just_pressed(key) || self.gamepad_just_pressed(button_type)
+    }
+
+    // The button on any gamepad, for the few controls that aren't actions
+    pub fn gamepad_just_pressed(&self, button_type: GamepadButtonType) -> bool {
+        self.gamepad_buttons(button_type)
+            .any(|button| self.gamepad_buttons.just_pressed(button))
+    }
+
+    // Where a finger first touched the screen this frame, in window coordinates like the cursor. Touches can't be
+    // reset like a key, so a used tap is remembered until the end of the frame instead.
+    pub fn tap(&self) -> Option<Vec2> {
+        self.touches
+            .iter_just_pressed()
+            .find(|touch| !self.used_taps.0.contains(&touch.id()))
+            .map(|touch| touch.position())
+    }
+
+    pub fn consume_tap(&mut self) {
+        let ids = self.touches.iter_just_pressed().map(|touch| touch.id());
+        self.used_taps.0.extend(ids);
+    }
+
+    // Whatever was pressed this frame, for rebinding
+    pub fn any_just_pressed(&self) -> Option<Binding> {
+        if let Some(key) = self.keys.get_just_pressed().next() {
+            return Some(Binding::Key(*key));
+        }
+        if let Some(button) = self.mouse.get_just_pressed().next() {
+            return Some(Binding::Mouse(*button));
+        }
+
+        self.gamepad_buttons
+            .get_just_pressed()
+            .next()
+            .map(|button| Binding::Gamepad(button.button_type))
+    }
+
+    // The button on every connected gamepad
+    fn gamepad_buttons(
+        &self,
+        button_type: GamepadButtonType,
+    ) -> impl Iterator<Item = GamepadButton> + '_ {
+        self.gamepads
+            .iter()
+            .map(move |gamepad| GamepadButton::new(*gamepad, button_type))
+    }
+}
+
+// Touches that already did something this frame, see ActionInput::tap
+#[derive(Default)]
+pub struct UsedTaps(Vec<u64>);
+
+pub fn clear_used_taps(mut used_taps: ResMut<UsedTaps>) {
+    used_taps.0.clear();
+}
+
+// What a plane is told to do this frame. Filled in from its pilot's controls, or by the autopilot during a demo.
+// A flap stays queued until a physics step uses it, so flaps are never lost or doubled at any frame rate.
+#[derive(Component, Default)]
+pub struct FlapInput {
+    pub pressed: bool,
+    pub just_pressed: bool,
+    // Queued like a flap, only used in armed mode
+    pub fire: bool,
+}
+
+pub fn flap_input_system(
+    input: ActionInput,
+    viewport: Res<ViewportSize>,
+    save: Res<SaveData>,
+    demo: Res<DemoMode>,
+    state: Res<State<GameState>>,
+    mut query: Query<(&Pilot, &mut FlapInput)>,
+) {
+    if demo.active {
+        return;
+    }
+
+    let bindings = &save.settings.bindings;
+
+    for (pilot, mut flap) in query.iter_mut() {
+        // Clicks on the menus shouldn't be waiting as a flap when the run starts
+        if state.current() != &GameState::Playing {
+            flap.pressed = false;
+            flap.just_pressed = false;
+            flap.fire = false;
+            continue;
+        }
+
+        match pilot {
+            Pilot::One => {
+                // The pause corner is left to pause_system
+                let flaps = |touch: &Touch| !in_pause_corner(touch.start_position(), &viewport);
+                let touched = input.touches.iter().any(flaps);
+                let tapped = input.touches.iter_just_pressed().any(flaps);
+
+                flap.pressed = input.pressed(bindings, Action::Flap) || touched;
+                flap.just_pressed |= input.just_pressed(bindings, Action::Flap) || tapped;
+                flap.fire |= input.just_pressed(bindings, Action::Shoot);
+            }
+            // Shares the keyboard with the first pilot, so it isn't rebindable
+            Pilot::Two => {
+                flap.pressed = input.keys.pressed(KeyCode::Up);
+                flap.just_pressed |= input.keys.just_pressed(KeyCode::Up);
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::f32::consts::TAU;
+
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use rand::prelude::*;
+
+use crate::{
+    tick_spawn_timer, CollisionPolygon, DespawnOffscreen, GameRng, Hazard, HazardKind, HazardSpawn,
+    HorizontalVelocity, Interpolated, Layer, Score, ScreenAnchor, SpawnHooks, Telegraph,
+    TelegraphStyle, COURSE_VELOCITY, PHYSICS_STEP,
+};
+
+// Only for players who have made it this far, the beam leaves little room for error
+const LASER_MIN_SCORE: u64 = 60;
+const BEAM_LENGTH: f32 = 110.0;
+const BEAM_WIDTH: f32 = 4.0;
+const GLOW_WIDTH: f32 = 16.0;
+// The beams reach out this far from the hub, so it's only gone once they are
+const LASER_MARGIN: f32 = BEAM_LENGTH + 20.0;
+
+// Seconds the beam is on, then off. The off phase is the window to fly past.
+const ON_TIME: f32 = 1.6;
+const OFF_TIME: f32 = 1.4;
+// The last part of the off phase shows where the beam is about to be
+const PREVIEW_TIME: f32 = 0.5;
+// Seconds of warning before the first time it fires
+const FIRST_WARNING: f32 = 1.5;
+
+// A hub that spins a beam around itself, switching it on and off. While the beam is on the hub has a segment
+// collider along it, while it's off there's nothing to hit.
+#[derive(Component, Clone)]
+pub struct LaserGate {
+    angle: f32,
+    // In radians per second, negative spins clockwise
+    spin: f32,
+    // Seconds into the current on/off cycle
+    cycle: f32,
+}
+
+impl LaserGate {
+    fn is_on(&self) -> bool {
+        self.cycle < ON_TIME
+    }
+
+    fn beam_end(&self) -> Vec2 {
+        Vec2::new(self.angle.cos(), self.angle.sin()) * BEAM_LENGTH
+    }
+}
+
+// The beam or its glow, with the opacity it has while the beam is on
+#[derive(Component)]
+pub struct LaserBeam {
+    alpha: f32,
+}
+
+pub struct LaserTimer(pub Timer);
+
+impl Default for LaserTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.0, false))
+    }
+}
+
+pub fn reset_lasers(mut timer: ResMut<LaserTimer>) {
+    *timer = LaserTimer::default();
+}
+
+pub fn laser_spawn_system(
+    mut commands: Commands,
+    mut timer: ResMut<LaserTimer>,
+    score: Res<Score>,
+    mut rng: ResMut<GameRng>,
+    hooks: Res<SpawnHooks>,
+) {
+    if score.0 < LASER_MIN_SCORE {
+        return;
+    }
+
+    if !tick_spawn_timer(&mut timer.0, || rng.course.gen_range(9.0..14.0)) {
+        return;
+    }
+
+    let y = rng.course.gen_range(-60.0..60.0);
+    let direction = if rng.course.gen_bool(0.5) { 1.0 } else { -1.0 };
+    let laser = LaserGate {
+        angle: rng.course.gen_range(0.0..TAU),
+        spin: rng.course.gen_range(1.0..1.5) * direction,
+        // About to fire, which it only does once its telegraph has run out
+        cycle: ON_TIME + OFF_TIME - PREVIEW_TIME,
+    };
+
+    let mut spawn = HazardSpawn::new(
+        HazardKind::LaserGate,
+        ScreenAnchor::Right.offset(LASER_MARGIN, y),
+        score.0,
+    );
+    if !hooks.hazard(&mut spawn) {
+        return;
+    }
+
+    let transform = Transform::from_translation(spawn.position.extend(Layer::Course.z()));
+    let mut entity = spawn_laser_gate(&mut commands, transform, laser);
+    entity.insert(Telegraph::new(FIRST_WARNING, TelegraphStyle::Edge).scrolling());
+    spawn.apply_attached(&mut entity);
+}
+
+pub fn spawn_laser_gate<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    transform: Transform,
+    laser: LaserGate,
+) -> EntityCommands<'w, 's, 'a> {
+    let rotation = Quat::from_rotation_z(laser.angle);
+
+    let mut entity = commands.spawn_bundle(SpriteBundle {
+        sprite: Sprite {
+            color: Color::rgb(0.3, 0.3, 0.35),
+            custom_size: Some(Vec2::new(18.0, 18.0)),
+            ..default()
+        },
+        transform,
+        ..default()
+    });
+
+    entity
+        .insert(HorizontalVelocity(COURSE_VELOCITY))
+        .insert(DespawnOffscreen {
+            margin: LASER_MARGIN,
+        })
+        .insert(Interpolated::default())
+        .insert(Hazard)
+        .insert(laser)
+        .with_children(|parent| {
+            // The glow behind the beam, then the beam itself
+            for (width, color, z) in [
+                (GLOW_WIDTH, Color::rgba(1.0, 0.2, 0.2, 0.3), -0.02),
+                (BEAM_WIDTH, Color::rgb(1.0, 0.85, 0.85), -0.01),
+            ] {
+                parent
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color,
+                            custom_size: Some(Vec2::new(BEAM_LENGTH, width)),
+                            anchor: Anchor::CenterLeft,
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(0.0, 0.0, z).with_rotation(rotation),
+                        visibility: Visibility { is_visible: false },
+                        ..default()
+                    })
+                    .insert(LaserBeam { alpha: color.a() });
+            }
+        });
+
+    entity
+}
+
+pub fn laser_system(
+    mut commands: Commands,
+    mut lasers: Query<
+        (
+            Entity,
+            &mut LaserGate,
+            &Transform,
+            &Children,
+            Option<&Telegraph>,
+            Option<&mut CollisionPolygon>,
+        ),
+        Without<LaserBeam>,
+    >,
+    mut beams: Query<(&LaserBeam, &mut Transform, &mut Visibility, &mut Sprite)>,
+) {
+    for (entity, mut laser, transform, children, telegraph, collider) in lasers.iter_mut() {
+        laser.angle = (laser.angle + laser.spin * PHYSICS_STEP).rem_euclid(TAU);
+
+        // Held just before firing until the telegraph is done
+        if telegraph.is_none() {
+            let was_on = laser.is_on();
+            laser.cycle = (laser.cycle + PHYSICS_STEP) % (ON_TIME + OFF_TIME);
+
+            if was_on && !laser.is_on() {
+                commands.entity(entity).remove::<CollisionPolygon>();
+            }
+        }
+
+        // Swapped out in place every step, since the beam keeps turning and the collision check runs before
+        // commands are applied. Only the step it switches on has to go through commands.
+        if laser.is_on() {
+            let segment = CollisionPolygon::segment(Vec2::ZERO, laser.beam_end());
+            match collider {
+                Some(mut collider) => *collider = segment,
+                None => {
+                    commands.entity(entity).insert(segment);
+                }
+            }
+        }
+
+        // Solid while it's on, a faint blinking line while it's about to fire
+        let about_to_fire = telegraph.is_some() || laser.cycle > ON_TIME + OFF_TIME - PREVIEW_TIME;
+        let (visible, alpha) = if laser.is_on() {
+            (true, 1.0)
+        } else {
+            (about_to_fire && (laser.cycle * 10.0) as u32 % 2 == 0, 0.25)
+        };
+
+        let rotation = Quat::from_rotation_z(laser.angle);
+
+        for child in children.iter() {
+            if let Ok((beam, mut beam_transform, mut visibility, mut sprite)) =
+                beams.get_mut(*child)
+            {
+                beam_transform.rotation = rotation;
+                visibility.is_visible = visible;
+                sprite.color.set_a(beam.alpha * alpha);
+            }
+        }
+    }
+}
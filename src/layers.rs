@@ -0,0 +1,34 @@
+use serde::Deserialize;
+
+// Where everything sits in z, back to front. Each layer gets a whole unit of z to itself, so something that has to
+// be just in front of or behind its neighbour (a fade over the sky, smoke behind the plane) can be offset by a
+// fraction without ending up in another layer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Layer {
+    // The sky and the parallax layers behind the course
+    Background,
+    Stars,
+    // Fireworks and batched particles
+    Particles,
+    Ghost,
+    Bullets,
+    // The player, rocks, enemies and everything else that can be collided with
+    Course,
+    // Only visible with the debug overlay on
+    CollisionOutlines,
+    // Parallax layers that scroll in front of the course, like the ground and the ceiling
+    Foreground,
+    Fog,
+    // Menu buttons and the title and game over images
+    Ui,
+    // Telegraph markers, in front of the fog so they still warn about what's behind it
+    Markers,
+    // Text, gauges and the widgets of the menu screens
+    Overlay,
+}
+
+impl Layer {
+    pub fn z(self) -> f32 {
+        self as u8 as f32
+    }
+}
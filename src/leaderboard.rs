@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    display_name, online_allowed, Action, ActionInput, BitmapText, BitmapTextBundle, GameModes,
+    GameResult, GameState, Layer, NewBest, RemoveAfterState, RunMode, RunSpeed, RunStats, SaveData,
+    Score, ScreenAnchor, TextEffect, TopScoreEntered, NEW_BEST_COLOR,
+};
+
+const LEADERBOARD_SIZE: usize = 10;
+const NAME_LENGTH: usize = 3;
+
+const TABLE_X: f32 = 120.0;
+const TABLE_Y: f32 = 55.0;
+const TABLE_LINE_HEIGHT: f32 = 23.0;
+
+// Horizontal distance between the letters on the name entry screen
+const LETTER_SPACING: f32 = 80.0;
+const LETTER_Y: f32 = -20.0;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: u64,
+    // None for entries from before runs were tagged with their speed
+    #[serde(default)]
+    pub speed: Option<RunSpeed>,
+}
+
+// The online top scores of each mode, best first. Filled in by online.rs when the feature is on, the menu shows
+// the local ones until they arrive or when they can't be had.
+#[derive(Default)]
+pub struct GlobalTopScores(pub HashMap<RunMode, Vec<LeaderboardEntry>>);
+
+// Local top scores, best first. Stored in the save file.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    pub fn qualifies(&self, score: u64) -> bool {
+        score > 0
+            && (self.entries.len() < LEADERBOARD_SIZE
+                || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    pub fn insert(&mut self, name: String, score: u64, speed: Option<RunSpeed>) {
+        // Ties go to whoever got there first
+        let position = self
+            .entries
+            .iter()
+            .position(|entry| score > entry.score)
+            .unwrap_or(self.entries.len());
+
+        self.entries
+            .insert(position, LeaderboardEntry { name, score, speed });
+        self.entries.truncate(LEADERBOARD_SIZE);
+    }
+
+    // Entries that are on both boards (e.g. from a profile that was exported earlier) are only kept once
+    pub fn merge(&mut self, other: Leaderboard) {
+        for entry in other.entries {
+            let duplicate = self
+                .entries
+                .iter()
+                .any(|existing| existing.name == entry.name && existing.score == entry.score);
+
+            if !duplicate && self.qualifies(entry.score) {
+                self.insert(entry.name, entry.score, entry.speed);
+            }
+        }
+    }
+}
+
+// Only exists while a top score is waiting for its name
+pub struct NameEntry {
+    mode: RunMode,
+    score: u64,
+    speed: RunSpeed,
+    letters: [char; NAME_LENGTH],
+    cursor: usize,
+}
+
+#[derive(Component)]
+pub struct NameEntryLetter(usize);
+
+#[derive(Component)]
+pub struct NameEntryCursor;
+
+#[derive(Component)]
+pub struct LeaderboardLine;
+
+pub fn check_leaderboard(
+    mut commands: Commands,
+    score: Res<Score>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    save: Res<SaveData>,
+    run: Res<RunStats>,
+) {
+    let qualifies = modes
+        .get(*mode)
+        .leaderboard(&save)
+        .map_or(false, |leaderboard| leaderboard.qualifies(score.0));
+
+    if qualifies {
+        commands.insert_resource(NameEntry {
+            mode: *mode,
+            score: score.0,
+            speed: run.speed,
+            letters: ['A'; NAME_LENGTH],
+            cursor: 0,
+        });
+    }
+}
+
+pub fn setup_name_entry(mut commands: Commands, entry: Res<NameEntry>) {
+    commands
+        .spawn_bundle(
+            BitmapTextBundle::at(ScreenAnchor::Left.offset(100.0, 120.0))
+                .with_text(format!("New top score {}", entry.score)),
+        )
+        .insert(
+            TextEffect::default()
+                .with_typewriter(20.0)
+                .with_bounce(8.0)
+                .with_pulse(0.06),
+        )
+        .insert(RemoveAfterState);
+
+    for (i, letter) in entry.letters.iter().enumerate() {
+        let x = letter_x(i);
+
+        commands
+            .spawn_bundle(BitmapTextBundle::new(x - 30.0, LETTER_Y).with_text(letter.to_string()))
+            .insert(NameEntryLetter(i))
+            .insert(RemoveAfterState);
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(50.0, 5.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(letter_x(0), LETTER_Y - 40.0, Layer::Overlay.z()),
+            ..default()
+        })
+        .insert(NameEntryCursor)
+        .insert(RemoveAfterState);
+}
+
+// Letters can be typed, picked with the arrow keys, or picked with the scroll wheel and confirmed by clicking or
+// tapping
+pub fn name_entry_system(
+    mut entry: ResMut<NameEntry>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut wheel: EventReader<MouseWheel>,
+    mut input: ActionInput,
+    save: Res<SaveData>,
+    mut state: ResMut<State<GameState>>,
+    mut letter_query: Query<(&NameEntryLetter, &mut BitmapText)>,
+    mut cursor_query: Query<&mut Transform, With<NameEntryCursor>>,
+) -> GameResult {
+    let mut step = 0;
+    for event in wheel.iter() {
+        step += event.y.signum() as i32;
+    }
+    if input.keys.just_pressed(KeyCode::Up) {
+        step += 1;
+    }
+    if input.keys.just_pressed(KeyCode::Down) {
+        step -= 1;
+    }
+    if step != 0 {
+        let cursor = entry.cursor;
+        entry.letters[cursor] = cycle_letter(entry.letters[cursor], step);
+    }
+
+    for event in characters.iter() {
+        if event.char.is_ascii_alphabetic() {
+            let cursor = entry.cursor;
+            entry.letters[cursor] = event.char.to_ascii_uppercase();
+            entry.cursor = (cursor + 1).min(NAME_LENGTH - 1);
+        }
+    }
+
+    if input.keys.just_pressed(KeyCode::Left) || input.keys.just_pressed(KeyCode::Back) {
+        entry.cursor = entry.cursor.saturating_sub(1);
+    }
+    if input.keys.just_pressed(KeyCode::Right) {
+        entry.cursor = (entry.cursor + 1).min(NAME_LENGTH - 1);
+    }
+
+    let bindings = &save.settings.bindings;
+    let tapped = input.tap().is_some();
+    if input.mouse.just_pressed(MouseButton::Left) || tapped {
+        input.mouse.reset(MouseButton::Left);
+        input.consume_tap();
+
+        if entry.cursor + 1 < NAME_LENGTH {
+            entry.cursor += 1;
+        } else {
+            state.set(GameState::Menu)?;
+        }
+    } else if input.just_pressed(bindings, Action::Confirm) {
+        state.set(GameState::Menu)?;
+        input.consume(bindings, Action::Confirm);
+    }
+
+    if !entry.is_changed() {
+        return Ok(());
+    }
+
+    for (letter, mut text) in letter_query.iter_mut() {
+        let c = entry.letters[letter.0].to_string();
+        if text.text != c {
+            text.text = c;
+        }
+    }
+
+    if let Ok(mut transform) = cursor_query.get_single_mut() {
+        transform.translation.x = letter_x(entry.cursor);
+    }
+
+    Ok(())
+}
+
+pub fn finish_name_entry(
+    mut commands: Commands,
+    entry: Res<NameEntry>,
+    modes: Res<GameModes>,
+    mut save: ResMut<SaveData>,
+    mut entered: EventWriter<TopScoreEntered>,
+) {
+    let name: String = entry.letters.iter().collect();
+
+    if let Some(leaderboard) = modes.get(entry.mode).leaderboard_mut(&mut save) {
+        leaderboard.insert(name.clone(), entry.score, Some(entry.speed));
+    }
+
+    entered.send(TopScoreEntered {
+        mode: entry.mode,
+        name,
+        score: entry.score,
+        speed: entry.speed,
+    });
+
+    commands.remove_resource::<NameEntry>();
+}
+
+// Shows the leaderboard of the selected mode, rebuilt whenever the mode changes or the online one arrives
+pub fn leaderboard_table_system(
+    mut commands: Commands,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    save: Res<SaveData>,
+    global: Res<GlobalTopScores>,
+    new_best: Option<Res<NewBest>>,
+    lines: Query<Entity, With<LeaderboardLine>>,
+) {
+    // Settings are part of the save, so this also catches streamer mode being toggled
+    if !mode.is_changed() && !save.is_changed() && !global.is_changed() && !lines.is_empty() {
+        return;
+    }
+
+    for line in lines.iter() {
+        commands.entity(line).despawn_recursive();
+    }
+
+    let local = match modes.get(*mode).leaderboard(&save) {
+        Some(leaderboard) => &leaderboard.entries,
+        None => return,
+    };
+    let (title, entries) = match global.0.get(&*mode) {
+        Some(entries) if online_allowed(&save.settings) && !entries.is_empty() => {
+            ("World top 10", entries)
+        }
+        _ => ("Your top 10", local),
+    };
+
+    if entries.is_empty() {
+        return;
+    }
+
+    let mut bundle = BitmapTextBundle::new(TABLE_X, TABLE_Y + TABLE_LINE_HEIGHT + 4.0)
+        .with_text(title.to_string());
+    bundle.transform.scale = Vec3::new(0.3, 0.3, 1.0);
+    commands
+        .spawn_bundle(bundle)
+        .insert(LeaderboardLine)
+        .insert(RemoveAfterState);
+
+    // The row of the run that just beat the best score, pulsing in the same color as its banner
+    let highlighted = new_best
+        .filter(|best| best.mode == *mode)
+        .and_then(|best| entries.iter().position(|entry| entry.score == best.score));
+
+    for (i, entry) in entries.iter().take(LEADERBOARD_SIZE).enumerate() {
+        let mut bundle = BitmapTextBundle::new(TABLE_X, TABLE_Y - i as f32 * TABLE_LINE_HEIGHT)
+            .with_text(format!(
+                "{} {} {}",
+                i + 1,
+                display_name(&entry.name, &save.settings),
+                entry.score
+            ));
+        bundle.transform.scale = Vec3::new(0.3, 0.3, 1.0);
+        if highlighted == Some(i) {
+            bundle = bundle.with_color(NEW_BEST_COLOR);
+        }
+
+        let mut line = commands.spawn_bundle(bundle);
+        line.insert(LeaderboardLine).insert(RemoveAfterState);
+
+        if highlighted == Some(i) {
+            line.insert(TextEffect::default().with_pulse(0.08));
+        }
+    }
+}
+
+fn letter_x(index: usize) -> f32 {
+    (index as f32 - (NAME_LENGTH - 1) as f32 / 2.0) * LETTER_SPACING
+}
+
+fn cycle_letter(letter: char, step: i32) -> char {
+    let index = (letter as u8 - b'A') as i32;
+    (b'A' + (index + step).rem_euclid(26) as u8) as char
+}
@@ -0,0 +1,1367 @@
+//! A plane flying through gaps between rocks, built on bevy.
+//!
+//! The game is a library so it can be embedded in other apps, driven by tests, or extended by mods. Everything
+//! comes with [`GamePlugin`]:
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use bevy_plane::GamePlugin;
+//!
+//! App::new().add_plugin(GamePlugin).run();
+//! ```
+//!
+//! Mods register [`SpawnHooks`] while the app is built and read the resources exported here, like the [`Score`]
+//! of the current run and the [`GameState`] it's in.
+
+mod achievements;
+mod armed;
+mod assets;
+mod audio;
+mod backgrounds;
+mod behaviors;
+mod blades;
+mod bounds;
+mod camera;
+mod celebration;
+#[cfg(all(feature = "clip", not(target_arch = "wasm32")))]
+mod clip;
+mod combo;
+mod config;
+#[cfg(feature = "dev-console")]
+mod console;
+mod continues;
+mod crumbling;
+mod cutscene;
+mod daily;
+mod damage;
+mod daynight;
+mod debug;
+mod demo;
+mod difficulty;
+mod easing;
+mod enemies;
+mod error;
+mod events;
+mod featured;
+mod fireworks;
+mod fuel;
+mod game_over;
+mod ghost;
+mod gravity_flip;
+mod handheld;
+mod headless;
+mod heatmap;
+mod hooks;
+mod hud;
+mod hulls;
+mod identity;
+mod input;
+mod lasers;
+mod layers;
+mod leaderboard;
+mod lifetime;
+mod lives;
+mod marquee;
+mod menu;
+mod milestones;
+mod modes;
+#[cfg(feature = "online")]
+mod online;
+mod packs;
+mod parallax;
+mod particles;
+mod patterns;
+mod pause;
+mod pendulums;
+mod photo_finish;
+mod physics;
+mod platform;
+mod power;
+mod powerups;
+mod practice;
+mod privacy;
+mod profile;
+mod profiles;
+mod quality;
+#[cfg(all(feature = "net-race", not(target_arch = "wasm32")))]
+mod race;
+#[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+mod recorder;
+mod records;
+mod resolution;
+#[cfg(feature = "online")]
+mod rival;
+mod rng;
+mod rock_pool;
+mod rocks;
+mod save;
+mod screen;
+mod seasons;
+mod settings;
+mod settings_screen;
+mod simulation_time;
+mod skins;
+mod speed_zones;
+mod speedrun;
+mod stalactites;
+mod stats;
+mod style;
+#[cfg(feature = "cloud-sync")]
+mod sync;
+#[cfg(feature = "debug-tools")]
+mod tas;
+mod telegraph;
+mod text;
+mod textures;
+mod theme;
+mod time_trial;
+mod toast;
+mod trail;
+mod tutorial;
+mod tween;
+mod versus;
+mod warm_up;
+mod weather;
+mod weekly;
+mod whats_new;
+mod wind_down;
+
+use std::f32::consts::PI;
+
+use achievements::*;
+use armed::*;
+use assets::*;
+use audio::*;
+use backgrounds::*;
+use behaviors::*;
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
+use bevy::ecs::system::EntityCommands;
+use bevy::gilrs::GilrsPlugin;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use bevy::winit::WinitPlugin;
+use bevy_prototype_lyon::prelude::*;
+use blades::*;
+use bounds::*;
+use camera::*;
+use celebration::*;
+#[cfg(all(feature = "clip", not(target_arch = "wasm32")))]
+use clip::*;
+use combo::*;
+use config::*;
+#[cfg(feature = "dev-console")]
+use console::*;
+use continues::*;
+use crumbling::*;
+use cutscene::*;
+use daily::*;
+use damage::*;
+use daynight::*;
+use debug::*;
+use demo::*;
+use difficulty::*;
+use easing::*;
+use enemies::*;
+use error::*;
+use events::*;
+use featured::*;
+use fireworks::*;
+use fuel::*;
+use game_over::*;
+use ghost::*;
+use gravity_flip::*;
+use handheld::*;
+use headless::*;
+use heatmap::*;
+use hooks::*;
+use hud::*;
+use hulls::*;
+use identity::*;
+use input::*;
+use lasers::*;
+use layers::*;
+use leaderboard::*;
+use lifetime::*;
+use lives::*;
+use marquee::*;
+use menu::*;
+use milestones::*;
+use modes::*;
+use ncollide2d::na::Point2;
+use ncollide2d::shape::ConvexPolygon;
+#[cfg(feature = "online")]
+use online::*;
+use packs::*;
+use parallax::*;
+use particles::*;
+use patterns::*;
+use pause::*;
+use pendulums::*;
+use photo_finish::*;
+use physics::*;
+use platform::*;
+use power::*;
+use powerups::*;
+use practice::*;
+use privacy::*;
+use profile::*;
+use profiles::*;
+use quality::*;
+#[cfg(all(feature = "net-race", not(target_arch = "wasm32")))]
+use race::*;
+#[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+use recorder::*;
+use records::*;
+use resolution::*;
+#[cfg(feature = "online")]
+use rival::*;
+use rng::*;
+use rock_pool::*;
+use rocks::*;
+use save::*;
+use screen::*;
+use seasons::*;
+use settings::*;
+use settings_screen::*;
+use simulation_time::*;
+use skins::*;
+use speed_zones::*;
+use speedrun::*;
+use stalactites::*;
+use stats::*;
+use style::*;
+#[cfg(feature = "cloud-sync")]
+use sync::*;
+#[cfg(feature = "debug-tools")]
+use tas::*;
+use telegraph::*;
+use text::*;
+use textures::*;
+use theme::*;
+use time_trial::*;
+use toast::*;
+use trail::*;
+use tutorial::*;
+use tween::*;
+use versus::*;
+use warm_up::*;
+use weather::*;
+use weekly::*;
+use whats_new::*;
+use wind_down::*;
+
+pub use behaviors::Behavior;
+pub use config::GameConfig;
+pub use headless::HeadlessPlugin;
+pub use hooks::{GateSpawn, HazardKind, HazardSpawn, SpawnDecision, SpawnHooks};
+pub use modes::RunMode;
+
+pub type PlayerShape = ConvexPolygon<f32>;
+
+#[derive(Component)]
+pub struct Player {
+    velocity: f32,
+    shape: PlayerShape,
+    // Of the plane sprite it flies, see PlaneHulls
+    hull: Vec<(f32, f32)>,
+    // Upside down, see GravityFlip
+    flipped: bool,
+    // Seconds spent out of sight, see BoundsRule
+    outside: f32,
+}
+
+impl Player {
+    fn new(hull: &[(f32, f32)]) -> Self {
+        Player {
+            velocity: BUMP,
+            shape: player_shape(hull, false),
+            hull: hull.to_vec(),
+            flipped: false,
+            outside: 0.0,
+        }
+    }
+
+    // The hull is mirrored along with the sprite
+    fn set_flipped(&mut self, flipped: bool, sprite: &mut Sprite) {
+        self.flipped = flipped;
+        self.shape = player_shape(&self.hull, flipped);
+        sprite.flip_y = flipped;
+    }
+
+    // For when the plane changes to another sprite
+    fn set_hull(&mut self, hull: &[(f32, f32)]) {
+        self.hull = hull.to_vec();
+        self.shape = player_shape(hull, self.flipped);
+    }
+}
+
+// The plane player one flies in every mode. It's the one the ghost is recorded from, the autopilot flies in
+// the demo and a photo finish zooms in on. Everything that has to do with every plane goes by Player instead.
+#[derive(Component)]
+pub struct Controlled;
+
+// The plane is drawn at half size
+fn player_shape(hull: &[(f32, f32)], flipped: bool) -> PlayerShape {
+    let y_scale = if flipped { -0.5 } else { 0.5 };
+    let points: Vec<Point2<f32>> = hull
+        .iter()
+        .map(|(x, y)| Point2::new(x / 2.0, y * y_scale))
+        .collect();
+
+    ConvexPolygon::try_from_points(&points)
+        .expect("load_plane_hulls checks that every hull is convex")
+}
+
+// A child of the plane, so the hull in sprite pixels is scaled along with the sprite
+pub fn add_player_outline(entity: &mut EntityCommands, hull: &[(f32, f32)]) {
+    add_collision_outline(entity, hull, Vec3::ONE);
+}
+
+/// The screen the game is on. A run goes from `Start` through `Playing` (and `Paused`) to `GameOver`, and to
+/// `NameEntry` from there when the score made the leaderboard. A crash that ends the run can stop at `Continue`
+/// on the way to `GameOver`.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum GameState {
+    /// Loading the assets, and measuring how well this machine runs the game on the first launch
+    Loading,
+    Menu,
+    Settings,
+    Stats,
+    /// Picking who plays, at startup when there's more than one profile and with P on the menu
+    Profiles,
+    /// The changelog of this version, once after an update and with N on the menu
+    WhatsNew,
+    /// Picking the plane to fly, with L on the menu. Locked ones show what unlocks them.
+    Hangar,
+    /// Browsing curated seeds, with G on the menu
+    Featured,
+    /// Plays whatever cutscene is up next
+    Cutscene,
+    /// Counting down to the run
+    Start,
+    Playing,
+    Paused,
+    /// Counting down after a crash that ended the run, clicking before it's over flies on instead
+    Continue,
+    GameOver,
+    NameEntry,
+}
+
+/// How fast the course scrolls, 1 at the start of a run and ramping up from there
+pub struct GameSpeed(pub f32);
+
+#[derive(Component)]
+struct RemoveAfterState;
+
+#[derive(Component, Clone)]
+struct HorizontalVelocity(f32);
+
+/// Points in the current run, kept until the next one starts
+pub struct Score(pub u64); // Clearly this needs to be u64 in case someone ever scores over 4 billion
+
+const WIDTH: f32 = 800.0;
+const HEIGHT: f32 = 480.0;
+const GRAVITY: f32 = 550.0;
+const BUMP: f32 = 239.0;
+const PLAYER_WIDTH: f32 = 88.0;
+// Where the plane flies, gusts can blow it back from here for a while
+const PLANE_X: f32 = -200.0;
+
+const GROUND_HEIGHT: f32 = 73.0;
+
+// At this velocity, the player is facing downwards
+const FREE_FALL_VELOCITY: f32 = BUMP - GRAVITY * 1.6;
+
+/// The whole game. Optional parts are plugins of their own behind the cargo features in Cargo.toml, so builds
+/// like web embeds only carry what they need.
+///
+/// It adds bevy's `DefaultPlugins` itself, with a window set up from the player's settings, so an app needs
+/// nothing else to run it. Add [`HeadlessPlugin`] before it to run without a window.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        // Tests run the game without a window, a GPU or anything on disk, see HeadlessPlugin
+        let headless = app.world.contains_resource::<Headless>();
+        let profiles = if headless {
+            Profiles::default()
+        } else {
+            load_profiles()
+        };
+        let mut save = if headless {
+            SaveData::in_memory()
+        } else {
+            load_save(&profiles.selected().file)
+        };
+        apply_device_preset(&mut save.settings);
+        // Always the built in assets and tuning for a save in memory
+        let (pack, config) = load_asset_pack(save.settings.asset_pack.as_deref());
+        let packs = if headless {
+            AvailablePacks(vec![])
+        } else {
+            find_asset_packs()
+        };
+
+        app.insert_resource(WindowDescriptor {
+            width: WIDTH,
+            height: HEIGHT,
+            mode: window_mode(&save.settings),
+            present_mode: present_mode(&save.settings),
+            // In a browser the canvas follows the size of the page, viewport_system letterboxes it like a window
+            fit_canvas_to_parent: true,
+            ..default()
+        })
+        // Fills the bars around the playfield, see ViewportSize
+        .insert_resource(ClearColor(Color::BLACK))
+        .insert_resource(config)
+        .insert_resource(pack)
+        .insert_resource(packs)
+        .insert_resource(GameSpeed(1.0))
+        .insert_resource(RockTimer(Timer::from_seconds(0.0, false)))
+        .insert_resource(load_obstacle_patterns())
+        .insert_resource(load_plane_hulls())
+        .insert_resource(load_background_themes())
+        .insert_resource(load_parallax_layers())
+        .insert_resource(load_cutscenes())
+        .insert_resource(load_featured_runs())
+        .insert_resource(profiles)
+        .init_resource::<NextCutscene>()
+        .init_resource::<FontMetrics>()
+        .init_resource::<ObstacleQueue>()
+        .init_resource::<RockPool>()
+        .init_resource::<RockShapes>()
+        .init_resource::<CollisionStats>()
+        .init_resource::<EnemyTimer>()
+        .init_resource::<Ammo>()
+        .init_resource::<TimeTrialClock>()
+        .init_resource::<PowerUpTimer>()
+        .init_resource::<Fuel>()
+        .init_resource::<FuelCanSpawner>()
+        .init_resource::<SpeedrunTimer>()
+        .init_resource::<Lives>()
+        .init_resource::<Continues>()
+        .init_resource::<WindDown>()
+        .init_resource::<Combo>()
+        .init_resource::<StalactiteTimer>()
+        .init_resource::<SpawnHooks>()
+        .init_resource::<PendulumTimer>()
+        .init_resource::<BladeTimer>()
+        .init_resource::<SpeedZone>()
+        .init_resource::<StreakSpawner>()
+        .init_resource::<TimeOfDay>()
+        .init_resource::<LaserTimer>()
+        .init_resource::<MenuSelection>()
+        .init_resource::<SettingsSelection>()
+        .init_resource::<Rebinding>()
+        .init_resource::<StartCountdown>()
+        .init_resource::<GameOverPanel>()
+        .init_resource::<GlobalTopScores>()
+        .insert_resource(Score(0))
+        .insert_resource(RunMode::Endless)
+        .add_game_mode(EndlessMode)
+        .add_game_mode(DailyMode)
+        .add_game_mode(WeeklyMode)
+        .add_game_mode(VersusMode)
+        .add_game_mode(ArmedMode)
+        .add_game_mode(PracticeMode)
+        .add_game_mode(TimeTrialMode)
+        .add_game_mode(FeaturedMode)
+        .add_game_mode(ZenMode)
+        .add_game_mode(HardcoreMode)
+        .init_resource::<GameRng>()
+        .init_resource::<ActiveMutators>()
+        .init_resource::<ActiveDifficulty>()
+        .init_resource::<StyleScore>()
+        .init_resource::<StyleTracker>()
+        .init_resource::<RunStats>()
+        .init_resource::<SessionStats>()
+        .init_resource::<GhostRecorder>()
+        .init_resource::<WindowFocus>()
+        .insert_resource(save)
+        .init_resource::<SimulationTime>()
+        .init_resource::<CameraZoom>()
+        .init_resource::<CameraShake>()
+        .init_resource::<DemoMode>()
+        .init_resource::<PhysicsClock>()
+        .init_resource::<WeatherSpawner>()
+        .init_resource::<WeatherEffects>()
+        .init_resource::<DynamicResolution>()
+        .init_resource::<ViewportSize>()
+        .init_resource::<UsedTaps>()
+        .init_resource::<DebugSettings>()
+        .init_resource::<WarmUp>()
+        .init_resource::<TextureMemory>()
+        .init_resource::<MenuIdle>()
+        .add_plugins_with(DefaultPlugins, |group| {
+            if headless {
+                group.disable::<WinitPlugin>().disable::<GilrsPlugin>();
+            }
+            group
+        })
+        .add_plugin(ShapePlugin)
+        .add_plugin(HudPlugin)
+        .add_plugin(TweenPlugin)
+        .add_plugin(FrameTimeDiagnosticsPlugin)
+        // Needs the save and the asset server
+        .init_resource::<ThemeState>()
+        .init_resource::<GameAssets>()
+        .add_event::<FlapEvent>()
+        .add_event::<GatePassedEvent>()
+        .add_event::<PhotoFinishEvent>()
+        .add_event::<NearMissEvent>()
+        .add_event::<CrashEvent>()
+        .add_event::<Shake>()
+        .add_event::<TopScoreEntered>()
+        .add_event::<ShowToast>()
+        .add_event::<SoundCue>()
+        .add_event::<PowerUpCollected>()
+        .add_event::<GameOverSection>()
+        .add_state(GameState::Loading)
+        .add_startup_system(setup_camera)
+        .add_startup_system(setup)
+        .add_startup_system(setup_style)
+        .add_startup_system(setup_particle_batch)
+        .add_startup_system(setup_debug_overlay)
+        .add_startup_system(setup_stars)
+        .add_startup_system(read_only_save_warning)
+        .add_system_set(
+            SystemSet::on_enter(GameState::Loading)
+                .with_system(setup_warm_up)
+                .with_system(setup_quality_benchmark)
+                .with_system(prewarm_rock_pool),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Loading)
+                .with_system(warm_up_system.chain(report_error))
+                .with_system(quality_benchmark_system),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(state_cleanup_system))
+        .add_system_set(
+            SystemSet::on_enter(GameState::Menu)
+                .with_system(end_demo)
+                .with_system(setup_menu)
+                .with_system(setup_mode_label)
+                .with_system(check_whats_new.chain(report_error)),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Menu)
+                .with_system(menu_system.chain(report_error).label(MenuSystem))
+                .with_system(menu_highlight_system.after(MenuSystem).after(TweenSystem))
+                .with_system(mode_select_system)
+                .with_system(mode_label_system)
+                .with_system(leaderboard_table_system)
+                .with_system(profile_system.chain(report_error))
+                .with_system(profile_screen_shortcut_system.chain(report_error))
+                .with_system(whats_new_shortcut_system.chain(report_error))
+                .with_system(hangar_shortcut_system.chain(report_error))
+                .with_system(featured_shortcut_system.chain(report_error))
+                .with_system(streamer_mode_toggle_system)
+                .with_system(demo_idle_system.chain(report_error))
+                .with_system(theme_select_system)
+                .with_system(speedrun_toggle_system)
+                .with_system(difficulty_select_system),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Menu)
+                .with_system(cancel_profile_import)
+                .with_system(state_cleanup_system),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::Settings).with_system(setup_settings_screen))
+        .add_system_set(
+            SystemSet::on_update(GameState::Settings)
+                .with_system(settings_system.chain(report_error))
+                .with_system(settings_widget_system),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Settings).with_system(state_cleanup_system))
+        .add_system_set(
+            SystemSet::on_enter(GameState::Stats)
+                .with_system(setup_stats_screen)
+                .with_system(setup_crash_heatmap),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Stats)
+                .with_system(stats_screen_system.chain(report_error)),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Stats).with_system(state_cleanup_system))
+        .add_system_set(SystemSet::on_enter(GameState::Profiles).with_system(setup_profile_screen))
+        .add_system_set(
+            SystemSet::on_update(GameState::Profiles)
+                .with_system(profile_screen_system.chain(report_error))
+                .with_system(profile_rows_system),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Profiles).with_system(state_cleanup_system))
+        .add_system_set(SystemSet::on_enter(GameState::WhatsNew).with_system(setup_whats_new))
+        .add_system_set(
+            SystemSet::on_update(GameState::WhatsNew)
+                .with_system(whats_new_system.chain(report_error)),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::WhatsNew).with_system(state_cleanup_system))
+        .add_system_set(SystemSet::on_enter(GameState::Hangar).with_system(setup_hangar))
+        .add_system_set(
+            SystemSet::on_update(GameState::Hangar)
+                .with_system(hangar_system.chain(report_error))
+                .with_system(hangar_highlight_system),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Hangar).with_system(state_cleanup_system))
+        .add_system_set(SystemSet::on_enter(GameState::Featured).with_system(setup_featured))
+        .add_system_set(
+            SystemSet::on_update(GameState::Featured)
+                .with_system(featured_system.chain(report_error))
+                .with_system(featured_text_system),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Featured).with_system(state_cleanup_system))
+        .add_system_set(
+            SystemSet::on_enter(GameState::Cutscene)
+                .with_system(setup_cutscene.chain(report_error)),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Cutscene)
+                .with_system(cutscene_system.chain(report_error))
+                .with_system(cutscene_shot_system),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Cutscene).with_system(state_cleanup_system))
+        .add_system_set(
+            SystemSet::on_enter(GameState::Start)
+                .with_system(setup_start)
+                .with_system(refuse_tuned_shared_course.chain(report_error))
+                .with_system(setup_countdown)
+                .with_system(clear_new_best)
+                .with_system(setup_session_ticker.with_run_criteria(demo_inactive))
+                .with_system(check_tutorial.with_run_criteria(demo_inactive)),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Start)
+                .with_system(wait_for_click.chain(report_error))
+                .with_system(countdown_system.chain(report_error)),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Start).with_system(state_cleanup_system))
+        .add_system_set(
+            SystemSet::on_enter(GameState::Playing)
+                .with_system(
+                    start_run_stats
+                        .with_run_criteria(demo_inactive)
+                        .after(setup_mutators)
+                        .after(setup_difficulty),
+                )
+                .with_system(seed_run_rng)
+                .with_system(
+                    pause_if_unfocused
+                        .chain(report_error)
+                        .with_run_criteria(demo_inactive),
+                )
+                .with_system(seed_featured_rng.after(seed_run_rng))
+                .with_system(setup_mutators)
+                .with_system(setup_difficulty.before(setup_tutorial))
+                .with_system(setup_ghost.with_run_criteria(demo_inactive))
+                .with_system(setup_demo_label.with_run_criteria(demo_active))
+                .with_system(setup_game_mode.exclusive_system())
+                .with_system(setup_fuel)
+                .with_system(setup_speedrun)
+                .with_system(setup_lives)
+                .with_system(setup_continues)
+                .with_system(reset_damage)
+                .with_system(reset_combo)
+                .with_system(reset_power_ups)
+                .with_system(reset_stalactites)
+                .with_system(reset_pendulums)
+                .with_system(reset_blades)
+                .with_system(reset_speed_zone)
+                .with_system(reset_lasers)
+                .with_system(reset_weather_effects)
+                .with_system(setup_tutorial.with_run_criteria(demo_inactive)),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(run_stats_system.with_run_criteria(demo_inactive))
+                .with_system(achievement_system.with_run_criteria(official_run))
+                .with_system(skin_unlock_system.with_run_criteria(official_run))
+                .with_system(photo_finish_system.with_run_criteria(demo_inactive))
+                .with_system(milestone_system.with_run_criteria(demo_inactive))
+                .with_system(ghost_record_system)
+                .with_system(ghost_playback_system)
+                .with_system(tutorial_system)
+                .with_system(tutorial_arrow_system)
+                .with_system(
+                    pause_system
+                        .chain(report_error)
+                        .with_run_criteria(demo_inactive),
+                )
+                .with_system(
+                    demo_exit_system
+                        .chain(report_error)
+                        .with_run_criteria(demo_active),
+                ),
+        )
+        // A demo skips the game over screen, so it cleans up when it leaves Playing instead
+        .add_system_set(
+            SystemSet::on_exit(GameState::Playing)
+                .with_system(cleanup_tutorial)
+                .with_system(reset_game.with_run_criteria(demo_active))
+                .with_system(reset_style.with_run_criteria(demo_active))
+                .with_system(state_cleanup_system.with_run_criteria(demo_active)),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(setup_pause))
+        .add_system_set(
+            SystemSet::on_update(GameState::Paused)
+                .with_system(resume_system.chain(report_error))
+                .with_system(end_practice_system.chain(report_error)),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(cleanup_pause))
+        .add_system_set(SystemSet::on_enter(GameState::Continue).with_system(setup_continue))
+        .add_system_set(
+            SystemSet::on_update(GameState::Continue)
+                .with_system(continue_system.chain(report_error))
+                .with_system(continue_count_system),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Continue).with_system(cleanup_continue))
+        .add_system_set(
+            SystemSet::on_enter(GameState::GameOver)
+                .with_system(setup_game_over)
+                .with_system(achievement_system.with_run_criteria(official_tuning))
+                .with_system(skin_unlock_system.with_run_criteria(official_tuning))
+                .with_system(
+                    celebrate_new_best
+                        .with_run_criteria(official_tuning)
+                        .before(finish_run_stats),
+                )
+                .with_system(finish_run_stats)
+                .with_system(save_ghost.with_run_criteria(official_tuning))
+                .with_system(check_leaderboard.with_run_criteria(official_tuning))
+                .with_system(setup_wind_down),
+        )
+        .add_game_over_section(versus_result_section)
+        .add_game_over_section(time_trial_results_section)
+        .add_game_over_section(score_section)
+        .add_game_over_section(distance_section)
+        .add_game_over_section(style_summary_section)
+        .add_system_set(
+            SystemSet::on_update(GameState::GameOver)
+                .with_system(wait_for_click.chain(report_error))
+                .with_system(game_over_panel_system)
+                .with_system(wind_down_system)
+                .with_system(loop_background.after(wind_down_system)),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::GameOver)
+                .with_system(reset_game)
+                .with_system(clear_wrecks)
+                .with_system(reset_style)
+                .with_system(reset_game_over_panel)
+                .with_system(state_cleanup_system),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::NameEntry).with_system(setup_name_entry))
+        .add_system_set(
+            SystemSet::on_update(GameState::NameEntry)
+                .with_system(name_entry_system.chain(report_error)),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::NameEntry)
+                .with_system(finish_name_entry)
+                .with_system(state_cleanup_system),
+        )
+        // The HUD, menus, camera and rendering keep going in every state, Paused included
+        .add_system(gravity_flip_ring_system)
+        .add_system(detail_layer_system)
+        .add_system(apply_skin_system)
+        .add_system(damage_tint_system)
+        .add_system(telegraph_marker_system)
+        .add_system(sky_theme_system.with_run_criteria(menu_awake))
+        .add_system(sky_fade_system.with_run_criteria(menu_awake))
+        .add_system(time_of_day_system)
+        .add_system(
+            day_night_tint_system
+                .after(time_of_day_system)
+                .after(sky_fade_system)
+                .after(theme_fade_system),
+        )
+        .add_system(livery_tag_system)
+        .add_system(toast_system.after(TweenSystem))
+        .add_system(save_system.chain(report_error))
+        .add_system(crash_effects_system)
+        .add_system(camera_shake_system.after(crash_effects_system))
+        .add_system(camera_zoom_system.after(camera_shake_system))
+        .add_system(
+            particle_batch_system
+                .with_run_criteria(menu_awake)
+                .after(particle_system),
+        )
+        .add_system(gust_indicator_system)
+        .add_system(font_metrics_system.before(bitmap_font_system))
+        .add_system(bitmap_font_system)
+        .add_system(marquee_system.before(bitmap_font_system))
+        .add_system(text_effect_system.after(bitmap_font_system))
+        .add_system(menu_idle_system)
+        .add_system(dynamic_resolution_system)
+        .add_system(window_mode_system)
+        .add_system(viewport_system)
+        .add_system(debug_toggle_system)
+        .add_system(debug_text_system)
+        .add_system(texture_memory_system)
+        .add_system(theme_swap_system)
+        .add_system(theme_fade_system)
+        // Whatever moves in the world outside of the physics steps, frozen while paused. Systems that animate the
+        // course go here rather than above, the physics stage is paused by its own clock.
+        .add_system_set(
+            SystemSet::new()
+                .with_run_criteria(simulation_running)
+                .with_system(floating_text_system)
+                .with_system(smoke_trail_system)
+                .with_system(trail_system)
+                .with_system(livery_trail_system)
+                .with_system(lifetime_system)
+                .with_system(firework_launch_system)
+                .with_system(firework_burst_system)
+                .with_system(speed_streak_system),
+        )
+        // Like the set above, but also wound down on an idle menu, see MenuIdle
+        .add_system(particle_system.with_run_criteria(particles_running))
+        .add_system(weather_system.with_run_criteria(weather_running))
+        .add_system_to_stage(CoreStage::PreUpdate, simulation_time_system)
+        .add_system_to_stage(CoreStage::PreUpdate, window_focus_system)
+        .add_system_to_stage(
+            CoreStage::PreUpdate,
+            fullscreen_toggle_system
+                .after(InputSystem)
+                .before(flap_input_system),
+        )
+        .add_system_to_stage(CoreStage::PreUpdate, flap_input_system.after(InputSystem))
+        .add_system_to_stage(CoreStage::First, clear_used_taps)
+        .add_system_to_stage(CoreStage::Last, frame_cap_system)
+        .add_system_to_stage(CoreStage::PreUpdate, autopilot_system.after(InputSystem))
+        // Only runs while playing, see physics_step
+        .add_stage_after(
+            CoreStage::Update,
+            PhysicsStage,
+            SystemStage::parallel().with_run_criteria(physics_step),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            store_previous_transforms.label(PhysicsSystem::StorePrevious),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            horizontal_movement
+                .label(PhysicsSystem::Movement)
+                .after(PhysicsSystem::StorePrevious),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            player_system
+                .label(PhysicsSystem::Movement)
+                .after(PhysicsSystem::StorePrevious),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            oscillation_system
+                .label(PhysicsSystem::Movement)
+                .after(PhysicsSystem::StorePrevious),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            behavior_system
+                .label(PhysicsSystem::Movement)
+                .after(PhysicsSystem::StorePrevious),
+        )
+        .add_system_to_stage(PhysicsStage, loop_background.after(PhysicsSystem::Movement))
+        .add_system_to_stage(
+            PhysicsStage,
+            bounds_system
+                .after(PhysicsSystem::Movement)
+                .before(PhysicsSystem::Collision),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            crumbling_system
+                .after(PhysicsSystem::Movement)
+                .before(PhysicsSystem::Collision),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            collision_system
+                .chain(report_error)
+                .label(PhysicsSystem::Collision)
+                .after(PhysicsSystem::Movement),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            rock_spawn_system
+                .chain(report_error)
+                .label(PhysicsSystem::Spawning)
+                .after(PhysicsSystem::Movement),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            combo_system
+                .after(PhysicsSystem::Movement)
+                .after(PhysicsSystem::Collision),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            rock_system
+                .label(PhysicsSystem::Scoring)
+                .after(PhysicsSystem::Movement)
+                .after(combo_system),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            enemy_spawn_system
+                .chain(report_error)
+                .label(PhysicsSystem::Spawning)
+                .after(PhysicsSystem::Movement),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            enemy_system
+                .label(PhysicsSystem::Movement)
+                .after(PhysicsSystem::StorePrevious),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            stalactite_spawn_system
+                .chain(report_error)
+                .label(PhysicsSystem::Spawning)
+                .after(PhysicsSystem::Movement),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            stalactite_system
+                .label(PhysicsSystem::Movement)
+                .after(PhysicsSystem::StorePrevious),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            pendulum_spawn_system
+                .chain(report_error)
+                .label(PhysicsSystem::Spawning)
+                .after(PhysicsSystem::Movement),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            pendulum_system
+                .label(PhysicsSystem::Movement)
+                .after(horizontal_movement),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            blade_spawn_system
+                .chain(report_error)
+                .label(PhysicsSystem::Spawning)
+                .after(PhysicsSystem::Movement),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            blade_system
+                .label(PhysicsSystem::Movement)
+                .after(PhysicsSystem::StorePrevious),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            laser_spawn_system
+                .label(PhysicsSystem::Spawning)
+                .after(PhysicsSystem::Movement),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            laser_system
+                .after(PhysicsSystem::Movement)
+                .before(PhysicsSystem::Collision),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            telegraph_system
+                .after(PhysicsSystem::Spawning)
+                .after(stalactite_system),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            enemy_score_system
+                .label(PhysicsSystem::Scoring)
+                .after(rock_system),
+        )
+        .add_system_to_stage(PhysicsStage, fire_system.after(PhysicsSystem::Movement))
+        .add_system_to_stage(PhysicsStage, fuel_system.before(player_system))
+        .add_system_to_stage(PhysicsStage, gravity_flip_system.before(player_system))
+        .add_system_to_stage(
+            PhysicsStage,
+            gravity_flip_pickup_system.after(power_up_pickup_system),
+        )
+        .add_system_to_stage(PhysicsStage, gust_system.before(player_system))
+        .add_system_to_stage(
+            PhysicsStage,
+            fuel_can_spawn_system.after(PhysicsSystem::Movement),
+        )
+        .add_system_to_stage(PhysicsStage, fuel_can_system.after(PhysicsSystem::Movement))
+        .add_system_to_stage(
+            PhysicsStage,
+            power_up_spawn_system.after(PhysicsSystem::Movement),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            power_up_pickup_system.after(PhysicsSystem::Movement),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            ammo_pickup_system.after(power_up_pickup_system),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            repair_kit_system.after(power_up_pickup_system),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            speed_zone_system
+                .after(power_up_pickup_system)
+                .before(game_speed_system),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            bullet_system
+                .label(PhysicsSystem::Scoring)
+                .after(rock_system),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            near_miss_bonus_system
+                .label(PhysicsSystem::Scoring)
+                .after(PhysicsSystem::Collision),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            speedrun_timer_system.after(PhysicsSystem::Scoring),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            time_trial_clock_system
+                .chain(report_error)
+                .after(PhysicsSystem::Scoring),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            extra_life_system.after(PhysicsSystem::Scoring),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            continue_token_system.after(PhysicsSystem::Scoring),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            invincibility_system.after(PhysicsSystem::Collision),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            record_crash_sites.after(PhysicsSystem::Collision),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            game_speed_system.after(PhysicsSystem::Scoring),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            run_distance_system
+                .with_run_criteria(demo_inactive)
+                .after(PhysicsSystem::Movement)
+                .before(game_speed_system),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            style_system
+                .after(PhysicsSystem::Scoring)
+                .after(PhysicsSystem::Collision),
+        )
+        .add_system_to_stage(
+            PhysicsStage,
+            despawn_offscreen_system.after(PhysicsSystem::Scoring),
+        )
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            interpolate_transforms.after(TransformSystem::TransformPropagate),
+        );
+
+        #[cfg(feature = "audio")]
+        app.add_plugin(SfxPlugin);
+
+        #[cfg(feature = "cloud-sync")]
+        app.add_plugin(CloudSyncPlugin);
+
+        #[cfg(feature = "online")]
+        app.add_plugin(OnlinePlugin);
+
+        #[cfg(all(feature = "net-race", not(target_arch = "wasm32")))]
+        app.add_plugin(RacePlugin);
+
+        #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+        app.add_plugin(RecorderPlugin);
+
+        #[cfg(all(feature = "clip", not(target_arch = "wasm32")))]
+        app.add_plugin(ClipPlugin);
+
+        #[cfg(feature = "debug-tools")]
+        app.add_plugin(DebugToolsPlugin);
+
+        #[cfg(feature = "dev-console")]
+        app.add_plugin(DevConsolePlugin);
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    pack: Res<AssetPack>,
+    assets: Res<GameAssets>,
+    hulls: Res<PlaneHulls>,
+    theme: Res<ThemeState>,
+    backgrounds: Res<BackgroundThemes>,
+    layers: Res<ParallaxLayers>,
+) {
+    spawn_parallax_layers(
+        &mut commands,
+        &asset_server,
+        &pack,
+        &layers,
+        &theme,
+        &backgrounds,
+    );
+
+    let livery = Pilot::One.livery();
+    let hull = hulls.get(livery.plane());
+
+    let mut player = commands.spawn_bundle(SpriteBundle {
+        texture: livery.texture(&assets),
+        transform: Transform::from_xyz(PLANE_X, 0.0, Layer::Course.z())
+            .with_scale(Vec3::new(0.5, 0.5, 1.0)),
+        ..default()
+    });
+
+    player
+        .insert(Player::new(hull))
+        .insert(Pilot::One)
+        .insert(Controlled)
+        .insert(FlapInput::default())
+        .insert(Interpolated::default())
+        .insert(Damage::default());
+
+    add_player_outline(&mut player, hull);
+    add_livery(&mut player, livery, &assets);
+}
+
+fn setup_start(mut commands: Commands, assets: Res<GameAssets>) {
+    commands
+        .spawn_bundle(SpriteBundle {
+            texture: assets.get_ready.clone(),
+            transform: Transform::from_xyz(0.0, 100.0, Layer::Ui.z()),
+            ..default()
+        })
+        .insert(RemoveAfterState);
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            texture: assets.tap_left.clone(),
+            transform: Transform::from_xyz(-200.0 + PLAYER_WIDTH / 1.5, 0.0, Layer::Ui.z())
+                .with_scale(Vec3::new(0.5, 0.5, 1.0)),
+            ..default()
+        })
+        .insert(RemoveAfterState);
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            texture: assets.tap_right.clone(),
+            transform: Transform::from_xyz(-200.0 - PLAYER_WIDTH / 1.5, 0.0, Layer::Ui.z())
+                .with_scale(Vec3::new(0.5, 0.5, 1.0)),
+            ..default()
+        })
+        .insert(RemoveAfterState);
+}
+
+fn setup_game_over(
+    mut commands: Commands,
+    assets: Res<GameAssets>,
+    mode: Res<RunMode>,
+    clock: Res<TimeTrialClock>,
+) {
+    // Nobody crashed, so the game over sign would be wrong
+    if *mode == RunMode::TimeTrial && clock.ran_out() {
+        commands
+            .spawn_bundle(
+                BitmapTextBundle::new(0.0, 100.0)
+                    .with_anchor(TextAnchor::Center)
+                    .with_color(Color::rgb(0.9, 0.2, 0.15))
+                    .with_text("Time up".to_string()),
+            )
+            .insert(
+                TextEffect::default()
+                    .with_typewriter(10.0)
+                    .with_bounce(12.0),
+            )
+            .insert(RemoveAfterState);
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            texture: assets.game_over.clone(),
+            transform: Transform::from_xyz(0.0, 100.0, Layer::Ui.z()),
+            ..default()
+        })
+        .insert(RemoveAfterState);
+}
+
+fn score_section(score: Res<Score>, mut sections: EventWriter<GameOverSection>) {
+    let text = format!("High Score {}", score.0);
+    sections.send(GameOverSection::line(100, text));
+}
+
+fn wait_for_click(
+    mut input: ActionInput,
+    save: Res<SaveData>,
+    mut state: ResMut<State<GameState>>,
+    name_entry: Option<Res<NameEntry>>,
+    countdown: Res<StartCountdown>,
+) -> GameResult {
+    let bindings = &save.settings.bindings;
+    let held = state.current() == &GameState::Start && countdown.held;
+    if !held && (input.just_pressed(bindings, Action::Confirm) || input.tap().is_some()) {
+        let next_state = match state.current() {
+            GameState::GameOver if name_entry.is_some() => GameState::NameEntry,
+            GameState::GameOver => GameState::Menu,
+            _ => GameState::Playing,
+        };
+
+        state.set(next_state)?;
+        input.consume(bindings, Action::Confirm);
+        input.consume_tap();
+    }
+
+    Ok(())
+}
+
+// Hazards that are still being telegraphed stay where they are
+fn horizontal_movement(
+    mut query: Query<(&mut Transform, &HorizontalVelocity, Option<&Telegraph>)>,
+    speed: Res<GameSpeed>,
+) {
+    let dt = PHYSICS_STEP;
+    let speed = speed.0;
+
+    for (mut transform, velocity, telegraph) in query.iter_mut() {
+        if telegraph.map_or(false, |telegraph| telegraph.holds()) {
+            continue;
+        }
+
+        transform.translation.x -= dt * speed * velocity.0;
+    }
+}
+
+// Everything is mirrored while gravity is flipped, velocities are turned around to work out the angle as if the
+// plane was the right way up
+fn player_system(
+    mut query: Query<(
+        &mut Player,
+        &mut Transform,
+        &mut Sprite,
+        &mut FlapInput,
+        Option<&GravityFlip>,
+    )>,
+    game_speed: Res<GameSpeed>,
+    mutators: Res<ActiveMutators>,
+    difficulty: Res<ActiveDifficulty>,
+    fuel: Res<Fuel>,
+    weather: Res<WeatherEffects>,
+    mut flap_events: EventWriter<FlapEvent>,
+) {
+    let dt = PHYSICS_STEP;
+    let gravity =
+        GRAVITY * mutators.gravity_factor() * difficulty.preset.gravity * weather.gravity_factor()
+            + weather.downdraft();
+
+    // Without fuel the plane can only glide down
+    let has_fuel = !fuel.is_empty();
+
+    for (mut player, mut transform, mut sprite, mut input, flip) in query.iter_mut() {
+        let direction = gravity_direction(flip);
+        if player.flipped != flip.is_some() {
+            player.set_flipped(flip.is_some(), &mut sprite);
+        }
+
+        if input.just_pressed {
+            input.just_pressed = false;
+
+            if has_fuel {
+                flap_events.send(FlapEvent {
+                    velocity: player.velocity,
+                });
+            }
+        }
+
+        if input.pressed && has_fuel {
+            player.velocity = direction * (BUMP + BUMP * ((1.0 - game_speed.0) * 0.6));
+        }
+
+        let velocity = player.velocity * direction;
+        let angle = if velocity >= 0.0 {
+            (velocity / BUMP) * (PI / 6.0)
+        } else if velocity > FREE_FALL_VELOCITY {
+            (PI * 2.0) - (velocity / FREE_FALL_VELOCITY) * (PI / 2.0)
+        } else {
+            PI * 1.5
+        };
+
+        transform.rotation = Quat::from_rotation_z(angle * direction);
+
+        transform.translation.y += player.velocity * dt;
+        transform.translation.x =
+            (transform.translation.x + weather.wind_velocity() * dt).min(PLANE_X);
+        player.velocity -= direction * gravity * dt * game_speed.0;
+    }
+}
+
+fn reset_game(
+    mut commands: Commands,
+    mut rock_timer: ResMut<RockTimer>,
+    mut obstacle_queue: ResMut<ObstacleQueue>,
+    mut enemy_timer: ResMut<EnemyTimer>,
+    mut player_query: Query<(Entity, &mut Transform, &mut Player, &mut Sprite)>,
+    obstacles: Query<(Entity, Option<&PooledRock>), Or<(With<Rock>, With<Hazard>)>>,
+    mut rock_pool: ResMut<RockPool>,
+    mut score: ResMut<Score>,
+    mut game_speed: ResMut<GameSpeed>,
+) {
+    rock_timer.0.reset();
+    obstacle_queue.0.clear();
+    *enemy_timer = EnemyTimer::default();
+
+    for (entity, mut player_transform, mut player, mut sprite) in player_query.iter_mut() {
+        commands.entity(entity).remove::<GravityFlip>();
+        player.set_flipped(false, &mut sprite);
+
+        player_transform.translation.x = PLANE_X;
+        player_transform.translation.y = 0.0;
+        player_transform.rotation = Quat::IDENTITY;
+        player.velocity = BUMP;
+    }
+
+    for (obstacle, pooled) in obstacles.iter() {
+        despawn_or_pool(&mut commands, &mut rock_pool, obstacle, pooled);
+    }
+
+    score.0 = 0;
+    game_speed.0 = 1.0;
+}
+
+fn state_cleanup_system(mut commands: Commands, entities: Query<Entity, With<RemoveAfterState>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn game_speed_system(
+    mut speed: ResMut<GameSpeed>,
+    score: Res<Score>,
+    mutators: Res<ActiveMutators>,
+    zone: Res<SpeedZone>,
+) {
+    // Scaled along with the speed zone, so the ramp ends up the same once it's over
+    if score.is_changed() {
+        speed.0 += 0.035 * mutators.speed_ramp_factor() * zone.multiplier();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+
+use crate::{
+    despawn_or_pool, HorizontalVelocity, PooledRock, RockPool, ScreenAnchor, SimulationTime,
+};
+
+// Despawns the entity once it has been around for this long. Advances with the simulation, so particles and
+// popups linger during slow motion along with everything else.
+#[derive(Component)]
+pub struct Lifetime(Timer);
+
+impl Lifetime {
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self(Timer::from_seconds(seconds, false))
+    }
+
+    // 1 when it's spawned, down to 0 when it's despawned. Things that fade out use this as their alpha.
+    pub fn percent_left(&self) -> f32 {
+        self.0.percent_left()
+    }
+}
+
+// Despawns the entity once it's further than the margin past the edge of the playfield it's heading towards.
+// Everything that scrolls is spawned off screen on the right, so only the edge in the direction of its
+// HorizontalVelocity counts.
+#[derive(Component, Clone)]
+pub struct DespawnOffscreen {
+    pub margin: f32,
+}
+
+pub fn lifetime_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Lifetime)>,
+    simulation_time: Res<SimulationTime>,
+) {
+    for (entity, mut lifetime) in query.iter_mut() {
+        if lifetime.0.tick(simulation_time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// Runs at the end of a physics step, so whatever is despawned has been moved, hit and scored for the last time.
+// Rocks go back to the pool instead.
+pub fn despawn_offscreen_system(
+    mut commands: Commands,
+    mut pool: ResMut<RockPool>,
+    query: Query<(
+        Entity,
+        &Transform,
+        &DespawnOffscreen,
+        &HorizontalVelocity,
+        Option<&PooledRock>,
+    )>,
+) {
+    for (entity, transform, offscreen, velocity, pooled) in query.iter() {
+        let x = transform.translation.x;
+
+        // A positive velocity moves to the left, see horizontal_movement
+        let gone = if velocity.0 > 0.0 {
+            x < ScreenAnchor::Left.offset(-offscreen.margin, 0.0).x
+        } else {
+            x > ScreenAnchor::Right.offset(offscreen.margin, 0.0).x
+        };
+
+        if gone {
+            despawn_or_pool(&mut commands, &mut pool, entity, pooled);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+
+use crate::{
+    BitmapText, BitmapTextBundle, DemoMode, GameConfig, GameModes, GravityFlip, HudVisibility,
+    Interpolated, Player, RemoveAfterState, RunMode, Score, ScreenAnchor, BUMP, PHYSICS_STEP,
+};
+
+// Times per second a respawned plane blinks while it can't crash
+const BLINK_RATE: f32 = 8.0;
+
+// Planes left in the current run, including the one that's flying. Versus and the demo always end on the
+// first crash.
+#[derive(Clone, Default)]
+pub struct Lives {
+    pub remaining: u32,
+    next_extra: u64,
+}
+
+impl Lives {
+    pub fn has_spare(&self) -> bool {
+        self.remaining > 1
+    }
+}
+
+// A respawned plane flies through rocks and enemies until this runs out
+#[derive(Component, Clone)]
+pub struct Invincible {
+    remaining: f32,
+}
+
+impl Invincible {
+    pub fn new(seconds: f32) -> Self {
+        Self { remaining: seconds }
+    }
+
+    // Never runs out and doesn't blink, for the console's god mode
+    #[cfg(feature = "dev-console")]
+    pub fn permanent() -> Self {
+        Self::new(f32::INFINITY)
+    }
+
+    #[cfg(feature = "dev-console")]
+    pub fn is_permanent(&self) -> bool {
+        self.remaining.is_infinite()
+    }
+}
+
+#[derive(Component)]
+pub struct LivesText;
+
+pub fn setup_lives(
+    mut commands: Commands,
+    mut lives: ResMut<Lives>,
+    config: Res<GameConfig>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    demo: Res<DemoMode>,
+) {
+    *lives = Lives {
+        remaining: config.lives.start,
+        next_extra: config.lives.points_per_extra,
+    };
+
+    // Practice has no use for lives, it never ends on a crash
+    let mode = modes.get(*mode);
+    if !mode.has_lives() || demo.active || mode.forgives_crashes() {
+        lives.remaining = 1;
+        return;
+    }
+
+    let mut bundle = BitmapTextBundle::at(ScreenAnchor::TopLeft.offset(15.0, -110.0))
+        .with_text(lives_text(lives.remaining));
+    bundle.transform.scale = Vec3::new(0.5, 0.5, 1.0);
+
+    commands
+        .spawn_bundle(bundle)
+        .insert(LivesText)
+        .insert(RemoveAfterState);
+}
+
+// Called by collision_system instead of ending the run when there's a life to spare
+pub fn respawn_plane(
+    commands: &mut Commands,
+    entity: Entity,
+    player: &mut Player,
+    transform: &mut Transform,
+    interpolated: &mut Interpolated,
+    invincibility: f32,
+) {
+    let respawn_at = Vec3::new(-200.0, 0.0, transform.translation.z);
+    interpolated.shift(respawn_at - transform.translation);
+    transform.translation = respawn_at;
+    transform.rotation = Quat::IDENTITY;
+    player.velocity = BUMP;
+
+    // Always comes back the right way up
+    commands
+        .entity(entity)
+        .remove::<GravityFlip>()
+        .insert(Invincible::new(invincibility));
+}
+
+// Only the run that's being played earns lives, the demo and modes without lives stay at one
+pub fn extra_life_system(
+    mut lives: ResMut<Lives>,
+    score: Res<Score>,
+    config: Res<GameConfig>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    demo: Res<DemoMode>,
+) {
+    if !modes.get(*mode).has_lives() || demo.active || config.lives.points_per_extra == 0 {
+        return;
+    }
+
+    while score.0 >= lives.next_extra {
+        lives.remaining += 1;
+        lives.next_extra += config.lives.points_per_extra;
+    }
+}
+
+pub fn invincibility_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Invincible, &mut Visibility)>,
+) {
+    for (entity, mut invincible, mut visibility) in query.iter_mut() {
+        invincible.remaining -= PHYSICS_STEP;
+
+        if invincible.remaining <= 0.0 {
+            visibility.is_visible = true;
+            commands.entity(entity).remove::<Invincible>();
+        } else if invincible.remaining.is_finite() {
+            visibility.is_visible = (invincible.remaining * BLINK_RATE) as u32 % 2 == 0;
+        }
+    }
+}
+
+pub fn lives_text_system(
+    lives: Res<Lives>,
+    hud: Res<HudVisibility>,
+    mut query: Query<&mut BitmapText, With<LivesText>>,
+) {
+    if !lives.is_changed() && !hud.is_changed() {
+        return;
+    }
+
+    for mut text in query.iter_mut() {
+        text.text = hud.text(lives_text(lives.remaining));
+    }
+}
+
+fn lives_text(lives: u32) -> String {
+    format!("Lives {}", lives)
+}
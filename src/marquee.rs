@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+
+use crate::{BitmapText, FontMetrics, GameAssets, SaveData, WIDTH};
+
+// Pixels per second
+const MARQUEE_SPEED: f32 = 120.0;
+// Seconds each message stays up with reduced motion, where they're swapped in place instead of sliding
+const MARQUEE_HOLD: f32 = 3.0;
+
+// Slides the text's messages across the screen from right to left, one after the other, starting over once
+// they've all been shown. Needs a BitmapText anchored on the left, which it writes the messages into.
+#[derive(Component)]
+pub struct Marquee {
+    messages: Vec<String>,
+    next: usize,
+    // Seconds the current message has been up, only used with reduced motion
+    held: f32,
+}
+
+impl Marquee {
+    pub fn new(messages: Vec<String>) -> Self {
+        Self {
+            messages,
+            next: 0,
+            held: 0.0,
+        }
+    }
+
+    fn advance(&mut self) -> String {
+        let message = self.messages[self.next % self.messages.len()].clone();
+        self.next = (self.next + 1) % self.messages.len();
+        self.held = 0.0;
+        message
+    }
+}
+
+pub fn marquee_system(
+    mut query: Query<(&mut Marquee, &mut BitmapText, &mut Transform)>,
+    metrics: Res<FontMetrics>,
+    assets: Res<GameAssets>,
+    save: Res<SaveData>,
+    time: Res<Time>,
+) {
+    for (mut marquee, mut text, mut transform) in query.iter_mut() {
+        if marquee.messages.is_empty() {
+            continue;
+        }
+
+        if text.text.is_empty() {
+            text.text = marquee.advance();
+            transform.translation.x = WIDTH / 2.0;
+        }
+
+        // Nothing moves until the message has been measured, or it could jump once it is
+        let width = match metrics.text_width(&assets.bitmap_font, &text) {
+            Some(width) => width,
+            None => continue,
+        };
+
+        if save.settings.reduced_motion {
+            transform.translation.x = width / -2.0;
+            marquee.held += time.delta_seconds();
+            if marquee.held >= MARQUEE_HOLD {
+                text.text = marquee.advance();
+            }
+            continue;
+        }
+
+        transform.translation.x -= MARQUEE_SPEED * time.delta_seconds();
+        if transform.translation.x + width < WIDTH / -2.0 {
+            text.text = marquee.advance();
+            transform.translation.x = WIDTH / 2.0;
+        }
+    }
+}
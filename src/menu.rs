@@ -0,0 +1,280 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::{
+    cursor_world_position, main_camera_world_position, Action, ActionInput, BitmapText,
+    BitmapTextBundle, Ease, GameAssets, GameResult, GameState, Layer, MainCamera, NextCutscene,
+    RemoveAfterState, RunMode, SaveData, ScreenAnchor, SoundCue, SpriteColor, Tween, ViewportSize,
+    INTRO_CUTSCENE,
+};
+
+const BUTTON_SIZE: Vec2 = Vec2::new(196.0, 70.0);
+const BUTTON_SCALE: f32 = 0.8;
+const BUTTON_X: f32 = -20.0;
+const BUTTON_TOP: f32 = 100.0;
+const BUTTON_SPACING: f32 = 55.0;
+const BUTTON_LABEL_SCALE: f32 = 0.35;
+
+const HIGHLIGHT_COLOR: Color = Color::rgb(1.0, 0.85, 0.5);
+const HIGHLIGHT_TIME: f32 = 0.15;
+
+// Seconds between leaving the menu and the run starting, clicking skips the rest
+const COUNTDOWN_TIME: f32 = 3.0;
+
+// Top to bottom
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum MenuButton {
+    // Plays whichever mode is selected
+    Play,
+    Daily,
+    Practice,
+    Stats,
+    Settings,
+    Quit,
+}
+
+impl MenuButton {
+    const ALL: [MenuButton; 6] = [
+        MenuButton::Play,
+        MenuButton::Daily,
+        MenuButton::Practice,
+        MenuButton::Stats,
+        MenuButton::Settings,
+        MenuButton::Quit,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            MenuButton::Play => "Play",
+            MenuButton::Daily => "Daily",
+            MenuButton::Practice => "Practice",
+            MenuButton::Stats => "Stats",
+            MenuButton::Settings => "Settings",
+            MenuButton::Quit => "Quit",
+        }
+    }
+}
+
+// The highlighted button, moved by hovering with the mouse or with the arrow keys and d-pad
+#[derive(Default)]
+pub struct MenuSelection(usize);
+
+// menu_system moves the selection, the highlight follows it in the same frame
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MenuSystem;
+
+pub struct StartCountdown {
+    timer: Timer,
+    // Set by a race until the other player is at the start too. Clicking doesn't skip it then.
+    pub held: bool,
+}
+
+impl Default for StartCountdown {
+    fn default() -> Self {
+        StartCountdown {
+            timer: Timer::from_seconds(COUNTDOWN_TIME, false),
+            held: false,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct CountdownText;
+
+pub fn setup_menu(mut commands: Commands, assets: Res<GameAssets>, save: Res<SaveData>) {
+    let ui_scale = save.settings.ui_scale;
+
+    for (i, button) in MenuButton::ALL.iter().enumerate() {
+        let position = button_position(i, ui_scale);
+
+        // The label is a child, so its scale is relative to the button
+        let mut label = BitmapTextBundle::new(BUTTON_SIZE.x / -2.0 + 25.0, 0.0)
+            .with_text(button.label().to_string());
+        label.transform.scale = Vec3::splat(BUTTON_LABEL_SCALE / BUTTON_SCALE);
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                texture: assets.menu_button.clone(),
+                transform: Transform::from_translation(position.extend(Layer::Ui.z()))
+                    .with_scale(Vec3::splat(BUTTON_SCALE * ui_scale)),
+                ..default()
+            })
+            .insert(*button)
+            .insert(RemoveAfterState)
+            .with_children(|parent| {
+                parent.spawn_bundle(label);
+            });
+    }
+}
+
+// Mouse, touch and keyboard share one selection, so whichever was used last decides what Return presses
+pub fn menu_system(
+    mut selection: ResMut<MenuSelection>,
+    mut mode: ResMut<RunMode>,
+    mut state: ResMut<State<GameState>>,
+    mut exit: EventWriter<AppExit>,
+    mut input: ActionInput,
+    save: Res<SaveData>,
+    mut next_cutscene: ResMut<NextCutscene>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    windows: Res<Windows>,
+    viewport: Res<ViewportSize>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut cues: EventWriter<SoundCue>,
+) -> GameResult {
+    let last = MenuButton::ALL.len() - 1;
+    if input.arrow_just_pressed(KeyCode::Up) {
+        selection.0 = selection.0.saturating_sub(1);
+    }
+    if input.arrow_just_pressed(KeyCode::Down) {
+        selection.0 = (selection.0 + 1).min(last);
+    }
+
+    let ui_scale = save.settings.ui_scale;
+    let button_under = |position| button_at(position, ui_scale);
+
+    // Only a moving mouse takes the selection, so a cursor resting on a button doesn't fight the arrow keys
+    let hovered = cursor_world_position(&windows, &viewport, &camera_query).and_then(button_under);
+    if cursor_moved.iter().count() > 0 && !save.settings.gamepad_menus {
+        if let Some(hovered) = hovered {
+            selection.0 = hovered;
+        }
+    }
+
+    // A tap presses whatever is under the finger, there's nothing to hover first
+    let tapped = input
+        .tap()
+        .and_then(|tap| main_camera_world_position(tap, &viewport, &camera_query))
+        .and_then(button_under);
+
+    let bindings = &save.settings.bindings;
+    let pressed = if input.mouse.just_pressed(MouseButton::Left) {
+        input.mouse.reset(MouseButton::Left);
+        hovered
+    } else if let Some(tapped) = tapped {
+        input.consume_tap();
+        selection.0 = tapped;
+        Some(tapped)
+    } else if input.just_pressed(bindings, Action::Confirm) {
+        input.consume(bindings, Action::Confirm);
+        Some(selection.0)
+    } else {
+        None
+    };
+
+    if pressed.is_some() {
+        cues.send(SoundCue("menu_click"));
+    }
+
+    match pressed.map(|i| MenuButton::ALL[i]) {
+        // The intro comes before the first run on a save, whichever mode it's in
+        Some(MenuButton::Play) if plays_intro(&save) => {
+            next_cutscene.play(INTRO_CUTSCENE, GameState::Start);
+            state.set(GameState::Cutscene)?;
+        }
+        Some(MenuButton::Play) => state.set(GameState::Start)?,
+        Some(MenuButton::Daily) => {
+            *mode = RunMode::Daily;
+            state.set(GameState::Start)?;
+        }
+        Some(MenuButton::Practice) => {
+            *mode = RunMode::Practice;
+            state.set(GameState::Start)?;
+        }
+        Some(MenuButton::Stats) => state.set(GameState::Stats)?,
+        Some(MenuButton::Settings) => state.set(GameState::Settings)?,
+        Some(MenuButton::Quit) => exit.send(AppExit),
+        None => {}
+    }
+
+    Ok(())
+}
+
+// Buttons fade to their color. One that's still fading when the selection changes again starts over from
+// wherever it got to.
+pub fn menu_highlight_system(
+    mut commands: Commands,
+    selection: Res<MenuSelection>,
+    buttons: Query<(Entity, &MenuButton, &Sprite, Option<&Tween<SpriteColor>>)>,
+) {
+    for (entity, button, sprite, tween) in buttons.iter() {
+        let color = if MenuButton::ALL[selection.0] == *button {
+            HIGHLIGHT_COLOR
+        } else {
+            Color::WHITE
+        };
+
+        if selection.is_changed() || (sprite.color != color && tween.is_none()) {
+            commands.entity(entity).insert(
+                Tween::new(SpriteColor, sprite.color, color, HIGHLIGHT_TIME)
+                    .with_ease(Ease::QuadOut),
+            );
+        }
+    }
+}
+
+pub fn setup_countdown(mut commands: Commands, mut countdown: ResMut<StartCountdown>) {
+    *countdown = StartCountdown::default();
+
+    commands
+        .spawn_bundle(
+            BitmapTextBundle::at(ScreenAnchor::Center.offset(-15.0, 20.0))
+                .with_text(countdown_text(&countdown.timer)),
+        )
+        .insert(CountdownText)
+        .insert(RemoveAfterState);
+}
+
+// Clicking through the countdown is handled by wait_for_click
+pub fn countdown_system(
+    mut countdown: ResMut<StartCountdown>,
+    mut state: ResMut<State<GameState>>,
+    time: Res<Time>,
+    mut query: Query<&mut BitmapText, With<CountdownText>>,
+) -> GameResult {
+    if countdown.held {
+        return Ok(());
+    }
+
+    if countdown.timer.tick(time.delta()).just_finished() {
+        // Overwritten because a click in the same frame also starts the run
+        state.overwrite_set(GameState::Playing)?;
+        return Ok(());
+    }
+
+    let text = countdown_text(&countdown.timer);
+    for mut countdown_text in query.iter_mut() {
+        if countdown_text.text != text {
+            countdown_text.text = text.clone();
+        }
+    }
+
+    Ok(())
+}
+
+fn countdown_text(timer: &Timer) -> String {
+    (timer.duration() - timer.elapsed())
+        .as_secs_f32()
+        .ceil()
+        .to_string()
+}
+
+// Bigger buttons are spaced further apart, the top one stays where it is
+fn button_position(index: usize, ui_scale: f32) -> Vec2 {
+    let y = BUTTON_TOP - index as f32 * BUTTON_SPACING * ui_scale;
+    Vec2::new(BUTTON_X, y)
+}
+
+fn button_at(position: Vec2, ui_scale: f32) -> Option<usize> {
+    let half_size = BUTTON_SIZE * BUTTON_SCALE * ui_scale / 2.0;
+
+    (0..MenuButton::ALL.len()).find(|i| {
+        let offset = (position - button_position(*i, ui_scale)).abs();
+        offset.x <= half_size.x && offset.y <= half_size.y
+    })
+}
+
+// Players who were already flying before the intro existed don't need it
+fn plays_intro(save: &SaveData) -> bool {
+    save.stats.runs == 0 && !save.seen_cutscenes.contains(INTRO_CUTSCENE)
+}
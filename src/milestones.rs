@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::{
+    BitmapTextBundle, Ease, GameAssets, GameModes, HudVisibility, RemoveAfterState, RunMode,
+    SaveData, Scale, Score, ScreenAnchor, TextAlpha, TextAnchor, TextEffect, Tween,
+};
+
+const MILESTONE_POINTS: u64 = 10;
+// Every this many milestones a word is shown instead of the score, the last one keeps being used after that
+const WORD_MILESTONES: u64 = 5;
+const WORDS: &[&str] = &["Nice", "Great", "Superb", "Unreal"];
+// Below this, halfway to the best is too close to the start to be worth pointing out
+const HALFWAY_MIN_BEST: u64 = 20;
+
+// Above the middle, clear of the plane's usual height
+const POSITION: Vec2 = ScreenAnchor::Center.offset(0.0, 60.0);
+const TEXT_SCALE: f32 = 0.9;
+const TEXT_COLOR: Color = Color::rgb(1.0, 0.95, 0.6);
+const SCALE_IN_TIME: f32 = 0.25;
+const HOLD_TIME: f32 = 0.6;
+const FADE_TIME: f32 = 0.4;
+
+#[derive(Component)]
+pub struct MilestoneText;
+
+// Flashes the score in the middle of the screen every MILESTONE_POINTS, and once when the run gets halfway to
+// the best of its mode. A newer milestone replaces one that's still on screen.
+pub fn milestone_system(
+    mut commands: Commands,
+    score: Res<Score>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    save: Res<SaveData>,
+    hud: Res<HudVisibility>,
+    assets: Res<GameAssets>,
+    shown: Query<Entity, With<MilestoneText>>,
+    mut previous: Local<u64>,
+) {
+    if !score.is_changed() {
+        return;
+    }
+
+    // Combos and speed zones can skip past a milestone, so it's about crossing one rather than landing on it
+    let before = *previous;
+    *previous = score.0;
+    if score.0 <= before || !hud.shows_extras() {
+        return;
+    }
+
+    let best = modes.get(*mode).best_score(&save);
+    let halfway = best / 2;
+    let milestone = score.0 / MILESTONE_POINTS;
+
+    let text = if best >= HALFWAY_MIN_BEST && before < halfway && score.0 >= halfway {
+        "Halfway to best".to_string()
+    } else if milestone > before / MILESTONE_POINTS {
+        if milestone % WORD_MILESTONES == 0 {
+            let word = (milestone / WORD_MILESTONES - 1) as usize;
+            WORDS[word.min(WORDS.len() - 1)].to_string()
+        } else {
+            (milestone * MILESTONE_POINTS).to_string()
+        }
+    } else {
+        return;
+    };
+
+    // Only with a glyph for it, see assets/glyphs.ron
+    let text = if assets.bitmap_font.has_glyph('!') {
+        format!("{}!", text)
+    } else {
+        text
+    };
+
+    for entity in shown.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let scales_in = !save.settings.reduced_motion;
+    let mut bundle = BitmapTextBundle::at(POSITION)
+        .with_anchor(TextAnchor::Center)
+        .with_scale(TEXT_SCALE)
+        .with_color(TEXT_COLOR)
+        .with_text(text);
+    if scales_in {
+        bundle.transform.scale = Vec3::ZERO;
+    }
+
+    let mut entity = commands.spawn_bundle(bundle);
+    entity
+        .insert(MilestoneText)
+        .insert(TextEffect::default().with_bounce(6.0))
+        .insert(
+            Tween::new(TextAlpha, 1.0, 0.0, FADE_TIME)
+                .with_ease(Ease::QuadIn)
+                .with_delay(SCALE_IN_TIME + HOLD_TIME)
+                .despawn_when_done(),
+        )
+        .insert(RemoveAfterState);
+
+    if scales_in {
+        entity.insert(
+            Tween::new(Scale, Vec3::ZERO, Vec3::ONE, SCALE_IN_TIME).with_ease(Ease::CubicOut),
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use bevy::ecs::system::CommandQueue;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    BitmapText, BitmapTextBundle, Difficulty, Leaderboard, Mutator, Pilot, RecordKey,
+    RemoveAfterState, SaveData, ScreenAnchor, GROUND_HEIGHT,
+};
+
+/// The mode picked on the menu, a resource that the next run is played in
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum RunMode {
+    Endless,
+    /// Same course for everyone, changes every day
+    Daily,
+    /// Same course for everyone with two mutators, changes every Monday
+    Weekly,
+    /// Two planes on one screen, the last one flying wins
+    Versus,
+    /// Endless with bullets that break rocks
+    Armed,
+    /// Crashes fly through instead of ending the run, with its own best score
+    Practice,
+    /// A minute to pass as many gates as possible
+    TimeTrial,
+    /// Two players on different machines fly the same course, only with the net-race feature
+    Race,
+    /// A curated seed picked from the gallery
+    Featured,
+    /// Crashes fly through and nothing is kept, for flying without a score to worry about
+    Zen,
+    /// A single life, no continues and a faster ramp, with records of its own
+    Hardcore,
+}
+
+// Everything that sets a mode apart. Modes are registered with add_game_mode when the app is built, the rest
+// of the game asks GameModes about the selected RunMode instead of matching on it.
+pub trait GameMode: Send + Sync + 'static {
+    fn run_mode(&self) -> RunMode;
+
+    // Selects the mode on the main menu, pressing it again goes back to endless
+    fn select_key(&self) -> Option<KeyCode> {
+        None
+    }
+
+    // Shown on the main menu while the mode is selected
+    fn label(&self, save: &SaveData) -> String;
+
+    // Given the seed from the command line, if there was one
+    fn course_seed(&self, seed: Option<u64>) -> Option<u64> {
+        seed
+    }
+
+    fn mutators(&self) -> Vec<Mutator> {
+        vec![]
+    }
+
+    // Runs when a run starts, for anything the mode adds to it
+    fn setup(&self, _commands: &mut Commands, _world: &World) {}
+
+    // The record scores of this mode count towards, None for modes that keep no records
+    fn record_key(&self) -> Option<RecordKey> {
+        None
+    }
+
+    fn has_leaderboard(&self) -> bool {
+        false
+    }
+
+    // Everyone who plays the mode flies the same course, so nothing should give one player an edge over another
+    fn shares_course(&self) -> bool {
+        false
+    }
+
+    // Whether the run starts with the configured lives and earns more. Modes that end on the first crash don't.
+    fn has_lives(&self) -> bool {
+        true
+    }
+
+    // Modes that fly a course shared with other players are always played at normal difficulty, so their scores
+    // stay comparable
+    fn uses_difficulty(&self) -> bool {
+        true
+    }
+
+    fn difficulty(&self, save: &SaveData) -> Difficulty {
+        if self.uses_difficulty() {
+            save.settings.difficulty
+        } else {
+            Difficulty::Normal
+        }
+    }
+
+    // record_key at the difficulty the mode is played at, every difficulty keeps records of its own
+    fn difficulty_record_key(&self, save: &SaveData) -> Option<RecordKey> {
+        self.record_key()
+            .map(|key| key.with_difficulty(self.difficulty(save)))
+    }
+
+    // The score to beat, 0 without a record
+    fn best_score(&self, save: &SaveData) -> u64 {
+        self.difficulty_record_key(save)
+            .map_or(0, |key| save.records.best(&key))
+    }
+
+    // Only called for runs that beat best_score
+    fn record_score(&self, save: &mut SaveData, score: u64) {
+        if let Some(key) = self.difficulty_record_key(save) {
+            save.records.set_best(key, score);
+        }
+    }
+
+    fn leaderboard<'a>(&self, save: &'a SaveData) -> Option<&'a Leaderboard> {
+        match self.difficulty_record_key(save) {
+            Some(key) if self.has_leaderboard() => Some(save.records.leaderboard(&key)),
+            _ => None,
+        }
+    }
+
+    fn leaderboard_mut<'a>(&self, save: &'a mut SaveData) -> Option<&'a mut Leaderboard> {
+        match self.difficulty_record_key(save) {
+            Some(key) if self.has_leaderboard() => Some(save.records.leaderboard_mut(key)),
+            _ => None,
+        }
+    }
+
+    // Crashing flies through whatever was hit instead of ending the run or costing a life. The run is ended from
+    // the pause screen instead.
+    fn forgives_crashes(&self) -> bool {
+        false
+    }
+
+    // Whether a crash that ends the run can be undone on the Continue screen. Modes that fly a course shared with
+    // other players can't, their scores would otherwise depend on the continues spent on them.
+    fn allows_continues(&self) -> bool {
+        true
+    }
+
+    // Gates are worth a point each without the combo, and nothing else scores
+    fn counts_only_gates(&self) -> bool {
+        false
+    }
+
+    // A crash ended the run, these planes were still flying
+    fn end_run(&self, _commands: &mut Commands, _flying: &[Pilot]) {}
+}
+
+#[derive(Default)]
+pub struct GameModes(Vec<Box<dyn GameMode>>);
+
+impl GameModes {
+    // Modes behind a feature that's turned off were never registered, they play like endless. A save or a replay
+    // from a build that had it can still ask for one.
+    pub fn get(&self, mode: RunMode) -> &dyn GameMode {
+        let registered = self
+            .0
+            .iter()
+            .find(|registered| registered.run_mode() == mode);
+        match registered {
+            Some(registered) => registered.as_ref(),
+            None => &EndlessMode,
+        }
+    }
+}
+
+pub trait AddGameMode {
+    fn add_game_mode(&mut self, mode: impl GameMode) -> &mut Self;
+}
+
+impl AddGameMode for App {
+    fn add_game_mode(&mut self, mode: impl GameMode) -> &mut Self {
+        self.init_resource::<GameModes>();
+        self.world
+            .resource_mut::<GameModes>()
+            .0
+            .push(Box::new(mode));
+        self
+    }
+}
+
+pub struct EndlessMode;
+
+impl GameMode for EndlessMode {
+    fn run_mode(&self) -> RunMode {
+        RunMode::Endless
+    }
+
+    fn label(&self, _save: &SaveData) -> String {
+        "Press D daily W weekly V versus A armed C time trial Z zen or B hardcore".to_string()
+    }
+
+    fn record_key(&self) -> Option<RecordKey> {
+        Some(RecordKey::new(RunMode::Endless, vec![], 0))
+    }
+
+    fn has_leaderboard(&self) -> bool {
+        true
+    }
+}
+
+pub struct ZenMode;
+
+impl GameMode for ZenMode {
+    fn run_mode(&self) -> RunMode {
+        RunMode::Zen
+    }
+
+    fn select_key(&self) -> Option<KeyCode> {
+        Some(KeyCode::Z)
+    }
+
+    fn label(&self, _save: &SaveData) -> String {
+        "Zen crashes fly through Q on the pause screen ends the run".to_string()
+    }
+
+    // Unlike practice it keeps no best score either
+    fn forgives_crashes(&self) -> bool {
+        true
+    }
+}
+
+pub struct HardcoreMode;
+
+impl GameMode for HardcoreMode {
+    fn run_mode(&self) -> RunMode {
+        RunMode::Hardcore
+    }
+
+    fn select_key(&self) -> Option<KeyCode> {
+        Some(KeyCode::B)
+    }
+
+    fn label(&self, save: &SaveData) -> String {
+        format!("Hardcore one life best {}", self.best_score(save))
+    }
+
+    fn mutators(&self) -> Vec<Mutator> {
+        vec![Mutator::FastRamp]
+    }
+
+    fn record_key(&self) -> Option<RecordKey> {
+        Some(RecordKey::new(RunMode::Hardcore, vec![], 0))
+    }
+
+    fn has_leaderboard(&self) -> bool {
+        true
+    }
+
+    fn has_lives(&self) -> bool {
+        false
+    }
+
+    fn allows_continues(&self) -> bool {
+        false
+    }
+}
+
+// Exclusive, so modes can read whatever they need from the world
+pub fn setup_game_mode(world: &mut World) {
+    let mode = *world.resource::<RunMode>();
+
+    let mut queue = CommandQueue::default();
+    let mut commands = Commands::new(&mut queue, world);
+    world
+        .resource::<GameModes>()
+        .get(mode)
+        .setup(&mut commands, world);
+    queue.apply(world);
+}
+
+#[derive(Component)]
+pub struct ModeLabel;
+
+// Normal goes without saying
+fn mode_label(mode: &dyn GameMode, save: &SaveData) -> String {
+    match mode.difficulty(save) {
+        Difficulty::Normal => mode.label(save),
+        difficulty => format!("{} {}", difficulty.name(), mode.label(save)),
+    }
+}
+
+pub fn setup_mode_label(
+    mut commands: Commands,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    save: Res<SaveData>,
+) {
+    let mut bundle =
+        BitmapTextBundle::at(ScreenAnchor::BottomLeft.offset(15.0, GROUND_HEIGHT + 20.0))
+            .with_text(mode_label(modes.get(*mode), &save));
+    bundle.transform.scale = Vec3::new(0.3, 0.3, 1.0);
+
+    commands
+        .spawn_bundle(bundle)
+        .insert(ModeLabel)
+        .insert(RemoveAfterState);
+}
+
+pub fn mode_select_system(
+    keys: Res<Input<KeyCode>>,
+    modes: Res<GameModes>,
+    mut mode: ResMut<RunMode>,
+) {
+    let selected = modes.0.iter().find(|registered| {
+        registered
+            .select_key()
+            .map_or(false, |key| keys.just_pressed(key))
+    });
+
+    let selected = match selected {
+        Some(selected) => selected.run_mode(),
+        None => return,
+    };
+
+    // Pressing the key of the current mode goes back to endless
+    *mode = if *mode == selected {
+        RunMode::Endless
+    } else {
+        selected
+    };
+}
+
+// Updated every frame so the weekly countdown keeps ticking
+pub fn mode_label_system(
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    save: Res<SaveData>,
+    mut label_query: Query<&mut BitmapText, With<ModeLabel>>,
+) {
+    let label = mode_label(modes.get(*mode), &save);
+
+    for mut text in label_query.iter_mut() {
+        if text.text != label {
+            text.text = label.clone();
+        }
+    }
+}
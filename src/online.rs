@@ -0,0 +1,523 @@
+use std::collections::HashSet;
+use std::fs;
+
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task};
+use futures_lite::future;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    demo_inactive, merge_season_bests, online_allowed, rival_ghost_system, rival_scores_system,
+    setup_rival, setup_rival_ghost, unix_seconds, AssetPack, BitmapTextBundle, FeaturedRun,
+    FeaturedRuns, GameModes, GameState, GhostRecorder, GhostRun, GlobalTopScores, LeaderboardEntry,
+    RemoveAfterState, RivalScores, RunMode, RunSpeed, SaveData, Score, Season, SeasonBest,
+    TopScoreEntered, SECONDS_PER_DAY,
+};
+
+const ONLINE_CONFIG_PATH: &str = "online.ron";
+const SUBMISSIONS_PATH: &str = "submissions.ron";
+
+// Failed submissions are retried after 30 seconds, then twice as long every time up to an hour
+const RETRY_BASE_SECONDS: u64 = 30;
+const RETRY_MAX_SECONDS: u64 = 60 * 60;
+
+// Right above the leaderboard table and its title
+const PENDING_LABEL_X: f32 = 120.0;
+const PENDING_LABEL_Y: f32 = 106.0;
+
+// Above that, the current season and how the last few went
+const SEASON_LABEL_Y: f32 = 190.0;
+const SEASON_LINE_HEIGHT: f32 = 20.0;
+const ARCHIVED_SEASONS_SHOWN: usize = 3;
+
+// Written by hand by players who want to take part, e.g. (url: "https://example.com/bevy-plane/scores")
+#[derive(Deserialize)]
+pub struct OnlineConfig {
+    pub url: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScoreSubmission {
+    pub mode: RunMode,
+    pub name: String,
+    pub score: u64,
+    // Flight path of the run, so the server can check the score is plausible
+    pub replay: GhostRun,
+    // How far and how fast the run went, None for submissions queued before runs were tagged
+    #[serde(default)]
+    pub speed: Option<RunSpeed>,
+    // None when the season wasn't known yet, the server files it by the time it arrives
+    #[serde(default)]
+    pub season: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct QueuedSubmission {
+    submission: ScoreSubmission,
+    attempts: u32,
+    // Unix time before which it isn't retried
+    retry_at: u64,
+}
+
+// Scores that haven't reached the server yet. Stored on disk, so they're retried on later launches too.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubmissionQueue {
+    pending: Vec<QueuedSubmission>,
+}
+
+pub struct OnlineLeaderboard {
+    url: String,
+    // Only one submission is sent at a time, always the first one in the queue
+    task: Option<Task<Result<(), String>>>,
+    // Asked for once when the game starts
+    season: Option<Season>,
+    season_task: Option<Task<Result<Season, String>>>,
+    // One mode at a time, for the table on the menu
+    top_scores_task: Option<(RunMode, Task<Result<Vec<LeaderboardEntry>, String>>)>,
+    // Asked for again once a score of that mode has been submitted, so it shows up
+    top_scores_asked: HashSet<RunMode>,
+    // Asked for once, the first time the featured runs are browsed
+    featured_task: Option<Task<Result<Vec<FeaturedRun>, String>>>,
+    featured_asked: bool,
+}
+
+#[derive(Component)]
+pub struct PendingSubmissionsLabel;
+
+#[derive(Component)]
+pub struct SeasonLabel;
+
+// Submitting scores, seasons, rivals, the global top ten and featured runs, for builds with the online feature
+pub struct OnlinePlugin;
+
+impl Plugin for OnlinePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_online)
+            .add_system(queue_score_submission)
+            .add_system(submission_system)
+            .add_system(season_system)
+            .add_system(rival_scores_system)
+            .add_system(featured_runs_system)
+            .add_system_set(SystemSet::on_enter(GameState::Start).with_system(setup_rival))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(setup_rival_ghost.with_run_criteria(demo_inactive)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(rival_ghost_system),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Menu)
+                    .with_system(global_top_scores_system)
+                    .with_system(pending_submissions_label_system)
+                    .with_system(season_label_system),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(season_best_system),
+            );
+    }
+}
+
+pub fn setup_online(mut commands: Commands) {
+    let contents = match fs::read_to_string(ONLINE_CONFIG_PATH) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+
+    let config: OnlineConfig = match ron::from_str(&contents) {
+        Ok(config) => config,
+        Err(err) => {
+            warn!(
+                "Could not read {}, the online leaderboard is off: {}",
+                ONLINE_CONFIG_PATH, err
+            );
+            return;
+        }
+    };
+
+    let url = config.url.clone();
+    let season_task = IoTaskPool::get().spawn(async move { fetch_season(&url) });
+
+    commands.insert_resource(RivalScores::fetch(&config.url));
+    commands.insert_resource(OnlineLeaderboard {
+        url: config.url,
+        task: None,
+        season: None,
+        season_task: Some(season_task),
+        top_scores_task: None,
+        top_scores_asked: HashSet::new(),
+        featured_task: None,
+        featured_asked: false,
+    });
+    commands.insert_resource(load_submissions());
+}
+
+// Every named top score goes through the queue, so a submission that fails is never lost. Scores flown with an
+// asset pack's own tuning stay local.
+pub fn queue_score_submission(
+    online: Option<Res<OnlineLeaderboard>>,
+    queue: Option<ResMut<SubmissionQueue>>,
+    mut entries: EventReader<TopScoreEntered>,
+    recorder: Res<GhostRecorder>,
+    pack: Res<AssetPack>,
+) {
+    let (online, mut queue) = match (online, queue) {
+        (Some(online), Some(queue)) if !pack.changes_tuning() => (online, queue),
+        _ => return,
+    };
+
+    let mut queued = false;
+    for entry in entries.iter() {
+        queue.pending.push(QueuedSubmission {
+            submission: ScoreSubmission {
+                mode: entry.mode,
+                name: entry.name.clone(),
+                score: entry.score,
+                replay: recorder.run().clone(),
+                speed: Some(entry.speed),
+                season: online.season.map(|season| season.id),
+            },
+            attempts: 0,
+            retry_at: 0,
+        });
+        queued = true;
+    }
+
+    if queued {
+        write_submissions(&queue);
+    }
+}
+
+pub fn submission_system(
+    online: Option<ResMut<OnlineLeaderboard>>,
+    queue: Option<ResMut<SubmissionQueue>>,
+    save: Res<SaveData>,
+) {
+    let (mut online, mut queue) = match (online, queue) {
+        (Some(online), Some(queue)) => (online, queue),
+        _ => return,
+    };
+
+    if let Some(task) = &mut online.task {
+        let result = match future::block_on(future::poll_once(task)) {
+            Some(result) => result,
+            None => return,
+        };
+
+        online.task = None;
+
+        match result {
+            Ok(()) => {
+                let submitted = queue.pending.remove(0);
+                online.top_scores_asked.remove(&submitted.submission.mode);
+            }
+            Err(err) => {
+                warn!("Could not submit score, retrying later: {}", err);
+
+                let first = &mut queue.pending[0];
+                first.attempts += 1;
+                first.retry_at = unix_seconds() + retry_delay(first.attempts);
+            }
+        }
+
+        write_submissions(&queue);
+    }
+
+    if !online_allowed(&save.settings) {
+        return;
+    }
+
+    let first = match queue.pending.first() {
+        Some(first) if first.retry_at <= unix_seconds() => first,
+        _ => return,
+    };
+
+    let body = match ron::to_string(&first.submission) {
+        Ok(body) => body,
+        Err(err) => {
+            error!("Could not serialize score submission: {}", err);
+            return;
+        }
+    };
+
+    let url = online.url.clone();
+    online.task = Some(IoTaskPool::get().spawn(async move { submit(&url, body) }));
+}
+
+// Asks for the global top ten of the mode picked on the menu. The game never waits for it, the local
+// leaderboard stays up until it's there.
+pub fn global_top_scores_system(
+    online: Option<ResMut<OnlineLeaderboard>>,
+    mut global: ResMut<GlobalTopScores>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    save: Res<SaveData>,
+) {
+    let mut online = match online {
+        Some(online) => online,
+        None => return,
+    };
+
+    if let Some((task_mode, task)) = &mut online.top_scores_task {
+        let result = match future::block_on(future::poll_once(task)) {
+            Some(result) => result,
+            None => return,
+        };
+
+        let task_mode = *task_mode;
+        online.top_scores_task = None;
+
+        // Not retried until the next submission of that mode, offline players keep the local leaderboard
+        match result {
+            Ok(entries) => {
+                global.0.insert(task_mode, entries);
+            }
+            Err(err) => warn!("Could not get the online top scores: {}", err),
+        }
+    }
+
+    if !online_allowed(&save.settings)
+        || !modes.get(*mode).has_leaderboard()
+        || online.top_scores_asked.contains(&*mode)
+    {
+        return;
+    }
+
+    let (url, mode) = (online.url.clone(), *mode);
+    online.top_scores_asked.insert(mode);
+    online.top_scores_task = Some((
+        mode,
+        IoTaskPool::get().spawn(async move { fetch_top_scores(&url, mode) }),
+    ));
+}
+
+// Added to the bundled ones, so they show up while the gallery is open. Nothing is lost when it fails, the
+// bundled runs are still there.
+pub fn featured_runs_system(
+    online: Option<ResMut<OnlineLeaderboard>>,
+    mut featured: ResMut<FeaturedRuns>,
+    state: Res<State<GameState>>,
+    save: Res<SaveData>,
+) {
+    let mut online = match online {
+        Some(online) => online,
+        None => return,
+    };
+
+    if let Some(task) = &mut online.featured_task {
+        let result = match future::block_on(future::poll_once(task)) {
+            Some(result) => result,
+            None => return,
+        };
+        online.featured_task = None;
+
+        match result {
+            Ok(runs) => featured.extend(runs),
+            Err(err) => warn!("Could not get the featured runs: {}", err),
+        }
+    }
+
+    if online.featured_asked
+        || state.current() != &GameState::Featured
+        || !online_allowed(&save.settings)
+    {
+        return;
+    }
+
+    let url = online.url.clone();
+    online.featured_asked = true;
+    online.featured_task = Some(IoTaskPool::get().spawn(async move { fetch_featured_runs(&url) }));
+}
+
+pub fn pending_submissions_label_system(
+    mut commands: Commands,
+    queue: Option<Res<SubmissionQueue>>,
+    labels: Query<Entity, With<PendingSubmissionsLabel>>,
+) {
+    let queue = match queue {
+        Some(queue) => queue,
+        None => return,
+    };
+
+    if !queue.is_changed() && !labels.is_empty() {
+        return;
+    }
+
+    for label in labels.iter() {
+        commands.entity(label).despawn_recursive();
+    }
+
+    let text = match queue.pending.len() {
+        0 => return,
+        1 => "1 score waiting to upload".to_string(),
+        count => format!("{} scores waiting to upload", count),
+    };
+
+    let mut bundle = BitmapTextBundle::new(PENDING_LABEL_X, PENDING_LABEL_Y).with_text(text);
+    bundle.transform.scale = Vec3::new(0.3, 0.3, 1.0);
+
+    commands
+        .spawn_bundle(bundle)
+        .insert(PendingSubmissionsLabel)
+        .insert(RemoveAfterState);
+}
+
+pub fn season_system(online: Option<ResMut<OnlineLeaderboard>>) {
+    let mut online = match online {
+        Some(online) => online,
+        None => return,
+    };
+
+    let task = match &mut online.season_task {
+        Some(task) => task,
+        None => return,
+    };
+    let result = match future::block_on(future::poll_once(task)) {
+        Some(result) => result,
+        None => return,
+    };
+
+    online.season_task = None;
+
+    // Not retried, scores are still submitted without a season
+    match result {
+        Ok(season) => online.season = Some(season),
+        Err(err) => warn!("Could not get the current season: {}", err),
+    }
+}
+
+// Every run of a mode with a leaderboard counts, not only the ones that make the local top ten
+pub fn season_best_system(
+    online: Option<Res<OnlineLeaderboard>>,
+    score: Res<Score>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    mut save: ResMut<SaveData>,
+) {
+    let season = match online.and_then(|online| online.season) {
+        Some(season) => season,
+        None => return,
+    };
+
+    let now = unix_seconds();
+    if !modes.get(*mode).has_leaderboard() || now < season.start || now >= season.end {
+        return;
+    }
+
+    let best = SeasonBest {
+        season,
+        best: score.0,
+    };
+    merge_season_bests(&mut save.seasons, vec![best]);
+}
+
+// The current season and the player's bests in the ones that ended, once the server has said which season it is
+pub fn season_label_system(
+    mut commands: Commands,
+    online: Option<Res<OnlineLeaderboard>>,
+    save: Res<SaveData>,
+    labels: Query<Entity, With<SeasonLabel>>,
+) {
+    let season = match online.and_then(|online| online.season) {
+        Some(season) if labels.is_empty() => season,
+        _ => return,
+    };
+
+    let days_left = season.end.saturating_sub(unix_seconds()) / SECONDS_PER_DAY;
+    let mut lines = vec![match days_left {
+        0 => format!("Season {} ends today", season.id),
+        1 => format!("Season {} ends tomorrow", season.id),
+        days => format!("Season {} ends in {} days", season.id, days),
+    }];
+
+    let archived = save
+        .seasons
+        .iter()
+        .rev()
+        .filter(|best| best.season.id < season.id);
+    for best in archived.take(ARCHIVED_SEASONS_SHOWN) {
+        lines.push(format!("Season {} best {}", best.season.id, best.best));
+    }
+
+    for (i, line) in lines.into_iter().enumerate() {
+        let y = SEASON_LABEL_Y - i as f32 * SEASON_LINE_HEIGHT;
+        let mut bundle = BitmapTextBundle::new(PENDING_LABEL_X, y).with_text(line);
+        bundle.transform.scale = Vec3::new(0.3, 0.3, 1.0);
+
+        commands
+            .spawn_bundle(bundle)
+            .insert(SeasonLabel)
+            .insert(RemoveAfterState);
+    }
+}
+
+fn retry_delay(attempts: u32) -> u64 {
+    RETRY_BASE_SECONDS
+        .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+        .min(RETRY_MAX_SECONDS)
+}
+
+fn submit(url: &str, body: String) -> Result<(), String> {
+    ureq::post(url)
+        .set("Content-Type", "application/ron")
+        .send_string(&body)
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+fn fetch_season(url: &str) -> Result<Season, String> {
+    let body = ureq::get(&format!("{}/season", url))
+        .call()
+        .map_err(|err| err.to_string())?
+        .into_string()
+        .map_err(|err| err.to_string())?;
+
+    ron::from_str(&body).map_err(|err| err.to_string())
+}
+
+fn fetch_top_scores(url: &str, mode: RunMode) -> Result<Vec<LeaderboardEntry>, String> {
+    let body = ureq::get(&format!("{}/top/{:?}", url, mode))
+        .call()
+        .map_err(|err| err.to_string())?
+        .into_string()
+        .map_err(|err| err.to_string())?;
+
+    ron::from_str(&body).map_err(|err| err.to_string())
+}
+
+fn fetch_featured_runs(url: &str) -> Result<Vec<FeaturedRun>, String> {
+    let body = ureq::get(&format!("{}/featured", url))
+        .call()
+        .map_err(|err| err.to_string())?
+        .into_string()
+        .map_err(|err| err.to_string())?;
+
+    ron::from_str(&body).map_err(|err| err.to_string())
+}
+
+fn load_submissions() -> SubmissionQueue {
+    let contents = match fs::read_to_string(SUBMISSIONS_PATH) {
+        Ok(contents) => contents,
+        Err(_) => return SubmissionQueue::default(),
+    };
+
+    ron::from_str(&contents).unwrap_or_else(|err| {
+        warn!("Could not read queued score submissions: {}", err);
+        SubmissionQueue::default()
+    })
+}
+
+fn write_submissions(queue: &SubmissionQueue) {
+    let contents = match ron::ser::to_string_pretty(queue, Default::default()) {
+        Ok(contents) => contents,
+        Err(err) => {
+            error!("Could not serialize queued score submissions: {}", err);
+            return;
+        }
+    };
+
+    if let Err(err) = fs::write(SUBMISSIONS_PATH, contents) {
+        error!("Could not write queued score submissions: {}", err);
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::asset::Asset;
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::{
+    demo_inactive, list_directories, load_game_config, read_file, DemoMode, GameConfig, GameModes,
+    GameResult, GameState, RunMode, ShowToast,
+};
+
+// Every folder in here with a pack.ron in it is an asset pack. It's inside the assets folder so the asset
+// server can load the pack's textures like any other.
+const PACKS_DIRECTORY: &str = "assets/packs";
+// The same folder as the asset server sees it
+const PACKS_ASSET_PATH: &str = "packs";
+const MANIFEST_FILE: &str = "pack.ron";
+
+// The pack.ron of an asset pack, e.g.
+//
+// (
+//     name: "Pixel planes",
+//     textures: {
+//         "Planes/planeBlue1.png": "blue.png",
+//         "Letters/letterA.png": "font/a.png",
+//     },
+//     config: Some("config.ron"),
+// )
+//
+// Textures are keyed by their path in the assets folder, planes, rocks, grounds, skies and the letters of the
+// bitmap font alike, and replaced by a file in the pack's folder. The config replaces assets/config.ron as a
+// whole.
+#[derive(Deserialize)]
+struct PackManifest {
+    name: String,
+    #[serde(default)]
+    textures: HashMap<String, String>,
+    #[serde(default)]
+    config: Option<String>,
+}
+
+// The asset pack picked in the settings, read once when the game starts. Textures it doesn't replace are the
+// built in ones.
+#[derive(Default)]
+pub struct AssetPack {
+    textures: HashMap<String, String>,
+    // Whether it came with a config of its own
+    tuned: bool,
+}
+
+impl AssetPack {
+    // Loads the pack's replacement of a file in the assets folder, or the file itself
+    pub fn load<T: Asset>(&self, asset_server: &AssetServer, path: &str) -> Handle<T> {
+        match self.textures.get(path) {
+            Some(replaced) => asset_server.load(replaced.as_str()),
+            None => asset_server.load(path),
+        }
+    }
+
+    // Scores flown with different tuning can't be compared with anyone else's
+    pub fn changes_tuning(&self) -> bool {
+        self.tuned
+    }
+}
+
+// For systems that keep records and leaderboards, hand out achievements or unlock skins. Like practice, runs
+// flown with a pack's own tuning don't count towards any of them.
+pub fn official_tuning(pack: Res<AssetPack>) -> ShouldRun {
+    if pack.changes_tuning() {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
+
+// official_tuning for systems that leave out the demo as well
+pub fn official_run(demo: Res<DemoMode>, pack: Res<AssetPack>) -> ShouldRun {
+    if pack.changes_tuning() {
+        ShouldRun::No
+    } else {
+        demo_inactive(demo)
+    }
+}
+
+// Courses shared with other players are only flown with the built in tuning, back to the menu otherwise
+pub fn refuse_tuned_shared_course(
+    pack: Res<AssetPack>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    mut state: ResMut<State<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+) -> GameResult {
+    if !pack.changes_tuning() || !modes.get(*mode).shares_course() {
+        return Ok(());
+    }
+
+    toasts.send(ShowToast("Not with a tuned asset pack".to_string()));
+    state.set(GameState::Menu)?;
+
+    Ok(())
+}
+
+// The folder names of every pack that can be picked, sorted
+pub struct AvailablePacks(pub Vec<String>);
+
+pub fn find_asset_packs() -> AvailablePacks {
+    let mut packs: Vec<String> = list_directories(PACKS_DIRECTORY)
+        .into_iter()
+        .filter(|folder| read_file(&manifest_path(folder)).is_ok())
+        .collect();
+    packs.sort();
+
+    AvailablePacks(packs)
+}
+
+// Along with the pack's tuning if it has any, the built in tuning otherwise. A pack that's gone or broken
+// shouldn't keep the game from starting, it starts with the built in assets instead.
+pub fn load_asset_pack(folder: Option<&str>) -> (AssetPack, GameConfig) {
+    let folder = match folder {
+        Some(folder) => folder,
+        None => return (AssetPack::default(), load_game_config()),
+    };
+
+    let path = manifest_path(folder);
+    let manifest: PackManifest = match read_ron(&path) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            warn!("Could not read {}: {}", path, err);
+            return (AssetPack::default(), load_game_config());
+        }
+    };
+
+    let config: Option<GameConfig> = manifest.config.and_then(|file| {
+        let path = format!("{}/{}/{}", PACKS_DIRECTORY, folder, file);
+        match read_ron(&path) {
+            Ok(config) => Some(config),
+            Err(err) => {
+                warn!(
+                    "Could not read {}, using the built in tuning: {}",
+                    path, err
+                );
+                None
+            }
+        }
+    });
+
+    let textures = manifest
+        .textures
+        .into_iter()
+        .map(|(path, replaced)| {
+            let replaced = format!("{}/{}/{}", PACKS_ASSET_PATH, folder, replaced);
+            (path, replaced)
+        })
+        .collect();
+
+    info!("Using the {} asset pack", manifest.name);
+
+    let pack = AssetPack {
+        textures,
+        tuned: config.is_some(),
+    };
+    (pack, config.unwrap_or_else(load_game_config))
+}
+
+fn manifest_path(folder: &str) -> String {
+    format!("{}/{}/{}", PACKS_DIRECTORY, folder, MANIFEST_FILE)
+}
+
+fn read_ron<T: DeserializeOwned>(path: &str) -> Result<T, String> {
+    let contents = read_file(path).map_err(|err| err.to_string())?;
+    ron::from_str(&contents).map_err(|err| err.to_string())
+}
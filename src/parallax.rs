@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    AssetPack, BackgroundThemes, BaseColor, HorizontalVelocity, Interpolated, Layer, SaveData, Sky,
+    ThemeState, ThemedGround, COURSE_VELOCITY,
+};
+
+// Embedded like the obstacle patterns, so the background is there from the first frame
+const PARALLAX: &str = include_str!("../assets/parallax.ron");
+
+// Between parallax layers on the same z-layer, later ones are in front. Small enough for a layer's fade to fit in
+// between.
+const PARALLAX_Z_STEP: f32 = 0.05;
+
+#[derive(Deserialize)]
+pub enum LayerTexture {
+    Sky,
+    Ground,
+    Ceiling,
+    Image(String),
+}
+
+#[derive(Deserialize)]
+pub struct ParallaxLayer {
+    pub texture: LayerTexture,
+    pub x: f32,
+    pub y: f32,
+    pub layer: Layer,
+    pub width: f32,
+    pub scroll_factor: f32,
+    #[serde(default)]
+    pub flip_y: bool,
+    // Only there for looks, hidden on low quality
+    #[serde(default)]
+    pub detail: bool,
+}
+
+// See assets/parallax.ron
+#[derive(Deserialize)]
+pub struct ParallaxLayers {
+    pub layers: Vec<ParallaxLayer>,
+}
+
+pub fn load_parallax_layers() -> ParallaxLayers {
+    ron::from_str(PARALLAX).expect("assets/parallax.ron is invalid")
+}
+
+#[derive(Component, Debug)]
+pub struct Background {
+    width: f32,
+}
+
+#[derive(Component)]
+pub struct DetailLayer;
+
+pub fn spawn_parallax_layers(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    pack: &AssetPack,
+    layers: &ParallaxLayers,
+    theme: &ThemeState,
+    backgrounds: &BackgroundThemes,
+) {
+    for (index, layer) in layers.layers.iter().enumerate() {
+        let z = layer.layer.z() + index as f32 * PARALLAX_Z_STEP;
+
+        let (texture, color) = match &layer.texture {
+            LayerTexture::Sky => (
+                pack.load(asset_server, &backgrounds.first().texture),
+                backgrounds.first().color(),
+            ),
+            LayerTexture::Ground => (theme.textures.ground.clone(), Color::WHITE),
+            LayerTexture::Ceiling => (theme.textures.ceiling.clone(), Color::WHITE),
+            LayerTexture::Image(path) => (pack.load(asset_server, path), Color::WHITE),
+        };
+
+        for i in 0..2 {
+            let mut entity = commands.spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    flip_y: layer.flip_y,
+                    ..default()
+                },
+                texture: texture.clone(),
+                transform: Transform::from_xyz(i as f32 * layer.width + layer.x, layer.y, z),
+                ..default()
+            });
+
+            entity
+                .insert(Background { width: layer.width })
+                .insert(BaseColor(color))
+                .insert(HorizontalVelocity(COURSE_VELOCITY * layer.scroll_factor))
+                .insert(Interpolated::default());
+
+            match layer.texture {
+                LayerTexture::Sky => {
+                    entity.insert(Sky);
+                }
+                LayerTexture::Ground | LayerTexture::Ceiling => {
+                    entity.insert(ThemedGround);
+                }
+                LayerTexture::Image(_) => {}
+            }
+
+            if layer.detail {
+                entity.insert(DetailLayer);
+            }
+        }
+    }
+}
+
+// Also picks up the quality the benchmark settles on after the layers were spawned
+pub fn detail_layer_system(
+    save: Res<SaveData>,
+    mut query: Query<&mut Visibility, With<DetailLayer>>,
+) {
+    if !save.is_changed() {
+        return;
+    }
+
+    let shown = save
+        .settings
+        .quality
+        .map_or(true, |quality| quality.shows_detail_layers());
+
+    for mut visibility in query.iter_mut() {
+        visibility.is_visible = shown;
+    }
+}
+
+pub fn loop_background(mut query: Query<(&mut Transform, &mut Interpolated, &Background)>) {
+    for (mut t, mut interpolated, background) in query.iter_mut() {
+        if t.translation.x < -background.width {
+            t.translation.x += background.width * 2.0;
+            interpolated.shift(Vec3::new(background.width * 2.0, 0.0, 0.0));
+        }
+    }
+}
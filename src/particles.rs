@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
+
+use crate::{Layer, Lifetime, SimulationTime};
+
+// A short-lived sprite or batched particle that flies around on its own. Its Lifetime despawns it.
+#[derive(Component)]
+pub struct Particle {
+    pub velocity: Vec2,
+    pub gravity: f32,
+    // Whether it fades out over its Lifetime
+    pub fade: bool,
+}
+
+impl Particle {
+    pub fn new(velocity: Vec2, gravity: f32) -> Self {
+        Self {
+            velocity,
+            gravity,
+            fade: true,
+        }
+    }
+}
+
+// A particle without a sprite of its own. All of them are drawn as a single mesh, so there can be thousands.
+#[derive(Component)]
+pub struct BatchedParticle {
+    pub color: Color,
+    pub size: Vec2,
+}
+
+// The mesh batched particles are written into, rebuilt every frame
+#[derive(Component)]
+pub struct ParticleBatch;
+
+pub fn setup_particle_batch(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    write_particle_mesh(&mut mesh, &[]);
+
+    commands
+        .spawn_bundle(MaterialMesh2dBundle {
+            mesh: meshes.add(mesh).into(),
+            material: materials.add(ColorMaterial::default()),
+            transform: Transform::from_xyz(0.0, 0.0, Layer::Particles.z()),
+            visibility: Visibility { is_visible: false },
+            ..default()
+        })
+        .insert(ParticleBatch);
+}
+
+pub fn particle_system(
+    mut query: Query<(
+        &mut Particle,
+        &mut Transform,
+        Option<&Lifetime>,
+        Option<&mut Sprite>,
+        Option<&mut BatchedParticle>,
+    )>,
+    simulation_time: Res<SimulationTime>,
+) {
+    let dt = simulation_time.delta_seconds();
+
+    for (mut particle, mut transform, lifetime, sprite, batched) in query.iter_mut() {
+        particle.velocity.y -= particle.gravity * dt;
+        transform.translation += particle.velocity.extend(0.0) * dt;
+
+        if let (true, Some(lifetime)) = (particle.fade, lifetime) {
+            let alpha = lifetime.percent_left();
+
+            if let Some(mut sprite) = sprite {
+                sprite.color.set_a(alpha);
+            }
+            if let Some(mut batched) = batched {
+                batched.color.set_a(alpha);
+            }
+        }
+    }
+}
+
+pub fn particle_batch_system(
+    particles: Query<(&Transform, &BatchedParticle)>,
+    mut batches: Query<(&Mesh2dHandle, &mut Visibility), With<ParticleBatch>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let quads: Vec<(&Transform, &BatchedParticle)> = particles.iter().collect();
+
+    for (handle, mut visibility) in batches.iter_mut() {
+        // Nothing to rewrite when the batch was already empty last frame
+        if quads.is_empty() && !visibility.is_visible {
+            continue;
+        }
+
+        if let Some(mesh) = meshes.get_mut(&handle.0) {
+            write_particle_mesh(mesh, &quads);
+            visibility.is_visible = !quads.is_empty();
+        }
+    }
+}
+
+// One quad per particle, positioned in world space. The color is per vertex so every particle shares a material.
+fn write_particle_mesh(mesh: &mut Mesh, quads: &[(&Transform, &BatchedParticle)]) {
+    let mut positions = Vec::with_capacity(quads.len() * 4);
+    let mut colors = Vec::with_capacity(quads.len() * 4);
+    let mut indices = Vec::with_capacity(quads.len() * 6);
+
+    for (transform, particle) in quads {
+        let half = particle.size.extend(0.0) * transform.scale / 2.0;
+        let start = positions.len() as u32;
+
+        for corner in [
+            Vec3::new(-half.x, -half.y, 0.0),
+            Vec3::new(half.x, -half.y, 0.0),
+            Vec3::new(half.x, half.y, 0.0),
+            Vec3::new(-half.x, half.y, 0.0),
+        ] {
+            let position =
+                transform.rotation * corner + transform.translation.truncate().extend(0.0);
+            positions.push(position.to_array());
+            colors.push(particle.color.as_linear_rgba_f32());
+        }
+
+        indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+
+    let vertex_count = positions.len();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; vertex_count]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; vertex_count]);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.set_indices(Some(Indices::U32(indices)));
+}
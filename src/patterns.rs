@@ -0,0 +1,108 @@
+use std::collections::VecDeque;
+
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::Behavior;
+
+// Embedded instead of loaded, so the patterns are there from the first frame on every platform
+const PATTERNS: &str = include_str!("../assets/patterns.ron");
+
+// Which rocks an obstacle has, see spawn_rocks for the rock types they map to
+#[derive(Clone, Copy, Deserialize)]
+pub enum RockKind {
+    Up,
+    Down,
+    Both,
+}
+
+impl RockKind {
+    pub fn rock_type(&self) -> u8 {
+        match self {
+            RockKind::Down => 0,
+            RockKind::Up => 1,
+            RockKind::Both => 2,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Obstacle {
+    // None for a random pair, drawn by next_rocks
+    pub rocks: Option<RockKind>,
+    pub scale: (f32, f32),
+    pub offset: f32,
+    // Seconds at normal speed until the next obstacle
+    pub delay: f32,
+    // More than 1 chains that many pairs into a cave
+    pub segments: u32,
+    pub behaviors: Vec<Behavior>,
+}
+
+impl Default for Obstacle {
+    fn default() -> Self {
+        Self {
+            rocks: None,
+            scale: (1.0, 1.0),
+            offset: 0.0,
+            delay: 1.0,
+            segments: 1,
+            behaviors: vec![],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ObstaclePattern {
+    pub name: String,
+    pub weight: u32,
+    #[serde(default)]
+    pub min_score: u64,
+    pub obstacles: Vec<Obstacle>,
+}
+
+#[derive(Deserialize)]
+pub struct ObstaclePatterns {
+    pub patterns: Vec<ObstaclePattern>,
+}
+
+impl ObstaclePatterns {
+    // Draws from the course rng, so seeded courses stay the same
+    pub fn pick<R: Rng>(&self, score: u64, rng: &mut R) -> Option<&ObstaclePattern> {
+        let available: Vec<&ObstaclePattern> = self
+            .patterns
+            .iter()
+            .filter(|pattern| pattern.min_score <= score)
+            .collect();
+
+        available
+            .choose_weighted(rng, |pattern| pattern.weight)
+            .ok()
+            .copied()
+    }
+}
+
+// What's left of the pattern that's being spawned
+#[derive(Default)]
+pub struct ObstacleQueue(pub VecDeque<Obstacle>);
+
+// A scale range the wrong way round would only panic once the course rng draws from it, so it's caught here
+pub fn load_obstacle_patterns() -> ObstaclePatterns {
+    let patterns: ObstaclePatterns =
+        ron::from_str(PATTERNS).expect("assets/patterns.ron is invalid");
+
+    for pattern in &patterns.patterns {
+        for (index, obstacle) in pattern.obstacles.iter().enumerate() {
+            let (min_scale, max_scale) = obstacle.scale;
+            if min_scale > max_scale {
+                panic!(
+                    "Obstacle {} of the {:?} pattern in assets/patterns.ron has a scale of ({}, {}), the first can't be bigger than the second",
+                    index, pattern.name, min_scale, max_scale
+                );
+            }
+        }
+    }
+
+    patterns
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::{
+    Action, ActionInput, BitmapTextBundle, GameModes, GameResult, GameState, RunMode, SaveData,
+    ScreenAnchor, ViewportSize,
+};
+
+// Much bigger than a pause button would be, so it's easy to hit with a thumb without looking
+const PAUSE_CORNER_SIZE: Vec2 = Vec2::new(160.0, 120.0);
+
+#[derive(Component)]
+pub struct PauseText;
+
+// Tracked in every state, so a run that starts while the window is in the background still knows about it
+pub struct WindowFocus(pub bool);
+
+impl Default for WindowFocus {
+    fn default() -> Self {
+        WindowFocus(true)
+    }
+}
+
+pub fn window_focus_system(
+    mut focused: EventReader<WindowFocused>,
+    mut focus: ResMut<WindowFocus>,
+) {
+    if let Some(event) = focused.iter().last() {
+        focus.0 = event.focused;
+    }
+}
+
+pub fn setup_pause(mut commands: Commands, mode: Res<RunMode>, modes: Res<GameModes>) {
+    let mut text = BitmapTextBundle::at(ScreenAnchor::Center.offset(-95.0, 20.0))
+        .with_text("Paused".to_string());
+    text.transform.scale = Vec3::new(0.8, 0.8, 1.0);
+    commands.spawn_bundle(text).insert(PauseText);
+
+    // Crashing doesn't end practice, see end_practice_system
+    if modes.get(*mode).forgives_crashes() {
+        let mut hint = BitmapTextBundle::at(ScreenAnchor::Center.offset(-85.0, -30.0))
+            .with_text("Q to end the run".to_string());
+        hint.transform.scale = Vec3::new(0.35, 0.35, 1.0);
+        commands.spawn_bundle(hint).insert(PauseText);
+    }
+}
+
+// Paused is pushed on top of Playing rather than replacing it, so the run is still there when it's resumed.
+// Losing focus pauses as well, alt-tabbing away would otherwise leave the plane to crash in the background.
+// Coming back doesn't resume on its own, the player does that when they're ready.
+pub fn pause_system(
+    mut input: ActionInput,
+    focus: Res<WindowFocus>,
+    viewport: Res<ViewportSize>,
+    save: Res<SaveData>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    let bindings = &save.settings.bindings;
+    if focus.is_changed() && !focus.0 {
+        state.push(GameState::Paused)?;
+    } else if input.just_pressed(bindings, Action::Pause) {
+        state.push(GameState::Paused)?;
+        input.consume(bindings, Action::Pause);
+    } else if let Some(tap) = input.tap().filter(|tap| in_pause_corner(*tap, &viewport)) {
+        state.push(GameState::Paused)?;
+        input.consume_tap();
+    }
+
+    Ok(())
+}
+
+// pause_system only sees focus being lost, a countdown that ran out in the background needs this
+pub fn pause_if_unfocused(
+    focus: Res<WindowFocus>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    if !focus.0 {
+        state.push(GameState::Paused)?;
+    }
+
+    Ok(())
+}
+
+// Confirming or tapping anywhere resumes too, since the mouse usually isn't bound to Pause
+pub fn resume_system(
+    mut input: ActionInput,
+    save: Res<SaveData>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    let bindings = &save.settings.bindings;
+    for action in [Action::Pause, Action::Confirm] {
+        if input.just_pressed(bindings, action) {
+            state.pop()?;
+            input.consume(bindings, action);
+            return Ok(());
+        }
+    }
+
+    if input.tap().is_some() {
+        state.pop()?;
+        input.consume_tap();
+    }
+
+    Ok(())
+}
+
+// The top right of the playfield, wherever the camera is. Taps there pause instead of flapping.
+pub fn in_pause_corner(window_position: Vec2, viewport: &ViewportSize) -> bool {
+    let ndc = match viewport.to_ndc(window_position) {
+        Some(ndc) => ndc,
+        None => return false,
+    };
+
+    let position = ndc * ScreenAnchor::TopRight.position();
+    let corner = ScreenAnchor::TopRight.offset(-PAUSE_CORNER_SIZE.x, -PAUSE_CORNER_SIZE.y);
+    position.x >= corner.x && position.y >= corner.y
+}
+
+pub fn cleanup_pause(mut commands: Commands, query: Query<Entity, With<PauseText>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
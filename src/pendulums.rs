@@ -0,0 +1,191 @@
+use std::f32::consts::TAU;
+
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    add_collision_polygon, tick_spawn_timer, DespawnOffscreen, GameResult, GameRng, Hazard,
+    HazardKind, HazardSpawn, HorizontalVelocity, Interpolated, Layer, Score, ScreenAnchor,
+    SpawnHooks, COURSE_VELOCITY, GROUND_HEIGHT, HEIGHT, PHYSICS_STEP,
+};
+
+const PENDULUM_MIN_SCORE: u64 = 30;
+const BALL_RADIUS: f32 = 18.0;
+// Just inside the ceiling strip, so the rope looks attached to it
+const PIVOT_Y: f32 = HEIGHT / 2.0 - GROUND_HEIGHT + 10.0;
+// Far enough for a ball swung out to the right to be off-screen as well
+const PENDULUM_MARGIN: f32 = 200.0;
+
+// A ball on a rope, swinging from the ceiling. The entity is the ball, so collision_system sees the polygon
+// where the ball is. The rope is a child that points back up to the pivot.
+#[derive(Component, Clone)]
+pub struct Pendulum {
+    length: f32,
+    // Largest angle from straight down, in radians
+    amplitude: f32,
+    // In swings per second
+    frequency: f32,
+    time: f32,
+    // Horizontal distance from the pivot to the ball
+    offset_x: f32,
+}
+
+impl Pendulum {
+    fn angle(&self) -> f32 {
+        self.amplitude * (self.time * self.frequency * TAU).sin()
+    }
+
+    fn ball_offset(&self) -> Vec2 {
+        let angle = self.angle();
+        Vec2::new(self.length * angle.sin(), -self.length * angle.cos())
+    }
+}
+
+#[derive(Component)]
+pub struct Rope;
+
+pub struct PendulumTimer(pub Timer);
+
+impl Default for PendulumTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.0, false))
+    }
+}
+
+pub fn reset_pendulums(mut timer: ResMut<PendulumTimer>) {
+    *timer = PendulumTimer::default();
+}
+
+pub fn pendulum_spawn_system(
+    mut commands: Commands,
+    mut timer: ResMut<PendulumTimer>,
+    score: Res<Score>,
+    mut rng: ResMut<GameRng>,
+    hooks: Res<SpawnHooks>,
+) -> GameResult {
+    if score.0 < PENDULUM_MIN_SCORE {
+        return Ok(());
+    }
+
+    if !tick_spawn_timer(&mut timer.0, || rng.course.gen_range(7.0..12.0)) {
+        return Ok(());
+    }
+
+    let frequency = 0.5;
+    let mut pendulum = Pendulum {
+        length: rng.course.gen_range(120.0..170.0),
+        amplitude: rng.course.gen_range(0.6..0.9),
+        frequency,
+        time: rng.course.gen_range(0.0..1.0 / frequency),
+        offset_x: 0.0,
+    };
+    pendulum.offset_x = pendulum.ball_offset().x;
+
+    let mut spawn = HazardSpawn::new(
+        HazardKind::Pendulum,
+        ScreenAnchor::Right.offset(100.0, PIVOT_Y),
+        score.0,
+    );
+    if !hooks.hazard(&mut spawn) {
+        return Ok(());
+    }
+
+    let mut entity = spawn_pendulum(&mut commands, spawn.position.x, pendulum)?;
+    spawn.apply_attached(&mut entity);
+
+    Ok(())
+}
+
+pub fn spawn_pendulum<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    pivot_x: f32,
+    pendulum: Pendulum,
+) -> GameResult<EntityCommands<'w, 's, 'a>> {
+    let offset = pendulum.ball_offset();
+
+    let ball = shapes::Circle {
+        radius: BALL_RADIUS,
+        center: Vec2::ZERO,
+    };
+    let rope = shapes::Line(Vec2::ZERO, Vec2::new(0.0, pendulum.length));
+
+    let mut entity = commands.spawn_bundle(GeometryBuilder::build_as(
+        &ball,
+        DrawMode::Outlined {
+            fill_mode: FillMode::color(Color::rgb(0.35, 0.33, 0.3)),
+            outline_mode: StrokeMode::new(Color::rgb(0.2, 0.19, 0.18), 3.0),
+        },
+        Transform::from_xyz(pivot_x + offset.x, PIVOT_Y + offset.y, Layer::Course.z()),
+    ));
+
+    // An octagon is close enough to a ball for the proximity test
+    let points: Vec<(f32, f32)> = (0..8)
+        .map(|i| {
+            let angle = i as f32 / 8.0 * TAU;
+            (BALL_RADIUS * angle.cos(), BALL_RADIUS * angle.sin())
+        })
+        .collect();
+    add_collision_polygon(&mut entity, &points, 1.0)?;
+
+    let angle = pendulum.angle();
+
+    entity
+        .insert(HorizontalVelocity(COURSE_VELOCITY))
+        .insert(DespawnOffscreen {
+            margin: PENDULUM_MARGIN,
+        })
+        .insert(Interpolated::default())
+        .insert(Hazard)
+        .insert(pendulum)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(GeometryBuilder::build_as(
+                    &rope,
+                    DrawMode::Stroke(StrokeMode::new(Color::rgb(0.3, 0.25, 0.2), 2.0)),
+                    Transform::from_xyz(0.0, 0.0, -0.1).with_rotation(Quat::from_rotation_z(angle)),
+                ))
+                .insert(Rope);
+        });
+
+    Ok(entity)
+}
+
+// Runs after horizontal_movement, which moves the ball along with the pivot
+pub fn pendulum_system(
+    mut query: Query<(&mut Pendulum, &mut Transform, &Children), Without<Rope>>,
+    mut ropes: Query<&mut Transform, With<Rope>>,
+) {
+    for (mut pendulum, mut transform, children) in query.iter_mut() {
+        pendulum.time += PHYSICS_STEP;
+        let offset = pendulum.ball_offset();
+
+        transform.translation.x += offset.x - pendulum.offset_x;
+        transform.translation.y = PIVOT_Y + offset.y;
+        pendulum.offset_x = offset.x;
+
+        let rotation = Quat::from_rotation_z(pendulum.angle());
+        for child in children.iter() {
+            if let Ok(mut rope) = ropes.get_mut(*child) {
+                rope.rotation = rotation;
+            }
+        }
+    }
+}
+
+// For savestates, spawns a pendulum exactly like the one captured
+#[cfg(feature = "debug-tools")]
+pub fn respawn_pendulum(
+    commands: &mut Commands,
+    transform: &Transform,
+    pendulum: &Pendulum,
+) -> GameResult {
+    spawn_pendulum(
+        commands,
+        transform.translation.x - pendulum.offset_x,
+        pendulum.clone(),
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+use crate::{
+    CameraZoom, Controlled, GameModes, GatePassedEvent, PhotoFinishEvent, RunMode, SaveData, Score,
+    SimulationTime,
+};
+
+const PHOTO_FINISH_TIME: f32 = 0.5;
+const PHOTO_FINISH_TIME_SCALE: f32 = 0.3;
+const PHOTO_FINISH_ZOOM: f32 = 0.8;
+
+// Briefly slows down time and zooms in on the plane when it passes the gate that beats the best score, and has
+// the recorder take a screenshot of it
+pub fn photo_finish_system(
+    mut gates: EventReader<GatePassedEvent>,
+    mut photo_finishes: EventWriter<PhotoFinishEvent>,
+    mut previous_score: Local<u64>,
+    score: Res<Score>,
+    save: Res<SaveData>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    mut simulation_time: ResMut<SimulationTime>,
+    mut zoom: ResMut<CameraZoom>,
+    player_query: Query<&Transform, With<Controlled>>,
+) {
+    // Kept every frame, so it starts over with the score when a new run starts
+    let previous = std::mem::replace(&mut *previous_score, score.0);
+    if gates.iter().count() == 0 {
+        return;
+    }
+
+    // Without a previous best there's nothing to celebrate beating. A gate can be worth more than a point, so
+    // the score can jump right past it.
+    let best = modes.get(*mode).best_score(&save);
+    if best == 0 || previous > best || score.0 <= best {
+        return;
+    }
+
+    photo_finishes.send(PhotoFinishEvent);
+    simulation_time.slow_motion(PHOTO_FINISH_TIME_SCALE, PHOTO_FINISH_TIME);
+
+    if save.settings.reduced_motion {
+        return;
+    }
+
+    if let Ok(transform) = player_query.get_single() {
+        let player_translation = transform.translation;
+        zoom.zoom_to(
+            PHOTO_FINISH_ZOOM,
+            Vec2::new(player_translation.x, player_translation.y),
+            PHOTO_FINISH_TIME,
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+
+use crate::{GameConfig, GameState, SimulationTime};
+
+// Simulation steps per second don't depend on the frame rate, so every machine flies the same course
+pub const PHYSICS_STEP: f32 = 1.0 / 120.0;
+
+// Everything that decides the course, collisions or the score runs in this stage and advances by PHYSICS_STEP.
+// Time, textures and GlobalTransform (which is interpolated) are for presentation only.
+#[derive(StageLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhysicsStage;
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PhysicsSystem {
+    StorePrevious,
+    Movement,
+    Spawning,
+    Collision,
+    Scoring,
+}
+
+// In radians, counterclockwise. The axis of to_axis_angle flips around for clockwise rotations, the angle doesn't.
+pub fn z_angle(rotation: Quat) -> f32 {
+    let (axis, angle) = rotation.to_axis_angle();
+    angle * axis.z.signum()
+}
+
+#[derive(Default)]
+pub struct PhysicsClock {
+    accumulator: f32,
+    stepping: bool,
+    // How far rendering is between the previous and the current step
+    alpha: f32,
+    // Frame advance for the TAS tools and headless tests: while frozen, steps only run when asked for
+    #[cfg(any(test, feature = "debug-tools"))]
+    frozen: bool,
+    #[cfg(any(test, feature = "debug-tools"))]
+    requested_steps: u32,
+}
+
+#[cfg(any(test, feature = "debug-tools"))]
+impl PhysicsClock {
+    #[cfg(feature = "debug-tools")]
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+        self.requested_steps = 0;
+    }
+
+    pub fn request_step(&mut self) {
+        self.requested_steps += 1;
+    }
+}
+
+// Entities that move during physics steps are drawn in between their last two steps, so motion stays smooth
+// at frame rates that don't line up with the step rate
+#[derive(Component, Default)]
+pub struct Interpolated {
+    previous_translation: Vec3,
+    previous_rotation: Quat,
+}
+
+impl Interpolated {
+    // Where the entity was before the current physics step moved it
+    pub fn previous_translation(&self) -> Vec3 {
+        self.previous_translation
+    }
+
+    pub fn previous_rotation(&self) -> Quat {
+        self.previous_rotation
+    }
+
+    // For jumps that shouldn't be smoothed out, like a background wrapping around
+    pub fn shift(&mut self, offset: Vec3) {
+        self.previous_translation += offset;
+    }
+}
+
+// Runs the physics stage as many times as there are whole steps of (scaled) time to catch up on.
+// State system sets only work in the stage the state is driven from, so this checks the state itself.
+pub fn physics_step(
+    mut clock: ResMut<PhysicsClock>,
+    simulation_time: Res<SimulationTime>,
+    state: Res<State<GameState>>,
+    config: Res<GameConfig>,
+) -> ShouldRun {
+    if state.current() != &GameState::Playing {
+        clock.accumulator = 0.0;
+        clock.alpha = 1.0;
+        return ShouldRun::No;
+    }
+
+    #[cfg(any(test, feature = "debug-tools"))]
+    if clock.frozen {
+        clock.accumulator = 0.0;
+        clock.alpha = 1.0;
+
+        if clock.requested_steps == 0 {
+            return ShouldRun::No;
+        }
+        clock.requested_steps -= 1;
+        return ShouldRun::YesAndCheckAgain;
+    }
+
+    if !clock.stepping {
+        clock.stepping = true;
+        // After a long hitch the simulation gives up on catching up instead of freezing the game with steps
+        let max_catch_up = config.physics.max_steps_per_frame as f32 * PHYSICS_STEP;
+        clock.accumulator = (clock.accumulator + simulation_time.delta_seconds()).min(max_catch_up);
+    }
+
+    if clock.accumulator >= PHYSICS_STEP {
+        clock.accumulator -= PHYSICS_STEP;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        clock.stepping = false;
+        clock.alpha = clock.accumulator / PHYSICS_STEP;
+        ShouldRun::No
+    }
+}
+
+pub fn store_previous_transforms(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in query.iter_mut() {
+        interpolated.previous_translation = transform.translation;
+        interpolated.previous_rotation = transform.rotation;
+    }
+}
+
+// Only for entities without a parent, their GlobalTransform is just their Transform
+pub fn interpolate_transforms(
+    clock: Res<PhysicsClock>,
+    mut query: Query<
+        (
+            &Transform,
+            &mut Interpolated,
+            ChangeTrackers<Interpolated>,
+            &mut GlobalTransform,
+        ),
+        Without<Parent>,
+    >,
+) {
+    for (transform, mut interpolated, tracker, mut global) in query.iter_mut() {
+        // Nothing to interpolate from until the first step
+        if tracker.is_added() {
+            interpolated.previous_translation = transform.translation;
+            interpolated.previous_rotation = transform.rotation;
+        }
+
+        *global = GlobalTransform::from(Transform {
+            translation: interpolated
+                .previous_translation
+                .lerp(transform.translation, clock.alpha),
+            rotation: interpolated
+                .previous_rotation
+                .slerp(transform.rotation, clock.alpha),
+            scale: transform.scale,
+        });
+    }
+}
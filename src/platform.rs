@@ -0,0 +1,152 @@
+// Everything that works differently in a browser, so the rest of the game doesn't need cfg attributes.
+// Natively files are kept next to the executable. In a browser they're localStorage entries named after the file,
+// so a save can be read and written the same way on both. Builds without the wasm-storage feature keep them in
+// memory instead.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use std::fs;
+    use std::io::{self, Write};
+    use std::thread;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    pub fn read_file(path: &str) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    // Written next to the file and renamed over it, so a crash halfway through leaves the old file intact
+    pub fn write_file(path: &str, contents: &str) -> io::Result<()> {
+        let temp_path = format!("{}.tmp", path);
+
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    }
+
+    pub fn copy_file(from: &str, to: &str) -> io::Result<()> {
+        fs::copy(from, to).map(|_| ())
+    }
+
+    // Names of the folders inside a folder, none if it doesn't exist
+    pub fn list_directories(path: &str) -> Vec<String> {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(_) => return vec![],
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect()
+    }
+
+    pub fn unix_seconds() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0)
+    }
+
+    pub fn sleep(duration: Duration) {
+        thread::sleep(duration);
+    }
+
+    // Steam sets this when it runs the game on a Steam Deck
+    pub fn is_handheld() -> bool {
+        std::env::var_os("SteamDeck").is_some()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::*;
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use std::io;
+    use std::time::Duration;
+
+    pub use storage::{read_file, write_file};
+
+    #[cfg(feature = "wasm-storage")]
+    mod storage {
+        use std::io;
+
+        use web_sys::Storage;
+
+        // Unavailable in some private browsing modes, the game still runs but nothing is kept
+        fn local_storage() -> io::Result<Storage> {
+            web_sys::window()
+                .and_then(|window| window.local_storage().ok().flatten())
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::Other, "localStorage is not available")
+                })
+        }
+
+        pub fn read_file(path: &str) -> io::Result<String> {
+            local_storage()?
+                .get_item(path)
+                .ok()
+                .flatten()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.to_string()))
+        }
+
+        pub fn write_file(path: &str, contents: &str) -> io::Result<()> {
+            local_storage()?
+                .set_item(path, contents)
+                .map_err(|_| io::Error::new(io::ErrorKind::Other, "localStorage is full"))
+        }
+    }
+
+    // For embeds, where a save that outlives the page isn't wanted
+    #[cfg(not(feature = "wasm-storage"))]
+    mod storage {
+        use std::cell::RefCell;
+        use std::collections::HashMap;
+        use std::io;
+
+        thread_local! {
+            static FILES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+        }
+
+        pub fn read_file(path: &str) -> io::Result<String> {
+            FILES
+                .with(|files| files.borrow().get(path).cloned())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.to_string()))
+        }
+
+        pub fn write_file(path: &str, contents: &str) -> io::Result<()> {
+            FILES.with(|files| {
+                files
+                    .borrow_mut()
+                    .insert(path.to_string(), contents.to_string())
+            });
+            Ok(())
+        }
+    }
+
+    pub fn copy_file(from: &str, to: &str) -> io::Result<()> {
+        write_file(to, &read_file(from)?)
+    }
+
+    // Storage is flat, there are no folders to look in
+    pub fn list_directories(_path: &str) -> Vec<String> {
+        vec![]
+    }
+
+    // SystemTime isn't implemented for wasm32-unknown-unknown and panics
+    pub fn unix_seconds() -> u64 {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+
+    // The browser paces frames itself, and blocking would freeze the page
+    pub fn sleep(_duration: Duration) {}
+
+    pub fn is_handheld() -> bool {
+        false
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use web::*;
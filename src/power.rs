@@ -0,0 +1,110 @@
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use bevy::winit::{UpdateMode, WinitSettings};
+
+use crate::{
+    simulation_running, Firework, GameState, MainCamera, Particle, Rotation, Scale, SkyFade,
+    SpriteAlpha, SpriteColor, TextAlpha, ThemeFade, Toast, Translation, Tween,
+};
+
+// Roughly 30 FPS while nothing on screen is moving. Input still wakes the game up immediately.
+const MENU_FRAME_TIME: Duration = Duration::from_millis(33);
+
+// How far a menu has wound down. Once nothing is animating the weather stops, and once its last particles are
+// gone the menu is idle: the particle and background systems are skipped and frames only come at MENU_FRAME_TIME.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MenuIdle {
+    Awake,
+    Settling,
+    Idle,
+}
+
+impl Default for MenuIdle {
+    fn default() -> Self {
+        MenuIdle::Awake
+    }
+}
+
+// Run criteria for the weather, which stops spawning as soon as a menu settles
+pub fn weather_running(state: Res<State<GameState>>, idle: Res<MenuIdle>) -> ShouldRun {
+    if *idle != MenuIdle::Awake {
+        return ShouldRun::No;
+    }
+    simulation_running(state)
+}
+
+// Run criteria for particles, which keep falling until they're gone
+pub fn particles_running(state: Res<State<GameState>>, idle: Res<MenuIdle>) -> ShouldRun {
+    if *idle == MenuIdle::Idle {
+        return ShouldRun::No;
+    }
+    simulation_running(state)
+}
+
+// Run criteria for the background systems, which have nothing to change on an idle menu
+pub fn menu_awake(idle: Res<MenuIdle>) -> ShouldRun {
+    if *idle == MenuIdle::Idle {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
+
+// Menus are mostly static, so there's no need to keep the GPU and CPU busy at full speed there. Headless apps
+// have no winit and are driven frame by frame anyway, so they're always awake.
+pub fn menu_idle_system(
+    state: Res<State<GameState>>,
+    winit: Option<ResMut<WinitSettings>>,
+    mut menu_idle: ResMut<MenuIdle>,
+    particles: Query<(), With<Particle>>,
+    animations: Query<
+        (),
+        Or<(
+            With<Toast>,
+            With<Firework>,
+            With<ThemeFade>,
+            With<SkyFade>,
+            With<Tween<Translation>>,
+            With<Tween<Scale>>,
+            With<Tween<Rotation>>,
+            With<Tween<SpriteColor>>,
+            With<Tween<SpriteAlpha>>,
+            With<Tween<TextAlpha>>,
+        )>,
+    >,
+    camera_query: Query<&OrthographicProjection, With<MainCamera>>,
+) {
+    let mut winit = match winit {
+        Some(winit) => winit,
+        None => return,
+    };
+
+    let zooming = camera_query
+        .iter()
+        .any(|projection| (projection.scale - 1.0).abs() > 0.001);
+
+    let settled = state.current() != &GameState::Playing && animations.is_empty() && !zooming;
+    let wanted = match (settled, particles.is_empty()) {
+        (false, _) => MenuIdle::Awake,
+        (true, false) => MenuIdle::Settling,
+        (true, true) => MenuIdle::Idle,
+    };
+    if *menu_idle != wanted {
+        *menu_idle = wanted;
+    }
+
+    let idle = wanted == MenuIdle::Idle;
+    let reduced = matches!(winit.focused_mode, UpdateMode::Reactive { .. });
+    if idle == reduced {
+        return;
+    }
+
+    winit.focused_mode = if idle {
+        UpdateMode::Reactive {
+            max_wait: MENU_FRAME_TIME,
+        }
+    } else {
+        UpdateMode::Continuous
+    };
+}
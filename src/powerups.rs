@@ -0,0 +1,239 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    tick_spawn_timer, Damage, DemoMode, DespawnOffscreen, Ease, GameAssets, GameConfig, GameMode,
+    GameModes, GameRng, HorizontalVelocity, Interpolated, Layer, Lives, Player, RemoveAfterState,
+    Rotation, RunMode, Scale, ScreenAnchor, ShowToast, Tween,
+};
+
+const POWER_UP_VELOCITY: f32 = 250.0;
+const POWER_UP_MARGIN: f32 = 40.0;
+const POWER_UP_RADIUS: f32 = 30.0;
+const POWER_UP_SCALE: Vec3 = Vec3::new(0.6, 0.6, 1.0);
+
+// Boost rings pulse and have a halo spinning behind them, so they stand out from the other stars
+const BOOST_PULSE_SCALE: Vec3 = Vec3::new(0.7, 0.7, 1.0);
+const BOOST_PULSE_TIME: f32 = 0.4;
+const BOOST_HALO_SCALE: f32 = 1.4;
+const BOOST_HALO_COLOR: Color = Color::rgba(0.5, 0.9, 1.0, 0.5);
+const BOOST_SPIN_TIME: f32 = 1.5;
+
+// Something that floats towards the plane and does something when it's flown through
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUp {
+    Ammo,
+    Repair,
+    GravityFlip,
+    // A ring that starts a speed zone, see speed_zone_system
+    Boost,
+}
+
+impl PowerUp {
+    fn texture<'a>(&self, assets: &'a GameAssets) -> &'a Handle<Image> {
+        match self {
+            PowerUp::Ammo => &assets.star_gold,
+            // There's no wrench sprite, silver stars are the repair kits
+            PowerUp::Repair => &assets.star_silver,
+            PowerUp::GravityFlip => &assets.star_silver,
+            // No ring sprite either
+            PowerUp::Boost => &assets.star_gold,
+        }
+    }
+
+    // Tints the texture, for kinds that share one
+    fn color(&self) -> Color {
+        match self {
+            PowerUp::GravityFlip => Color::rgb(0.7, 0.5, 1.0),
+            PowerUp::Boost => Color::rgb(0.5, 0.9, 1.0),
+            _ => Color::WHITE,
+        }
+    }
+}
+
+// Sent for every power-up a plane flies through, the systems of each kind pick out their own
+pub struct PowerUpCollected {
+    pub power_up: PowerUp,
+    pub player: Entity,
+}
+
+pub struct PowerUpTimer(pub Timer);
+
+impl Default for PowerUpTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.0, false))
+    }
+}
+
+pub fn reset_power_ups(mut timer: ResMut<PowerUpTimer>) {
+    *timer = PowerUpTimer::default();
+}
+
+// The power-ups that can show up in a run and how often, relative to each other
+pub fn power_up_table(mode: &dyn GameMode, demo: bool) -> Vec<(PowerUp, u32)> {
+    let mut table = vec![];
+
+    if mode.run_mode() == RunMode::Armed {
+        table.push((PowerUp::Ammo, 3));
+    }
+
+    // Modes without lives and the demo only have one, see setup_lives. Spare lives on a shared course would make
+    // scores depend on luck with the kits rather than on flying.
+    if mode.has_lives() && !mode.shares_course() && !demo {
+        table.push((PowerUp::Repair, 1));
+    }
+
+    // The autopilot only knows how to fly the right way up, and at the speeds it was tuned for
+    if !demo {
+        table.push((PowerUp::GravityFlip, 1));
+        table.push((PowerUp::Boost, 1));
+    }
+
+    table
+}
+
+pub fn power_up_spawn_system(
+    mut commands: Commands,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    demo: Res<DemoMode>,
+    mut timer: ResMut<PowerUpTimer>,
+    mut rng: ResMut<GameRng>,
+    assets: Res<GameAssets>,
+) {
+    let table = power_up_table(modes.get(*mode), demo.active);
+    if table.is_empty() {
+        return;
+    }
+
+    // Drawn along with the delay like an enemy's altitude
+    let mut y = 0.0;
+    let spawn = tick_spawn_timer(&mut timer.0, || {
+        let (next_y, delay) = next_power_up(&mut rng.course);
+        y = next_y;
+        delay
+    });
+    if !spawn {
+        return;
+    }
+
+    // Everything is drawn from the course rng, so seeded runs stay the same
+    let power_up = if table.len() == 1 {
+        table[0].0
+    } else {
+        table
+            .choose_weighted(&mut rng.course, |(_, weight)| *weight)
+            .unwrap()
+            .0
+    };
+
+    let position = ScreenAnchor::Right.offset(40.0, y);
+
+    let mut entity = commands.spawn_bundle(SpriteBundle {
+        sprite: Sprite {
+            color: power_up.color(),
+            ..default()
+        },
+        texture: power_up.texture(&assets).clone(),
+        transform: Transform::from_translation(position.extend(Layer::Course.z()))
+            .with_scale(POWER_UP_SCALE),
+        ..default()
+    });
+
+    entity
+        .insert(power_up)
+        .insert(HorizontalVelocity(POWER_UP_VELOCITY))
+        .insert(DespawnOffscreen {
+            margin: POWER_UP_MARGIN,
+        })
+        .insert(Interpolated::default())
+        .insert(RemoveAfterState);
+
+    if power_up == PowerUp::Boost {
+        entity
+            .insert(
+                Tween::new(Scale, POWER_UP_SCALE, BOOST_PULSE_SCALE, BOOST_PULSE_TIME)
+                    .with_ease(Ease::SmoothStep)
+                    .mirrored()
+                    .repeat_forever()
+                    .on_simulation_time(),
+            )
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color: BOOST_HALO_COLOR,
+                            ..default()
+                        },
+                        texture: assets.star_silver.clone(),
+                        transform: Transform::from_xyz(0.0, 0.0, -0.01)
+                            .with_scale(Vec3::splat(BOOST_HALO_SCALE)),
+                        ..default()
+                    })
+                    .insert(
+                        Tween::new(Rotation, 0.0, TAU, BOOST_SPIN_TIME)
+                            .repeat_forever()
+                            .on_simulation_time(),
+                    );
+            });
+    }
+}
+
+// The altitude of the next power-up and the delay before the one after it
+pub fn next_power_up<R: Rng>(rng: &mut R) -> (f32, f32) {
+    let y = rng.gen_range(-120.0..120.0);
+    let delay = rng.gen_range(6.0..10.0);
+
+    (y, delay)
+}
+
+pub fn power_up_pickup_system(
+    mut commands: Commands,
+    mut collected_events: EventWriter<PowerUpCollected>,
+    power_ups: Query<(Entity, &PowerUp, &Transform)>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+) {
+    for (entity, power_up, transform) in power_ups.iter() {
+        let collector = player_query.iter().find(|(_, player)| {
+            player
+                .translation
+                .truncate()
+                .distance(transform.translation.truncate())
+                < POWER_UP_RADIUS
+        });
+
+        if let Some((player, _)) = collector {
+            collected_events.send(PowerUpCollected {
+                power_up: *power_up,
+                player,
+            });
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+// A repair kit takes away one tier of damage and gives back a life, but never more than the run started with
+pub fn repair_kit_system(
+    mut collected_events: EventReader<PowerUpCollected>,
+    mut lives: ResMut<Lives>,
+    config: Res<GameConfig>,
+    mut damage_query: Query<&mut Damage>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    for event in collected_events.iter() {
+        if event.power_up != PowerUp::Repair {
+            continue;
+        }
+
+        if lives.remaining < config.lives.start {
+            lives.remaining += 1;
+        }
+        if let Ok(mut damage) = damage_query.get_mut(event.player) {
+            damage.mend();
+        }
+
+        toasts.send(ShowToast("Repaired".to_string()));
+    }
+}
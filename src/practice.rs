@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+
+use crate::{
+    respawn_plane, ActionInput, GameMode, GameModes, GameResult, GameState, Interpolated,
+    Invincible, Player, RecordKey, RunMode, SaveData, GROUND_HEIGHT, HEIGHT,
+};
+
+// Long enough to get clear of a rock at full speed
+const PASS_THROUGH_TIME: f32 = 1.0;
+// Closer than this to the top or bottom of the playfield, a crash puts the plane back in the middle instead.
+// Flying through the ground would only leave it falling forever.
+const EDGE_MARGIN: f32 = GROUND_HEIGHT + 30.0;
+
+// For learning the rhythm of flapping and trying out obstacle patterns. Its best score is kept apart from the
+// others and it has no leaderboard.
+pub struct PracticeMode;
+
+impl GameMode for PracticeMode {
+    fn run_mode(&self) -> RunMode {
+        RunMode::Practice
+    }
+
+    fn label(&self, _save: &SaveData) -> String {
+        "Practice crashes fly through Q on the pause screen ends the run".to_string()
+    }
+
+    fn record_key(&self) -> Option<RecordKey> {
+        Some(RecordKey::new(RunMode::Practice, vec![], 0))
+    }
+
+    fn forgives_crashes(&self) -> bool {
+        true
+    }
+}
+
+// Called by collision_system instead of ending the run. The plane blinks while it flies through.
+pub fn pass_through(
+    commands: &mut Commands,
+    entity: Entity,
+    player: &mut Player,
+    transform: &mut Transform,
+    interpolated: &mut Interpolated,
+) {
+    if transform.translation.y.abs() > HEIGHT / 2.0 - EDGE_MARGIN {
+        respawn_plane(
+            commands,
+            entity,
+            player,
+            transform,
+            interpolated,
+            PASS_THROUGH_TIME,
+        );
+    } else {
+        commands
+            .entity(entity)
+            .insert(Invincible::new(PASS_THROUGH_TIME));
+    }
+}
+
+// Q or select on a gamepad. The run goes to the game over screen like any other, so its score still counts.
+pub fn end_practice_system(
+    input: ActionInput,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    if !modes.get(*mode).forgives_crashes() {
+        return Ok(());
+    }
+
+    let quit = input.keys.just_pressed(KeyCode::Q)
+        || input.gamepad_just_pressed(GamepadButtonType::Select);
+    if quit {
+        // Unwinds Paused and Playing before the game over screen shows up
+        state.replace(GameState::GameOver)?;
+    }
+
+    Ok(())
+}
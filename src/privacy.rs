@@ -0,0 +1,39 @@
+use bevy::prelude::*;
+
+use crate::{SaveData, Settings, ShowToast};
+
+// Shown instead of player names while streamer mode is on, the bitmap font only has letters and digits
+const HIDDEN_NAME: &str = "XXX";
+
+// Every screen that shows a player name goes through this
+pub fn display_name<'a>(name: &'a str, settings: &Settings) -> &'a str {
+    if settings.streamer_mode {
+        HIDDEN_NAME
+    } else {
+        name
+    }
+}
+
+// Whether anything may be sent over the network
+pub fn online_allowed(settings: &Settings) -> bool {
+    !settings.streamer_mode
+}
+
+pub fn streamer_mode_toggle_system(
+    keys: Res<Input<KeyCode>>,
+    mut save: ResMut<SaveData>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !keys.just_pressed(KeyCode::H) {
+        return;
+    }
+
+    save.settings.streamer_mode = !save.settings.streamer_mode;
+
+    let message = if save.settings.streamer_mode {
+        "Streamer mode on"
+    } else {
+        "Streamer mode off"
+    };
+    toasts.send(ShowToast(message.to_string()));
+}
@@ -0,0 +1,160 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    merge_season_bests, read_file, write_file, BitmapTextBundle, GameError, GameResult,
+    RemoveAfterState, SaveData, ScreenAnchor, ShowToast, GROUND_HEIGHT,
+};
+
+const PROFILE_PATH: &str = "profile.ron";
+
+// Bump this whenever a profile written by this version can't be read by older versions
+const PROFILE_VERSION: u32 = 1;
+
+// Everything about a player in a single file, for moving to another machine
+#[derive(Serialize)]
+struct ProfileExport<'a> {
+    version: u32,
+    save: &'a SaveData,
+}
+
+// Read on its own first, so profiles from newer versions are rejected with a clear message
+#[derive(Deserialize)]
+struct ProfileHeader {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct ProfileImport {
+    save: SaveData,
+}
+
+// An imported profile waiting for the player to pick merge or replace
+pub struct PendingImport(SaveData);
+
+#[derive(Component)]
+pub struct ImportPrompt;
+
+// E exports the profile, I imports it and asks whether to merge it with the current one or replace it
+pub fn profile_system(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    pending: Option<ResMut<PendingImport>>,
+    mut save: ResMut<SaveData>,
+    mut toasts: EventWriter<ShowToast>,
+    prompts: Query<Entity, With<ImportPrompt>>,
+) -> GameResult {
+    if let Some(mut pending) = pending {
+        let merge = keys.just_pressed(KeyCode::M);
+        let replace = keys.just_pressed(KeyCode::R);
+        if !merge && !replace && !keys.just_pressed(KeyCode::Escape) {
+            return Ok(());
+        }
+
+        commands.remove_resource::<PendingImport>();
+        for prompt in prompts.iter() {
+            commands.entity(prompt).despawn_recursive();
+        }
+
+        let imported = std::mem::take(&mut pending.0);
+
+        if merge {
+            merge_saves(&mut save, imported);
+            toasts.send(ShowToast("Profile merged".to_string()));
+        } else if replace {
+            // Settings belong to the machine, not the player
+            let settings = std::mem::take(&mut save.settings);
+            save.replace(imported);
+            save.settings = settings;
+            toasts.send(ShowToast("Profile replaced".to_string()));
+        }
+
+        return Ok(());
+    }
+
+    if keys.just_pressed(KeyCode::E) {
+        export_profile(&save)?;
+        toasts.send(ShowToast(format!("Exported to {}", PROFILE_PATH)));
+    } else if keys.just_pressed(KeyCode::I) {
+        commands.insert_resource(PendingImport(import_profile()?));
+
+        let mut bundle =
+            BitmapTextBundle::at(ScreenAnchor::BottomLeft.offset(15.0, GROUND_HEIGHT + 45.0))
+                .with_text("Import profile M merge R replace Esc cancel".to_string());
+        bundle.transform.scale = Vec3::new(0.3, 0.3, 1.0);
+
+        commands
+            .spawn_bundle(bundle)
+            .insert(ImportPrompt)
+            .insert(RemoveAfterState);
+    }
+
+    Ok(())
+}
+
+// Starting a run while the prompt is up counts as cancelling the import
+pub fn cancel_profile_import(mut commands: Commands) {
+    commands.remove_resource::<PendingImport>();
+}
+
+fn export_profile(save: &SaveData) -> GameResult {
+    let profile = ProfileExport {
+        version: PROFILE_VERSION,
+        save,
+    };
+
+    let contents = ron::ser::to_string_pretty(&profile, Default::default())?;
+    write_file(PROFILE_PATH, &contents).map_err(|source| GameError::Io {
+        path: PROFILE_PATH,
+        source,
+    })
+}
+
+fn import_profile() -> GameResult<SaveData> {
+    let contents = read_file(PROFILE_PATH).map_err(|source| GameError::Io {
+        path: PROFILE_PATH,
+        source,
+    })?;
+    let unreadable = |source| GameError::Unreadable {
+        path: PROFILE_PATH,
+        source,
+    };
+
+    let header: ProfileHeader = ron::from_str(&contents).map_err(unreadable)?;
+    if header.version > PROFILE_VERSION {
+        return Err(GameError::NewerVersion { path: PROFILE_PATH });
+    }
+
+    let profile: ProfileImport = ron::from_str(&contents).map_err(unreadable)?;
+
+    let mut save = profile.save;
+    if save.is_newer_version() {
+        return Err(GameError::NewerVersion { path: PROFILE_PATH });
+    }
+
+    save.migrate();
+    Ok(save)
+}
+
+// Keeps the best of both profiles. Stats aren't added up since both profiles usually share history.
+fn merge_saves(save: &mut SaveData, other: SaveData) {
+    save.records.merge(other.records);
+    save.stats.runs = save.stats.runs.max(other.stats.runs);
+    save.stats.rocks_passed = save.stats.rocks_passed.max(other.stats.rocks_passed);
+    save.stats.flaps = save.stats.flaps.max(other.stats.flaps);
+    save.stats.time_played = save.stats.time_played.max(other.stats.time_played);
+    save.stats.best_score = save.stats.best_score.max(other.stats.best_score);
+    // The average goes with whichever profile has more runs behind it
+    if other.stats.scored_runs > save.stats.scored_runs {
+        save.stats.total_score = other.stats.total_score;
+        save.stats.scored_runs = other.stats.scored_runs;
+    }
+    save.achievements.extend(other.achievements);
+    save.unlocks.skins.extend(other.unlocks.skins);
+    save.seen_cutscenes.extend(other.seen_cutscenes);
+    merge_season_bests(&mut save.seasons, other.seasons);
+
+    if other.ghost.score > save.ghost.score {
+        save.ghost = other.ghost;
+    }
+}
@@ -0,0 +1,255 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    apply_device_preset, cursor_world_position, load_save, read_file, write_file, Action,
+    ActionInput, BitmapText, BitmapTextBundle, GameError, GameResult, GameState, MainCamera,
+    RemoveAfterState, SaveData, ShowToast, ViewportSize, DEFAULT_SAVE_PATH,
+};
+
+const PROFILES_PATH: &str = "profiles.ron";
+
+const MAX_NAME_LENGTH: usize = 10;
+
+const LABEL_X: f32 = -200.0;
+const ROW_TOP: f32 = 120.0;
+const ROW_SPACING: f32 = 40.0;
+const ROW_SCALE: f32 = 0.45;
+
+// The players sharing this install. Each profile has a save file of its own with its records, settings,
+// achievements and stats, see SaveData.
+#[derive(Serialize, Deserialize)]
+pub struct Profiles {
+    profiles: Vec<Profile>,
+    // The one played last, loaded on the next start
+    selected: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    file: String,
+}
+
+// Installs from before profiles had a single save, which becomes the first profile
+impl Default for Profiles {
+    fn default() -> Self {
+        Self {
+            profiles: vec![Profile {
+                name: "Player".to_string(),
+                file: DEFAULT_SAVE_PATH.to_string(),
+            }],
+            selected: 0,
+        }
+    }
+}
+
+impl Profiles {
+    pub fn selected(&self) -> &Profile {
+        &self.profiles[self.selected]
+    }
+
+    // Only asked at startup when there's a choice to make
+    pub fn has_choice(&self) -> bool {
+        self.profiles.len() > 1
+    }
+
+    fn add(&mut self, name: String) -> usize {
+        let file = (self.profiles.len()..)
+            .map(|number| format!("save-{}.ron", number))
+            .find(|file| self.profiles.iter().all(|profile| &profile.file != file))
+            .expect("there's always an unused file name");
+
+        self.profiles.push(Profile { name, file });
+        self.profiles.len() - 1
+    }
+
+    fn write(&self) -> GameResult {
+        let contents = ron::ser::to_string_pretty(self, Default::default())?;
+        write_file(PROFILES_PATH, &contents).map_err(|source| GameError::Io {
+            path: PROFILES_PATH,
+            source,
+        })
+    }
+}
+
+// Falls back to the single profile of older installs, the saves themselves are never touched here
+pub fn load_profiles() -> Profiles {
+    let contents = match read_file(PROFILES_PATH) {
+        Ok(contents) => contents,
+        Err(_) => return Profiles::default(),
+    };
+
+    match ron::from_str::<Profiles>(&contents) {
+        Ok(profiles) if profiles.selected < profiles.profiles.len() => profiles,
+        Ok(_) => Profiles::default(),
+        Err(err) => {
+            warn!(
+                "Could not read {}, using the first profile: {}",
+                PROFILES_PATH, err
+            );
+            Profiles::default()
+        }
+    }
+}
+
+// Rows are the profiles followed by one for a new profile, its name is typed in on that row
+pub struct ProfileSelection {
+    row: usize,
+    new_name: String,
+}
+
+#[derive(Component)]
+pub struct ProfileRow(usize);
+
+pub fn setup_profile_screen(mut commands: Commands, profiles: Res<Profiles>) {
+    commands.insert_resource(ProfileSelection {
+        row: profiles.selected,
+        new_name: String::new(),
+    });
+
+    let mut title =
+        BitmapTextBundle::new(LABEL_X, ROW_TOP + 60.0).with_text("Profiles".to_string());
+    title.transform.scale = Vec3::new(0.6, 0.6, 1.0);
+    commands.spawn_bundle(title).insert(RemoveAfterState);
+
+    for row in 0..=profiles.profiles.len() {
+        let mut text = BitmapTextBundle::new(LABEL_X, row_y(row));
+        text.transform.scale = Vec3::new(ROW_SCALE, ROW_SCALE, 1.0);
+        commands
+            .spawn_bundle(text)
+            .insert(ProfileRow(row))
+            .insert(RemoveAfterState);
+    }
+}
+
+// Up and down pick a profile, confirming or clicking plays it. Escape keeps the current one.
+pub fn profile_screen_system(
+    mut selection: ResMut<ProfileSelection>,
+    mut profiles: ResMut<Profiles>,
+    mut save: ResMut<SaveData>,
+    mut state: ResMut<State<GameState>>,
+    mut input: ActionInput,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut toasts: EventWriter<ShowToast>,
+    windows: Res<Windows>,
+    viewport: Res<ViewportSize>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> GameResult {
+    let new_row = profiles.profiles.len();
+
+    for event in characters.iter() {
+        let typeable = event.char.is_ascii_alphanumeric();
+        if selection.row == new_row && typeable && selection.new_name.len() < MAX_NAME_LENGTH {
+            selection.new_name.push(event.char.to_ascii_uppercase());
+        }
+    }
+    if input.keys.just_pressed(KeyCode::Back) {
+        selection.new_name.pop();
+    }
+
+    if input.keys.just_pressed(KeyCode::Escape) {
+        state.set(GameState::Menu)?;
+        return Ok(());
+    }
+
+    if input.arrow_just_pressed(KeyCode::Up) {
+        selection.row = selection.row.saturating_sub(1);
+    }
+    if input.arrow_just_pressed(KeyCode::Down) {
+        selection.row = (selection.row + 1).min(new_row);
+    }
+
+    let bindings = &save.settings.bindings;
+    let clicked = if input.mouse.just_pressed(MouseButton::Left) {
+        input.mouse.reset(MouseButton::Left);
+        cursor_world_position(&windows, &viewport, &camera_query).and_then(|cursor| {
+            (0..=new_row).find(|row| (cursor.y - row_y(*row)).abs() <= ROW_SPACING / 2.0)
+        })
+    } else {
+        None
+    };
+    let confirmed = input.just_pressed(bindings, Action::Confirm);
+
+    let chosen = match clicked {
+        Some(row) => row,
+        None if confirmed => selection.row,
+        None => return Ok(()),
+    };
+    input.consume(bindings, Action::Confirm);
+    selection.row = chosen;
+
+    let chosen = if chosen == new_row {
+        let name = selection.new_name.trim().to_string();
+        if name.is_empty() {
+            toasts.send(ShowToast("Type a name first".to_string()));
+            return Ok(());
+        }
+
+        profiles.add(name)
+    } else {
+        chosen
+    };
+
+    if save.path() != profiles.profiles[chosen].file {
+        profiles.selected = chosen;
+        profiles.write()?;
+
+        // Replaced whole, the new save brings its own file along
+        *save = load_save(&profiles.selected().file);
+        apply_device_preset(&mut save.settings);
+        toasts.send(ShowToast(format!(
+            "Playing as {}",
+            profiles.selected().name
+        )));
+    }
+
+    state.set(GameState::Menu)?;
+    Ok(())
+}
+
+pub fn profile_rows_system(
+    selection: Res<ProfileSelection>,
+    profiles: Res<Profiles>,
+    mut rows: Query<(&ProfileRow, &mut BitmapText, &mut Transform)>,
+) {
+    if !selection.is_changed() {
+        return;
+    }
+
+    for (row, mut text, mut transform) in rows.iter_mut() {
+        let label = match profiles.profiles.get(row.0) {
+            Some(profile) => profile.name.clone(),
+            None if selection.new_name.is_empty() => "New profile".to_string(),
+            None => format!("New {}", selection.new_name),
+        };
+
+        // The selected row sticks out a little instead of having a cursor
+        let x = if row.0 == selection.row {
+            LABEL_X + 20.0
+        } else {
+            LABEL_X
+        };
+
+        if text.text != label {
+            text.text = label;
+        }
+        transform.translation.x = x;
+    }
+}
+
+// P on the menu
+pub fn profile_screen_shortcut_system(
+    keys: Res<Input<KeyCode>>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    if keys.just_pressed(KeyCode::P) {
+        state.set(GameState::Profiles)?;
+    }
+
+    Ok(())
+}
+
+fn row_y(row: usize) -> f32 {
+    ROW_TOP - row as f32 * ROW_SPACING
+}
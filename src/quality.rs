@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    BatchedParticle, EffectsIntensity, GameRng, Lifetime, Particle, RemoveAfterState, SaveData,
+    Settings, ThemeState, WarmUp, HEIGHT, WIDTH,
+};
+
+// Far more than a run ever has on screen at once, so a machine that copes with it copes with anything
+const STRESS_PARTICLES: usize = 3000;
+const STRESS_SPRITES: usize = 300;
+
+// Frames right after loading can still hitch, they're left out of the average
+const SETTLE_FRAMES: u32 = 10;
+const BENCHMARK_FRAMES: u32 = 60;
+
+// Average frame times of the stress scene. With vsync on nothing goes faster than the refresh rate, so these are
+// about keeping up with a 60Hz screen rather than about raw speed.
+const HIGH_FRAME_TIME: f32 = 1.0 / 55.0;
+const MEDIUM_FRAME_TIME: f32 = 1.0 / 35.0;
+
+// Picked by timing a stress scene on the first launch, players can change it in the settings after that
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quality {
+    Low,
+    Medium,
+    High,
+}
+
+impl Quality {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Quality::Low => "Low",
+            Quality::Medium => "Medium",
+            Quality::High => "High",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Quality::Low => Quality::Medium,
+            Quality::Medium => Quality::High,
+            Quality::High => Quality::Low,
+        }
+    }
+
+    // Only touches the settings that cost frames
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.quality = Some(*self);
+        settings.effects_intensity = match self {
+            Quality::Low => EffectsIntensity::Off,
+            Quality::Medium => EffectsIntensity::Low,
+            Quality::High => EffectsIntensity::Full,
+        };
+        settings.dynamic_resolution =
+            *self == Quality::Low || Settings::default().dynamic_resolution;
+    }
+
+    // Parallax layers that are only there for looks, see ParallaxLayer
+    pub fn shows_detail_layers(&self) -> bool {
+        *self != Quality::Low
+    }
+
+    fn from_frame_time(frame_time: f32) -> Self {
+        if frame_time <= HIGH_FRAME_TIME {
+            Quality::High
+        } else if frame_time <= MEDIUM_FRAME_TIME {
+            Quality::Medium
+        } else {
+            Quality::Low
+        }
+    }
+}
+
+// Only exists while the benchmark runs, warm_up_system waits for it to be removed
+#[derive(Default)]
+pub struct QualityBenchmark {
+    frames: u32,
+    total_time: f32,
+}
+
+// Runs during loading, with everything drawn invisibly like the warm up
+pub fn setup_quality_benchmark(
+    mut commands: Commands,
+    save: Res<SaveData>,
+    theme: Res<ThemeState>,
+    mut rng: ResMut<GameRng>,
+) {
+    if save.settings.quality.is_some() {
+        return;
+    }
+
+    commands.insert_resource(QualityBenchmark::default());
+
+    let invisible = Color::rgba(1.0, 1.0, 1.0, 0.0);
+    let mut random_position = || {
+        Vec3::new(
+            rng.cosmetic.gen_range(-WIDTH / 2.0..WIDTH / 2.0),
+            rng.cosmetic.gen_range(-HEIGHT / 2.0..HEIGHT / 2.0),
+            0.0,
+        )
+    };
+
+    for _ in 0..STRESS_PARTICLES {
+        let mut particle = Particle::new(Vec2::ZERO, 0.0);
+        particle.fade = false;
+
+        commands
+            .spawn()
+            .insert(Transform::from_translation(random_position()))
+            .insert(BatchedParticle {
+                color: invisible,
+                size: Vec2::new(8.0, 8.0),
+            })
+            .insert(particle)
+            .insert(Lifetime::from_seconds(60.0))
+            .insert(RemoveAfterState);
+    }
+
+    for _ in 0..STRESS_SPRITES {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: invisible,
+                    ..default()
+                },
+                texture: theme.textures.rock_up.clone(),
+                transform: Transform::from_translation(random_position()),
+                ..default()
+            })
+            .insert(RemoveAfterState);
+    }
+}
+
+// Starts timing once the warm up is done, when the shaders are compiled and the textures are loaded
+pub fn quality_benchmark_system(
+    mut commands: Commands,
+    benchmark: Option<ResMut<QualityBenchmark>>,
+    warm_up: Res<WarmUp>,
+    time: Res<Time>,
+    mut save: ResMut<SaveData>,
+) {
+    let mut benchmark = match benchmark {
+        Some(benchmark) if warm_up.is_done() => benchmark,
+        _ => return,
+    };
+
+    benchmark.frames += 1;
+    if benchmark.frames <= SETTLE_FRAMES {
+        return;
+    }
+
+    benchmark.total_time += time.delta_seconds();
+    if benchmark.frames < SETTLE_FRAMES + BENCHMARK_FRAMES {
+        return;
+    }
+
+    let frame_time = benchmark.total_time / BENCHMARK_FRAMES as f32;
+    let quality = Quality::from_frame_time(frame_time);
+    info!(
+        "Quality benchmark took {:.1}ms per frame, picked {:?}",
+        frame_time * 1000.0,
+        quality
+    );
+
+    quality.apply(&mut save.settings);
+    commands.remove_resource::<QualityBenchmark>();
+}
@@ -0,0 +1,508 @@
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    read_file, seed_run_rng, z_angle, AddGameMode, AssetPack, BitmapTextBundle, GameAssets,
+    GameMode, GameOverSection, GameRng, GameState, GhostRun, GhostSample, Layer, Pilot, Player,
+    RemoveAfterState, RunMode, RunStats, SaveData, Score, ScreenAnchor, ShowToast, StartCountdown,
+};
+
+const RACE_CONFIG_PATH: &str = "race.ron";
+
+const HELLO_INTERVAL: f32 = 0.25;
+const POSITION_INTERVAL: f32 = 1.0 / 30.0;
+// The other plane is shown this far behind, so there's always a newer position to interpolate towards
+const REMOTE_DELAY: f32 = 0.1;
+// Without a packet for this long the other player counts as gone
+const TIMEOUT: f32 = 3.0;
+// Lost packets are never resent, so the one that ends the race goes out a few times
+const CRASH_REPEATS: usize = 3;
+const MAX_PACKET_SIZE: usize = 1024;
+
+const REMOTE_ALPHA: f32 = 0.6;
+
+// Written by hand by both players, e.g. (port: 7777, peer: "203.0.113.5:7777"). A relay that forwards the packets
+// between the two works as the peer too.
+#[derive(Deserialize)]
+struct RaceConfig {
+    port: u16,
+    peer: String,
+}
+
+#[derive(Serialize, Deserialize)]
+enum RaceMessage {
+    // Sent all the time, so the two find each other and know when both are at the start. Carries the seed the
+    // sender settled on, which tells the other player it can start too.
+    Hello {
+        nonce: u64,
+        waiting: bool,
+        seed: Option<u64>,
+    },
+    Position(GhostSample),
+    Crashed {
+        time: f32,
+        score: u64,
+    },
+}
+
+// The connection to the other player, only exists when race.ron could be read
+pub struct RaceLink {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    // Picked again at every start, the course is seeded from both players' nonces
+    nonce: u64,
+    // On the start screen of a race
+    waiting: bool,
+    // Set once both players are at the start, until the next race
+    seed: Option<u64>,
+    // The seed the other player's last hello carried. The countdown only starts once it matches ours.
+    peer_seed: Option<u64>,
+    // Seconds since the last packet from the other player
+    silence: f32,
+    since_hello: f32,
+    since_position: f32,
+    remote: GhostRun,
+    remote_crash: Option<(f32, u64)>,
+    result_sent: bool,
+}
+
+impl RaceLink {
+    fn connected(&self) -> bool {
+        self.silence < TIMEOUT
+    }
+
+    // UDP, so there's nothing to do about a packet that doesn't arrive
+    fn send(&self, message: &RaceMessage) {
+        let result = ron::to_string(message)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            .and_then(|packet| self.socket.send_to(packet.as_bytes(), self.peer));
+
+        if let Err(err) = result {
+            warn!("Could not send to the other racer: {}", err);
+        }
+    }
+
+    fn hello(&self) -> RaceMessage {
+        RaceMessage::Hello {
+            nonce: self.nonce,
+            waiting: self.waiting,
+            seed: self.seed,
+        }
+    }
+}
+
+// Only in builds with the net-race feature, browsers can't open UDP sockets
+pub struct RacePlugin;
+
+impl Plugin for RacePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_game_mode(RaceMode)
+            .add_startup_system(setup_race_link)
+            .add_system(race_network_system)
+            .add_system_set(SystemSet::on_enter(GameState::Start).with_system(setup_race_start))
+            .add_system_set(SystemSet::on_update(GameState::Start).with_system(race_start_system))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(seed_race_rng.after(seed_run_rng)),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(race_position_system)
+                    .with_system(remote_plane_system),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(race_crash_system))
+            .add_system_set(
+                SystemSet::on_update(GameState::GameOver).with_system(race_result_system),
+            );
+    }
+}
+
+pub struct RaceMode;
+
+impl GameMode for RaceMode {
+    fn run_mode(&self) -> RunMode {
+        RunMode::Race
+    }
+
+    fn select_key(&self) -> Option<KeyCode> {
+        Some(KeyCode::O)
+    }
+
+    fn label(&self, _save: &SaveData) -> String {
+        "Race another player online".to_string()
+    }
+
+    fn shares_course(&self) -> bool {
+        true
+    }
+
+    // Both planes have to fly the same course the same way
+    fn uses_difficulty(&self) -> bool {
+        false
+    }
+
+    fn allows_continues(&self) -> bool {
+        false
+    }
+
+    // The other player's plane, drawn like a ghost since it never collides with anything here
+    fn setup(&self, commands: &mut Commands, world: &World) {
+        if world.get_resource::<RaceLink>().is_none() {
+            return;
+        }
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 1.0, 1.0, REMOTE_ALPHA),
+                    ..default()
+                },
+                texture: Pilot::Two.livery().texture(world.resource::<GameAssets>()),
+                transform: Transform::from_xyz(-200.0, 0.0, Layer::Ghost.z())
+                    .with_scale(Vec3::new(0.5, 0.5, 1.0)),
+                ..default()
+            })
+            .insert(RemotePlane)
+            .insert(RemoveAfterState);
+    }
+
+    // Races are about beating the other player, like versus they keep no records
+}
+
+#[derive(Component)]
+pub struct RemotePlane;
+
+#[derive(Component)]
+pub struct RaceWaitingText;
+
+pub fn setup_race_link(mut commands: Commands) {
+    let contents = match read_file(RACE_CONFIG_PATH) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+
+    let config: RaceConfig = match ron::from_str(&contents) {
+        Ok(config) => config,
+        Err(err) => {
+            warn!(
+                "Could not read {}, racing is off: {}",
+                RACE_CONFIG_PATH, err
+            );
+            return;
+        }
+    };
+
+    match open_link(&config) {
+        Ok(link) => commands.insert_resource(link),
+        Err(err) => warn!("Could not reach {}, racing is off: {}", config.peer, err),
+    }
+}
+
+fn open_link(config: &RaceConfig) -> io::Result<RaceLink> {
+    let peer = config
+        .peer
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the peer has no address"))?;
+
+    let socket = UdpSocket::bind(("0.0.0.0", config.port))?;
+    socket.set_nonblocking(true)?;
+
+    Ok(RaceLink {
+        socket,
+        peer,
+        nonce: rand::random(),
+        waiting: false,
+        seed: None,
+        peer_seed: None,
+        silence: TIMEOUT,
+        since_hello: 0.0,
+        since_position: 0.0,
+        remote: GhostRun::default(),
+        remote_crash: None,
+        result_sent: false,
+    })
+}
+
+// Reads everything that arrived since the last frame and keeps saying hello
+pub fn race_network_system(link: Option<ResMut<RaceLink>>, time: Res<Time>) {
+    let mut link = match link {
+        Some(link) => link,
+        None => return,
+    };
+
+    link.silence += time.delta_seconds();
+
+    let mut buffer = [0; MAX_PACKET_SIZE];
+    loop {
+        let (length, from) = match link.socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+            // Windows reports a hello that found nobody listening yet this way
+            Err(err) if err.kind() == io::ErrorKind::ConnectionReset => continue,
+            Err(err) => {
+                warn!("Could not receive from the other racer: {}", err);
+                break;
+            }
+        };
+
+        if from != link.peer {
+            continue;
+        }
+
+        let message = match std::str::from_utf8(&buffer[..length])
+            .ok()
+            .and_then(|packet| ron::from_str::<RaceMessage>(packet).ok())
+        {
+            Some(message) => message,
+            None => continue,
+        };
+
+        link.silence = 0.0;
+        receive(&mut *link, message);
+    }
+
+    link.since_hello += time.delta_seconds();
+    if link.since_hello >= HELLO_INTERVAL {
+        link.since_hello = 0.0;
+        link.send(&link.hello());
+    }
+}
+
+fn receive(link: &mut RaceLink, message: RaceMessage) {
+    match message {
+        // Both sides end up with the same seed, whichever hello arrived first. The other player may already have
+        // settled on it and stopped waiting, its hellos keep carrying the seed until its next start.
+        RaceMessage::Hello {
+            nonce,
+            waiting,
+            seed,
+        } => {
+            let ours = nonce ^ link.nonce;
+            if link.waiting && (waiting || seed == Some(ours)) {
+                link.seed = Some(ours);
+            }
+            link.peer_seed = seed;
+        }
+        // Packets can arrive out of order, older positions are of no use by then
+        RaceMessage::Position(sample) => {
+            let newer = link
+                .remote
+                .samples
+                .last()
+                .map_or(true, |last| sample.time > last.time);
+            if newer {
+                link.remote.samples.push(sample);
+            }
+        }
+        RaceMessage::Crashed { time, score } => link.remote_crash = Some((time, score)),
+    }
+}
+
+pub fn setup_race_start(
+    mut commands: Commands,
+    link: Option<ResMut<RaceLink>>,
+    mode: Res<RunMode>,
+    pack: Res<AssetPack>,
+    mut countdown: ResMut<StartCountdown>,
+) {
+    // Sent back to the menu by refuse_tuned_shared_course, the other racer shouldn't pair up with it
+    let mut link = match link {
+        Some(link) if *mode == RunMode::Race && !pack.changes_tuning() => link,
+        _ => return,
+    };
+
+    link.nonce = rand::random();
+    link.waiting = true;
+    link.seed = None;
+    link.peer_seed = None;
+    link.remote = GhostRun::default();
+    link.remote_crash = None;
+    link.result_sent = false;
+    link.send(&link.hello());
+
+    countdown.held = true;
+
+    let mut text = BitmapTextBundle::at(ScreenAnchor::Center.offset(-160.0, -60.0))
+        .with_text("Waiting for the other racer\nEscape to give up".to_string());
+    text.transform.scale = Vec3::new(0.35, 0.35, 1.0);
+    commands
+        .spawn_bundle(text)
+        .insert(RaceWaitingText)
+        .insert(RemoveAfterState);
+}
+
+// The countdown starts once both players are at the start, so they take off together
+pub fn race_start_system(
+    mut commands: Commands,
+    link: Option<ResMut<RaceLink>>,
+    mode: Res<RunMode>,
+    keys: Res<Input<KeyCode>>,
+    mut countdown: ResMut<StartCountdown>,
+    mut state: ResMut<State<GameState>>,
+    mut toasts: EventWriter<ShowToast>,
+    texts: Query<Entity, With<RaceWaitingText>>,
+) {
+    if *mode != RunMode::Race {
+        return;
+    }
+
+    let mut link = match link {
+        Some(link) => link,
+        None => {
+            toasts.send(ShowToast(format!("Racing needs {}", RACE_CONFIG_PATH)));
+            if let Err(err) = state.set(GameState::Menu) {
+                warn!("Could not leave the start screen: {}", err);
+            }
+            return;
+        }
+    };
+
+    // Hellos are sent again and again, so a lost one only holds the start up a little
+    if link.seed.is_none() || link.peer_seed != link.seed {
+        if keys.just_pressed(KeyCode::Escape) {
+            link.waiting = false;
+            link.seed = None;
+            countdown.held = false;
+            if let Err(err) = state.set(GameState::Menu) {
+                warn!("Could not leave the start screen: {}", err);
+            }
+        }
+        return;
+    }
+
+    link.waiting = false;
+    countdown.held = false;
+    for text in texts.iter() {
+        commands.entity(text).despawn_recursive();
+    }
+}
+
+// After seed_run_rng, so it has the last word on the course
+pub fn seed_race_rng(link: Option<Res<RaceLink>>, mode: Res<RunMode>, mut rng: ResMut<GameRng>) {
+    let seed = match link.and_then(|link| link.seed) {
+        Some(seed) if *mode == RunMode::Race => seed,
+        _ => return,
+    };
+
+    rng.course = StdRng::seed_from_u64(seed);
+}
+
+pub fn race_position_system(
+    link: Option<ResMut<RaceLink>>,
+    mode: Res<RunMode>,
+    run: Res<RunStats>,
+    time: Res<Time>,
+    planes: Query<(&Pilot, &Transform), With<Player>>,
+) {
+    let mut link = match link {
+        Some(link) if *mode == RunMode::Race => link,
+        _ => return,
+    };
+
+    link.since_position += time.delta_seconds();
+    if link.since_position < POSITION_INTERVAL {
+        return;
+    }
+    link.since_position = 0.0;
+
+    for (pilot, transform) in planes.iter() {
+        if *pilot != Pilot::One {
+            continue;
+        }
+
+        link.send(&RaceMessage::Position(GhostSample {
+            time: run.time,
+            y: transform.translation.y,
+            angle: z_angle(transform.rotation),
+        }));
+    }
+}
+
+// Interpolated between the positions that came in, or held at the newest one while the next is late
+pub fn remote_plane_system(
+    mut commands: Commands,
+    link: Option<Res<RaceLink>>,
+    run: Res<RunStats>,
+    mut toasts: EventWriter<ShowToast>,
+    mut planes: Query<(Entity, &mut Transform, &mut Visibility), With<RemotePlane>>,
+) {
+    let link = match link {
+        Some(link) => link,
+        None => return,
+    };
+
+    let time = run.time - REMOTE_DELAY;
+    let sample = link.remote.sample_at(time).or_else(|| {
+        link.remote
+            .samples
+            .last()
+            .map(|last| (last.y, Quat::from_rotation_z(last.angle)))
+    });
+
+    for (entity, mut transform, mut visibility) in planes.iter_mut() {
+        if let Some((crash_time, score)) = link.remote_crash {
+            if time >= crash_time {
+                toasts.send(ShowToast(format!("The other racer crashed at {}", score)));
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        }
+
+        visibility.is_visible = sample.is_some() && link.connected();
+        if let Some((y, rotation)) = sample {
+            transform.translation.y = y;
+            transform.rotation = rotation;
+        }
+    }
+}
+
+pub fn race_crash_system(
+    link: Option<Res<RaceLink>>,
+    mode: Res<RunMode>,
+    run: Res<RunStats>,
+    score: Res<Score>,
+) {
+    let link = match link {
+        Some(link) if *mode == RunMode::Race => link,
+        _ => return,
+    };
+
+    let crashed = RaceMessage::Crashed {
+        time: run.time,
+        score: score.0,
+    };
+    for _ in 0..CRASH_REPEATS {
+        link.send(&crashed);
+    }
+}
+
+// Whoever flew longer wins. The other player may still be flying when the game over screen shows up, so this
+// keeps checking until their crash comes in instead of being a section that's sent once.
+pub fn race_result_system(
+    link: Option<ResMut<RaceLink>>,
+    mode: Res<RunMode>,
+    run: Res<RunStats>,
+    mut sections: EventWriter<GameOverSection>,
+) {
+    let mut link = match link {
+        Some(link) if *mode == RunMode::Race && !link.result_sent => link,
+        _ => return,
+    };
+
+    let text = match link.remote_crash {
+        Some((time, _)) if time < run.time => "You won the race",
+        Some((time, _)) if time > run.time => "You lost the race",
+        Some(_) => "Draw",
+        None if !link.connected() => "The other racer left",
+        None => return,
+    };
+
+    link.result_sent = true;
+    sections.send(GameOverSection::line(0, text.to_string()));
+}
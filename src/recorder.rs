@@ -0,0 +1,619 @@
+use std::fs;
+use std::io::{self, Write};
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::sync::Mutex;
+use std::thread;
+
+use bevy::prelude::*;
+use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::render::main_graph::node::CAMERA_DRIVER;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext};
+use bevy::render::render_resource::{
+    Buffer, BufferDescriptor, BufferUsages, Extent3d, ImageCopyBuffer, ImageDataLayout, MapMode,
+    TextureDimension, TextureFormat, TextureUsages, COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use bevy::render::texture::BevyDefault;
+use bevy::render::{Extract, RenderApp, RenderStage};
+
+use crate::{
+    report_error, unix_seconds, GameError, GameResult, MainCamera, PhotoFinishEvent, ShowToast,
+    HEIGHT, WIDTH,
+};
+
+// Always the playfield at its own size, whatever the window looks like
+const FRAME_WIDTH: u32 = WIDTH as u32;
+const FRAME_HEIGHT: u32 = HEIGHT as u32;
+const BYTES_PER_PIXEL: u32 = 4;
+// Frames are handed over one per rendered frame, so a run that drops frames plays back a little fast
+const FRAME_RATE: u32 = 60;
+// Frames waiting for ffmpeg. When it falls behind the game waits for it instead of skipping frames.
+const QUEUED_FRAMES: usize = 8;
+
+const INDICATOR_SIZE: f32 = 14.0;
+const INDICATOR_BLINK_RATE: f64 = 1.0;
+
+const SCREENSHOT_DIRECTORY: &str = "screenshots";
+const FLASH_TIME: f32 = 0.25;
+const FLASH_ALPHA: f32 = 0.6;
+
+// Records the game to an mp4 by piping every frame to ffmpeg, which has to be on the PATH. F8 starts and stops it.
+// F12 saves a single frame as a png, without needing ffmpeg.
+#[derive(Default)]
+pub struct Recorder {
+    recording: Option<Recording>,
+    screenshot: Option<Screenshot>,
+    // Gets every frame as well while it's set, see start_frame_tap
+    tap: Option<SyncSender<Vec<u8>>>,
+    // Shared by all of them, it's only around while one of them needs it
+    capture: Option<Capture>,
+}
+
+impl Recorder {
+    // Frames are sent without waiting, so whoever receives them misses some when it falls behind instead of holding
+    // up the game like a recording does. False when there's no camera to follow.
+    pub fn start_frame_tap(
+        &mut self,
+        frames: SyncSender<Vec<u8>>,
+        commands: &mut Commands,
+        images: &mut Assets<Image>,
+        cameras: &Query<Entity, With<MainCamera>>,
+    ) -> bool {
+        if !start_capture(self, commands, images, cameras) {
+            return false;
+        }
+
+        self.tap = Some(frames);
+        true
+    }
+
+    pub fn stop_frame_tap(&mut self, commands: &mut Commands, images: &mut Assets<Image>) {
+        if self.tap.take().is_some() {
+            release_capture(self, commands, images);
+        }
+    }
+}
+
+struct Recording {
+    frames: SyncSender<Vec<u8>>,
+}
+
+// Holds on to the first frame it gets, the writer thread tells how that went once the file is written
+struct Screenshot {
+    frame: SyncSender<Vec<u8>>,
+    saved: Mutex<Receiver<io::Result<String>>>,
+}
+
+impl Screenshot {
+    // None while it's still being written
+    fn saved(&self) -> Option<io::Result<String>> {
+        match self.saved.lock().expect("only locked here").try_recv() {
+            Ok(saved) => Some(saved),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(io::ErrorKind::Interrupted.into())),
+        }
+    }
+}
+
+struct Capture {
+    target: Handle<Image>,
+    camera: Entity,
+}
+
+// A second camera drawing the same world into an image, copied out by FrameCaptureNode. It has no UI, so the
+// recording indicator isn't in the video.
+#[derive(Component)]
+pub struct RecorderCamera;
+
+#[derive(Component)]
+pub struct RecordingIndicator;
+
+pub fn setup_recording_indicator(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            color: Color::RED.into(),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(8.0),
+                    right: Val::Px(8.0),
+                    ..default()
+                },
+                size: Size::new(Val::Px(INDICATOR_SIZE), Val::Px(INDICATOR_SIZE)),
+                display: Display::None,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(RecordingIndicator);
+}
+
+pub fn recorder_toggle_system(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut recorder: ResMut<Recorder>,
+    mut images: ResMut<Assets<Image>>,
+    cameras: Query<Entity, With<MainCamera>>,
+    mut toasts: EventWriter<ShowToast>,
+) -> GameResult {
+    if !keys.just_pressed(KeyCode::F8) {
+        return Ok(());
+    }
+
+    // Dropping the sender closes ffmpeg's input, which makes it finish the file
+    if recorder.recording.take().is_some() {
+        release_capture(&mut recorder, &mut commands, &mut images);
+        toasts.send(ShowToast("Recording saved".to_string()));
+        return Ok(());
+    }
+
+    let path = format!("recording-{}.mp4", unix_seconds());
+    let frames = start_ffmpeg(&path).map_err(|source| GameError::Io {
+        path: "ffmpeg",
+        source,
+    })?;
+
+    if !start_capture(&mut recorder, &mut commands, &mut images, &cameras) {
+        return Ok(());
+    }
+
+    info!("Recording to {}", path);
+    toasts.send(ShowToast("Recording".to_string()));
+    recorder.recording = Some(Recording { frames });
+
+    Ok(())
+}
+
+// Also works while recording, the frame comes from the same camera. A photo finish takes one as well.
+pub fn screenshot_system(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut photo_finishes: EventReader<PhotoFinishEvent>,
+    mut recorder: ResMut<Recorder>,
+    mut images: ResMut<Assets<Image>>,
+    cameras: Query<Entity, With<MainCamera>>,
+) {
+    let photo_finish = photo_finishes.iter().count() > 0;
+    if !(keys.just_pressed(KeyCode::F12) || photo_finish) || recorder.screenshot.is_some() {
+        return;
+    }
+
+    if !start_capture(&mut recorder, &mut commands, &mut images, &cameras) {
+        return;
+    }
+
+    recorder.screenshot = Some(start_png_writer());
+}
+
+// Flashes the screen once the file is there. The flash is UI, which the recorder camera doesn't draw.
+pub fn screenshot_saved_system(
+    mut commands: Commands,
+    mut recorder: ResMut<Recorder>,
+    mut images: ResMut<Assets<Image>>,
+    mut toasts: EventWriter<ShowToast>,
+) -> GameResult {
+    let saved = match recorder.screenshot.as_ref().and_then(Screenshot::saved) {
+        Some(saved) => saved,
+        None => return Ok(()),
+    };
+
+    recorder.screenshot = None;
+    release_capture(&mut recorder, &mut commands, &mut images);
+
+    let path = saved.map_err(|source| GameError::Io {
+        path: SCREENSHOT_DIRECTORY,
+        source,
+    })?;
+
+    info!("Saved a screenshot to {}", path);
+    toasts.send(ShowToast("Saved".to_string()));
+    commands
+        .spawn_bundle(NodeBundle {
+            color: Color::rgba(1.0, 1.0, 1.0, FLASH_ALPHA).into(),
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(ScreenshotFlash(Timer::from_seconds(FLASH_TIME, false)));
+
+    Ok(())
+}
+
+#[derive(Component)]
+pub struct ScreenshotFlash(Timer);
+
+// Real time, so it fades the same while paused
+pub fn screenshot_flash_system(
+    mut commands: Commands,
+    mut flashes: Query<(Entity, &mut ScreenshotFlash, &mut UiColor)>,
+    time: Res<Time>,
+) {
+    for (entity, mut flash, mut color) in flashes.iter_mut() {
+        if flash.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            color.0.set_a(FLASH_ALPHA * flash.0.percent_left());
+        }
+    }
+}
+
+// False when there's no camera to follow
+fn start_capture(
+    recorder: &mut Recorder,
+    commands: &mut Commands,
+    images: &mut Assets<Image>,
+    cameras: &Query<Entity, With<MainCamera>>,
+) -> bool {
+    if recorder.capture.is_some() {
+        return true;
+    }
+
+    let main_camera = match cameras.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return false,
+    };
+
+    let target = images.add(frame_image());
+    let mut camera = Camera2dBundle::default();
+    camera.camera.target = RenderTarget::Image(target.clone());
+    // Drawn before the main camera, which clears the window after it
+    camera.camera.priority = -1;
+    camera.projection.scaling_mode = ScalingMode::Auto {
+        min_width: WIDTH,
+        min_height: HEIGHT,
+    };
+    // Follows the main camera around as its child, shakes included
+    camera.transform = Transform::IDENTITY;
+
+    let camera = commands
+        .spawn_bundle(camera)
+        .insert(UiCameraConfig { show_ui: false })
+        .insert(RecorderCamera)
+        .id();
+    commands.entity(main_camera).add_child(camera);
+
+    recorder.capture = Some(Capture { target, camera });
+    true
+}
+
+// Once nothing needs it anymore
+fn release_capture(recorder: &mut Recorder, commands: &mut Commands, images: &mut Assets<Image>) {
+    if recorder.recording.is_some() || recorder.screenshot.is_some() || recorder.tap.is_some() {
+        return;
+    }
+
+    if let Some(capture) = recorder.capture.take() {
+        commands.entity(capture.camera).despawn_recursive();
+        images.remove(&capture.target);
+    }
+}
+
+// Zooming changes the scale of the projection rather than the transform
+pub fn recorder_camera_system(
+    main_cameras: Query<&OrthographicProjection, (With<MainCamera>, Without<RecorderCamera>)>,
+    mut recorder_cameras: Query<&mut OrthographicProjection, With<RecorderCamera>>,
+) {
+    let main_projection = match main_cameras.get_single() {
+        Ok(projection) => projection,
+        Err(_) => return,
+    };
+
+    for mut projection in recorder_cameras.iter_mut() {
+        if projection.scale != main_projection.scale {
+            projection.scale = main_projection.scale;
+        }
+    }
+}
+
+pub fn recording_indicator_system(
+    recorder: Res<Recorder>,
+    time: Res<Time>,
+    mut indicators: Query<&mut Style, With<RecordingIndicator>>,
+) {
+    let blink_on = (time.seconds_since_startup() * INDICATOR_BLINK_RATE * 2.0) as u64 % 2 == 0;
+    let display = if recorder.recording.is_some() && blink_on {
+        Display::Flex
+    } else {
+        Display::None
+    };
+
+    for mut style in indicators.iter_mut() {
+        if style.display != display {
+            style.display = display;
+        }
+    }
+}
+
+fn frame_image() -> Image {
+    let size = Extent3d {
+        width: FRAME_WIDTH,
+        height: FRAME_HEIGHT,
+        depth_or_array_layers: 1,
+    };
+    // The same format the window uses, so the sprite pipelines work for both
+    let mut image = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::bevy_default(),
+    );
+    image.texture_descriptor.usage = TextureUsages::TEXTURE_BINDING
+        | TextureUsages::COPY_DST
+        | TextureUsages::COPY_SRC
+        | TextureUsages::RENDER_ATTACHMENT;
+
+    image
+}
+
+// ffmpeg gets the raw frames on stdin, fed by a thread so the game only waits when ffmpeg falls behind
+fn start_ffmpeg(path: &str) -> io::Result<SyncSender<Vec<u8>>> {
+    let mut child = Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error"])
+        .args(["-f", "rawvideo", "-pix_fmt", pixel_format()])
+        .args(["-s", &format!("{}x{}", FRAME_WIDTH, FRAME_HEIGHT)])
+        .args(["-r", &FRAME_RATE.to_string(), "-i", "-"])
+        .args(["-c:v", "libx264", "-preset", "ultrafast"])
+        .args(["-pix_fmt", "yuv420p"])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .expect("ffmpeg was started with a piped stdin");
+    let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(QUEUED_FRAMES);
+
+    thread::spawn(move || {
+        for frame in receiver {
+            if let Err(err) = stdin.write_all(&frame) {
+                error!("Could not write to ffmpeg: {}", err);
+                break;
+            }
+        }
+
+        // Closing stdin ends the video
+        drop(stdin);
+        if let Err(err) = child.wait() {
+            error!("ffmpeg did not finish: {}", err);
+        }
+    });
+
+    Ok(sender)
+}
+
+// Encoding and writing the png happens off the main thread, like feeding ffmpeg
+fn start_png_writer() -> Screenshot {
+    let (frame, frames) = mpsc::sync_channel::<Vec<u8>>(1);
+    let (sender, saved) = mpsc::channel();
+
+    thread::spawn(move || {
+        // Gone when the screenshot was abandoned
+        if let Ok(frame) = frames.recv() {
+            let _ = sender.send(write_png(frame));
+        }
+    });
+
+    Screenshot {
+        frame,
+        saved: Mutex::new(saved),
+    }
+}
+
+// Named after when it was taken, with a number after it for more than one in the same second
+fn write_png(frame: Vec<u8>) -> io::Result<String> {
+    fs::create_dir_all(SCREENSHOT_DIRECTORY)?;
+
+    let timestamp = unix_seconds();
+    let mut path = format!("{}/screenshot-{}.png", SCREENSHOT_DIRECTORY, timestamp);
+    let mut count = 1;
+    while Path::new(&path).exists() {
+        count += 1;
+        path = format!(
+            "{}/screenshot-{}-{}.png",
+            SCREENSHOT_DIRECTORY, timestamp, count
+        );
+    }
+
+    let mut image = frame_image();
+    image.data = frame;
+    let image = image
+        .try_into_dynamic()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+    image
+        .save(&path)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+
+    Ok(path)
+}
+
+fn pixel_format() -> &'static str {
+    match TextureFormat::bevy_default() {
+        TextureFormat::Rgba8UnormSrgb | TextureFormat::Rgba8Unorm => "rgba",
+        _ => "bgra",
+    }
+}
+
+// Rows of a copied texture are padded to a multiple of the copy alignment
+fn padded_bytes_per_row() -> u32 {
+    let bytes_per_row = FRAME_WIDTH * BYTES_PER_PIXEL;
+    let align = COPY_BYTES_PER_ROW_ALIGNMENT;
+    (bytes_per_row + align - 1) / align * align
+}
+
+// F8 and the indicator, F12, and the render graph node that copies the recorder camera's image out of the GPU
+// every frame while recording, taking a screenshot or tapped
+pub struct RecorderPlugin;
+
+impl Plugin for RecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recorder>()
+            .add_startup_system(setup_recording_indicator)
+            .add_system(recorder_toggle_system.chain(report_error))
+            .add_system(screenshot_system)
+            .add_system(screenshot_saved_system.chain(report_error))
+            .add_system(screenshot_flash_system)
+            .add_system(recorder_camera_system)
+            .add_system(recording_indicator_system);
+
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+
+        render_app
+            .init_resource::<CapturedFrame>()
+            .add_system_to_stage(RenderStage::Extract, extract_recording)
+            .add_system_to_stage(RenderStage::Cleanup, send_captured_frame);
+
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(FrameCaptureNode::NAME, FrameCaptureNode);
+        graph
+            .add_node_edge(CAMERA_DRIVER, FrameCaptureNode::NAME)
+            .expect("the camera driver is part of the main render graph");
+    }
+}
+
+// The render world's view of the recording and screenshot, set again every frame
+#[derive(Default)]
+struct CapturedFrame {
+    target: Option<Handle<Image>>,
+    frames: Option<SyncSender<Vec<u8>>>,
+    screenshot: Option<SyncSender<Vec<u8>>>,
+    tap: Option<SyncSender<Vec<u8>>>,
+    buffer: Option<Buffer>,
+}
+
+fn extract_recording(
+    recorder: Extract<Res<Recorder>>,
+    mut captured: ResMut<CapturedFrame>,
+    render_device: Res<RenderDevice>,
+) {
+    let capture = match &recorder.capture {
+        Some(capture) => capture,
+        None => {
+            *captured = CapturedFrame::default();
+            return;
+        }
+    };
+
+    captured.target = Some(capture.target.clone());
+    captured.frames = recorder
+        .recording
+        .as_ref()
+        .map(|recording| recording.frames.clone());
+    captured.screenshot = recorder
+        .screenshot
+        .as_ref()
+        .map(|screenshot| screenshot.frame.clone());
+    captured.tap = recorder.tap.clone();
+
+    if captured.buffer.is_none() {
+        captured.buffer = Some(render_device.create_buffer(&BufferDescriptor {
+            label: Some("recorder_frame_buffer"),
+            size: (padded_bytes_per_row() * FRAME_HEIGHT) as u64,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        }));
+    }
+}
+
+struct FrameCaptureNode;
+
+impl FrameCaptureNode {
+    const NAME: &'static str = "frame_capture";
+}
+
+impl Node for FrameCaptureNode {
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let captured = world.resource::<CapturedFrame>();
+        let (target, buffer) = match (&captured.target, &captured.buffer) {
+            (Some(target), Some(buffer)) => (target, buffer),
+            _ => return Ok(()),
+        };
+        // Not uploaded yet on the first frame of a recording
+        let image = match world.resource::<RenderAssets<Image>>().get(target) {
+            Some(image) => image,
+            None => return Ok(()),
+        };
+
+        render_context.command_encoder.copy_texture_to_buffer(
+            image.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_bytes_per_row()),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width: FRAME_WIDTH,
+                height: FRAME_HEIGHT,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+// Runs after the frame has been submitted. Waiting for the copy stalls the GPU a little, which only happens while
+// recording, tapped or for the few frames it takes to save a screenshot.
+fn send_captured_frame(
+    captured: Res<CapturedFrame>,
+    images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+) {
+    let (target, buffer) = match (&captured.target, &captured.buffer) {
+        (Some(target), Some(buffer)) => (target, buffer),
+        _ => return,
+    };
+    // Same check as FrameCaptureNode, nothing was copied without the image
+    if images.get(target).is_none() {
+        return;
+    }
+
+    let slice = buffer.slice(..);
+    slice.map_async(MapMode::Read, |result| {
+        if let Err(err) = result {
+            error!("Could not read a recorded frame: {}", err);
+        }
+    });
+    render_device.poll(wgpu::Maintain::Wait);
+
+    let padded = padded_bytes_per_row() as usize;
+    let row = (FRAME_WIDTH * BYTES_PER_PIXEL) as usize;
+    let frame: Vec<u8> = slice
+        .get_mapped_range()
+        .chunks(padded)
+        .flat_map(|padded_row| &padded_row[..row])
+        .copied()
+        .collect();
+    buffer.unmap();
+
+    // The screenshot only takes the first frame, later ones find it full or already gone
+    if let Some(screenshot) = &captured.screenshot {
+        let _ = screenshot.try_send(frame.clone());
+    }
+    if let Some(tap) = &captured.tap {
+        let _ = tap.try_send(frame.clone());
+    }
+
+    // Only fails once the writer thread gave up, which it already logged
+    if let Some(frames) = &captured.frames {
+        let _ = frames.send(frame);
+    }
+}
@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Difficulty, Leaderboard, Mutator, RunMode};
+
+const GAME_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Stands in for the leaderboard of a record that hasn't been set yet
+static EMPTY_LEADERBOARD: Leaderboard = Leaderboard {
+    entries: Vec::new(),
+};
+
+// What a score was set with. Only scores with the same key are compared, so modes, difficulties and mutators
+// never clobber each other's bests.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RecordKey {
+    pub mode: RunMode,
+    pub difficulty: Difficulty,
+    pub mutators: Vec<Mutator>,
+    // The day of a daily record or the week of a weekly one, 0 for records that never expire
+    pub period: u64,
+}
+
+impl RecordKey {
+    pub fn new(mode: RunMode, mutators: Vec<Mutator>, period: u64) -> Self {
+        Self {
+            mode,
+            difficulty: Difficulty::Normal,
+            mutators,
+            period,
+        }
+    }
+
+    pub fn with_difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = difficulty;
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ScoreRecord {
+    pub key: RecordKey,
+    pub best: u64,
+    pub leaderboard: Leaderboard,
+    // Version of the game the best was set in, empty for records from before versions were kept
+    #[serde(default)]
+    pub version: String,
+}
+
+// Every record of the player, stored in the save file
+#[derive(Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Records(Vec<ScoreRecord>);
+
+impl Records {
+    pub fn best(&self, key: &RecordKey) -> u64 {
+        self.get(key).map_or(0, |record| record.best)
+    }
+
+    pub fn leaderboard(&self, key: &RecordKey) -> &Leaderboard {
+        self.get(key)
+            .map_or(&EMPTY_LEADERBOARD, |record| &record.leaderboard)
+    }
+
+    pub fn set_best(&mut self, key: RecordKey, score: u64) {
+        let record = self.get_or_insert(key);
+        if score > record.best {
+            record.best = score;
+            record.version = GAME_VERSION.to_string();
+        }
+    }
+
+    pub fn leaderboard_mut(&mut self, key: RecordKey) -> &mut Leaderboard {
+        &mut self.get_or_insert(key).leaderboard
+    }
+
+    // For saves from before records were kept per mode, which don't know what version they're from
+    pub fn add_legacy(&mut self, key: RecordKey, best: u64, leaderboard: Leaderboard) {
+        self.merge(Records(vec![ScoreRecord {
+            key,
+            best,
+            leaderboard,
+            version: String::new(),
+        }]));
+    }
+
+    // Keeps the best of both, for importing profiles
+    pub fn merge(&mut self, other: Records) {
+        for other in other.0 {
+            match self.0.iter_mut().find(|record| record.key == other.key) {
+                Some(record) => {
+                    if other.best > record.best {
+                        record.best = other.best;
+                        record.version = other.version;
+                    }
+                    record.leaderboard.merge(other.leaderboard);
+                }
+                None => self.0.push(other),
+            }
+        }
+
+        self.remove_expired();
+    }
+
+    fn get(&self, key: &RecordKey) -> Option<&ScoreRecord> {
+        self.0.iter().find(|record| record.key == *key)
+    }
+
+    // Drops the records of days and weeks that have passed, once there's a newer one
+    fn get_or_insert(&mut self, key: RecordKey) -> &mut ScoreRecord {
+        self.remove_expired();
+
+        let index = match self.0.iter().position(|record| record.key == key) {
+            Some(index) => index,
+            None => {
+                self.0.push(ScoreRecord {
+                    key,
+                    best: 0,
+                    leaderboard: Leaderboard::default(),
+                    version: GAME_VERSION.to_string(),
+                });
+                self.0.len() - 1
+            }
+        };
+
+        &mut self.0[index]
+    }
+
+    // Only the latest day or week of a mode is kept, like the single daily best and weekly board used to be
+    fn remove_expired(&mut self) {
+        let latest: Vec<(RunMode, u64)> = self
+            .0
+            .iter()
+            .map(|record| (record.key.mode, record.key.period))
+            .collect();
+
+        self.0.retain(|record| {
+            !latest
+                .iter()
+                .any(|(mode, period)| *mode == record.key.mode && *period > record.key.period)
+        });
+    }
+}
@@ -0,0 +1,229 @@
+use bevy::prelude::*;
+use bevy::render::camera::{ScalingMode, Viewport};
+use bevy::window::{PresentMode, WindowResized, WindowScaleFactorChanged};
+
+use crate::{GameState, MainCamera, SaveData, Settings, HEIGHT, WIDTH};
+
+const FRAME_BUDGET: f32 = 1.0 / 60.0;
+
+// How much each frame moves the average, so a single hitch doesn't change the resolution
+const FRAME_TIME_SMOOTHING: f32 = 0.05;
+
+// Seconds the average has to stay over budget before lowering the resolution,
+// and within budget before trying the next step up again
+const LOWER_DELAY: f32 = 1.0;
+const RAISE_DELAY: f32 = 5.0;
+
+const RENDER_SCALE_STEP: f64 = 0.1;
+const MIN_RENDER_SCALE: f64 = 0.5;
+
+// Renders fewer pixels when frames take too long. The window keeps its logical size, so the result is upscaled.
+pub struct DynamicResolution {
+    render_scale: f64,
+    average_frame_time: f32,
+    over_budget: f32,
+    within_budget: f32,
+}
+
+impl Default for DynamicResolution {
+    fn default() -> Self {
+        Self {
+            render_scale: 1.0,
+            average_frame_time: FRAME_BUDGET,
+            over_budget: 0.0,
+            within_budget: 0.0,
+        }
+    }
+}
+
+pub fn dynamic_resolution_system(
+    mut resolution: ResMut<DynamicResolution>,
+    save: Res<SaveData>,
+    state: Res<State<GameState>>,
+    time: Res<Time>,
+    mut windows: ResMut<Windows>,
+) {
+    let window = match windows.get_primary_mut() {
+        Some(window) => window,
+        None => return,
+    };
+
+    if !save.settings.dynamic_resolution {
+        if resolution.render_scale < 1.0 {
+            *resolution = DynamicResolution::default();
+            window.set_scale_factor_override(None);
+        }
+        return;
+    }
+
+    // Menus deliberately run at a low frame rate, see menu_idle_system
+    if state.current() != &GameState::Playing {
+        resolution.over_budget = 0.0;
+        resolution.within_budget = 0.0;
+        return;
+    }
+
+    let dt = time.delta_seconds();
+    resolution.average_frame_time += (dt - resolution.average_frame_time) * FRAME_TIME_SMOOTHING;
+
+    // With vsync frames never come in much under budget, so being on budget is as much headroom as can be seen
+    if resolution.average_frame_time > FRAME_BUDGET * 1.1 {
+        resolution.over_budget += dt;
+        resolution.within_budget = 0.0;
+    } else {
+        resolution.within_budget += dt;
+        resolution.over_budget = 0.0;
+    }
+
+    let mut render_scale = resolution.render_scale;
+    if resolution.over_budget >= LOWER_DELAY {
+        render_scale = (render_scale - RENDER_SCALE_STEP).max(MIN_RENDER_SCALE);
+        resolution.over_budget = 0.0;
+    } else if resolution.within_budget >= RAISE_DELAY {
+        render_scale = (render_scale + RENDER_SCALE_STEP).min(1.0);
+        resolution.within_budget = 0.0;
+    }
+
+    if render_scale != resolution.render_scale {
+        resolution.render_scale = render_scale;
+        window.set_scale_factor_override(Some(window.backend_scale_factor() * render_scale));
+    }
+}
+
+pub fn window_mode(settings: &Settings) -> WindowMode {
+    if settings.fullscreen {
+        WindowMode::BorderlessFullscreen
+    } else {
+        WindowMode::Windowed
+    }
+}
+
+// Fifo is the one mode every platform supports, browsers always vsync whatever this says
+pub fn present_mode(settings: &Settings) -> PresentMode {
+    if settings.vsync {
+        PresentMode::Fifo
+    } else {
+        PresentMode::Immediate
+    }
+}
+
+// The window starts out the way it was left, see main. This follows changes from the settings screen and the
+// fullscreen keys.
+pub fn window_mode_system(save: Res<SaveData>, mut windows: ResMut<Windows>) {
+    if !save.is_changed() {
+        return;
+    }
+
+    let window = match windows.get_primary_mut() {
+        Some(window) => window,
+        None => return,
+    };
+
+    let mode = window_mode(&save.settings);
+    if window.mode() != mode {
+        window.set_mode(mode);
+    }
+
+    let present_mode = present_mode(&save.settings);
+    if window.present_mode() != present_mode {
+        window.set_present_mode(present_mode);
+    }
+
+    let cursor_visible = !save.settings.gamepad_menus;
+    if window.cursor_visible() != cursor_visible {
+        window.set_cursor_visibility(cursor_visible);
+    }
+}
+
+// F11, or Alt+Enter like most Windows games. Runs before anything else reads the keyboard, so the Enter of
+// Alt+Enter doesn't also press a menu button.
+pub fn fullscreen_toggle_system(mut keys: ResMut<Input<KeyCode>>, mut save: ResMut<SaveData>) {
+    let alt = keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]);
+    let alt_enter = alt && keys.just_pressed(KeyCode::Return);
+
+    if keys.just_pressed(KeyCode::F11) || alt_enter {
+        save.settings.fullscreen = !save.settings.fullscreen;
+        keys.reset(KeyCode::Return);
+    }
+}
+
+// The part of the window the game is drawn in, in logical pixels from the bottom left like the cursor. The
+// playfield is always WIDTH by HEIGHT, a window of a different shape gets black bars on the sides or at the top
+// and bottom instead of showing more or less of the course.
+pub struct ViewportSize {
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+impl Default for ViewportSize {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            size: Vec2::new(WIDTH, HEIGHT),
+        }
+    }
+}
+
+impl ViewportSize {
+    fn fit(window_size: Vec2) -> Self {
+        let scale = (window_size.x / WIDTH).min(window_size.y / HEIGHT);
+        let size = Vec2::new(WIDTH, HEIGHT) * scale;
+
+        Self {
+            position: (window_size - size) / 2.0,
+            size,
+        }
+    }
+
+    // From -1 to 1 across the viewport, None on the bars
+    pub fn to_ndc(&self, window_position: Vec2) -> Option<Vec2> {
+        let ndc = (window_position - self.position) / self.size * 2.0 - Vec2::ONE;
+        (ndc.abs().max_element() <= 1.0).then_some(ndc)
+    }
+}
+
+pub fn setup_camera(mut commands: Commands) {
+    let mut camera = Camera2dBundle::default();
+    // Whatever the size of the viewport, it shows the whole playfield and nothing else
+    camera.projection.scaling_mode = ScalingMode::Auto {
+        min_width: WIDTH,
+        min_height: HEIGHT,
+    };
+
+    commands.spawn_bundle(camera).insert(MainCamera);
+}
+
+// The bars are the clear color, so they're drawn without anything else having to know about them
+pub fn viewport_system(
+    mut resized: EventReader<WindowResized>,
+    mut scale_factor_changed: EventReader<WindowScaleFactorChanged>,
+    windows: Res<Windows>,
+    mut viewport: ResMut<ViewportSize>,
+    mut camera_query: Query<&mut Camera, With<MainCamera>>,
+) {
+    let changed = resized.iter().count() > 0 || scale_factor_changed.iter().count() > 0;
+
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let mut camera = match camera_query.get_single_mut() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+
+    // Nothing gets resized before the first frame, so that one is set up here too
+    if !changed && camera.viewport.is_some() {
+        return;
+    }
+
+    *viewport = ViewportSize::fit(Vec2::new(window.width(), window.height()));
+
+    // Centered, so the position is the same counted from the top as from the bottom
+    let scale_factor = window.scale_factor() as f32;
+    camera.viewport = Some(Viewport {
+        physical_position: (viewport.position * scale_factor).as_uvec2(),
+        physical_size: (viewport.size * scale_factor).as_uvec2().max(UVec2::ONE),
+        ..default()
+    });
+}
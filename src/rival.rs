@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task};
+use futures_lite::future;
+
+use crate::{
+    BitmapTextBundle, GameAssets, GameModes, Layer, RemoveAfterState, RunMode, RunStats, SaveData,
+    Score, ScoreSubmission, ScreenAnchor, ShowToast,
+};
+
+const RIVAL_GHOST_ALPHA: f32 = 0.4;
+
+// The online endless scores, asked for once when the game starts. Every submission comes with its flight path,
+// so any of them can be raced.
+pub struct RivalScores {
+    scores: Vec<ScoreSubmission>,
+    task: Option<Task<Result<Vec<ScoreSubmission>, String>>>,
+}
+
+impl RivalScores {
+    // Started by setup_online, only players taking part in the online leaderboard get rivals
+    pub fn fetch(url: &str) -> Self {
+        let url = url.to_string();
+
+        Self {
+            scores: vec![],
+            task: Some(IoTaskPool::get().spawn(async move { fetch_scores(&url) })),
+        }
+    }
+}
+
+// The score just above the player's endless best, picked again before every run so beating a rival moves on to
+// the next one
+pub struct Rival(ScoreSubmission);
+
+// Drawn like the player's own ghost, in the second plane's color so the two can be told apart
+#[derive(Component)]
+pub struct RivalGhost;
+
+pub fn rival_scores_system(scores: Option<ResMut<RivalScores>>) {
+    let mut scores = match scores {
+        Some(scores) => scores,
+        None => return,
+    };
+
+    let task = match &mut scores.task {
+        Some(task) => task,
+        None => return,
+    };
+    let result = match future::block_on(future::poll_once(task)) {
+        Some(result) => result,
+        None => return,
+    };
+
+    scores.task = None;
+
+    // Not retried, endless runs simply go without a rival
+    match result {
+        Ok(fetched) => scores.scores = fetched,
+        Err(err) => warn!("Could not get the online scores: {}", err),
+    }
+}
+
+// Shown on the start screen, under the get ready sign
+pub fn setup_rival(
+    mut commands: Commands,
+    scores: Option<Res<RivalScores>>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    save: Res<SaveData>,
+) {
+    commands.remove_resource::<Rival>();
+
+    let scores = match scores {
+        Some(scores) if *mode == RunMode::Endless => scores,
+        _ => return,
+    };
+
+    let best = modes.get(*mode).best_score(&save);
+    let rival = scores
+        .scores
+        .iter()
+        .filter(|entry| entry.mode == RunMode::Endless && entry.score > best)
+        .filter(|entry| !entry.replay.samples.is_empty())
+        .min_by_key(|entry| entry.score);
+
+    let rival = match rival {
+        Some(rival) => rival.clone(),
+        None => return,
+    };
+
+    let mut bundle = BitmapTextBundle::at(ScreenAnchor::Bottom.offset(-150.0, 80.0))
+        .with_text(format!("Rival {} {}", rival.name, rival.score));
+    bundle.transform.scale = Vec3::new(0.5, 0.5, 1.0);
+
+    commands.spawn_bundle(bundle).insert(RemoveAfterState);
+    commands.insert_resource(Rival(rival));
+}
+
+pub fn setup_rival_ghost(
+    mut commands: Commands,
+    rival: Option<Res<Rival>>,
+    assets: Res<GameAssets>,
+) {
+    if rival.is_none() {
+        return;
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 1.0, 1.0, RIVAL_GHOST_ALPHA),
+                ..default()
+            },
+            texture: assets.second_player.clone(),
+            transform: Transform::from_xyz(-200.0, 0.0, Layer::Ghost.z())
+                .with_scale(Vec3::new(0.5, 0.5, 1.0)),
+            ..default()
+        })
+        .insert(RivalGhost)
+        .insert(RemoveAfterState);
+}
+
+// Follows the rival's flight path until the player's score goes past theirs
+pub fn rival_ghost_system(
+    mut commands: Commands,
+    rival: Option<Res<Rival>>,
+    run: Res<RunStats>,
+    score: Res<Score>,
+    mut toasts: EventWriter<ShowToast>,
+    mut ghost_query: Query<(Entity, &mut Transform, &mut Visibility), With<RivalGhost>>,
+) {
+    let rival = match rival {
+        Some(rival) => rival,
+        None => return,
+    };
+
+    for (entity, mut transform, mut visibility) in ghost_query.iter_mut() {
+        if score.0 > rival.0.score {
+            toasts.send(ShowToast(format!("You passed {}", rival.0.name)));
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        match rival.0.replay.sample_at(run.time) {
+            Some((y, rotation)) => {
+                transform.translation.y = y;
+                transform.rotation = rotation;
+            }
+            None => visibility.is_visible = false,
+        }
+    }
+}
+
+fn fetch_scores(url: &str) -> Result<Vec<ScoreSubmission>, String> {
+    let body = ureq::get(&format!("{}/scores/{:?}", url, RunMode::Endless))
+        .call()
+        .map_err(|err| err.to_string())?
+        .into_string()
+        .map_err(|err| err.to_string())?;
+
+    ron::from_str(&body).map_err(|err| err.to_string())
+}
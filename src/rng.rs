@@ -0,0 +1,63 @@
+use std::env;
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::{GameModes, RunMode};
+
+const SEED_ENV_VAR: &str = "BEVY_PLANE_SEED";
+const SEED_ARG: &str = "--seed";
+
+// All randomness in the game comes from here, so runs can be reproduced from a seed.
+// The course and cosmetic effects use separate streams, so text and particles never change the rocks.
+pub struct GameRng {
+    // Fixed seed from the command line or environment, if any
+    pub seed: Option<u64>,
+    pub course: StdRng,
+    pub cosmetic: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed,
+            course: seeded_or_random(seed),
+            cosmetic: seeded_or_random(seed.map(|seed| !seed)),
+        }
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(fixed_seed())
+    }
+}
+
+// Reads the seed from `--seed <n>`, falling back to the BEVY_PLANE_SEED environment variable
+pub fn fixed_seed() -> Option<u64> {
+    let mut args = env::args().skip_while(|arg| arg != SEED_ARG).skip(1);
+
+    args.next()
+        .or_else(|| env::var(SEED_ENV_VAR).ok())
+        .and_then(|seed| match seed.parse() {
+            Ok(seed) => Some(seed),
+            Err(_) => {
+                warn!("Ignoring invalid seed {:?}", seed);
+                None
+            }
+        })
+}
+
+pub fn seed_run_rng(mut rng: ResMut<GameRng>, mode: Res<RunMode>, modes: Res<GameModes>) {
+    let seed = modes.get(*mode).course_seed(rng.seed);
+
+    rng.course = seeded_or_random(seed);
+}
+
+fn seeded_or_random(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::{
+    add_collision_outline, BaseColor, Behaviors, CollisionPolygon, Crumbling, DespawnOffscreen,
+    HorizontalVelocity, Interpolated, Layer, Oscillation, Rock, ROCK_DOWN_POINTS, ROCK_UP_POINTS,
+};
+
+// About as many rocks of each kind as fit on screen at once, more are spawned if a run ever needs them
+const PREWARMED_ROCKS: usize = 8;
+
+// Rocks that went off screen, kept for the next gates instead of being despawned. Spawning a rock builds the
+// mesh of its collision outline, so recycling them saves that along with the entity and its child.
+#[derive(Default)]
+pub struct RockPool {
+    up: Vec<Entity>,
+    down: Vec<Entity>,
+}
+
+// Which way a rock from the pool points. Stays on it while it's on the course, so it can go back to the pool.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PooledRock {
+    Up,
+    Down,
+}
+
+impl PooledRock {
+    pub fn points(self) -> &'static [(f32, f32)] {
+        match self {
+            PooledRock::Up => ROCK_UP_POINTS,
+            PooledRock::Down => ROCK_DOWN_POINTS,
+        }
+    }
+}
+
+impl RockPool {
+    fn free(&mut self, kind: PooledRock) -> &mut Vec<Entity> {
+        match kind {
+            PooledRock::Up => &mut self.up,
+            PooledRock::Down => &mut self.down,
+        }
+    }
+
+    // A hidden rock without any of the components that put it on the course, see spawn_rocks
+    pub fn acquire(&mut self, commands: &mut Commands, kind: PooledRock) -> Entity {
+        self.free(kind)
+            .pop()
+            .unwrap_or_else(|| spawn_pooled_rock(commands, kind))
+    }
+
+    // Takes the rock off the course. Its outline stays, that doesn't depend on the scale the rock is spawned at.
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity, kind: PooledRock) {
+        commands
+            .entity(entity)
+            .remove_bundle::<(
+                Rock,
+                Oscillation,
+                Behaviors,
+                Crumbling,
+                CollisionPolygon,
+                HorizontalVelocity,
+                DespawnOffscreen,
+                Interpolated,
+                BaseColor,
+            )>()
+            .insert(Visibility { is_visible: false });
+        self.free(kind).push(entity);
+    }
+}
+
+// Everything that despawns rocks goes through here, rocks that weren't spawned from the pool (like the ones a
+// savestate restores) are despawned as usual
+pub fn despawn_or_pool(
+    commands: &mut Commands,
+    pool: &mut RockPool,
+    entity: Entity,
+    pooled: Option<&PooledRock>,
+) {
+    match pooled {
+        Some(kind) => pool.release(commands, entity, *kind),
+        None => commands.entity(entity).despawn_recursive(),
+    }
+}
+
+// The outline is built from the unscaled shape, it gets stretched along with the rock
+fn spawn_pooled_rock(commands: &mut Commands, kind: PooledRock) -> Entity {
+    let mut entity = commands.spawn_bundle(SpriteBundle {
+        transform: Transform::from_xyz(0.0, 0.0, Layer::Course.z()),
+        visibility: Visibility { is_visible: false },
+        ..default()
+    });
+    entity.insert(kind);
+    add_collision_outline(&mut entity, kind.points(), Vec3::ONE);
+
+    entity.id()
+}
+
+// Done while loading, so the first gates of the first run don't have to build their outlines
+pub fn prewarm_rock_pool(mut commands: Commands, mut pool: ResMut<RockPool>) {
+    for kind in [PooledRock::Up, PooledRock::Down] {
+        while pool.free(kind).len() < PREWARMED_ROCKS {
+            let entity = spawn_pooled_rock(&mut commands, kind);
+            pool.free(kind).push(entity);
+        }
+    }
+}
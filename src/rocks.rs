@@ -1,26 +1,55 @@
-use crate::{GameState, HorizontalVelocity, Player, PlayerShape, Score, HEIGHT, WIDTH, GameSpeed};
+use std::f32::consts::TAU;
+
+use crate::{
+    pass_through, respawn_plane, spawn_floating_text, z_angle, ActiveDifficulty, ActiveMutators,
+    BaseColor, Behaviors, CollisionOutline, Combo, Continues, CrashCause, CrashEvent, Crumbling,
+    Damage, DemoMode, DespawnOffscreen, Fuel, GameConfig, GameError, GameModes, GameResult,
+    GameRng, GameSpeed, GameState, GatePassedEvent, GateSpawn, HorizontalVelocity, Interpolated,
+    Invincible, Layer, Lives, NearMissEvent, ObstaclePatterns, ObstacleQueue, Pilot, Player,
+    PlayerShape, PooledRock, RockPool, RockTypesConfig, RunMode, SaveData, Score, ScreenAnchor,
+    SpawnHooks, SpeedZone, ThemeState, ThemeTextures, Tutorial, CRUMBLING_CHANCE,
+    CRUMBLING_MIN_SCORE, GROUND_HEIGHT, HEIGHT, PHYSICS_STEP,
+};
 use bevy::ecs::system::EntityCommands;
 use bevy::prelude::*;
-use bevy::utils::Duration;
-#[cfg(debug_assertions)]
+use bevy::utils::{Duration, HashMap};
 use bevy_prototype_lyon::prelude::*;
+use ncollide2d::bounding_volume::BoundingVolume;
 use ncollide2d::na;
 use ncollide2d::na::{Isometry2, Point2, Vector2};
-use ncollide2d::query::{self, Proximity};
-use ncollide2d::shape::ConvexPolygon;
+use ncollide2d::query::{self, DefaultTOIDispatcher, PointQuery, Proximity};
+use ncollide2d::shape::{ConvexPolygon, Segment, Shape, ShapeHandle};
 use rand::prelude::*;
 
 const ROCK_WIDTH: f32 = 108.0;
-const ROCK_HEIGHT: f32 = 239.0;
-const ROCK_MIN_X: f32 = -WIDTH / 2.0 - ROCK_WIDTH;
+pub const ROCK_HEIGHT: f32 = 239.0;
 
-const ROCK_UP_POINTS: &'static [(f32, f32)] = &[
+// Distance between the pairs of a cave, close enough that their slopes meet
+const CAVE_SEGMENT_SPACING: f32 = ROCK_WIDTH / 2.0;
+
+// Distance from a rock at which flying past it counts as a near miss
+const NEAR_MISS_MARGIN: f32 = 12.0;
+const NEAR_MISS_BONUS: u64 = 1;
+
+// How fast rocks scroll towards the plane at normal game speed
+pub const COURSE_VELOCITY: f32 = 250.0;
+
+pub const SCORE_POPUP_SCALE: f32 = 0.4;
+
+// Rock colliders are built for the scale rounded to a step of 1/200, off by well under a pixel
+const SHAPE_SCALE_STEPS: f32 = 200.0;
+
+// Moving rocks only show up once the player has had some time to get used to the game
+const OSCILLATION_MIN_SCORE: u64 = 20;
+const OSCILLATION_CHANCE: f64 = 0.3;
+
+pub const ROCK_UP_POINTS: &'static [(f32, f32)] = &[
     (-ROCK_WIDTH / 2.0 + 6.0, -ROCK_HEIGHT / 2.0),
     (ROCK_WIDTH / 2.0 - 6.0, -ROCK_HEIGHT / 2.0),
     (12.0, ROCK_HEIGHT / 2.0),
 ];
 
-const ROCK_DOWN_POINTS: &'static [(f32, f32)] = &[
+pub const ROCK_DOWN_POINTS: &'static [(f32, f32)] = &[
     (12.0, -ROCK_HEIGHT / 2.0),
     (-ROCK_WIDTH / 2.0 + 6.0, ROCK_HEIGHT / 2.0),
     (ROCK_WIDTH / 2.0 - 6.0, ROCK_HEIGHT / 2.0),
@@ -28,14 +57,95 @@ const ROCK_DOWN_POINTS: &'static [(f32, f32)] = &[
 
 pub struct RockTimer(pub Timer);
 
-#[derive(Component)]
+// The collision shape of an entity, relative to its translation. A convex polygon for almost everything,
+// lasers use a segment. Cloning it shares the shape.
+#[derive(Component, Clone)]
 pub struct CollisionPolygon {
-    polygon: ConvexPolygon<f32>,
+    shape: ShapeHandle<f32>,
+}
+
+impl CollisionPolygon {
+    // A line from a to b, which can be swapped out every step for things that rotate
+    pub fn segment(a: Vec2, b: Vec2) -> Self {
+        Self {
+            shape: ShapeHandle::new(Segment::new(to_point2(a), to_point2(b))),
+        }
+    }
+
+    // Whether a point is inside the polygon of an entity at the given position, for things too small for a shape
+    pub fn contains(&self, position: Vec3, point: Vec3) -> bool {
+        let iso = Isometry2::new(to_vector2(position), na::zero());
+        self.shape
+            .as_point_query()
+            .map_or(false, |shape| shape.contains_point(&iso, &to_point2(point)))
+    }
+}
+
+// Collision shapes of rocks by kind and rounded scale. A handful of shapes cover every gate, so most rocks share
+// one that was built for an earlier gate instead of building their own.
+#[derive(Default)]
+pub struct RockShapes(HashMap<(PooledRock, u32), CollisionPolygon>);
+
+impl RockShapes {
+    // None if the shape isn't convex at that scale
+    pub fn get(&mut self, kind: PooledRock, scale: f32) -> Option<CollisionPolygon> {
+        let step = (scale * SHAPE_SCALE_STEPS).round() as u32;
+
+        if let Some(polygon) = self.0.get(&(kind, step)) {
+            return Some(polygon.clone());
+        }
+
+        let polygon = scaled_polygon(kind.points(), step as f32 / SHAPE_SCALE_STEPS)?;
+        self.0.insert((kind, step), polygon.clone());
+        Some(polygon)
+    }
 }
 
+// How many rocks and hazards the last physics step compared the planes against, and how many of those were close
+// enough for the full collision checks. Shown in the debug overlay.
+#[derive(Default)]
+pub struct CollisionStats {
+    pub pairs: usize,
+    pub checked: usize,
+}
+
+// The collision layer of everything other than rocks that crashes a plane on contact. Unlike rocks these
+// aren't scored as gates and don't count for near misses.
 #[derive(Component)]
+pub struct Hazard;
+
+#[derive(Component, Clone)]
 pub struct Rock {
+    // Both rocks of a pair share it, no two pairs ever do
+    gate: u64,
+    // Which side of the plane it was on after the last step, None until it has had one
+    ahead: Option<bool>,
     has_scored: bool,
+    // Only the last pair of a cave scores its gate, once the plane comes out of it. Every other pair has one.
+    trigger: bool,
+    near_missed: bool,
+    // Vertical middle of the gap this rock is part of
+    pub gap_center: f32,
+    // What passing its gate is worth before the combo, see the rocks section of config.ron
+    points: u64,
+}
+
+impl Rock {
+    pub fn gate(&self) -> u64 {
+        self.gate
+    }
+}
+
+// Moves a pair of rocks up and down around where they spawned, so the gap shifts while the player approaches it.
+// Small enough that the bottom of a rock never comes out from behind the ground strip.
+#[derive(Component, Clone)]
+pub struct Oscillation {
+    amplitude: f32,
+    // In cycles per second
+    frequency: f32,
+    time: f32,
+    base_y: f32,
+    base_gap_center: f32,
 }
 
 enum BevyVec {
@@ -89,161 +199,712 @@ fn to_point2<T: Into<BevyVec>>(v: T) -> Point2<f32> {
 }
 
 pub fn collision_system(
-    player_query: Query<(&Player, &Transform)>,
-    rock_query: Query<(&CollisionPolygon, &Transform), With<Rock>>,
+    mut commands: Commands,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut Player,
+            &Pilot,
+            &mut Transform,
+            &mut Interpolated,
+            Option<&mut Damage>,
+        ),
+        Without<Invincible>,
+    >,
+    // Without<Player> tells bevy these queries never overlap the planes, which are borrowed mutably for respawns
+    mut rock_query: Query<
+        (&CollisionPolygon, &Transform, &Interpolated, &mut Rock),
+        Without<Player>,
+    >,
+    hazard_query: Query<
+        (&CollisionPolygon, &Transform, &Interpolated),
+        (With<Hazard>, Without<Player>),
+    >,
     mut state: ResMut<State<GameState>>,
-) {
-    let (player, player_transform) = player_query.single();
+    mut near_miss_events: EventWriter<NearMissEvent>,
+    mut crash_events: EventWriter<CrashEvent>,
+    demo: Res<DemoMode>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    mut lives: ResMut<Lives>,
+    mut fuel: ResMut<Fuel>,
+    config: Res<GameConfig>,
+    mut stats: ResMut<CollisionStats>,
+    continues: Res<Continues>,
+    save: Res<SaveData>,
+) -> GameResult {
+    let mut crashed = vec![];
+    *stats = CollisionStats::default();
+
+    for (_, player, pilot, player_transform, player_previous, _) in player_query.iter() {
+        // Out of sight for too long, see bounds_system
+        if config.bounds.is_out(player) {
+            crashed.push(*pilot);
+            crash_events.send(CrashEvent {
+                position: player_transform.translation,
+                cause: CrashCause::Bounds,
+            });
+            continue;
+        }
 
-    let (_, player_angle) = player_transform.rotation.to_axis_angle();
+        let player_motion = StepMotion::new(player_transform, player_previous);
 
-    for (rock_polygon, rock_transform) in rock_query.iter() {
-        if is_rock_collision(
-            player_transform.translation,
-            &player.shape,
-            player_angle,
-            rock_transform,
-            rock_polygon,
-        ) {
-            state.set(GameState::GameOver).unwrap();
-            return;
+        for (rock_polygon, rock_transform, rock_previous, mut rock) in rock_query.iter_mut() {
+            stats.pairs += 1;
+            let rock_motion = StepMotion::new(rock_transform, rock_previous);
+            if !might_touch(&player_motion, &player.shape, &rock_motion, rock_polygon) {
+                continue;
+            }
+            stats.checked += 1;
+
+            let proximity = step_proximity(
+                &player_motion,
+                &player.shape,
+                &rock_motion,
+                rock_polygon,
+                config.physics.collision_substeps,
+            );
+
+            if matches!(proximity, Proximity::Intersecting) {
+                crashed.push(*pilot);
+                crash_events.send(CrashEvent {
+                    position: player_transform.translation,
+                    cause: CrashCause::Rock,
+                });
+                break;
+            }
+
+            if matches!(proximity, Proximity::WithinMargin) && !rock.near_missed {
+                rock.near_missed = true;
+                near_miss_events.send(NearMissEvent {
+                    position: player_transform.translation,
+                    before_gate: !rock.has_scored,
+                });
+            }
         }
+
+        if crashed.contains(pilot) {
+            continue;
+        }
+
+        for (hazard_polygon, hazard_transform, hazard_previous) in hazard_query.iter() {
+            stats.pairs += 1;
+            let hazard_motion = StepMotion::new(hazard_transform, hazard_previous);
+            if !might_touch(
+                &player_motion,
+                &player.shape,
+                &hazard_motion,
+                hazard_polygon,
+            ) {
+                continue;
+            }
+            stats.checked += 1;
+
+            let proximity = step_proximity(
+                &player_motion,
+                &player.shape,
+                &hazard_motion,
+                hazard_polygon,
+                config.physics.collision_substeps,
+            );
+
+            if matches!(proximity, Proximity::Intersecting) {
+                crashed.push(*pilot);
+                crash_events.send(CrashEvent {
+                    position: player_transform.translation,
+                    cause: CrashCause::Hazard,
+                });
+                break;
+            }
+        }
+    }
+
+    if crashed.is_empty() {
+        return Ok(());
+    }
+
+    // Practice flies on through whatever was hit. The tank is filled up for the same reason as a respawn.
+    if modes.get(*mode).forgives_crashes() {
+        fuel.0 = config.fuel.capacity;
+
+        for (entity, mut player, pilot, mut transform, mut interpolated, _) in
+            player_query.iter_mut()
+        {
+            if crashed.contains(pilot) {
+                pass_through(
+                    &mut commands,
+                    entity,
+                    &mut player,
+                    &mut transform,
+                    &mut interpolated,
+                );
+            }
+        }
+
+        return Ok(());
+    }
+
+    // With a life to spare the plane starts over instead. The tank is filled up too, otherwise a plane that
+    // ran dry would only glide into the next crash.
+    if lives.has_spare() {
+        lives.remaining -= 1;
+        fuel.0 = config.fuel.capacity;
+
+        for (entity, mut player, pilot, mut transform, mut interpolated, damage) in
+            player_query.iter_mut()
+        {
+            if crashed.contains(pilot) {
+                if let Some(mut damage) = damage {
+                    damage.hit();
+                }
+
+                respawn_plane(
+                    &mut commands,
+                    entity,
+                    &mut player,
+                    &mut transform,
+                    &mut interpolated,
+                    config.lives.invincibility,
+                );
+            }
+        }
+
+        return Ok(());
     }
+
+    // The run waits on the Continue screen, which ends it if the countdown runs out. Overwritten like below.
+    if continues.can_offer(&save) {
+        state.overwrite_push(GameState::Continue)?;
+        return Ok(());
+    }
+
+    let flying: Vec<Pilot> = player_query
+        .iter()
+        .map(|(_, _, pilot, _, _, _)| *pilot)
+        .filter(|pilot| !crashed.contains(pilot))
+        .collect();
+    modes.get(*mode).end_run(&mut commands, &flying);
+
+    // A crashing demo goes straight back to the menu.
+    // Overwritten because a click can also end the demo, and more than one physics step can run in a frame.
+    let next_state = if demo.active {
+        GameState::Menu
+    } else {
+        GameState::GameOver
+    };
+    state.overwrite_set(next_state)?;
+
+    Ok(())
+}
+
+// Where an entity was at the start of the step and where it ended up. Translations are lerped and rotations
+// slerped in between, so the angle takes the short way round when it wraps.
+struct StepMotion {
+    start: Vec3,
+    end: Vec3,
+    start_rotation: Quat,
+    end_rotation: Quat,
 }
 
-fn is_rock_collision(
-    player_pos: Vec3,
+impl StepMotion {
+    fn new(transform: &Transform, previous: &Interpolated) -> Self {
+        Self {
+            start: previous.previous_translation(),
+            end: transform.translation,
+            start_rotation: previous.previous_rotation(),
+            end_rotation: transform.rotation,
+        }
+    }
+
+    fn translation(&self, t: f32) -> Vec3 {
+        self.start.lerp(self.end, t)
+    }
+
+    // Negative while gravity is flipped, the mirrored hull has to turn the same way as the sprite
+    fn angle(&self, t: f32) -> f32 {
+        z_angle(self.start_rotation.slerp(self.end_rotation, t))
+    }
+
+    // Where it was a fraction t into the step
+    fn isometry(&self, t: f32) -> Isometry2<f32> {
+        Isometry2::new(to_vector2(self.translation(t)), self.angle(t))
+    }
+}
+
+// Whether the bounding boxes of both shapes, stretched over where they moved during the step, come within the near
+// miss margin of each other. Far cheaper than the proximity query and the sweep, which can only find something
+// when this is true.
+fn might_touch(
+    player: &StepMotion,
     player_shape: &PlayerShape,
-    player_angle: f32,
-    rock_transform: &Transform,
+    rock: &StepMotion,
     rock_polygon: &CollisionPolygon,
 ) -> bool {
-    let rock_translation = rock_transform.translation;
-
-    let rock_pos = Isometry2::new(to_vector2(rock_translation), na::zero());
-    let player_iso = Isometry2::new(to_vector2(player_pos), player_angle);
+    let player_aabb = player_shape
+        .aabb(&player.isometry(0.0))
+        .merged(&player_shape.aabb(&player.isometry(1.0)));
+    let rock_aabb = rock_polygon
+        .shape
+        .aabb(&rock.isometry(0.0))
+        .merged(&rock_polygon.shape.aabb(&rock.isometry(1.0)));
+
+    player_aabb
+        .loosened(NEAR_MISS_MARGIN)
+        .intersects(&rock_aabb)
+}
 
-    let p = query::proximity(
-        &rock_pos,
-        &rock_polygon.polygon,
-        &player_iso,
-        player_shape,
-        1.0,
-    );
+// The step is checked in physics.collision_substeps parts, see assets/config.ron. Each part sweeps both shapes
+// from where the last one ended, since the proximity test alone could let a fast fall skip past a rock tip.
+// Time of impact only handles linear motion, so a shape keeps the angle it has at the end of the part for the
+// whole sweep. More parts follow a plane that turns while it falls more closely.
+fn step_proximity(
+    player: &StepMotion,
+    player_shape: &PlayerShape,
+    rock: &StepMotion,
+    rock_polygon: &CollisionPolygon,
+    substeps: u32,
+) -> Proximity {
+    let substeps = substeps.max(1);
+    let mut proximity = Proximity::Disjoint;
+
+    for substep in 0..substeps {
+        let from = substep as f32 / substeps as f32;
+        let to = (substep + 1) as f32 / substeps as f32;
+
+        match query::proximity(
+            &rock.isometry(to),
+            &*rock_polygon.shape,
+            &player.isometry(to),
+            player_shape,
+            NEAR_MISS_MARGIN,
+        ) {
+            Proximity::Intersecting => return Proximity::Intersecting,
+            Proximity::WithinMargin => proximity = Proximity::WithinMargin,
+            Proximity::Disjoint => {}
+        }
 
-    match p {
-        Proximity::Intersecting => true,
-        _ => false,
+        // Velocities are the movement over the part, so any impact during it happens before a time of 1
+        let toi = query::time_of_impact(
+            &DefaultTOIDispatcher,
+            &Isometry2::new(to_vector2(rock.translation(from)), rock.angle(to)),
+            &to_vector2(rock.translation(to) - rock.translation(from)),
+            &*rock_polygon.shape,
+            &Isometry2::new(to_vector2(player.translation(from)), player.angle(to)),
+            &to_vector2(player.translation(to) - player.translation(from)),
+            player_shape,
+            1.0,
+            0.0,
+        );
+        if matches!(toi, Ok(Some(_))) {
+            return Proximity::Intersecting;
+        }
     }
+
+    proximity
 }
 
 pub fn rock_system(
     mut commands: Commands,
-    mut query: Query<(&Transform, Entity, &mut Rock)>,
+    mut query: Query<(&Transform, &mut Rock)>,
     player_query: Query<&Transform, With<Player>>,
     mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+    config: Res<GameConfig>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    mut gate_events: EventWriter<GatePassedEvent>,
+    zone: Res<SpeedZone>,
 ) {
-    let player_x = player_query.single().translation.x;
+    let only_gates = modes.get(*mode).counts_only_gates();
+
+    // All planes fly at the same x, so any of them will do for scoring
+    let player_translation = match player_query.iter().next() {
+        Some(transform) => transform.translation,
+        None => return,
+    };
+
+    // Gates whose rocks went from ahead of the plane to behind it this step, with the middle of their gap and
+    // what they're worth
+    let mut crossed: Vec<(u64, f32, u64)> = vec![];
 
-    for (transform, entity, mut rock) in query.iter_mut() {
-        if transform.translation.x < ROCK_MIN_X {
-            commands.entity(entity).despawn_recursive();
+    for (transform, mut rock) in query.iter_mut() {
+        let ahead = transform.translation.x >= player_translation.x;
+        let was_ahead = rock.ahead.replace(ahead);
+
+        // Rocks that show up behind the plane, e.g. after a rewind, were never flown through
+        if rock.has_scored || !rock.trigger || ahead || was_ahead != Some(true) {
+            continue;
         }
 
-        if !rock.has_scored && transform.translation.x < player_x {
+        // Both rocks of a pair cross at once, the gate scores its points once rather than per rock
+        if !crossed.iter().any(|(gate, _, _)| *gate == rock.gate) {
+            crossed.push((rock.gate, rock.gap_center, rock.points));
+        }
+    }
 
-            // If we fly inbetween two rocks it should still count as 1 point
-            if !score.is_changed() {
-                score.0 += 1;
-            }
+    if crossed.is_empty() {
+        return;
+    }
 
+    // The other rock of a pair is done too, even if it's somewhere else by now
+    for (_, mut rock) in query.iter_mut() {
+        if crossed.iter().any(|(gate, _, _)| *gate == rock.gate) {
             rock.has_scored = true;
         }
     }
+
+    for (_, gap_center, rock_points) in crossed {
+        let points = if only_gates {
+            1
+        } else {
+            combo.pass_gate(&config.combo);
+            rock_points * combo.multiplier(&config.combo) * zone.score_factor()
+        };
+
+        score.0 += points;
+        spawn_floating_text(
+            &mut commands,
+            player_translation + Vec3::new(0.0, 30.0, 0.0),
+            format!("+{}", points),
+            SCORE_POPUP_SCALE,
+        );
+        gate_events.send(GatePassedEvent {
+            offset: player_translation.y - gap_center,
+        });
+    }
+}
+
+// A close call with a rock before its gate is passed is worth a point
+pub fn near_miss_bonus_system(
+    mut commands: Commands,
+    mut near_misses: EventReader<NearMissEvent>,
+    mut score: ResMut<Score>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+) {
+    let only_gates = modes.get(*mode).counts_only_gates();
+
+    for near_miss in near_misses.iter() {
+        if !near_miss.before_gate || only_gates {
+            continue;
+        }
+
+        score.0 += NEAR_MISS_BONUS;
+
+        spawn_floating_text(
+            &mut commands,
+            near_miss.position + Vec3::new(0.0, 30.0, 0.0),
+            "Close call".to_string(),
+            0.3,
+        );
+    }
+}
+
+pub fn oscillation_system(mut query: Query<(&mut Transform, &mut Rock, &mut Oscillation)>) {
+    for (mut transform, mut rock, mut oscillation) in query.iter_mut() {
+        oscillation.time += PHYSICS_STEP;
+
+        let offset = oscillation.amplitude * (oscillation.time * oscillation.frequency * TAU).sin();
+        transform.translation.y = oscillation.base_y + offset;
+        rock.gap_center = oscillation.base_gap_center + offset;
+    }
 }
 
 pub fn rock_spawn_system(
     mut commands: Commands,
     mut timer: ResMut<RockTimer>,
-    time: Res<Time>,
-    asset_server: Res<AssetServer>,
+    mut queue: ResMut<ObstacleQueue>,
+    patterns: Res<ObstaclePatterns>,
     game_speed: Res<GameSpeed>,
-) {
-    if timer.0.tick(time.delta()).finished() {
-        let mut rng = thread_rng();
-        let scale = rng.gen_range(0.7..1.2);
-        let rock_type = rng.gen_range(0..=2);
-        spawn_rocks(&mut commands, asset_server, scale, rock_type);
-        let next_time: f32 = rng.gen_range(0.7..1.6) / game_speed.0;
+    mut tutorial: Option<ResMut<Tutorial>>,
+    mut rng: ResMut<GameRng>,
+    mutators: Res<ActiveMutators>,
+    difficulty: Res<ActiveDifficulty>,
+    theme: Res<ThemeState>,
+    score: Res<Score>,
+    hooks: Res<SpawnHooks>,
+    mut pool: ResMut<RockPool>,
+    mut shapes: ResMut<RockShapes>,
+    config: Res<GameConfig>,
+    mut next_gate: Local<u64>,
+) -> GameResult {
+    let step = Duration::from_secs_f32(PHYSICS_STEP);
+    if timer.0.tick(step).finished() {
+        if queue.0.is_empty() {
+            if let Some(pattern) = patterns.pick(score.0, &mut rng.course) {
+                debug!("Spawning the {} pattern", pattern.name);
+                queue.0.extend(pattern.obstacles.iter().cloned());
+            }
+        }
+
+        let obstacle = queue.0.pop_front().unwrap_or_default();
+        let (scale, rock_type, delay) = match obstacle.rocks {
+            Some(rocks) => {
+                let (min_scale, max_scale) = obstacle.scale;
+                let scale = rng.course.gen_range(min_scale..=max_scale);
+                (scale, rocks.rock_type(), obstacle.delay)
+            }
+            None => next_rocks(&mut rng.course, &config.rocks),
+        };
+
+        // Only drawn past the threshold, so the early part of a seeded course doesn't change
+        let oscillation = if score.0 >= OSCILLATION_MIN_SCORE {
+            next_oscillation(&mut rng.course)
+        } else {
+            None
+        };
+
+        let mut gate = GateSpawn::new(
+            scale * mutators.rock_scale_factor() * difficulty.preset.rock_scale,
+            rock_type,
+            obstacle.offset,
+            oscillation,
+            score.0,
+        );
+        gate.x = ScreenAnchor::Right.offset(60.0, 0.0).x;
+        gate.segments = obstacle.segments.max(1);
+        // An obstacle with behaviors of its own only moves the way they say. The oscillation is still drawn above,
+        // so the rest of a seeded course doesn't change.
+        if !obstacle.behaviors.is_empty() {
+            gate.oscillation = None;
+            gate.behaviors = obstacle.behaviors;
+        }
+        // Drawn past a threshold like oscillation. Never for pairs or caves, a crumbled side alone doesn't open
+        // them up.
+        gate.crumbling = rock_type != 2
+            && gate.segments == 1
+            && score.0 >= CRUMBLING_MIN_SCORE
+            && rng.course.gen_bool(CRUMBLING_CHANCE);
+        if let Some(tutorial) = tutorial.as_mut() {
+            tutorial.shape_gate(&mut gate);
+        }
+
+        let spawned = if hooks.gate(&mut gate) {
+            *next_gate += 1;
+            spawn_rocks(
+                &mut commands,
+                &mut pool,
+                &mut shapes,
+                &theme.textures,
+                &gate,
+                *next_gate,
+                config.rocks.get(gate.rock_type).points,
+            )
+        } else {
+            Ok(())
+        };
+
+        // The delay of a cave starts once all of it is on screen
+        let length = (gate.segments - 1) as f32 * CAVE_SEGMENT_SPACING;
+        let delay = delay * difficulty.preset.spawn_delay;
+        let next_time: f32 = (delay + length / COURSE_VELOCITY) / game_speed.0;
         timer.0.set_duration(Duration::from_secs_f32(next_time));
         timer.0.reset();
+
+        return spawned;
     }
+
+    Ok(())
 }
 
-fn spawn_rocks(commands: &mut Commands, asset_server: Res<AssetServer>, scale: f32, rock_type: u8) {
-    let mut rock_descriptions: Vec<(f32, &str, Vec<(f32, f32)>)> = vec![];
+// Draws the scale and type of the next rocks, and the delay before the ones after that at normal speed.
+// Everything about the course comes from here, so the same rng state always gives the same course.
+pub fn next_rocks<R: Rng>(rng: &mut R, types: &RockTypesConfig) -> (f32, u8, f32) {
+    let scale = rng.gen_range(0.7..1.2);
+    // Both sides if every weight is 0
+    let rock_type = [0, 1, 2]
+        .choose_weighted(rng, |rock_type| types.get(*rock_type).weight)
+        .copied()
+        .unwrap_or(2);
+    let delay = rng.gen_range(0.7..1.6);
+
+    (scale, rock_type, delay)
+}
+
+// The amplitude and frequency of a moving pair of rocks, if the next pair moves at all
+pub fn next_oscillation<R: Rng>(rng: &mut R) -> Option<(f32, f32)> {
+    if !rng.gen_bool(OSCILLATION_CHANCE) {
+        return None;
+    }
 
-    let scale = if rock_type == 2 { scale * 0.7 } else { scale };
+    let amplitude = rng.gen_range(20.0..40.0);
+    let frequency = rng.gen_range(0.3..0.7);
+
+    Some((amplitude, frequency))
+}
+
+fn spawn_rocks(
+    commands: &mut Commands,
+    pool: &mut RockPool,
+    shapes: &mut RockShapes,
+    textures: &ThemeTextures,
+    gate: &GateSpawn,
+    id: u64,
+    points: u64,
+) -> GameResult {
+    let mut rock_descriptions: Vec<(f32, &Handle<Image>, PooledRock)> = vec![];
+
+    let rock_type = gate.rock_type;
+    let offset = gate.offset;
+    let scale = if rock_type == 2 {
+        gate.scale * 0.7
+    } else {
+        gate.scale
+    };
+
+    // Without a rock on one side, the gap stretches up to the ground strip on that side
+    let floor = if rock_type != 0 {
+        HEIGHT / -2.0 + ROCK_HEIGHT * scale
+    } else {
+        HEIGHT / -2.0 + GROUND_HEIGHT
+    };
+    let ceiling = if rock_type != 1 {
+        HEIGHT / 2.0 - ROCK_HEIGHT * scale
+    } else {
+        HEIGHT / 2.0 - GROUND_HEIGHT
+    };
+    let gap_center = (floor + ceiling) / 2.0 + offset;
 
     if rock_type != 0 {
         rock_descriptions.push((
-            HEIGHT / -2.0 + (ROCK_HEIGHT * scale) / 2.0,
-            "rockGrass.png",
-            ROCK_UP_POINTS.to_vec(),
+            HEIGHT / -2.0 + (ROCK_HEIGHT * scale) / 2.0 + offset,
+            &textures.rock_up,
+            PooledRock::Up,
         ));
     }
 
     if rock_type != 1 {
         rock_descriptions.push((
-            HEIGHT / 2.0 - (ROCK_HEIGHT * scale) / 2.0,
-            "rockDown.png",
-            ROCK_DOWN_POINTS.to_vec(),
+            HEIGHT / 2.0 - (ROCK_HEIGHT * scale) / 2.0 + offset,
+            &textures.rock_down,
+            PooledRock::Down,
         ));
     }
 
-    for (y, texture, points) in rock_descriptions.iter() {
-        let mut entity = commands.spawn_bundle(SpriteBundle {
-            transform: Transform::from_xyz(WIDTH / 2.0 + 60.0, *y, 1.0)
-                .with_scale(Vec3::new(1.0, scale, 1.0)),
-            texture: asset_server.load(*texture),
-            ..default()
-        });
+    // A cave is a row of pairs that overlap into one formation, scored as a single gate
+    for segment in 0..gate.segments {
+        let x = gate.x + segment as f32 * CAVE_SEGMENT_SPACING;
+        let trigger = segment + 1 == gate.segments;
+
+        for (y, texture, kind) in rock_descriptions.iter() {
+            let rock = pool.acquire(commands, *kind);
+            let polygon = match shapes.get(*kind, scale) {
+                Some(polygon) => polygon,
+                None => {
+                    pool.release(commands, rock, *kind);
+                    return Err(GameError::InvalidPolygon);
+                }
+            };
+
+            // Everything a rock from the pool could have left over from its last gate is replaced
+            let mut entity = commands.entity(rock);
+            entity
+                .insert_bundle(SpriteBundle {
+                    transform: Transform::from_xyz(x, *y, Layer::Course.z())
+                        .with_scale(Vec3::new(1.0, scale, 1.0)),
+                    texture: (*texture).clone(),
+                    ..default()
+                })
+                .insert(polygon)
+                .insert(HorizontalVelocity(COURSE_VELOCITY))
+                .insert(DespawnOffscreen { margin: ROCK_WIDTH })
+                .insert(Interpolated::default())
+                .insert(BaseColor(Color::WHITE))
+                .insert(Rock {
+                    gate: id,
+                    ahead: None,
+                    has_scored: false,
+                    trigger,
+                    // Grazing the walls of a cave is worth a single close call, at its exit
+                    near_missed: !trigger,
+                    gap_center,
+                    points,
+                });
+
+            if gate.crumbling {
+                entity.insert(Crumbling::default());
+            }
 
-        add_collision_polygon(&mut entity, points, scale);
+            // Both rocks of a pair share the same motion, so the gap keeps its size
+            if let Some((amplitude, frequency)) = gate.oscillation {
+                entity.insert(Oscillation {
+                    amplitude,
+                    frequency,
+                    time: 0.0,
+                    base_y: *y,
+                    base_gap_center: gap_center,
+                });
+            }
 
-        entity
-            .insert(HorizontalVelocity(250.0))
-            .insert(Rock { has_scored: false });
+            if !gate.behaviors.is_empty() {
+                entity.insert(Behaviors::new(gate.behaviors.clone(), *y, gap_center));
+            }
+        }
     }
+
+    Ok(())
 }
 
-fn add_collision_polygon(entity: &mut EntityCommands, coords: &Vec<(f32, f32)>, scale: f32) {
+// Despawns the entity if the coordinates aren't a convex shape, something that can't be hit shouldn't be flown
+// past either
+pub fn add_collision_polygon(
+    entity: &mut EntityCommands,
+    coords: &Vec<(f32, f32)>,
+    scale: f32,
+) -> GameResult {
+    let polygon = match scaled_polygon(coords, scale) {
+        Some(polygon) => polygon,
+        None => {
+            entity.despawn();
+            return Err(GameError::InvalidPolygon);
+        }
+    };
+    entity.insert(polygon);
+
     let coords: Vec<(f32, f32)> = coords.iter().map(|(x, y)| (*x, y * scale)).collect();
 
-    let points = coords.iter().map(to_point2).collect();
-    let polygon = ConvexPolygon::try_new(points).unwrap();
-    entity.insert(CollisionPolygon { polygon });
+    // During debugging it's sometimes useful to be able to see the collision outline, see DebugSettings
+    add_collision_outline(entity, &coords, Vec3::new(1.0, 1.0 / scale, 1.0));
+
+    Ok(())
+}
 
-    // During debugging it's sometimes useful to be able to see the collision outline
-    #[cfg(debug_assertions)]
-    {
-        let fill_color = Color::rgba(0.2, 0.2, 0.8, 0.6);
+// The shape stretched vertically by the scale, None if that isn't convex
+pub fn scaled_polygon(coords: &[(f32, f32)], scale: f32) -> Option<CollisionPolygon> {
+    let points = coords
+        .iter()
+        .map(|(x, y)| to_point2((*x, y * scale)))
+        .collect();
 
-        let vecs: Vec<Vec2> = coords.iter().map(to_vec2).collect();
-        let polygon = shapes::Polygon {
-            points: vecs,
-            closed: true,
-        };
+    ConvexPolygon::try_new(points).map(|polygon| CollisionPolygon {
+        shape: ShapeHandle::new(polygon),
+    })
+}
+
+pub fn add_collision_outline(entity: &mut EntityCommands, coords: &[(f32, f32)], scale: Vec3) {
+    let fill_color = Color::rgba(0.2, 0.2, 0.8, 0.6);
+
+    let vecs: Vec<Vec2> = coords.iter().map(to_vec2).collect();
+    let polygon = shapes::Polygon {
+        points: vecs,
+        closed: true,
+    };
+
+    let mut outline = GeometryBuilder::build_as(
+        &polygon,
+        DrawMode::Fill(FillMode::color(fill_color)),
+        // Relative to whatever it's on, which is always on the course layer
+        Transform::from_xyz(0.0, 0.0, Layer::CollisionOutlines.z() - Layer::Course.z())
+            .with_scale(scale),
+    );
+    // Shown by debug_toggle_system when the overlay is on
+    outline.visibility.is_visible = false;
 
-        let child = entity
-            .commands()
-            .spawn_bundle(GeometryBuilder::build_as(
-                &polygon,
-                DrawMode::Fill(FillMode::color(fill_color)),
-                Transform::from_xyz(0.0, 0.0, 2.0).with_scale(Vec3::new(1.0, 1.0 / scale, 1.0)),
-            ))
-            .id();
+    let child = entity
+        .commands()
+        .spawn_bundle(outline)
+        .insert(CollisionOutline)
+        .id();
 
-        entity.add_child(child);
-    }
+    entity.add_child(child);
 }
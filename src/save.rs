@@ -0,0 +1,227 @@
+use std::collections::BTreeSet;
+use std::mem;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    copy_file, read_file, unix_seconds, weekly_mutators, write_file, DailyBest, GameError,
+    GameResult, GameState, GhostRun, Leaderboard, PlayerStats, RecordKey, Records, RunMode,
+    SeasonBest, Settings, ShowToast, Unlocks, WeeklyRecords,
+};
+
+// The first profile's, from before there were profiles
+pub const DEFAULT_SAVE_PATH: &str = "save.ron";
+
+// Bump this and add a step to MIGRATIONS whenever older versions can't read what this version writes.
+// Version 1 is everything from before saves had a version, with a single endless high score.
+const SAVE_VERSION: u32 = 2;
+
+// Each step takes a save from one version to the next, starting at version 1
+const MIGRATIONS: &[fn(&mut SaveData)] = &[move_records_per_mode];
+
+// Read on its own first, so saves from newer versions are recognised even when the rest can't be read
+#[derive(Deserialize)]
+struct SaveHeader {
+    #[serde(default)]
+    version: u32,
+}
+
+// Everything that should survive closing the game
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveData {
+    // Missing from saves written before versioning, which read as 0
+    version: u32,
+    // Unix time of the last write, used to pick the newest save when syncing
+    pub modified: u64,
+    pub records: Records,
+    pub settings: Settings,
+    pub stats: PlayerStats,
+    pub achievements: BTreeSet<String>,
+    pub unlocks: Unlocks,
+    // Earned by scoring, spent on flying on after a crash, see continues.rs
+    pub continue_tokens: u32,
+    // The game version the what's new screen was last dismissed in
+    pub seen_version: String,
+    // Names from cutscenes.ron, each only plays once
+    pub seen_cutscenes: BTreeSet<String>,
+    // Flight path of the endless personal best
+    pub ghost: GhostRun,
+    // Oldest season first, see online.rs
+    pub seasons: Vec<SeasonBest>,
+    // Records from before they were kept per mode, only read to move them into records
+    #[serde(rename = "high_score", skip_serializing)]
+    legacy_high_score: u64,
+    #[serde(rename = "daily_best", skip_serializing)]
+    legacy_daily_best: DailyBest,
+    #[serde(rename = "weekly", skip_serializing)]
+    legacy_weekly: WeeklyRecords,
+    #[serde(rename = "leaderboard", skip_serializing)]
+    legacy_leaderboard: Leaderboard,
+    // Set when the save on disk couldn't be loaded safely, so it's never overwritten
+    #[serde(skip)]
+    read_only: bool,
+    // The file of the profile this save belongs to
+    #[serde(skip)]
+    path: String,
+}
+
+impl SaveData {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn is_newer_version(&self) -> bool {
+        self.version > SAVE_VERSION
+    }
+
+    // Whether and where the file can be written belongs to the file, not to the save that's now in it
+    pub fn replace(&mut self, other: SaveData) {
+        let read_only = self.read_only;
+        let path = mem::take(&mut self.path);
+        *self = other;
+        self.read_only = read_only;
+        self.path = path;
+    }
+
+    // Has to run on every save that's read, whether from disk, an imported profile or the cloud.
+    // Saves from newer versions have to be turned away before this.
+    pub fn migrate(&mut self) {
+        let from = self.version.max(1) as usize;
+        for step in MIGRATIONS.iter().skip(from - 1) {
+            step(self);
+        }
+
+        self.version = SAVE_VERSION;
+    }
+
+    // Never read from or written to disk, for running the game headless
+    pub fn in_memory() -> Self {
+        SaveData {
+            read_only: true,
+            ..default()
+        }
+    }
+
+    fn read_only(path: &str) -> Self {
+        SaveData {
+            read_only: true,
+            ..SaveData::fresh(path)
+        }
+    }
+
+    fn fresh(path: &str) -> Self {
+        SaveData {
+            path: path.to_string(),
+            ..default()
+        }
+    }
+}
+
+// Version 1 to 2
+fn move_records_per_mode(save: &mut SaveData) {
+    let high_score = mem::take(&mut save.legacy_high_score);
+    let leaderboard = mem::take(&mut save.legacy_leaderboard);
+    if high_score > 0 || !leaderboard.entries.is_empty() {
+        let key = RecordKey::new(RunMode::Endless, vec![], 0);
+        save.records.add_legacy(key, high_score, leaderboard);
+    }
+
+    let daily_best = mem::take(&mut save.legacy_daily_best);
+    if daily_best.score > 0 {
+        let key = RecordKey::new(RunMode::Daily, vec![], daily_best.day);
+        save.records
+            .add_legacy(key, daily_best.score, Leaderboard::default());
+    }
+
+    // The old weekly best was the top of its leaderboard
+    let weekly = mem::take(&mut save.legacy_weekly);
+    if let Some(best) = weekly.leaderboard.entries.first().map(|entry| entry.score) {
+        let key = RecordKey::new(RunMode::Weekly, weekly_mutators(weekly.week), weekly.week);
+        save.records.add_legacy(key, best, weekly.leaderboard);
+    }
+}
+
+pub fn load_save(path: &str) -> SaveData {
+    let contents = match read_file(path) {
+        Ok(contents) => contents,
+        Err(_) => return SaveData::fresh(path),
+    };
+
+    // Writing it back would drop everything this version doesn't know about
+    let version = ron::from_str::<SaveHeader>(&contents).map_or(0, |header| header.version);
+    if version > SAVE_VERSION {
+        error!(
+            "Save file is version {}, this game only knows up to {}. It won't be overwritten.",
+            version, SAVE_VERSION
+        );
+        return SaveData::read_only(path);
+    }
+
+    let mut save: SaveData = match ron::from_str(&contents) {
+        Ok(save) => save,
+        Err(err) => {
+            warn!("Could not read save file, starting fresh: {}", err);
+            return if back_up_save(path, "unreadable") {
+                SaveData::fresh(path)
+            } else {
+                SaveData::read_only(path)
+            };
+        }
+    };
+
+    // Older versions of the game can still read the backup if a migration goes wrong
+    if save.version < SAVE_VERSION && !back_up_save(path, &format!("v{}", save.version.max(1))) {
+        return SaveData::read_only(path);
+    }
+
+    save.migrate();
+    save.path = path.to_string();
+    save
+}
+
+// The save file is left alone if this fails. save.ron is backed up to save.v1.ron and so on.
+fn back_up_save(path: &str, suffix: &str) -> bool {
+    let backup = format!("{}.{}.ron", path.trim_end_matches(".ron"), suffix);
+
+    match copy_file(path, &backup) {
+        Ok(()) => true,
+        Err(err) => {
+            error!("Could not back up the save file to {}: {}", backup, err);
+            false
+        }
+    }
+}
+
+// The player should know before playing that nothing will be kept
+pub fn read_only_save_warning(save: Res<SaveData>, mut toasts: EventWriter<ShowToast>) {
+    if save.read_only {
+        toasts.send(ShowToast("Progress will not be saved".to_string()));
+    }
+}
+
+// Writes the save file whenever it changed, but never in the middle of a run to avoid hitches
+pub fn save_system(
+    mut save: ResMut<SaveData>,
+    state: Res<State<GameState>>,
+    mut dirty: Local<bool>,
+) -> GameResult {
+    if save.is_changed() && !save.is_added() {
+        *dirty = true;
+    }
+
+    if !*dirty || state.current() == &GameState::Playing || save.read_only {
+        return Ok(());
+    }
+
+    *dirty = false;
+    save.modified = unix_seconds();
+    save.version = SAVE_VERSION;
+
+    let contents = ron::ser::to_string_pretty(&*save, Default::default())?;
+    write_file(&save.path, &contents).map_err(|source| GameError::Io {
+        path: "save file",
+        source,
+    })
+}
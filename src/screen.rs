@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+use crate::{MainCamera, ViewportSize, HEIGHT, WIDTH};
+
+// A point on the edge of the playfield, in world coordinates. HUD text and anything that flies in from off
+// screen is placed relative to one of these instead of working out half the width or height each time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScreenAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+}
+
+impl ScreenAnchor {
+    pub const fn position(self) -> Vec2 {
+        let x = match self {
+            ScreenAnchor::TopLeft | ScreenAnchor::Left | ScreenAnchor::BottomLeft => WIDTH / -2.0,
+            ScreenAnchor::Top | ScreenAnchor::Center | ScreenAnchor::Bottom => 0.0,
+            ScreenAnchor::TopRight | ScreenAnchor::Right => WIDTH / 2.0,
+        };
+        let y = match self {
+            ScreenAnchor::TopLeft | ScreenAnchor::Top | ScreenAnchor::TopRight => HEIGHT / 2.0,
+            ScreenAnchor::Left | ScreenAnchor::Center | ScreenAnchor::Right => 0.0,
+            ScreenAnchor::BottomLeft | ScreenAnchor::Bottom => HEIGHT / -2.0,
+        };
+
+        Vec2::new(x, y)
+    }
+
+    // Positive is right and up, same as the world
+    pub const fn offset(self, x: f32, y: f32) -> Vec2 {
+        let position = self.position();
+        Vec2::new(position.x + x, position.y + y)
+    }
+}
+
+// None on the bars around the playfield
+pub fn window_to_world(
+    window_position: Vec2,
+    viewport: &ViewportSize,
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+) -> Option<Vec2> {
+    let ndc = viewport.to_ndc(window_position)?;
+    let world = camera_transform.compute_matrix() * camera.projection_matrix().inverse();
+
+    Some(world.project_point3(ndc.extend(-1.0)).truncate())
+}
+
+// None when the cursor is outside of the window or on the bars around the playfield
+pub fn cursor_world_position(
+    windows: &Windows,
+    viewport: &ViewportSize,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<Vec2> {
+    let cursor = windows.get_primary()?.cursor_position()?;
+    main_camera_world_position(cursor, viewport, camera_query)
+}
+
+// Same as the cursor, for a tap from ActionInput::tap
+pub fn main_camera_world_position(
+    window_position: Vec2,
+    viewport: &ViewportSize,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<Vec2> {
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    window_to_world(window_position, viewport, camera, camera_transform)
+}
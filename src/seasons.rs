@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+// A ranked season of the online leaderboard, as announced by the server. Times are unix seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Season {
+    pub id: u32,
+    pub start: u64,
+    pub end: u64,
+}
+
+// The player's best in a season of a mode with a leaderboard. Kept in the save after the season ends, so the
+// menu can show how past seasons went.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SeasonBest {
+    pub season: Season,
+    pub best: u64,
+}
+
+// Keeps the higher best of seasons that are in both
+pub fn merge_season_bests(bests: &mut Vec<SeasonBest>, other: Vec<SeasonBest>) {
+    for other in other {
+        let id = other.season.id;
+        match bests.iter_mut().find(|best| best.season.id == id) {
+            Some(best) => best.best = best.best.max(other.best),
+            None => bests.push(other),
+        }
+    }
+
+    bests.sort_by_key(|best| best.season.id);
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{DevicePreset, Difficulty, FrameCap, InputBindings, Quality, Theme, SKINS};
+
+// Player preferences, stored in the save file
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Kept for when there's music, nothing plays any yet
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub effects_intensity: EffectsIntensity,
+    // None until the benchmark has run on the first launch, see Quality
+    pub quality: Option<Quality>,
+    pub screen_shake: bool,
+    // Skips camera movement effects, the tilt of letters and bouncing text for players who find them
+    // uncomfortable
+    pub reduced_motion: bool,
+    // Tints obstacles apart from the background, puts a shadow under the score and stripes on warning markers,
+    // for players who have trouble telling them apart by color
+    pub high_contrast: bool,
+    // Hides player names and keeps the game offline, for streaming
+    pub streamer_mode: bool,
+    pub theme: Theme,
+    // Lowers the render resolution when frames take too long. Changing the scale factor resizes the window
+    // on desktop, so it's only on by default on the web where the canvas keeps its size.
+    pub dynamic_resolution: bool,
+    // Gate count and run time for speedrunners, timed by the rules in assets/config.ron
+    pub speedrun_timer: bool,
+    // Of every mode that doesn't fly a course shared with other players, see GameMode::difficulty
+    pub difficulty: Difficulty,
+    // Borderless, at the resolution of the monitor
+    pub fullscreen: bool,
+    // Turning it off can lower input latency a little, at the cost of tearing
+    pub vsync: bool,
+    pub frame_cap: FrameCap,
+    // None picks a preset for the device the game is running on, see apply_device_preset
+    pub device_preset: Option<DevicePreset>,
+    pub applied_preset: Option<DevicePreset>,
+    // Size of the menu buttons, bigger on small screens
+    pub ui_scale: f32,
+    // Hides the mouse cursor and ignores hovering, menus are picked with the d-pad instead
+    pub gamepad_menus: bool,
+    // Only the score is shown while playing, see HudVisibility
+    pub clean_hud: bool,
+    // None on saves from before the tutorial, see needs_tutorial. Setting it back to false shows it again.
+    pub tutorial_done: Option<bool>,
+    // Id of the plane flown alone, see SKINS
+    pub skin: String,
+    // Folder of the asset pack in assets/packs, None for the built in assets. Only read when the game starts.
+    pub asset_pack: Option<String>,
+    pub bindings: InputBindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            effects_intensity: EffectsIntensity::Full,
+            quality: None,
+            screen_shake: true,
+            reduced_motion: false,
+            high_contrast: false,
+            streamer_mode: false,
+            theme: Theme::Grass,
+            dynamic_resolution: cfg!(target_arch = "wasm32"),
+            speedrun_timer: false,
+            difficulty: Difficulty::Normal,
+            fullscreen: false,
+            vsync: true,
+            frame_cap: FrameCap::Off,
+            device_preset: None,
+            applied_preset: None,
+            ui_scale: 1.0,
+            gamepad_menus: false,
+            clean_hud: false,
+            tutorial_done: None,
+            skin: SKINS[0].id.to_string(),
+            asset_pack: None,
+            bindings: InputBindings::default(),
+        }
+    }
+}
+
+// How much purely cosmetic effects (particles and such) are toned down
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum EffectsIntensity {
+    Off,
+    Low,
+    Full,
+}
+
+impl EffectsIntensity {
+    pub fn factor(&self) -> f32 {
+        match self {
+            EffectsIntensity::Off => 0.0,
+            EffectsIntensity::Low => 0.5,
+            EffectsIntensity::Full => 1.0,
+        }
+    }
+}
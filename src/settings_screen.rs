@@ -0,0 +1,518 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::{
+    apply_device_preset, cursor_world_position, main_camera_world_position, Action, ActionInput,
+    AvailablePacks, BitmapText, BitmapTextBundle, DevicePreset, GameResult, GameState,
+    InputBindings, Layer, MainCamera, Quality, RemoveAfterState, SaveData, Settings, ShowToast,
+    SoundCue, ViewportSize,
+};
+
+const ROW_TOP: f32 = 160.0;
+const ROW_SPACING: f32 = 23.0;
+const ROW_SCALE: f32 = 0.35;
+// Smaller than the labels, an action can have a key, a mouse button and a gamepad button
+const BINDING_SCALE: f32 = 0.25;
+const LABEL_X: f32 = -260.0;
+const WIDGET_X: f32 = 40.0;
+
+const SLIDER_SIZE: Vec2 = Vec2::new(200.0, 14.0);
+// Volumes stay on whole tenths, so the arrow keys can always get back to exactly 0 or 1
+const SLIDER_STEPS: f32 = 10.0;
+const TOGGLE_SIZE: Vec2 = Vec2::new(24.0, 24.0);
+
+const ON_COLOR: Color = Color::rgb(0.3, 0.8, 0.3);
+const OFF_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.5);
+
+// Played when the sound volume changes, so it can be heard right away
+const PREVIEW_CUE: &str = "menu_click";
+
+// Top to bottom
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsRow {
+    Music,
+    Sound,
+    ScreenShake,
+    ReducedMotion,
+    HighContrast,
+    Fullscreen,
+    Vsync,
+    FrameCap,
+    // Applies a bundle of the settings that cost frames, see Quality
+    Quality,
+    // Applies a bundle of the settings above, see DevicePreset
+    Preset,
+    // One of the folders in assets/packs, used from the next start
+    Pack,
+    // Shows the tutorial again on the next endless run
+    Tutorial,
+    // Pressing it waits for the input to bind, Delete puts back the defaults
+    Bind(Action),
+    Back,
+}
+
+impl SettingsRow {
+    const ALL: [SettingsRow; 17] = [
+        SettingsRow::Music,
+        SettingsRow::Sound,
+        SettingsRow::ScreenShake,
+        SettingsRow::ReducedMotion,
+        SettingsRow::HighContrast,
+        SettingsRow::Fullscreen,
+        SettingsRow::Vsync,
+        SettingsRow::FrameCap,
+        SettingsRow::Quality,
+        SettingsRow::Preset,
+        SettingsRow::Pack,
+        SettingsRow::Tutorial,
+        SettingsRow::Bind(Action::Flap),
+        SettingsRow::Bind(Action::Shoot),
+        SettingsRow::Bind(Action::Pause),
+        SettingsRow::Bind(Action::Confirm),
+        SettingsRow::Back,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            SettingsRow::Music => "Music",
+            SettingsRow::Sound => "Sound",
+            SettingsRow::ScreenShake => "Screen shake",
+            SettingsRow::ReducedMotion => "Reduce motion",
+            SettingsRow::HighContrast => "High contrast",
+            SettingsRow::Fullscreen => "Fullscreen",
+            SettingsRow::Vsync => "Vsync",
+            SettingsRow::FrameCap => "Frame cap",
+            SettingsRow::Quality => "Quality",
+            SettingsRow::Preset => "Preset",
+            SettingsRow::Pack => "Asset pack",
+            SettingsRow::Tutorial => "Tutorial",
+            SettingsRow::Bind(action) => action.label(),
+            SettingsRow::Back => "Back",
+        }
+    }
+
+    // None for rows that aren't a slider
+    fn volume(&self, settings: &Settings) -> Option<f32> {
+        match self {
+            SettingsRow::Music => Some(settings.music_volume),
+            SettingsRow::Sound => Some(settings.sfx_volume),
+            _ => None,
+        }
+    }
+
+    fn set_volume(&self, settings: &mut Settings, volume: f32) {
+        match self {
+            SettingsRow::Music => settings.music_volume = volume,
+            SettingsRow::Sound => settings.sfx_volume = volume,
+            _ => {}
+        }
+    }
+
+    // None for rows that aren't a toggle
+    fn is_on(&self, settings: &Settings) -> Option<bool> {
+        match self {
+            SettingsRow::ScreenShake => Some(settings.screen_shake),
+            SettingsRow::ReducedMotion => Some(settings.reduced_motion),
+            SettingsRow::HighContrast => Some(settings.high_contrast),
+            SettingsRow::Fullscreen => Some(settings.fullscreen),
+            SettingsRow::Vsync => Some(settings.vsync),
+            _ => None,
+        }
+    }
+
+    fn toggle(&self, settings: &mut Settings) {
+        match self {
+            SettingsRow::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            SettingsRow::ReducedMotion => settings.reduced_motion = !settings.reduced_motion,
+            SettingsRow::HighContrast => settings.high_contrast = !settings.high_contrast,
+            SettingsRow::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            SettingsRow::Vsync => settings.vsync = !settings.vsync,
+            _ => {}
+        }
+    }
+
+    // None for rows that aren't a choice between a few values
+    fn choice<'a>(&self, settings: &'a Settings) -> Option<&'a str> {
+        match self {
+            SettingsRow::FrameCap => Some(settings.frame_cap.label()),
+            SettingsRow::Quality => {
+                Some(settings.quality.map_or("Auto", |quality| quality.label()))
+            }
+            // No preset picked, so it goes by the device
+            SettingsRow::Preset => {
+                let preset = settings.device_preset;
+                Some(preset.map_or("Auto", |preset| preset.label()))
+            }
+            SettingsRow::Pack => Some(settings.asset_pack.as_deref().unwrap_or("Built in")),
+            SettingsRow::Tutorial => match settings.tutorial_done {
+                Some(false) => Some("Next run"),
+                _ => Some("Done"),
+            },
+            _ => None,
+        }
+    }
+
+    fn cycle(&self, settings: &mut Settings, packs: &AvailablePacks) {
+        match self {
+            SettingsRow::FrameCap => settings.frame_cap = settings.frame_cap.next(),
+            SettingsRow::Quality => {
+                let quality = settings
+                    .quality
+                    .map_or(Quality::High, |quality| quality.next());
+                quality.apply(settings);
+            }
+            SettingsRow::Preset => {
+                settings.device_preset = match settings.device_preset {
+                    None => Some(DevicePreset::Desktop),
+                    Some(DevicePreset::Desktop) => Some(DevicePreset::Handheld),
+                    Some(DevicePreset::Handheld) => None,
+                };
+                apply_device_preset(settings);
+            }
+            // Back to the built in assets after the last pack, or from a pack that's no longer there
+            SettingsRow::Pack => {
+                let next = match &settings.asset_pack {
+                    None => 0,
+                    Some(folder) => packs
+                        .0
+                        .iter()
+                        .position(|pack| pack == folder)
+                        .map_or(packs.0.len(), |index| index + 1),
+                };
+                settings.asset_pack = packs.0.get(next).cloned();
+            }
+            SettingsRow::Tutorial => {
+                settings.tutorial_done = Some(settings.tutorial_done != Some(false));
+            }
+            _ => {}
+        }
+    }
+}
+
+// The highlighted row, moved by hovering with the mouse or with the arrow keys
+#[derive(Default)]
+pub struct SettingsSelection(usize);
+
+// The action waiting for an input to be bound to it
+#[derive(Default)]
+pub struct Rebinding(Option<Action>);
+
+#[derive(Component)]
+pub struct SliderFill(SettingsRow);
+
+#[derive(Component)]
+pub struct ToggleBox(SettingsRow);
+
+#[derive(Component)]
+pub struct BindingText(Action);
+
+#[derive(Component)]
+pub struct ChoiceText(SettingsRow);
+
+#[derive(Component)]
+pub struct SettingsCursor;
+
+pub fn setup_settings_screen(
+    mut commands: Commands,
+    save: Res<SaveData>,
+    selection: Res<SettingsSelection>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    rebinding.0 = None;
+
+    let mut title =
+        BitmapTextBundle::new(LABEL_X, ROW_TOP + 55.0).with_text("Settings".to_string());
+    title.transform.scale = Vec3::new(0.6, 0.6, 1.0);
+    commands.spawn_bundle(title).insert(RemoveAfterState);
+
+    for (i, row) in SettingsRow::ALL.iter().enumerate() {
+        let y = row_y(i);
+
+        let mut label = BitmapTextBundle::new(LABEL_X, y).with_text(row.label().to_string());
+        label.transform.scale = Vec3::new(ROW_SCALE, ROW_SCALE, 1.0);
+        commands.spawn_bundle(label).insert(RemoveAfterState);
+
+        if let Some(volume) = row.volume(&save.settings) {
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: OFF_COLOR,
+                        custom_size: Some(SLIDER_SIZE),
+                        anchor: Anchor::CenterLeft,
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(WIDGET_X, y, Layer::Overlay.z()),
+                    ..default()
+                })
+                .insert(RemoveAfterState);
+
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: ON_COLOR,
+                        custom_size: Some(slider_size(volume)),
+                        anchor: Anchor::CenterLeft,
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(WIDGET_X, y, Layer::Overlay.z() + 0.1),
+                    ..default()
+                })
+                .insert(SliderFill(*row))
+                .insert(RemoveAfterState);
+        }
+
+        if let Some(on) = row.is_on(&save.settings) {
+            commands
+                .spawn_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: toggle_color(on),
+                        custom_size: Some(TOGGLE_SIZE),
+                        anchor: Anchor::CenterLeft,
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(WIDGET_X, y, Layer::Overlay.z()),
+                    ..default()
+                })
+                .insert(ToggleBox(*row))
+                .insert(RemoveAfterState);
+        }
+
+        if let Some(choice) = row.choice(&save.settings) {
+            let mut text = BitmapTextBundle::new(WIDGET_X, y).with_text(choice.to_string());
+            text.transform.scale = Vec3::new(ROW_SCALE, ROW_SCALE, 1.0);
+            commands
+                .spawn_bundle(text)
+                .insert(ChoiceText(*row))
+                .insert(RemoveAfterState);
+        }
+
+        if let SettingsRow::Bind(action) = row {
+            let text = binding_text(&save.settings.bindings, *action, None);
+            let mut text = BitmapTextBundle::new(WIDGET_X, y).with_text(text);
+            text.transform.scale = Vec3::new(BINDING_SCALE, BINDING_SCALE, 1.0);
+            commands
+                .spawn_bundle(text)
+                .insert(BindingText(*action))
+                .insert(RemoveAfterState);
+        }
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(10.0, 10.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(LABEL_X - 20.0, row_y(selection.0), Layer::Overlay.z()),
+            ..default()
+        })
+        .insert(SettingsCursor)
+        .insert(RemoveAfterState);
+}
+
+// Up and down pick a row, left and right change it. Sliders can also be clicked where the volume should be.
+pub fn settings_system(
+    mut save: ResMut<SaveData>,
+    mut selection: ResMut<SettingsSelection>,
+    mut rebinding: ResMut<Rebinding>,
+    mut state: ResMut<State<GameState>>,
+    mut cues: EventWriter<SoundCue>,
+    mut toasts: EventWriter<ShowToast>,
+    packs: Res<AvailablePacks>,
+    mut input: ActionInput,
+    mut cursor_moved: EventReader<CursorMoved>,
+    windows: Res<Windows>,
+    viewport: Res<ViewportSize>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> GameResult {
+    // Escape cancels, so it can't be bound. Pause has it by default and gets it back when it's reset.
+    if let Some(action) = rebinding.0 {
+        if input.keys.just_pressed(KeyCode::Escape) {
+            rebinding.0 = None;
+        } else if let Some(binding) = input.any_just_pressed() {
+            save.settings.bindings.bind(action, binding);
+            rebinding.0 = None;
+        }
+
+        return Ok(());
+    }
+
+    if input.keys.just_pressed(KeyCode::Escape) {
+        state.set(GameState::Menu)?;
+        return Ok(());
+    }
+
+    let last = SettingsRow::ALL.len() - 1;
+    if input.arrow_just_pressed(KeyCode::Up) {
+        selection.0 = selection.0.saturating_sub(1);
+    }
+    if input.arrow_just_pressed(KeyCode::Down) {
+        selection.0 = (selection.0 + 1).min(last);
+    }
+
+    let cursor = cursor_world_position(&windows, &viewport, &camera_query);
+    let hovered = cursor.and_then(row_at);
+    if cursor_moved.iter().count() > 0 && !save.settings.gamepad_menus {
+        if let Some(hovered) = hovered {
+            selection.0 = hovered;
+        }
+    }
+
+    // A tap selects and presses the row under the finger in one go
+    let tapped = input
+        .tap()
+        .and_then(|tap| main_camera_world_position(tap, &viewport, &camera_query))
+        .and_then(|tap| Some((tap, row_at(tap)?)));
+
+    let mouse_clicked = input.mouse.just_pressed(MouseButton::Left) && hovered == Some(selection.0);
+    let clicked_at = if mouse_clicked {
+        input.mouse.reset(MouseButton::Left);
+        cursor
+    } else if let Some((tap, row)) = tapped {
+        input.consume_tap();
+        selection.0 = row;
+        Some(tap)
+    } else {
+        None
+    };
+    let clicked = clicked_at.is_some();
+
+    let left = input.arrow_just_pressed(KeyCode::Left);
+    let right = input.arrow_just_pressed(KeyCode::Right);
+    // A click anywhere else isn't a confirm
+    let confirmed = !input.mouse.just_pressed(MouseButton::Left)
+        && input.just_pressed(&save.settings.bindings, Action::Confirm);
+    let pressed = clicked || confirmed;
+
+    let row = SettingsRow::ALL[selection.0];
+
+    if let Some(volume) = row.volume(&save.settings) {
+        let mut wanted = volume;
+        if left {
+            wanted -= 1.0 / SLIDER_STEPS;
+        }
+        if right {
+            wanted += 1.0 / SLIDER_STEPS;
+        }
+        if let Some(clicked_at) = clicked_at {
+            wanted = (clicked_at.x - WIDGET_X) / SLIDER_SIZE.x;
+        }
+
+        let wanted = (wanted.clamp(0.0, 1.0) * SLIDER_STEPS).round() / SLIDER_STEPS;
+        if wanted != volume {
+            row.set_volume(&mut save.settings, wanted);
+
+            if row == SettingsRow::Sound {
+                cues.send(SoundCue(PREVIEW_CUE));
+            }
+        }
+    } else if let SettingsRow::Bind(action) = row {
+        let reset_keys = [KeyCode::Delete, KeyCode::Back];
+        if pressed {
+            rebinding.0 = Some(action);
+        } else if input.keys.any_just_pressed(reset_keys) {
+            save.settings.bindings.reset(action);
+        }
+    } else if row == SettingsRow::Back {
+        if pressed {
+            state.set(GameState::Menu)?;
+        }
+    } else if row.choice(&save.settings).is_some() {
+        if pressed || left || right {
+            row.cycle(&mut save.settings, &packs);
+
+            // Textures and tuning are only loaded when the game starts
+            if row == SettingsRow::Pack {
+                toasts.send(ShowToast("Restart the game to switch packs".to_string()));
+            }
+        }
+    } else if pressed || left || right {
+        row.toggle(&mut save.settings);
+    }
+
+    Ok(())
+}
+
+pub fn settings_widget_system(
+    save: Res<SaveData>,
+    selection: Res<SettingsSelection>,
+    rebinding: Res<Rebinding>,
+    mut fills: Query<(&SliderFill, &mut Sprite), Without<ToggleBox>>,
+    mut toggles: Query<(&ToggleBox, &mut Sprite), Without<SliderFill>>,
+    mut bindings: Query<(&BindingText, &mut BitmapText), Without<ChoiceText>>,
+    mut choices: Query<(&ChoiceText, &mut BitmapText), Without<BindingText>>,
+    mut cursor_query: Query<&mut Transform, With<SettingsCursor>>,
+) {
+    if !save.is_changed() && !selection.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+
+    for (fill, mut sprite) in fills.iter_mut() {
+        if let Some(volume) = fill.0.volume(&save.settings) {
+            sprite.custom_size = Some(slider_size(volume));
+        }
+    }
+
+    for (toggle, mut sprite) in toggles.iter_mut() {
+        if let Some(on) = toggle.0.is_on(&save.settings) {
+            sprite.color = toggle_color(on);
+        }
+    }
+
+    for (binding, mut text) in bindings.iter_mut() {
+        let wanted = binding_text(&save.settings.bindings, binding.0, rebinding.0);
+        if text.text != wanted {
+            text.text = wanted;
+        }
+    }
+
+    for (choice, mut text) in choices.iter_mut() {
+        if let Some(wanted) = choice.0.choice(&save.settings) {
+            if text.text != wanted {
+                text.text = wanted.to_string();
+            }
+        }
+    }
+
+    for mut transform in cursor_query.iter_mut() {
+        transform.translation.y = row_y(selection.0);
+    }
+}
+
+fn row_y(index: usize) -> f32 {
+    ROW_TOP - index as f32 * ROW_SPACING
+}
+
+// Anywhere between the label and the end of the slider counts
+fn row_at(position: Vec2) -> Option<usize> {
+    if position.x < LABEL_X - 30.0 || position.x > WIDGET_X + SLIDER_SIZE.x + 20.0 {
+        return None;
+    }
+
+    (0..SettingsRow::ALL.len()).find(|i| (position.y - row_y(*i)).abs() <= ROW_SPACING / 2.0)
+}
+
+fn binding_text(bindings: &InputBindings, action: Action, rebinding: Option<Action>) -> String {
+    if rebinding == Some(action) {
+        return "Press a button".to_string();
+    }
+
+    let labels: Vec<String> = bindings
+        .get(action)
+        .iter()
+        .map(|binding| binding.label())
+        .collect();
+    labels.join("  ")
+}
+
+fn slider_size(volume: f32) -> Vec2 {
+    Vec2::new(SLIDER_SIZE.x * volume, SLIDER_SIZE.y)
+}
+
+fn toggle_color(on: bool) -> Color {
+    if on {
+        ON_COLOR
+    } else {
+        OFF_COLOR
+    }
+}
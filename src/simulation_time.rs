@@ -0,0 +1,76 @@
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+use crate::GameState;
+
+// The clock of the simulation, which effects can slow down or stop for a moment. The physics steps, particles
+// and weather advance by it. Anything that isn't part of the simulation (UI, camera) keeps using the unscaled
+// Time.
+#[derive(Default)]
+pub struct SimulationTime {
+    // Temporary scales and the real time they have left. The slowest one wins, so a hit-stop during a slow
+    // motion effect doesn't speed things back up once it's over.
+    scales: Vec<(f32, Timer)>,
+    delta: Duration,
+}
+
+impl SimulationTime {
+    // Runs the simulation at the given scale for a number of real seconds
+    pub fn slow_motion(&mut self, scale: f32, seconds: f32) {
+        self.scales
+            .push((scale, Timer::from_seconds(seconds, false)));
+    }
+
+    // Stops the simulation for a number of real seconds
+    pub fn hit_stop(&mut self, seconds: f32) {
+        self.slow_motion(0.0, seconds);
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scales
+            .iter()
+            .map(|(scale, _)| *scale)
+            .fold(1.0, f32::min)
+    }
+
+    // Simulated time since the last frame
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+}
+
+// Run criteria for systems that move things in the world, see main
+pub fn simulation_running(state: Res<State<GameState>>) -> ShouldRun {
+    if state.current() == &GameState::Paused {
+        ShouldRun::No
+    } else {
+        ShouldRun::Yes
+    }
+}
+
+// Runs at the start of every frame, before anything reads the delta. It stands still while paused, so effects
+// that are still running pick up where they were.
+pub fn simulation_time_system(
+    mut simulation_time: ResMut<SimulationTime>,
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+) {
+    if state.current() == &GameState::Paused {
+        simulation_time.delta = Duration::ZERO;
+        return;
+    }
+
+    simulation_time.delta = time.delta().mul_f32(simulation_time.scale());
+
+    for (_, timer) in simulation_time.scales.iter_mut() {
+        timer.tick(time.delta());
+    }
+    simulation_time
+        .scales
+        .retain(|(_, timer)| !timer.finished());
+}
@@ -0,0 +1,426 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    add_player_outline, cursor_world_position, Action, ActionInput, BitmapTextBundle,
+    CollisionOutline, Condition, GameAssets, GameResult, GameState, Layer, Lifetime, MainCamera,
+    Particle, Pilot, PlaneHulls, PlaneSprite, Player, RemoveAfterState, RunStats, SaveData, Score,
+    ShowToast, ViewportSize,
+};
+
+const COLUMNS: usize = 3;
+const CELL_SIZE: Vec2 = Vec2::new(220.0, 190.0);
+const GRID_TOP: f32 = 100.0;
+const PLANE_SCALE: f32 = 0.8;
+// The picked plane is drawn a little bigger instead of having a cursor
+const PICKED_SCALE: f32 = 1.0;
+const NAME_SCALE: f32 = 0.35;
+const REQUIREMENT_SCALE: f32 = 0.22;
+const LOCKED_COLOR: Color = Color::rgb(0.2, 0.2, 0.22);
+
+const BURST_STARS: usize = 12;
+const BURST_SPEED: f32 = 260.0;
+const BURST_LIFETIME: f32 = 0.8;
+const BURST_GRAVITY: f32 = 400.0;
+
+pub struct Skin {
+    // Stored in the save file, so never change these once released
+    pub id: &'static str,
+    pub name: &'static str,
+    plane: PlaneSprite,
+    // Multiplied with the damage tint, white keeps the sprite as it is
+    tint: Color,
+    // Of the ribbon behind the plane, see trail_system
+    trail: Color,
+    // None for the ones every save starts with
+    unlock: Option<Condition>,
+    // Shown under the padlock, with the bitmap font like the names
+    requirement: &'static str,
+}
+
+// The plane a single player flies, in multiplayer everyone gets their livery instead. The first one is free.
+pub const SKINS: &[Skin] = &[
+    Skin {
+        id: "blue",
+        name: "Blue",
+        plane: PlaneSprite::Blue,
+        tint: Color::WHITE,
+        trail: Color::rgb(0.6, 0.8, 1.0),
+        unlock: None,
+        requirement: "",
+    },
+    Skin {
+        id: "red",
+        name: "Red",
+        plane: PlaneSprite::Red,
+        tint: Color::WHITE,
+        trail: Color::rgb(1.0, 0.6, 0.55),
+        unlock: Some(Condition::Score(25)),
+        requirement: "Score 25",
+    },
+    Skin {
+        id: "green",
+        name: "Green",
+        plane: PlaneSprite::Green,
+        tint: Color::WHITE,
+        trail: Color::rgb(0.6, 1.0, 0.6),
+        unlock: Some(Condition::TotalRocks(100)),
+        requirement: "Pass 100 rocks",
+    },
+    Skin {
+        id: "yellow",
+        name: "Yellow",
+        plane: PlaneSprite::Yellow,
+        tint: Color::WHITE,
+        trail: Color::rgb(1.0, 0.95, 0.6),
+        unlock: Some(Condition::SurviveSeconds(60.0)),
+        requirement: "Survive 60 seconds",
+    },
+    Skin {
+        id: "midnight",
+        name: "Midnight",
+        plane: PlaneSprite::Blue,
+        tint: Color::rgb(0.45, 0.45, 0.75),
+        trail: Color::rgb(0.55, 0.55, 0.9),
+        unlock: Some(Condition::Score(50)),
+        requirement: "Score 50",
+    },
+    Skin {
+        id: "ember",
+        name: "Ember",
+        plane: PlaneSprite::Red,
+        tint: Color::rgb(1.0, 0.6, 0.35),
+        trail: Color::rgb(1.0, 0.55, 0.25),
+        unlock: Some(Condition::TotalRocks(500)),
+        requirement: "Pass 500 rocks",
+    },
+];
+
+impl Skin {
+    // Enemies fly green too, the skin is there for players who earned it anyway
+    fn texture(&self, assets: &GameAssets) -> Handle<Image> {
+        self.plane.texture(assets)
+    }
+
+    fn is_unlocked(&self, unlocks: &Unlocks) -> bool {
+        self.unlock.is_none() || unlocks.skins.contains(self.id)
+    }
+}
+
+// Everything earned towards the game itself rather than a record
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Unlocks {
+    // Ids from SKINS, the free ones aren't in here
+    pub skins: BTreeSet<String>,
+}
+
+// Falls back to the free one when the picked skin is unknown or still locked, say from an edited save
+fn picked_skin(save: &SaveData) -> &'static Skin {
+    SKINS
+        .iter()
+        .find(|skin| skin.id == save.settings.skin && skin.is_unlocked(&save.unlocks))
+        .unwrap_or(&SKINS[0])
+}
+
+// Kept apart from the sprite's color, which damage_tint_system sets every frame
+#[derive(Component)]
+pub struct SkinTint(pub Color);
+
+pub fn skin_trail_color(save: &SaveData) -> Color {
+    picked_skin(save).trail
+}
+
+// Player one flies the picked skin while there's nobody else, the liveries tell planes apart otherwise
+// The hull and its outline change along with the sprite
+pub fn apply_skin_system(
+    mut commands: Commands,
+    save: Res<SaveData>,
+    assets: Res<GameAssets>,
+    hulls: Res<PlaneHulls>,
+    mut planes: Query<(
+        Entity,
+        &Pilot,
+        &Handle<Image>,
+        Option<&SkinTint>,
+        &mut Player,
+        Option<&Children>,
+    )>,
+    outlines: Query<(), With<CollisionOutline>>,
+) {
+    let alone = planes.iter().count() == 1;
+
+    for (entity, pilot, texture, current_tint, mut player, children) in planes.iter_mut() {
+        if *pilot != Pilot::One {
+            continue;
+        }
+
+        let (plane, tint) = if alone {
+            let skin = picked_skin(&save);
+            (skin.plane, skin.tint)
+        } else {
+            (pilot.livery().plane(), Color::WHITE)
+        };
+
+        let wanted = plane.texture(&assets);
+        if *texture != wanted {
+            let hull = hulls.get(plane);
+            player.set_hull(hull);
+
+            for child in children.into_iter().flatten() {
+                if outlines.contains(*child) {
+                    commands.entity(*child).despawn_recursive();
+                }
+            }
+
+            let mut entity = commands.entity(entity);
+            entity.insert(wanted);
+            add_player_outline(&mut entity, hull);
+        }
+        if current_tint.map(|current| current.0) != Some(tint) {
+            commands.entity(entity).insert(SkinTint(tint));
+        }
+    }
+}
+
+// Checked during the run like achievements, so the celebration comes the moment a threshold is crossed
+pub fn skin_unlock_system(
+    mut commands: Commands,
+    mut save: ResMut<SaveData>,
+    run: Res<RunStats>,
+    score: Res<Score>,
+    state: Res<State<GameState>>,
+    assets: Res<GameAssets>,
+    planes: Query<(&Pilot, &GlobalTransform), With<Player>>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    let crashed = state.current() == &GameState::GameOver;
+
+    for skin in SKINS {
+        let condition = match &skin.unlock {
+            Some(condition) => condition,
+            None => continue,
+        };
+        if save.unlocks.skins.contains(skin.id) || !condition.is_met(&run, &score, &save, crashed) {
+            continue;
+        }
+
+        save.unlocks.skins.insert(skin.id.to_string());
+        toasts.send(ShowToast(format!("New plane {}", skin.name)));
+
+        let position = planes
+            .iter()
+            .find(|(pilot, _)| **pilot == Pilot::One)
+            .map_or(Vec3::ZERO, |(_, transform)| transform.translation());
+        spawn_unlock_burst(&mut commands, &assets, position);
+    }
+}
+
+// A ring of stars flying out of the plane
+fn spawn_unlock_burst(commands: &mut Commands, assets: &GameAssets, position: Vec3) {
+    for i in 0..BURST_STARS {
+        let angle = i as f32 / BURST_STARS as f32 * std::f32::consts::TAU;
+        let velocity = Vec2::new(angle.cos(), angle.sin()) * BURST_SPEED;
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                texture: assets.star_gold.clone(),
+                transform: Transform::from_xyz(position.x, position.y, Layer::Overlay.z())
+                    .with_scale(Vec3::new(0.4, 0.4, 1.0)),
+                ..default()
+            })
+            .insert(Particle::new(velocity, BURST_GRAVITY))
+            .insert(Lifetime::from_seconds(BURST_LIFETIME));
+    }
+}
+
+// L on the menu
+pub fn hangar_shortcut_system(
+    keys: Res<Input<KeyCode>>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    if keys.just_pressed(KeyCode::L) {
+        state.set(GameState::Hangar)?;
+    }
+
+    Ok(())
+}
+
+// Index into SKINS of the plane the cursor is on
+pub struct HangarSelection(usize);
+
+#[derive(Component)]
+pub struct HangarPlane(usize);
+
+pub fn setup_hangar(mut commands: Commands, save: Res<SaveData>, assets: Res<GameAssets>) {
+    let picked = SKINS
+        .iter()
+        .position(|skin| std::ptr::eq(skin, picked_skin(&save)))
+        .unwrap_or(0);
+    commands.insert_resource(HangarSelection(picked));
+
+    let mut title = BitmapTextBundle::new(-340.0, GRID_TOP + 110.0).with_text("Hangar".to_string());
+    title.transform.scale = Vec3::new(0.6, 0.6, 1.0);
+    commands.spawn_bundle(title).insert(RemoveAfterState);
+
+    for (i, skin) in SKINS.iter().enumerate() {
+        let center = cell_center(i);
+        let unlocked = skin.is_unlocked(&save.unlocks);
+        let color = if unlocked { skin.tint } else { LOCKED_COLOR };
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite { color, ..default() },
+                texture: skin.texture(&assets),
+                transform: Transform::from_translation(center.extend(Layer::Ui.z()))
+                    .with_scale(Vec3::new(PLANE_SCALE, PLANE_SCALE, 1.0)),
+                ..default()
+            })
+            .insert(HangarPlane(i))
+            .insert(RemoveAfterState);
+
+        let mut name = BitmapTextBundle::new(center.x - 60.0, center.y - 60.0)
+            .with_text(skin.name.to_string());
+        name.transform.scale = Vec3::new(NAME_SCALE, NAME_SCALE, 1.0);
+        commands.spawn_bundle(name).insert(RemoveAfterState);
+
+        if unlocked {
+            continue;
+        }
+
+        spawn_padlock(&mut commands, center);
+
+        let mut requirement = BitmapTextBundle::new(center.x - 90.0, center.y - 88.0)
+            .with_text(skin.requirement.to_string());
+        requirement.transform.scale = Vec3::new(REQUIREMENT_SCALE, REQUIREMENT_SCALE, 1.0);
+        commands.spawn_bundle(requirement).insert(RemoveAfterState);
+    }
+}
+
+// Lyon shapes like the tutorial arrow, there's no lock sprite. The shackle is a ring half hidden by the body.
+fn spawn_padlock(commands: &mut Commands, center: Vec2) {
+    let z = Layer::Ui.z() + 0.1;
+
+    let shackle = shapes::Circle {
+        radius: 11.0,
+        center: Vec2::ZERO,
+    };
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &shackle,
+            DrawMode::Stroke(StrokeMode::new(Color::rgb(0.75, 0.75, 0.78), 5.0)),
+            Transform::from_xyz(center.x, center.y + 10.0, z),
+        ))
+        .insert(RemoveAfterState);
+
+    let body = shapes::Rectangle {
+        extents: Vec2::new(36.0, 28.0),
+        origin: RectangleOrigin::Center,
+    };
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &body,
+            DrawMode::Outlined {
+                fill_mode: FillMode::color(Color::rgb(0.95, 0.75, 0.2)),
+                outline_mode: StrokeMode::new(Color::BLACK, 2.0),
+            },
+            Transform::from_xyz(center.x, center.y - 4.0, z + 0.01),
+        ))
+        .insert(RemoveAfterState);
+}
+
+// Arrows move around the grid, confirming or clicking flies the plane if it's unlocked. Escape keeps the
+// current one.
+pub fn hangar_system(
+    mut selection: ResMut<HangarSelection>,
+    mut save: ResMut<SaveData>,
+    mut state: ResMut<State<GameState>>,
+    mut input: ActionInput,
+    mut toasts: EventWriter<ShowToast>,
+    windows: Res<Windows>,
+    viewport: Res<ViewportSize>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> GameResult {
+    if input.keys.just_pressed(KeyCode::Escape) {
+        state.set(GameState::Menu)?;
+        return Ok(());
+    }
+
+    let last = SKINS.len() - 1;
+    if input.arrow_just_pressed(KeyCode::Left) {
+        selection.0 = selection.0.saturating_sub(1);
+    }
+    if input.arrow_just_pressed(KeyCode::Right) {
+        selection.0 = (selection.0 + 1).min(last);
+    }
+    if input.arrow_just_pressed(KeyCode::Up) {
+        selection.0 = selection.0.saturating_sub(COLUMNS);
+    }
+    if input.arrow_just_pressed(KeyCode::Down) {
+        selection.0 = (selection.0 + COLUMNS).min(last);
+    }
+
+    let bindings = &save.settings.bindings;
+    let clicked = if input.mouse.just_pressed(MouseButton::Left) {
+        input.mouse.reset(MouseButton::Left);
+        cursor_world_position(&windows, &viewport, &camera_query).and_then(|cursor| {
+            (0..SKINS.len()).find(|i| {
+                (cursor - cell_center(*i))
+                    .abs()
+                    .cmple(CELL_SIZE / 2.0)
+                    .all()
+            })
+        })
+    } else {
+        None
+    };
+    let confirmed = input.just_pressed(bindings, Action::Confirm);
+
+    let chosen = match clicked {
+        Some(i) => i,
+        None if confirmed => selection.0,
+        None => return Ok(()),
+    };
+    input.consume(bindings, Action::Confirm);
+    selection.0 = chosen;
+
+    let skin = &SKINS[chosen];
+    if !skin.is_unlocked(&save.unlocks) {
+        toasts.send(ShowToast(format!("{} to unlock", skin.requirement)));
+        return Ok(());
+    }
+
+    if save.settings.skin != skin.id {
+        save.settings.skin = skin.id.to_string();
+    }
+    state.set(GameState::Menu)?;
+
+    Ok(())
+}
+
+pub fn hangar_highlight_system(
+    selection: Res<HangarSelection>,
+    mut planes: Query<(&HangarPlane, &mut Transform)>,
+) {
+    if !selection.is_changed() {
+        return;
+    }
+
+    for (plane, mut transform) in planes.iter_mut() {
+        let scale = if plane.0 == selection.0 {
+            PICKED_SCALE
+        } else {
+            PLANE_SCALE
+        };
+        transform.scale = Vec3::new(scale, scale, 1.0);
+    }
+}
+
+fn cell_center(i: usize) -> Vec2 {
+    let column = (i % COLUMNS) as f32 - (COLUMNS - 1) as f32 / 2.0;
+    let row = (i / COLUMNS) as f32;
+    Vec2::new(column * CELL_SIZE.x, GRID_TOP - row * CELL_SIZE.y)
+}
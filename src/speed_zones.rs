@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    tick_spawn_timer, BatchedParticle, Ease, GameRng, GameSpeed, Lifetime, Particle, PowerUp,
+    PowerUpCollected, SaveData, Score, ShowToast, SimulationTime, HEIGHT, PHYSICS_STEP, WIDTH,
+};
+
+const ZONE_MIN_SCORE: u64 = 35;
+const ZONE_SPEED: f32 = 1.5;
+const ZONE_TIME: f32 = 5.0;
+// Seconds it takes to get up to speed, and to slow down again afterwards
+const ZONE_EASE_TIME: f32 = 0.8;
+const ZONE_SCORE_FACTOR: u64 = 2;
+
+// Streaks per second at full effects intensity, while fully up to speed
+const STREAK_RATE: f32 = 40.0;
+const STREAK_SPEED: f32 = 900.0;
+const STREAK_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.35);
+
+// A short stretch where the course speeds up and every gate is worth double. Starts at random past
+// ZONE_MIN_SCORE, or straight away when a plane flies through a boost ring. Drawn from the course rng, so
+// seeded runs get theirs at the same time.
+#[derive(Clone)]
+pub struct SpeedZone {
+    timer: Timer,
+    time_left: f32,
+    // How far the speed is on its way from normal to ZONE_SPEED, before easing
+    blend: f32,
+}
+
+impl Default for SpeedZone {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(0.0, false),
+            time_left: 0.0,
+            blend: 0.0,
+        }
+    }
+}
+
+impl SpeedZone {
+    pub fn is_active(&self) -> bool {
+        self.time_left > 0.0
+    }
+
+    // From 0 outside of a zone to 1 once it's fully up to speed, for effects that build up with it
+    pub fn intensity(&self) -> f32 {
+        Ease::SmoothStep.apply(self.blend)
+    }
+
+    // What GameSpeed is multiplied by on top of the usual ramp
+    pub fn multiplier(&self) -> f32 {
+        1.0 + (ZONE_SPEED - 1.0) * self.intensity()
+    }
+
+    // Gates only score double once the zone has started, not while it's slowing down afterwards
+    pub fn score_factor(&self) -> u64 {
+        if self.is_active() {
+            ZONE_SCORE_FACTOR
+        } else {
+            1
+        }
+    }
+
+    fn start(&mut self) {
+        self.time_left = ZONE_TIME;
+    }
+}
+
+pub fn reset_speed_zone(mut zone: ResMut<SpeedZone>) {
+    *zone = SpeedZone::default();
+}
+
+pub fn speed_zone_system(
+    mut zone: ResMut<SpeedZone>,
+    mut collected_events: EventReader<PowerUpCollected>,
+    mut game_speed: ResMut<GameSpeed>,
+    score: Res<Score>,
+    mut rng: ResMut<GameRng>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    let boosted = collected_events
+        .iter()
+        .any(|event| event.power_up == PowerUp::Boost);
+
+    let mut started = boosted && !zone.is_active();
+    if boosted {
+        zone.start();
+    }
+
+    if score.0 >= ZONE_MIN_SCORE
+        && tick_spawn_timer(&mut zone.timer, || rng.course.gen_range(20.0..35.0))
+        && !zone.is_active()
+    {
+        zone.start();
+        started = true;
+    }
+
+    if started {
+        toasts.send(ShowToast("Speed zone".to_string()));
+    }
+
+    let before = zone.multiplier();
+
+    zone.time_left = (zone.time_left - PHYSICS_STEP).max(0.0);
+    let step = PHYSICS_STEP / ZONE_EASE_TIME;
+    zone.blend = if zone.is_active() {
+        (zone.blend + step).min(1.0)
+    } else {
+        (zone.blend - step).max(0.0)
+    };
+
+    // Only the zone's part of the speed changes, the ramp from scoring carries on underneath it
+    let after = zone.multiplier();
+    if after != before {
+        game_speed.0 *= after / before;
+    }
+}
+
+// Fractional streaks carry over to the next frame, like weather drops
+#[derive(Default)]
+pub struct StreakSpawner {
+    pending: f32,
+}
+
+// Thin lines rushing past while the course is sped up, fading in and out along with the speed
+pub fn speed_streak_system(
+    mut commands: Commands,
+    mut spawner: ResMut<StreakSpawner>,
+    zone: Res<SpeedZone>,
+    save: Res<SaveData>,
+    simulation_time: Res<SimulationTime>,
+    mut rng: ResMut<GameRng>,
+) {
+    let rate = STREAK_RATE * zone.intensity() * save.settings.effects_intensity.factor();
+    if rate == 0.0 {
+        spawner.pending = 0.0;
+        return;
+    }
+
+    spawner.pending += rate * simulation_time.delta_seconds();
+    let rng = &mut rng.cosmetic;
+
+    while spawner.pending >= 1.0 {
+        spawner.pending -= 1.0;
+
+        let size = Vec2::new(rng.gen_range(40.0..90.0), 2.0);
+        let speed = STREAK_SPEED * rng.gen_range(0.8..1.2);
+        let y = rng.gen_range(HEIGHT / -2.0..HEIGHT / 2.0);
+
+        let mut particle = Particle::new(Vec2::new(-speed, 0.0), 0.0);
+        particle.fade = false;
+
+        commands
+            .spawn()
+            .insert(Transform::from_xyz(WIDTH / 2.0 + size.x, y, 0.0))
+            .insert(BatchedParticle {
+                color: STREAK_COLOR,
+                size,
+            })
+            .insert(particle)
+            .insert(Lifetime::from_seconds((WIDTH + size.x * 2.0) / speed));
+    }
+}
@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    DemoMode, FlapEvent, GameAssets, GameConfig, GatePassedEvent, HudVisibility, RemoveAfterState,
+    SaveData, ShowToast, GROUND_HEIGHT, PHYSICS_STEP,
+};
+
+// When the speedrun timer starts, see assets/config.ron
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum TimingStart {
+    RunStart,
+    FirstFlap,
+}
+
+// When the speedrun timer stops, see assets/config.ron
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum TimingStop {
+    Crash,
+    Gates(u32),
+}
+
+#[derive(Deserialize)]
+pub struct SpeedrunRules {
+    pub start: TimingStart,
+    pub stop: TimingStop,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum TimerState {
+    Waiting,
+    Running,
+    Stopped,
+}
+
+impl Default for TimerState {
+    fn default() -> Self {
+        TimerState::Waiting
+    }
+}
+
+// Counts physics steps instead of frame time, so a run times the same on every machine
+#[derive(Default, Clone)]
+pub struct SpeedrunTimer {
+    state: TimerState,
+    gates: u32,
+    steps: u64,
+}
+
+#[derive(Component)]
+pub struct SpeedrunText;
+
+pub fn speedrun_toggle_system(
+    keys: Res<Input<KeyCode>>,
+    mut save: ResMut<SaveData>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !keys.just_pressed(KeyCode::S) {
+        return;
+    }
+
+    save.settings.speedrun_timer = !save.settings.speedrun_timer;
+
+    let message = if save.settings.speedrun_timer {
+        "Speedrun timer on"
+    } else {
+        "Speedrun timer off"
+    };
+    toasts.send(ShowToast(message.to_string()));
+}
+
+pub fn setup_speedrun(
+    mut commands: Commands,
+    mut timer: ResMut<SpeedrunTimer>,
+    config: Res<GameConfig>,
+    save: Res<SaveData>,
+    demo: Res<DemoMode>,
+    assets: Res<GameAssets>,
+) {
+    *timer = SpeedrunTimer::default();
+    if config.speedrun.start == TimingStart::RunStart {
+        timer.state = TimerState::Running;
+    }
+
+    if !save.settings.speedrun_timer || demo.active {
+        return;
+    }
+
+    // Kept up on the game over screen, so the final time can be read off
+    commands
+        .spawn_bundle(
+            TextBundle::from_section(
+                speedrun_text(&timer),
+                TextStyle {
+                    font: assets.ui_font.clone(),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(GROUND_HEIGHT + 5.0),
+                    right: Val::Px(15.0),
+                    ..default()
+                },
+                ..default()
+            }),
+        )
+        .insert(SpeedrunText)
+        .insert(RemoveAfterState);
+}
+
+// After scoring, so the gate that stops the timer is counted in the same step.
+// A crash stops the timer by ending the physics steps, the step of the crash is still counted.
+pub fn speedrun_timer_system(
+    mut timer: ResMut<SpeedrunTimer>,
+    config: Res<GameConfig>,
+    mut flaps: EventReader<FlapEvent>,
+    mut gates: EventReader<GatePassedEvent>,
+) {
+    let flapped = flaps.iter().count() > 0;
+    let passed = gates.iter().count() as u32;
+
+    if timer.state == TimerState::Stopped {
+        return;
+    }
+
+    if timer.state == TimerState::Waiting && flapped {
+        timer.state = TimerState::Running;
+    }
+
+    timer.gates += passed;
+    if timer.state == TimerState::Running {
+        timer.steps += 1;
+    }
+
+    if let TimingStop::Gates(gates) = config.speedrun.stop {
+        if timer.gates >= gates {
+            timer.state = TimerState::Stopped;
+        }
+    }
+}
+
+pub fn speedrun_text_system(
+    timer: Res<SpeedrunTimer>,
+    hud: Res<HudVisibility>,
+    mut text_query: Query<&mut Text, With<SpeedrunText>>,
+) {
+    if !timer.is_changed() && !hud.is_changed() {
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = hud.text(speedrun_text(&timer));
+    }
+}
+
+// Centiseconds are rounded down, a time is never shown as faster than it was
+fn speedrun_text(timer: &SpeedrunTimer) -> String {
+    let centiseconds = (timer.steps as f64 * PHYSICS_STEP as f64 * 100.0) as u64;
+    let seconds = centiseconds / 100;
+
+    format!(
+        "Gate {}  {:02}:{:02}.{:02}",
+        timer.gates,
+        seconds / 60,
+        seconds % 60,
+        centiseconds % 100
+    )
+}
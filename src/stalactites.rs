@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    add_collision_polygon, tick_spawn_timer, BaseColor, DespawnOffscreen, GameAssets, GameResult,
+    GameRng, Hazard, HazardKind, HazardSpawn, HorizontalVelocity, Interpolated, Layer, Lifetime,
+    Particle, Player, Score, ScreenAnchor, SpawnHooks, Telegraph, TelegraphStyle, ThemeState,
+    GROUND_HEIGHT, HEIGHT, PHYSICS_STEP, ROCK_DOWN_POINTS, ROCK_HEIGHT,
+};
+
+// Stalactites show up once the rocks alone are old news
+const STALACTITE_MIN_SCORE: u64 = 15;
+const STALACTITE_SCALE: f32 = 0.35;
+const STALACTITE_MARGIN: f32 = 60.0;
+
+// How far ahead of the plane a stalactite starts to crack
+const TRIGGER_DISTANCE: f32 = 220.0;
+const CRACK_TIME: f32 = 0.5;
+const FALL_GRAVITY: f32 = 1800.0;
+// Seconds a fallen stalactite stays on the ground, still in the way
+const LINGER_TIME: f32 = 1.5;
+
+const DEBRIS_PUFFS: usize = 6;
+const DEBRIS_TIME: f32 = 0.6;
+
+#[derive(Clone, Copy)]
+pub enum StalactiteState {
+    Hanging,
+    // Telegraphed, falls once its Telegraph is gone
+    Cracking,
+    Falling { velocity: f32 },
+    Landed { remaining: f32 },
+}
+
+// A small rock hanging from the ceiling that drops when a plane gets close
+#[derive(Component, Clone)]
+pub struct Stalactite {
+    state: StalactiteState,
+}
+
+pub struct StalactiteTimer(pub Timer);
+
+impl Default for StalactiteTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.0, false))
+    }
+}
+
+pub fn reset_stalactites(mut timer: ResMut<StalactiteTimer>) {
+    *timer = StalactiteTimer::default();
+}
+
+pub fn stalactite_spawn_system(
+    mut commands: Commands,
+    mut timer: ResMut<StalactiteTimer>,
+    score: Res<Score>,
+    mut rng: ResMut<GameRng>,
+    theme: Res<ThemeState>,
+    hooks: Res<SpawnHooks>,
+) -> GameResult {
+    if score.0 < STALACTITE_MIN_SCORE {
+        return Ok(());
+    }
+
+    if !tick_spawn_timer(&mut timer.0, || rng.course.gen_range(5.0..9.0)) {
+        return Ok(());
+    }
+
+    let height = ROCK_HEIGHT * STALACTITE_SCALE;
+    let y = HEIGHT / 2.0 - GROUND_HEIGHT - height / 2.0 + 20.0;
+
+    let mut spawn = HazardSpawn::new(
+        HazardKind::Stalactite,
+        ScreenAnchor::Right.offset(60.0, y),
+        score.0,
+    );
+    if !hooks.hazard(&mut spawn) {
+        return Ok(());
+    }
+
+    let mut entity = commands.spawn_bundle(SpriteBundle {
+        transform: Transform::from_xyz(spawn.position.x, spawn.position.y, Layer::Course.z())
+            .with_scale(Vec3::new(1.0, STALACTITE_SCALE, 1.0)),
+        texture: theme.textures.rock_down.clone(),
+        ..default()
+    });
+
+    add_collision_polygon(&mut entity, &ROCK_DOWN_POINTS.to_vec(), STALACTITE_SCALE)?;
+
+    entity
+        .insert(HorizontalVelocity(COURSE_VELOCITY))
+        .insert(DespawnOffscreen {
+            margin: STALACTITE_MARGIN,
+        })
+        .insert(Interpolated::default())
+        .insert(BaseColor(Color::WHITE))
+        .insert(Hazard)
+        .insert(Stalactite {
+            state: StalactiteState::Hanging,
+        });
+
+    spawn.apply_attached(&mut entity);
+
+    Ok(())
+}
+
+pub fn stalactite_system(
+    mut commands: Commands,
+    mut query: Query<
+        (
+            Entity,
+            &mut Stalactite,
+            &mut Transform,
+            &mut BaseColor,
+            Option<&Telegraph>,
+        ),
+        Without<Player>,
+    >,
+    player_query: Query<&Transform, With<Player>>,
+    mut rng: ResMut<GameRng>,
+    assets: Res<GameAssets>,
+) {
+    let ground = -HEIGHT / 2.0 + GROUND_HEIGHT;
+    let half_height = ROCK_HEIGHT * STALACTITE_SCALE / 2.0;
+
+    for (entity, mut stalactite, mut transform, mut color, telegraph) in query.iter_mut() {
+        match stalactite.state {
+            StalactiteState::Hanging => {
+                let x = transform.translation.x;
+                let approached = player_query.iter().any(|player| {
+                    x > player.translation.x && x - player.translation.x < TRIGGER_DISTANCE
+                });
+
+                if approached {
+                    stalactite.state = StalactiteState::Cracking;
+                    color.0 = Color::rgb(0.7, 0.6, 0.55);
+                    commands
+                        .entity(entity)
+                        .insert(Telegraph::new(CRACK_TIME, TelegraphStyle::Shadow).scrolling());
+                }
+            }
+            StalactiteState::Cracking => {
+                if telegraph.is_none() {
+                    stalactite.state = StalactiteState::Falling { velocity: 0.0 };
+                }
+            }
+            StalactiteState::Falling { velocity } => {
+                let velocity = velocity + FALL_GRAVITY * PHYSICS_STEP;
+                transform.translation.y -= velocity * PHYSICS_STEP;
+
+                if transform.translation.y - half_height > ground {
+                    stalactite.state = StalactiteState::Falling { velocity };
+                    continue;
+                }
+
+                transform.translation.y = ground + half_height;
+                stalactite.state = StalactiteState::Landed {
+                    remaining: LINGER_TIME,
+                };
+                spawn_debris(&mut commands, &mut rng, &assets, transform.translation);
+            }
+            StalactiteState::Landed { remaining } => {
+                let remaining = remaining - PHYSICS_STEP;
+                if remaining > 0.0 {
+                    stalactite.state = StalactiteState::Landed { remaining };
+                } else {
+                    spawn_debris(&mut commands, &mut rng, &assets, transform.translation);
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        }
+    }
+}
+
+fn spawn_debris(commands: &mut Commands, rng: &mut GameRng, assets: &GameAssets, position: Vec3) {
+    for _ in 0..DEBRIS_PUFFS {
+        let velocity = Vec2::new(
+            rng.cosmetic.gen_range(-150.0..150.0),
+            rng.cosmetic.gen_range(50.0..200.0),
+        );
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                texture: assets.puff.clone(),
+                transform: Transform::from_xyz(position.x, position.y, position.z + 0.1)
+                    .with_scale(Vec3::new(0.5, 0.5, 1.0)),
+                ..default()
+            })
+            .insert(Particle::new(velocity, FALL_GRAVITY / 3.0))
+            .insert(Lifetime::from_seconds(DEBRIS_TIME));
+    }
+}
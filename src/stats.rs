@@ -0,0 +1,275 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Action, ActionInput, ActiveDifficulty, ActiveMutators, AssetPack, BitmapTextBundle, CrashSite,
+    Difficulty, FlapEvent, GameModes, GameOverSection, GameResult, GameSpeed, GameState,
+    GatePassedEvent, Marquee, RemoveAfterState, RunMode, SaveData, Score, ScreenAnchor,
+    SimulationTime, COURSE_VELOCITY, PHYSICS_STEP,
+};
+
+const LABEL_X: f32 = -260.0;
+const VALUE_X: f32 = 60.0;
+const ROW_TOP: f32 = 120.0;
+const ROW_SPACING: f32 = 40.0;
+const ROW_SCALE: f32 = 0.4;
+
+const TICKER_POSITION: Vec2 = ScreenAnchor::Bottom.offset(0.0, 30.0);
+const TICKER_SCALE: f32 = 0.3;
+const TICKER_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.8);
+
+// A few seconds of flying at the starting speed
+const WORLD_UNITS_PER_KM: f32 = 1000.0;
+
+// Stats for the current (or just finished) run
+#[derive(Default)]
+pub struct RunStats {
+    // Simulated seconds, so slow motion and hit-stops don't make a run last longer
+    pub time: f32,
+    pub rocks_passed: u64,
+    pub speed: RunSpeed,
+}
+
+// The speed settings a run was flown with. Kept with its leaderboard entry and online submission, so scores are
+// only compared between runs flown alike and the server can tell a score from how far the run got.
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunSpeed {
+    // World units the course scrolled by. Counted in physics steps at the game speed, so it's the same at any
+    // frame rate or time scale.
+    pub distance: f32,
+    // How quickly the course speeds up, 1 without mutators
+    pub ramp: f32,
+    // Simulated seconds flown below full speed
+    pub slowed: f32,
+    pub difficulty: Difficulty,
+}
+
+impl RunSpeed {
+    pub fn kilometres(&self) -> u64 {
+        (self.distance / WORLD_UNITS_PER_KM) as u64
+    }
+
+    // Points per km, the same for equal flying whether the run was sped up or slowed down
+    pub fn score_rate(&self, score: u64) -> u64 {
+        if self.distance < WORLD_UNITS_PER_KM {
+            return 0;
+        }
+
+        (score as f32 * WORLD_UNITS_PER_KM / self.distance).round() as u64
+    }
+}
+
+// Stats across all runs, stored in the save file and shown on the stats screen. Demo runs don't count.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerStats {
+    pub runs: u64,
+    pub rocks_passed: u64,
+    pub flaps: u64,
+    // In seconds, only counting time spent playing
+    pub time_played: f64,
+    // Saves from before these were kept have runs that aren't in here, so the average uses its own count
+    pub total_score: u64,
+    pub scored_runs: u64,
+    pub best_score: u64,
+    // The latest crashes, oldest first, for the heatmap on the stats screen
+    pub crash_sites: Vec<CrashSite>,
+}
+
+impl PlayerStats {
+    pub fn average_score(&self) -> u64 {
+        self.total_score.checked_div(self.scored_runs).unwrap_or(0)
+    }
+
+    // Label and value for each row of the stats screen
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Runs", self.runs.to_string()),
+            ("Rocks passed", self.rocks_passed.to_string()),
+            ("Flaps", self.flaps.to_string()),
+            ("Time played", time_played_text(self.time_played)),
+            ("Average score", self.average_score().to_string()),
+            ("Best score", self.best_score.to_string()),
+        ]
+    }
+}
+
+// Runs since the game was started, for the ticker on the start screen. Not saved.
+#[derive(Default)]
+pub struct SessionStats {
+    pub runs: u64,
+    pub last_score: u64,
+    pub best_score: u64,
+    pub rocks_passed: u64,
+}
+
+impl SessionStats {
+    fn ticker_messages(&self) -> Vec<String> {
+        let runs = if self.runs == 1 { "run" } else { "runs" };
+        vec![
+            format!("{} {} this session", self.runs, runs),
+            format!("Last score {}", self.last_score),
+            format!("Session best {}", self.best_score),
+            format!("{} rocks passed", self.rocks_passed),
+        ]
+    }
+}
+
+// Hours and minutes, or minutes and seconds for the first hour
+fn time_played_text(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m {}s", minutes, seconds % 60)
+    }
+}
+
+pub fn start_run_stats(
+    mut run: ResMut<RunStats>,
+    mut save: ResMut<SaveData>,
+    mutators: Res<ActiveMutators>,
+    difficulty: Res<ActiveDifficulty>,
+) {
+    *run = RunStats::default();
+    run.speed.ramp = mutators.speed_ramp_factor();
+    run.speed.difficulty = difficulty.difficulty;
+    save.stats.runs += 1;
+}
+
+pub fn finish_run_stats(
+    score: Res<Score>,
+    run: Res<RunStats>,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    pack: Res<AssetPack>,
+    mut save: ResMut<SaveData>,
+    mut session: ResMut<SessionStats>,
+) {
+    session.runs += 1;
+    session.last_score = score.0;
+    session.best_score = session.best_score.max(score.0);
+    session.rocks_passed += run.rocks_passed;
+
+    save.stats.total_score += score.0;
+    save.stats.scored_runs += 1;
+
+    // See official_tuning
+    if pack.changes_tuning() {
+        return;
+    }
+
+    save.stats.best_score = save.stats.best_score.max(score.0);
+
+    let mode = modes.get(*mode);
+    if score.0 > mode.best_score(&save) {
+        mode.record_score(&mut save, score.0);
+    }
+}
+
+pub fn run_stats_system(
+    mut run: ResMut<RunStats>,
+    mut save: ResMut<SaveData>,
+    mut gates: EventReader<GatePassedEvent>,
+    mut flaps: EventReader<FlapEvent>,
+    time: Res<Time>,
+    simulation_time: Res<SimulationTime>,
+) {
+    run.time += simulation_time.delta_seconds();
+    if simulation_time.scale() < 1.0 {
+        run.speed.slowed += simulation_time.delta_seconds();
+    }
+    save.stats.time_played += time.delta_seconds_f64();
+    save.stats.flaps += flaps.iter().count() as u64;
+
+    let passed = gates.iter().count() as u64;
+    if passed > 0 {
+        run.rocks_passed += passed;
+        save.stats.rocks_passed += passed;
+    }
+}
+
+// Runs with the other physics systems, everything on the course moves COURSE_VELOCITY times the game speed
+pub fn run_distance_system(mut run: ResMut<RunStats>, game_speed: Res<GameSpeed>) {
+    run.speed.distance += PHYSICS_STEP * game_speed.0 * COURSE_VELOCITY;
+}
+
+// Below the score, so runs at different speeds can still be compared
+pub fn distance_section(
+    run: Res<RunStats>,
+    score: Res<Score>,
+    mut sections: EventWriter<GameOverSection>,
+) {
+    let km = run.speed.kilometres();
+    if km == 0 {
+        return;
+    }
+
+    let text = format!(
+        "Flew {} km at {} points a km",
+        km,
+        run.speed.score_rate(score.0)
+    );
+    sections.send(GameOverSection::line(150, text));
+}
+
+// Only back on the start screen after a run, there's nothing to tell before the first one
+pub fn setup_session_ticker(mut commands: Commands, session: Res<SessionStats>) {
+    if session.runs == 0 {
+        return;
+    }
+
+    commands
+        .spawn_bundle(
+            BitmapTextBundle::at(TICKER_POSITION)
+                .with_scale(TICKER_SCALE)
+                .with_color(TICKER_COLOR),
+        )
+        .insert(Marquee::new(session.ticker_messages()))
+        .insert(RemoveAfterState);
+}
+
+pub fn setup_stats_screen(mut commands: Commands, save: Res<SaveData>) {
+    let mut title = BitmapTextBundle::new(LABEL_X, ROW_TOP + 60.0).with_text("Stats".to_string());
+    title.transform.scale = Vec3::new(0.6, 0.6, 1.0);
+    commands.spawn_bundle(title).insert(RemoveAfterState);
+
+    for (i, (label, value)) in save.stats.rows().into_iter().enumerate() {
+        let y = ROW_TOP - i as f32 * ROW_SPACING;
+
+        let mut label = BitmapTextBundle::new(LABEL_X, y).with_text(label.to_string());
+        label.transform.scale = Vec3::new(ROW_SCALE, ROW_SCALE, 1.0);
+        commands.spawn_bundle(label).insert(RemoveAfterState);
+
+        let mut value = BitmapTextBundle::new(VALUE_X, y).with_text(value);
+        value.transform.scale = Vec3::new(ROW_SCALE, ROW_SCALE, 1.0);
+        commands.spawn_bundle(value).insert(RemoveAfterState);
+    }
+}
+
+// Nothing to pick on this screen, so anything that confirms goes back to the menu
+pub fn stats_screen_system(
+    mut input: ActionInput,
+    mut state: ResMut<State<GameState>>,
+    save: Res<SaveData>,
+) -> GameResult {
+    let bindings = &save.settings.bindings;
+    let back = input.keys.just_pressed(KeyCode::Escape)
+        || input.just_pressed(bindings, Action::Confirm)
+        || input.mouse.just_pressed(MouseButton::Left)
+        || input.tap().is_some();
+    if !back {
+        return Ok(());
+    }
+
+    // Otherwise the menu would press whatever is selected in the same frame
+    input.consume(bindings, Action::Confirm);
+    input.mouse.reset(MouseButton::Left);
+    input.consume_tap();
+    state.set(GameState::Menu)?;
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+use crate::{
+    BitmapText, BitmapTextBundle, FlapEvent, GameOverSection, GatePassedEvent, HudVisibility,
+    NearMissEvent, RemoveAfterState, ScreenAnchor, TextAnchor, FREE_FALL_VELOCITY, PHYSICS_STEP,
+};
+
+const NEAR_MISS_POINTS: u64 = 5;
+const PERFECT_CENTER_POINTS: u64 = 3;
+const LOOP_POINTS: u64 = 10;
+const GLIDE_POINTS: u64 = 2;
+
+// How far from the middle of a gap the player can be for a pass to still count as perfect
+const PERFECT_CENTER_TOLERANCE: f32 = 10.0;
+
+// Seconds without flapping before a glide counts
+const GLIDE_TIME: f32 = 1.2;
+
+// Secondary score for flying with some flair. Lifetime is kept across runs for unlocks.
+#[derive(Default)]
+pub struct StyleScore {
+    pub run: u64,
+    pub lifetime: u64,
+}
+
+#[derive(Default)]
+pub struct StyleTracker {
+    since_flap: f32,
+    glide_counted: bool,
+}
+
+#[derive(Component)]
+pub struct StyleText;
+
+pub fn setup_style(mut commands: Commands) {
+    let mut bundle = BitmapTextBundle::at(ScreenAnchor::TopRight.offset(-15.0, -120.0))
+        .with_anchor(TextAnchor::Right)
+        .with_text(style_text(0));
+    bundle.transform.scale = Vec3::new(0.5, 0.5, 1.0);
+
+    commands.spawn_bundle(bundle).insert(StyleText);
+}
+
+pub fn style_system(
+    mut style: ResMut<StyleScore>,
+    mut tracker: ResMut<StyleTracker>,
+    mut flaps: EventReader<FlapEvent>,
+    mut gates: EventReader<GatePassedEvent>,
+    mut near_misses: EventReader<NearMissEvent>,
+) {
+    let mut points = near_misses.iter().count() as u64 * NEAR_MISS_POINTS;
+
+    for gate in gates.iter() {
+        if gate.offset.abs() <= PERFECT_CENTER_TOLERANCE {
+            points += PERFECT_CENTER_POINTS;
+        }
+    }
+
+    let mut flapped = false;
+    for flap in flaps.iter() {
+        flapped = true;
+
+        // Pulling out of a full nose-down dive is about as close to a loop as this plane gets
+        if flap.velocity <= FREE_FALL_VELOCITY {
+            points += LOOP_POINTS;
+        }
+    }
+
+    if flapped {
+        tracker.since_flap = 0.0;
+        tracker.glide_counted = false;
+    } else {
+        tracker.since_flap += PHYSICS_STEP;
+
+        if !tracker.glide_counted && tracker.since_flap >= GLIDE_TIME {
+            tracker.glide_counted = true;
+            points += GLIDE_POINTS;
+        }
+    }
+
+    if points > 0 {
+        style.run += points;
+        style.lifetime += points;
+    }
+}
+
+pub fn style_text_system(
+    style: Res<StyleScore>,
+    hud: Res<HudVisibility>,
+    mut text_query: Query<&mut BitmapText, With<StyleText>>,
+) {
+    if !style.is_changed() && !hud.is_changed() {
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.text = hud.text(style_text(style.run));
+    }
+}
+
+// Right under the score
+pub fn style_summary_section(style: Res<StyleScore>, mut sections: EventWriter<GameOverSection>) {
+    sections.send(GameOverSection::line(200, style_text(style.run)));
+}
+
+pub fn reset_style(mut style: ResMut<StyleScore>, mut tracker: ResMut<StyleTracker>) {
+    style.run = 0;
+    *tracker = StyleTracker::default();
+}
+
+fn style_text(points: u64) -> String {
+    format!("Style {}", points)
+}
@@ -0,0 +1,376 @@
+use std::fs;
+
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task};
+use futures_lite::future;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{online_allowed, unix_seconds, SaveData, ShowToast, SECONDS_PER_DAY};
+
+const SYNC_CONFIG_PATH: &str = "sync.ron";
+
+// Written by hand by players who want their save in the cloud, e.g.
+// (backend: WebDav(url: "https://example.com/dav/bevy-plane.ron", username: "me", password: "secret"))
+#[derive(Deserialize)]
+pub struct SyncConfig {
+    pub backend: SyncBackend,
+}
+
+#[derive(Clone, Deserialize)]
+pub enum SyncBackend {
+    WebDav {
+        // Full url of the file to store the save in
+        url: String,
+        username: String,
+        password: String,
+    },
+    S3 {
+        // e.g. https://s3.eu-central-1.amazonaws.com, buckets are addressed path-style
+        endpoint: String,
+        region: String,
+        bucket: String,
+        key: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+enum SyncState {
+    Downloading,
+    Ready,
+    // Something went wrong downloading, so uploading could overwrite a newer save
+    Disabled,
+}
+
+enum SyncResult {
+    // None if there's nothing in the cloud yet
+    Downloaded(Option<String>),
+    Uploaded,
+    Failed(String),
+}
+
+pub struct CloudSync {
+    backend: SyncBackend,
+    state: SyncState,
+    task: Option<Task<SyncResult>>,
+    // Modification time of the newest save that was sent to (or came from) the cloud
+    synced: u64,
+}
+
+pub struct CloudSyncPlugin;
+
+impl Plugin for CloudSyncPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_cloud_sync)
+            .add_system(cloud_sync_system);
+    }
+}
+
+pub fn setup_cloud_sync(mut commands: Commands) {
+    let contents = match fs::read_to_string(SYNC_CONFIG_PATH) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+
+    let config: SyncConfig = match ron::from_str(&contents) {
+        Ok(config) => config,
+        Err(err) => {
+            warn!(
+                "Could not read {}, cloud sync is off: {}",
+                SYNC_CONFIG_PATH, err
+            );
+            return;
+        }
+    };
+
+    let backend = config.backend.clone();
+    let task = IoTaskPool::get().spawn(async move { download(&backend) });
+
+    commands.insert_resource(CloudSync {
+        backend: config.backend,
+        state: SyncState::Downloading,
+        task: Some(task),
+        synced: 0,
+    });
+}
+
+// Newest save wins: a newer cloud save replaces the local one, a newer local save gets uploaded
+pub fn cloud_sync_system(
+    sync: Option<ResMut<CloudSync>>,
+    mut save: ResMut<SaveData>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    let mut sync = match sync {
+        Some(sync) => sync,
+        None => return,
+    };
+
+    if let Some(task) = &mut sync.task {
+        let result = match future::block_on(future::poll_once(task)) {
+            Some(result) => result,
+            None => return,
+        };
+
+        sync.task = None;
+
+        match result {
+            SyncResult::Downloaded(Some(contents)) => match ron::from_str::<SaveData>(&contents) {
+                // Uploading over it would lose whatever this version doesn't know about
+                Ok(remote) if remote.is_newer_version() => {
+                    sync.state = SyncState::Disabled;
+                    warn!("Cloud save is from a newer version, cloud sync is off");
+                    toasts.send(ShowToast("Cloud sync unavailable".to_string()));
+                }
+                Ok(mut remote) => {
+                    sync.state = SyncState::Ready;
+                    sync.synced = remote.modified;
+
+                    if remote.modified > save.modified {
+                        remote.migrate();
+                        save.replace(remote);
+                        toasts.send(ShowToast("Loaded save from the cloud".to_string()));
+                    }
+                }
+                Err(err) => {
+                    sync.state = SyncState::Disabled;
+                    warn!("Cloud save is unreadable, cloud sync is off: {}", err);
+                }
+            },
+            SyncResult::Downloaded(None) => sync.state = SyncState::Ready,
+            SyncResult::Uploaded => {}
+            SyncResult::Failed(err) => {
+                warn!("Cloud sync failed: {}", err);
+
+                if let SyncState::Downloading = sync.state {
+                    sync.state = SyncState::Disabled;
+                    toasts.send(ShowToast("Cloud sync unavailable".to_string()));
+                }
+            }
+        }
+    }
+
+    if !matches!(sync.state, SyncState::Ready)
+        || save.modified <= sync.synced
+        || !online_allowed(&save.settings)
+    {
+        return;
+    }
+
+    let body = match ron::ser::to_string_pretty(&*save, Default::default()) {
+        Ok(body) => body,
+        Err(err) => {
+            error!("Could not serialize save for cloud sync: {}", err);
+            return;
+        }
+    };
+
+    // Failed uploads are retried with the next save instead of every frame
+    sync.synced = save.modified;
+
+    let backend = sync.backend.clone();
+    sync.task = Some(IoTaskPool::get().spawn(async move { upload(&backend, body) }));
+}
+
+fn download(backend: &SyncBackend) -> SyncResult {
+    let request = match backend {
+        SyncBackend::WebDav { url, .. } => ureq::get(url),
+        SyncBackend::S3 { .. } => ureq::get(&s3_url(backend)),
+    };
+
+    match authorize(request, backend, "GET", b"").call() {
+        Ok(response) => match response.into_string() {
+            Ok(contents) => SyncResult::Downloaded(Some(contents)),
+            Err(err) => SyncResult::Failed(err.to_string()),
+        },
+        Err(ureq::Error::Status(404, _)) => SyncResult::Downloaded(None),
+        Err(err) => SyncResult::Failed(err.to_string()),
+    }
+}
+
+fn upload(backend: &SyncBackend, body: String) -> SyncResult {
+    let request = match backend {
+        SyncBackend::WebDav { url, .. } => ureq::put(url),
+        SyncBackend::S3 { .. } => ureq::put(&s3_url(backend)),
+    };
+
+    match authorize(request, backend, "PUT", body.as_bytes()).send_string(&body) {
+        Ok(_) => SyncResult::Uploaded,
+        Err(err) => SyncResult::Failed(err.to_string()),
+    }
+}
+
+fn authorize(
+    request: ureq::Request,
+    backend: &SyncBackend,
+    method: &str,
+    body: &[u8],
+) -> ureq::Request {
+    match backend {
+        SyncBackend::WebDav {
+            username, password, ..
+        } => {
+            let credentials = base64::encode(format!("{}:{}", username, password));
+            request.set("Authorization", &format!("Basic {}", credentials))
+        }
+        SyncBackend::S3 { .. } => sign_s3(request, backend, method, body),
+    }
+}
+
+fn s3_url(backend: &SyncBackend) -> String {
+    match backend {
+        SyncBackend::S3 {
+            endpoint,
+            bucket,
+            key,
+            ..
+        } => {
+            let scheme = if endpoint.starts_with("http://") {
+                "http://"
+            } else {
+                "https://"
+            };
+            let (host, _) = split_endpoint(endpoint);
+            format!("{}{}{}", scheme, host, s3_path(endpoint, bucket, key))
+        }
+        _ => unreachable!(),
+    }
+}
+
+// The host and the path in front of the bucket, for services behind a prefix like https://example.com/s3.
+// Only the host goes in the signed host header, the prefix is part of the path.
+fn split_endpoint(endpoint: &str) -> (&str, &str) {
+    let endpoint = endpoint
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/');
+
+    match endpoint.find('/') {
+        Some(index) => endpoint.split_at(index),
+        None => (endpoint, ""),
+    }
+}
+
+// The same path goes in the URL and the signature, so S3 sees what was signed even when a key has spaces or
+// other characters that need escaping. Slashes between the segments are kept.
+fn s3_path(endpoint: &str, bucket: &str, key: &str) -> String {
+    let (_, prefix) = split_endpoint(endpoint);
+
+    prefix
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .chain(std::iter::once(bucket))
+        .chain(key.split('/'))
+        .map(|segment| format!("/{}", uri_encode(segment)))
+        .collect()
+}
+
+// Percent-encodes everything but the unreserved characters, the way signature version 4 expects
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+// AWS signature version 4, which every S3-compatible service understands
+fn sign_s3(
+    request: ureq::Request,
+    backend: &SyncBackend,
+    method: &str,
+    body: &[u8],
+) -> ureq::Request {
+    let (endpoint, region, bucket, key, access_key, secret_key) = match backend {
+        SyncBackend::S3 {
+            endpoint,
+            region,
+            bucket,
+            key,
+            access_key,
+            secret_key,
+        } => (endpoint, region, bucket, key, access_key, secret_key),
+        _ => unreachable!(),
+    };
+
+    let (host, _) = split_endpoint(endpoint);
+
+    let now = unix_seconds();
+    let (year, month, day) = civil_from_days((now / SECONDS_PER_DAY) as i64);
+    let seconds_today = now % SECONDS_PER_DAY;
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+    let timestamp = format!(
+        "{}T{:02}{:02}{:02}Z",
+        date,
+        seconds_today / 3600,
+        seconds_today / 60 % 60,
+        seconds_today % 60
+    );
+
+    let payload_hash = hex::encode(Sha256::digest(body));
+    let canonical_request = format!(
+        "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+        method,
+        s3_path(endpoint, bucket, key),
+        host,
+        payload_hash,
+        timestamp,
+        payload_hash
+    );
+
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        timestamp,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let signing_key = [region.as_str(), "s3", "aws4_request"].iter().fold(
+        hmac_sha256(format!("AWS4{}", secret_key).as_bytes(), &date),
+        |key, part| hmac_sha256(&key, part),
+    );
+    let signature = hex::encode(hmac_sha256(&signing_key, &string_to_sign));
+
+    request
+        .set("x-amz-date", &timestamp)
+        .set("x-amz-content-sha256", &payload_hash)
+        .set(
+            "Authorization",
+            &format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                access_key, scope, signature
+            ),
+        )
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+// Converts days since the unix epoch to a (year, month, day) date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400;
+
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
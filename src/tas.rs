@@ -0,0 +1,567 @@
+use std::collections::VecDeque;
+
+use bevy::ecs::event::Events;
+use bevy::ecs::system::CommandQueue;
+use bevy::hierarchy::despawn_with_children_recursive;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::{
+    fire_system, player_system, respawn_blade, respawn_pendulum, spawn_laser_gate, write_file,
+    Ammo, BaseColor, Behaviors, Blade, BladeTimer, Bullet, CollisionPolygon, Combo, Continues,
+    Damage, DespawnOffscreen, Enemy, EnemyTimer, FlapInput, Fuel, FuelCan, FuelCanSpawner, GameRng,
+    GameSpeed, GameState, GravityFlip, Hazard, HorizontalVelocity, Interpolated, Invincible,
+    LaserGate, LaserTimer, Lives, Obstacle, ObstacleQueue, Oscillation, Pendulum, PendulumTimer,
+    PhysicsClock, PhysicsStage, Pilot, Player, PowerUp, PowerUpTimer, RemoveAfterState, Rock,
+    RockTimer, RunMode, Score, ShowToast, SpeedZone, SpeedrunTimer, Stalactite, StalactiteTimer,
+    Telegraph, WeatherEffects,
+};
+
+const TAS_PATH: &str = "recording.tas";
+
+const SLOT_KEYS: [KeyCode; 4] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+
+// A plane as far as the simulation is concerned
+#[derive(Clone)]
+struct PlaneSnapshot {
+    pilot: Pilot,
+    transform: Transform,
+    velocity: f32,
+    outside: f32,
+    invincible: Option<Invincible>,
+    gravity_flip: Option<GravityFlip>,
+    damage: Damage,
+}
+
+// Anything that scrolls past and can be hit, scored or collected
+#[derive(Clone)]
+struct CourseEntitySnapshot {
+    transform: Transform,
+    sprite: Sprite,
+    base_color: Option<BaseColor>,
+    texture: Handle<Image>,
+    velocity: HorizontalVelocity,
+    offscreen: Option<DespawnOffscreen>,
+    rock: Option<Rock>,
+    oscillation: Option<Oscillation>,
+    behaviors: Option<Behaviors>,
+    polygon: Option<CollisionPolygon>,
+    enemy: Option<Enemy>,
+    telegraph: Option<Telegraph>,
+    stalactite: Option<Stalactite>,
+    hazard: bool,
+    bullet: bool,
+    power_up: Option<PowerUp>,
+    fuel_can: bool,
+}
+
+// Everything the simulation needs to carry on from a physics step exactly as it did the first time.
+// Cosmetic state like particles, the style score and the camera isn't part of it.
+// Collision outlines of restored rocks are left out, they only exist for the debug overlay.
+#[derive(Clone)]
+pub struct RunSnapshot {
+    planes: Vec<PlaneSnapshot>,
+    course: Vec<CourseEntitySnapshot>,
+    // Pendulums and blades have no sprite, so they're respawned with their own functions
+    pendulums: Vec<(Transform, Pendulum)>,
+    blades: Vec<(Transform, Blade)>,
+    // Laser gates have children for their beam, so they're respawned the same way
+    lasers: Vec<(Transform, LaserGate, Option<Telegraph>)>,
+    score: u64,
+    lives: Lives,
+    continues: Continues,
+    game_speed: f32,
+    rock_timer: Timer,
+    obstacle_queue: VecDeque<Obstacle>,
+    enemy_timer: Timer,
+    stalactite_timer: Timer,
+    pendulum_timer: Timer,
+    blade_timer: Timer,
+    laser_timer: Timer,
+    course_rng: StdRng,
+    fuel: f32,
+    fuel_can_spawner: FuelCanSpawner,
+    ammo: u32,
+    power_up_timer: Timer,
+    speed_zone: SpeedZone,
+    speedrun: SpeedrunTimer,
+    combo: Combo,
+    weather: WeatherEffects,
+    // Length of the input recording when the snapshot was taken, if one was running
+    recorded_steps: Option<usize>,
+}
+
+type CourseFilter = Or<(
+    With<Rock>,
+    With<Hazard>,
+    With<Bullet>,
+    With<PowerUp>,
+    With<FuelCan>,
+)>;
+
+impl RunSnapshot {
+    pub fn capture(world: &mut World) -> Self {
+        let planes = world
+            .query::<(
+                (&Pilot, &Transform, &Player),
+                (Option<&Invincible>, Option<&GravityFlip>, &Damage),
+            )>()
+            .iter(world)
+            .map(
+                |((pilot, transform, player), (invincible, gravity_flip, damage))| PlaneSnapshot {
+                    pilot: *pilot,
+                    transform: *transform,
+                    velocity: player.velocity,
+                    outside: player.outside,
+                    invincible: invincible.cloned(),
+                    gravity_flip: gravity_flip.cloned(),
+                    damage: damage.clone(),
+                },
+            )
+            .collect();
+
+        let course = world
+            .query_filtered::<(
+                (&Transform, &Sprite, &Handle<Image>, &HorizontalVelocity),
+                Option<&BaseColor>,
+                Option<&DespawnOffscreen>,
+                Option<&Rock>,
+                (Option<&Oscillation>, Option<&Behaviors>),
+                Option<&CollisionPolygon>,
+                (Option<&Enemy>, Option<&Telegraph>),
+                (Option<&Stalactite>, Option<&Hazard>),
+                (Option<&Bullet>, Option<&PowerUp>, Option<&FuelCan>),
+            ), (CourseFilter, Without<LaserGate>)>()
+            .iter(world)
+            .map(
+                |(
+                    (transform, sprite, texture, velocity),
+                    base_color,
+                    offscreen,
+                    rock,
+                    (oscillation, behaviors),
+                    polygon,
+                    (enemy, telegraph),
+                    (stalactite, hazard),
+                    (bullet, power_up, fuel_can),
+                )| CourseEntitySnapshot {
+                    transform: *transform,
+                    sprite: sprite.clone(),
+                    base_color: base_color.copied(),
+                    texture: texture.clone(),
+                    velocity: velocity.clone(),
+                    offscreen: offscreen.cloned(),
+                    rock: rock.cloned(),
+                    oscillation: oscillation.cloned(),
+                    behaviors: behaviors.cloned(),
+                    polygon: polygon.cloned(),
+                    enemy: enemy.cloned(),
+                    telegraph: telegraph.cloned(),
+                    stalactite: stalactite.cloned(),
+                    hazard: hazard.is_some(),
+                    bullet: bullet.is_some(),
+                    power_up: power_up.copied(),
+                    fuel_can: fuel_can.is_some(),
+                },
+            )
+            .collect();
+
+        let pendulums = world
+            .query::<(&Transform, &Pendulum)>()
+            .iter(world)
+            .map(|(transform, pendulum)| (*transform, pendulum.clone()))
+            .collect();
+
+        let blades = world
+            .query::<(&Transform, &Blade)>()
+            .iter(world)
+            .map(|(transform, blade)| (*transform, blade.clone()))
+            .collect();
+
+        let lasers = world
+            .query::<(&Transform, &LaserGate, Option<&Telegraph>)>()
+            .iter(world)
+            .map(|(transform, laser, telegraph)| (*transform, laser.clone(), telegraph.cloned()))
+            .collect();
+
+        Self {
+            planes,
+            course,
+            pendulums,
+            blades,
+            lasers,
+            score: world.resource::<Score>().0,
+            lives: world.resource::<Lives>().clone(),
+            continues: world.resource::<Continues>().clone(),
+            game_speed: world.resource::<GameSpeed>().0,
+            rock_timer: world.resource::<RockTimer>().0.clone(),
+            obstacle_queue: world.resource::<ObstacleQueue>().0.clone(),
+            enemy_timer: world.resource::<EnemyTimer>().0.clone(),
+            stalactite_timer: world.resource::<StalactiteTimer>().0.clone(),
+            pendulum_timer: world.resource::<PendulumTimer>().0.clone(),
+            blade_timer: world.resource::<BladeTimer>().0.clone(),
+            laser_timer: world.resource::<LaserTimer>().0.clone(),
+            course_rng: world.resource::<GameRng>().course.clone(),
+            fuel: world.resource::<Fuel>().0,
+            fuel_can_spawner: world.resource::<FuelCanSpawner>().clone(),
+            ammo: world.resource::<Ammo>().0,
+            power_up_timer: world.resource::<PowerUpTimer>().0.clone(),
+            speed_zone: world.resource::<SpeedZone>().clone(),
+            speedrun: world.resource::<SpeedrunTimer>().clone(),
+            combo: world.resource::<Combo>().clone(),
+            weather: world.resource::<WeatherEffects>().clone(),
+            recorded_steps: world
+                .resource::<TasTools>()
+                .recording
+                .as_ref()
+                .map(|recording| recording.steps.len()),
+        }
+    }
+
+    pub fn restore(&self, world: &mut World) {
+        let mut restored = vec![];
+        for (entity, pilot, mut transform, mut player, mut interpolated, mut damage) in world
+            .query::<(
+                Entity,
+                &Pilot,
+                &mut Transform,
+                &mut Player,
+                &mut Interpolated,
+                &mut Damage,
+            )>()
+            .iter_mut(world)
+        {
+            if let Some(plane) = self.planes.iter().find(|plane| plane.pilot == *pilot) {
+                // A jump, not something to smooth out
+                interpolated.shift(plane.transform.translation - transform.translation);
+                *transform = plane.transform;
+                player.velocity = plane.velocity;
+                player.outside = plane.outside;
+                *damage = plane.damage.clone();
+                restored.push((entity, plane));
+            }
+        }
+
+        // Invincibility and flipped gravity come and go, so they're inserted or removed rather than changed
+        for (entity, plane) in restored {
+            let mut entity = world.entity_mut(entity);
+            match &plane.invincible {
+                Some(invincible) => {
+                    entity.insert(invincible.clone());
+                }
+                None => {
+                    entity.remove::<Invincible>();
+                }
+            }
+            match &plane.gravity_flip {
+                Some(flip) => {
+                    entity.insert(flip.clone());
+                }
+                None => {
+                    entity.remove::<GravityFlip>();
+                }
+            }
+        }
+
+        let current: Vec<Entity> = world
+            .query_filtered::<Entity, CourseFilter>()
+            .iter(world)
+            .collect();
+        for entity in current {
+            despawn_with_children_recursive(world, entity);
+        }
+
+        for snapshot in self.course.iter() {
+            let mut entity = world.spawn();
+            entity
+                .insert_bundle(SpriteBundle {
+                    transform: snapshot.transform,
+                    sprite: snapshot.sprite.clone(),
+                    texture: snapshot.texture.clone(),
+                    ..default()
+                })
+                .insert(snapshot.velocity.clone())
+                .insert(Interpolated::default());
+
+            if let Some(base_color) = snapshot.base_color {
+                entity.insert(base_color);
+            }
+            if let Some(offscreen) = &snapshot.offscreen {
+                entity.insert(offscreen.clone());
+            }
+            if let Some(rock) = &snapshot.rock {
+                entity.insert(rock.clone());
+            }
+            if let Some(oscillation) = &snapshot.oscillation {
+                entity.insert(oscillation.clone());
+            }
+            if let Some(behaviors) = &snapshot.behaviors {
+                entity.insert(behaviors.clone());
+            }
+            if let Some(polygon) = &snapshot.polygon {
+                entity.insert(polygon.clone());
+            }
+            if let Some(enemy) = &snapshot.enemy {
+                entity.insert(enemy.clone());
+            }
+            if let Some(telegraph) = &snapshot.telegraph {
+                entity.insert(telegraph.clone());
+            }
+            if let Some(stalactite) = &snapshot.stalactite {
+                entity.insert(stalactite.clone());
+            }
+            if snapshot.hazard {
+                entity.insert(Hazard);
+            }
+            if snapshot.bullet {
+                entity.insert(Bullet).insert(RemoveAfterState);
+            }
+            if let Some(power_up) = snapshot.power_up {
+                entity.insert(power_up).insert(RemoveAfterState);
+            }
+            if snapshot.fuel_can {
+                entity.insert(FuelCan).insert(RemoveAfterState);
+            }
+        }
+
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, world);
+        for (transform, pendulum) in self.pendulums.iter() {
+            // Exclusive, so this can't be chained into report_error
+            if let Err(err) = respawn_pendulum(&mut commands, transform, pendulum) {
+                error!("{}", err);
+            }
+        }
+        for (transform, blade) in self.blades.iter() {
+            if let Err(err) = respawn_blade(&mut commands, transform, blade) {
+                error!("{}", err);
+            }
+        }
+        for (transform, laser, telegraph) in self.lasers.iter() {
+            let mut entity = spawn_laser_gate(&mut commands, *transform, laser.clone());
+            if let Some(telegraph) = telegraph {
+                entity.insert(telegraph.clone());
+            }
+        }
+        queue.apply(world);
+
+        world.resource_mut::<Score>().0 = self.score;
+        *world.resource_mut::<Lives>() = self.lives.clone();
+        *world.resource_mut::<Continues>() = self.continues.clone();
+        world.resource_mut::<GameSpeed>().0 = self.game_speed;
+        world.resource_mut::<RockTimer>().0 = self.rock_timer.clone();
+        world.resource_mut::<ObstacleQueue>().0 = self.obstacle_queue.clone();
+        world.resource_mut::<EnemyTimer>().0 = self.enemy_timer.clone();
+        world.resource_mut::<StalactiteTimer>().0 = self.stalactite_timer.clone();
+        world.resource_mut::<PendulumTimer>().0 = self.pendulum_timer.clone();
+        world.resource_mut::<BladeTimer>().0 = self.blade_timer.clone();
+        world.resource_mut::<LaserTimer>().0 = self.laser_timer.clone();
+        world.resource_mut::<GameRng>().course = self.course_rng.clone();
+        world.resource_mut::<Fuel>().0 = self.fuel;
+        *world.resource_mut::<FuelCanSpawner>() = self.fuel_can_spawner.clone();
+        world.resource_mut::<Ammo>().0 = self.ammo;
+        world.resource_mut::<PowerUpTimer>().0 = self.power_up_timer.clone();
+        *world.resource_mut::<SpeedZone>() = self.speed_zone.clone();
+        *world.resource_mut::<SpeedrunTimer>() = self.speedrun.clone();
+        *world.resource_mut::<Combo>() = self.combo.clone();
+        *world.resource_mut::<WeatherEffects>() = self.weather.clone();
+
+        // Rerecording: inputs after the snapshot are replaced by whatever comes next
+        if let (Some(steps), Some(recording)) = (
+            self.recorded_steps,
+            world.resource_mut::<TasTools>().recording.as_mut(),
+        ) {
+            recording.steps.truncate(steps);
+        }
+    }
+}
+
+// Inputs of every physics step, written out in TAS format when the recording stops
+pub struct TasRecording {
+    seed: Option<u64>,
+    mode: RunMode,
+    steps: Vec<String>,
+}
+
+pub struct TasTools {
+    slot: usize,
+    slots: Vec<Option<RunSnapshot>>,
+    recording: Option<TasRecording>,
+}
+
+impl Default for TasTools {
+    fn default() -> Self {
+        Self {
+            slot: 0,
+            slots: vec![None; SLOT_KEYS.len()],
+            recording: None,
+        }
+    }
+}
+
+// Only in builds with the debug-tools feature
+pub struct DebugToolsPlugin;
+
+impl Plugin for DebugToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TasTools>()
+            .add_system(savestate_system.exclusive_system())
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(frame_advance_system)
+                    .with_system(tas_recording_toggle_system),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::GameOver).with_system(finish_tas_recording),
+            )
+            .add_system_to_stage(
+                PhysicsStage,
+                tas_record_system.before(player_system).before(fire_system),
+            );
+    }
+}
+
+// P freezes the simulation, . advances it by a single physics step while frozen
+pub fn frame_advance_system(
+    keys: Res<Input<KeyCode>>,
+    mut clock: ResMut<PhysicsClock>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if keys.just_pressed(KeyCode::P) {
+        let frozen = !clock.is_frozen();
+        clock.set_frozen(frozen);
+
+        let message = if frozen { "Frozen" } else { "Running" };
+        toasts.send(ShowToast(message.to_string()));
+    }
+
+    if keys.just_pressed(KeyCode::Period) && clock.is_frozen() {
+        clock.request_step();
+    }
+}
+
+// 1 to 4 pick a slot, F5 saves a snapshot to it and F9 loads it again.
+// Exclusive, since a snapshot covers most of the world.
+pub fn savestate_system(world: &mut World) {
+    if world.resource::<State<GameState>>().current() != &GameState::Playing {
+        return;
+    }
+
+    let keys = world.resource::<Input<KeyCode>>();
+    let selected = SLOT_KEYS.iter().position(|key| keys.just_pressed(*key));
+    let save = keys.just_pressed(KeyCode::F5);
+    let load = keys.just_pressed(KeyCode::F9);
+
+    if let Some(slot) = selected {
+        world.resource_mut::<TasTools>().slot = slot;
+        toast(world, format!("Slot {}", slot + 1));
+    }
+
+    let slot = world.resource::<TasTools>().slot;
+
+    if save {
+        let snapshot = RunSnapshot::capture(world);
+        world.resource_mut::<TasTools>().slots[slot] = Some(snapshot);
+        toast(world, format!("Saved slot {}", slot + 1));
+    } else if load {
+        match world.resource::<TasTools>().slots[slot].clone() {
+            Some(snapshot) => {
+                snapshot.restore(world);
+                toast(world, format!("Loaded slot {}", slot + 1));
+            }
+            None => toast(world, format!("Slot {} is empty", slot + 1)),
+        }
+    }
+}
+
+// F6 starts and stops recording inputs
+pub fn tas_recording_toggle_system(
+    keys: Res<Input<KeyCode>>,
+    mut tools: ResMut<TasTools>,
+    rng: Res<GameRng>,
+    mode: Res<RunMode>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if !keys.just_pressed(KeyCode::F6) {
+        return;
+    }
+
+    match tools.recording.take() {
+        Some(recording) => toasts.send(ShowToast(write_recording(&recording))),
+        None => {
+            // Without a fixed seed the course can't be flown again, but the inputs are still worth having
+            let message = if rng.seed.is_some() {
+                "Recording"
+            } else {
+                "Recording without a seed"
+            };
+
+            tools.recording = Some(TasRecording {
+                seed: rng.seed,
+                mode: *mode,
+                steps: vec![],
+            });
+            toasts.send(ShowToast(message.to_string()));
+        }
+    }
+}
+
+// Before player_system and fire_system, which use up the queued flaps and shots
+pub fn tas_record_system(mut tools: ResMut<TasTools>, query: Query<(&Pilot, &FlapInput)>) {
+    let recording = match tools.recording.as_mut() {
+        Some(recording) => recording,
+        None => return,
+    };
+
+    let mut inputs: Vec<(&Pilot, &FlapInput)> = query.iter().collect();
+    inputs.sort_by_key(|(pilot, _)| **pilot as u8);
+
+    let mut line = String::from("|");
+    for (_, input) in inputs {
+        line.push(if input.pressed { 'H' } else { '.' });
+        line.push(if input.just_pressed { 'F' } else { '.' });
+        line.push(if input.fire { 'X' } else { '.' });
+        line.push('|');
+    }
+
+    recording.steps.push(line);
+}
+
+// A crash ends the recording, the run can't go on from there
+pub fn finish_tas_recording(mut tools: ResMut<TasTools>, mut toasts: EventWriter<ShowToast>) {
+    if let Some(recording) = tools.recording.take() {
+        toasts.send(ShowToast(write_recording(&recording)));
+    }
+}
+
+// Returns the message for the player
+fn write_recording(recording: &TasRecording) -> String {
+    let seed = recording
+        .seed
+        .map_or("none".to_string(), |seed| seed.to_string());
+
+    // One line per physics step, with a column for each plane in pilot order.
+    // H: flap held, F: flap pressed in this step, X: fire pressed in this step.
+    let mut contents = format!(
+        "// bevy-plane TAS, one line per physics step\nseed {}\nmode {:?}\n",
+        seed, recording.mode
+    );
+    for step in recording.steps.iter() {
+        contents.push_str(step);
+        contents.push('\n');
+    }
+
+    match write_file(TAS_PATH, &contents) {
+        Ok(()) => format!("Saved {} steps", recording.steps.len()),
+        Err(err) => {
+            error!("Could not write {}: {}", TAS_PATH, err);
+            "Saving the recording failed".to_string()
+        }
+    }
+}
+
+fn toast(world: &mut World, message: String) {
+    world
+        .resource_mut::<Events<ShowToast>>()
+        .send(ShowToast(message));
+}
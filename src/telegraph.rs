@@ -0,0 +1,225 @@
+use bevy::prelude::*;
+
+use crate::{
+    CollisionPolygon, Layer, RemoveAfterState, SaveData, SoundCue, GROUND_HEIGHT, HEIGHT,
+    PHYSICS_STEP, WIDTH,
+};
+
+// Distance between an edge marker and the edge of the screen
+const MARKER_MARGIN: f32 = 20.0;
+const MARKER_SIZE: Vec2 = Vec2::new(10.0, 30.0);
+const SHADOW_SIZE: Vec2 = Vec2::new(50.0, 10.0);
+
+const MARKER_COLOR: Color = Color::rgba(1.0, 0.2, 0.1, 0.8);
+// With high contrast on, markers are yellow with black stripes across them so they don't rely on red
+const CONTRAST_MARKER_COLOR: Color = Color::rgb(1.0, 0.85, 0.0);
+const STRIPE_COLOR: Color = Color::BLACK;
+const STRIPES: usize = 3;
+
+// Times per second a marker blinks, it speeds up as the hazard gets closer to going off
+const BLINK_RATE: f32 = 4.0;
+const FINAL_BLINK_RATE: f32 = 12.0;
+
+#[derive(Clone, Copy)]
+pub enum TelegraphStyle {
+    // A marker on the edge of the screen closest to the hazard, for things flying in
+    Edge,
+    // A shadow on the ground under the hazard, for things falling down
+    Shadow,
+}
+
+impl TelegraphStyle {
+    fn marker_size(self) -> Vec2 {
+        match self {
+            TelegraphStyle::Edge => MARKER_SIZE,
+            TelegraphStyle::Shadow => SHADOW_SIZE,
+        }
+    }
+}
+
+// Every hazard that starts off-screen or goes off after a delay gets this first. Until the warning has
+// run out it's held in place, so a plane only ever meets it after it's been shown to the player.
+#[derive(Component, Clone)]
+pub struct Telegraph {
+    remaining: f32,
+    warning: f32,
+    style: TelegraphStyle,
+    hold: bool,
+    // Given to the hazard once the warning has run out, see arming
+    collider: Option<CollisionPolygon>,
+}
+
+impl Telegraph {
+    pub fn new(warning: f32, style: TelegraphStyle) -> Self {
+        Self {
+            remaining: warning,
+            warning,
+            style,
+            hold: true,
+            collider: None,
+        }
+    }
+
+    // The hazard has nothing to hit until the warning is over, it only gets its collider then
+    pub fn arming(mut self, collider: CollisionPolygon) -> Self {
+        self.collider = Some(collider);
+        self
+    }
+
+    // Keeps scrolling along with the course, for hazards that are already on screen and only go off later
+    pub fn scrolling(mut self) -> Self {
+        self.hold = false;
+        self
+    }
+
+    pub fn holds(&self) -> bool {
+        self.hold
+    }
+}
+
+#[derive(Component)]
+pub struct TelegraphMarker {
+    hazard: Entity,
+}
+
+// Children of a marker, which blink along with it
+#[derive(Component)]
+pub struct TelegraphStripe;
+
+pub fn telegraph_system(
+    mut commands: Commands,
+    mut hazards: Query<(Entity, &mut Telegraph)>,
+    new_hazards: Query<Entity, Added<Telegraph>>,
+    save: Res<SaveData>,
+    mut cues: EventWriter<SoundCue>,
+) {
+    let high_contrast = save.settings.high_contrast;
+    let color = if high_contrast {
+        CONTRAST_MARKER_COLOR
+    } else {
+        MARKER_COLOR
+    };
+
+    for hazard in new_hazards.iter() {
+        let style = match hazards.get(hazard) {
+            Ok((_, telegraph)) => telegraph.style,
+            Err(_) => continue,
+        };
+
+        let mut marker = commands.spawn_bundle(SpriteBundle {
+            sprite: Sprite { color, ..default() },
+            // Until telegraph_marker_system has moved it into place
+            visibility: Visibility { is_visible: false },
+            ..default()
+        });
+        marker
+            .insert(TelegraphMarker { hazard })
+            .insert(RemoveAfterState);
+
+        if high_contrast {
+            marker.with_children(|parent| {
+                for (position, size) in stripes(style.marker_size()) {
+                    parent
+                        .spawn_bundle(SpriteBundle {
+                            sprite: Sprite {
+                                color: STRIPE_COLOR,
+                                custom_size: Some(size),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(position.extend(0.1)),
+                            visibility: Visibility { is_visible: false },
+                            ..default()
+                        })
+                        .insert(TelegraphStripe);
+                }
+            });
+        }
+
+        cues.send(SoundCue("warning"));
+    }
+
+    for (entity, mut telegraph) in hazards.iter_mut() {
+        telegraph.remaining -= PHYSICS_STEP;
+        if telegraph.remaining > 0.0 {
+            continue;
+        }
+
+        let mut hazard = commands.entity(entity);
+        hazard.remove::<Telegraph>();
+        if let Some(collider) = telegraph.collider.take() {
+            hazard.insert(collider);
+        }
+    }
+}
+
+// Markers follow their hazard and go away once it's active or gone
+pub fn telegraph_marker_system(
+    mut commands: Commands,
+    hazards: Query<(&Telegraph, &Transform)>,
+    mut markers: Query<
+        (
+            Entity,
+            &TelegraphMarker,
+            &mut Transform,
+            &mut Sprite,
+            &mut Visibility,
+            Option<&Children>,
+        ),
+        Without<Telegraph>,
+    >,
+    mut stripe_query: Query<&mut Visibility, (With<TelegraphStripe>, Without<TelegraphMarker>)>,
+) {
+    for (entity, marker, mut transform, mut sprite, mut visibility, children) in markers.iter_mut()
+    {
+        let (telegraph, hazard_transform) = match hazards.get(marker.hazard) {
+            Ok(hazard) => hazard,
+            Err(_) => {
+                commands.entity(entity).despawn_recursive();
+                continue;
+            }
+        };
+
+        let hazard = hazard_transform.translation;
+        let position = match telegraph.style {
+            TelegraphStyle::Edge => {
+                let max = Vec2::new(WIDTH / 2.0, HEIGHT / 2.0) - MARKER_MARGIN;
+                hazard.truncate().clamp(-max, max)
+            }
+            TelegraphStyle::Shadow => Vec2::new(hazard.x, -HEIGHT / 2.0 + GROUND_HEIGHT),
+        };
+
+        transform.translation = position.extend(Layer::Markers.z());
+        sprite.custom_size = Some(telegraph.style.marker_size());
+
+        let progress = 1.0 - telegraph.remaining / telegraph.warning;
+        let rate = BLINK_RATE + (FINAL_BLINK_RATE - BLINK_RATE) * progress;
+        let elapsed = telegraph.warning - telegraph.remaining;
+        visibility.is_visible = (elapsed * rate) as u32 % 2 == 0;
+
+        if let Some(children) = children {
+            for child in children.iter() {
+                if let Ok(mut stripe) = stripe_query.get_mut(*child) {
+                    stripe.is_visible = visibility.is_visible;
+                }
+            }
+        }
+    }
+}
+
+// Bands across the long side of a marker, with a gap as wide as a band between two of them and at both ends
+fn stripes(size: Vec2) -> Vec<(Vec2, Vec2)> {
+    let vertical = size.y > size.x;
+    let length = if vertical { size.y } else { size.x };
+    let band = length / (STRIPES * 2 + 1) as f32;
+
+    (0..STRIPES)
+        .map(|i| {
+            let offset = -length / 2.0 + band * (i * 2 + 1) as f32 + band / 2.0;
+            if vertical {
+                (Vec2::new(0.0, offset), Vec2::new(size.x, band))
+            } else {
+                (Vec2::new(offset, 0.0), Vec2::new(band, size.y))
+            }
+        })
+        .collect()
+}
@@ -1,24 +1,189 @@
-use std::f32::consts::PI;
+use std::collections::BTreeMap;
+use std::f32::consts::{PI, TAU};
 
-use bevy::ecs::system::EntityCommands;
+use bevy::asset::{HandleId, LoadState};
 use bevy::prelude::*;
 use bevy::sprite::Anchor;
-use bevy::utils::HashMap;
-use rand::{thread_rng, Rng};
+use bevy::utils::{HashMap, HashSet};
+use serde::Deserialize;
 
-pub struct BitmapFont(HashMap<char, Handle<Image>>);
+use crate::{AssetPack, GameAssets, HudVisibility, Layer, Lifetime, SaveData, SimulationTime};
 
+const GLYPHS: &str = include_str!("../assets/glyphs.ron");
+
+// From one line to the next in multi-line text, a little more than the tallest glyph
+pub const TEXT_LINE_HEIGHT: f32 = 84.0;
+
+const SPACE_WIDTH: f32 = 16.0;
+const DEFAULT_LETTER_SPACING: f32 = 1.0;
+
+// Compact numbers stay written out below this, they still fit
+const COMPACT_FROM: u64 = 100_000;
+const COMPACT_SUFFIXES: &[&str] = &["K", "M", "B", "T", "Q", "Qi"];
+
+// Upwards, in pixels per second
+const FLOATING_TEXT_SPEED: f32 = 40.0;
+const FLOATING_TEXT_TIME: f32 = 0.8;
+// Scale relative to the start that floating text shrinks to by the time it disappears
+const FLOATING_TEXT_END_SCALE: f32 = 0.5;
+
+const MAX_GLYPH_TILT: f32 = PI / 24.0;
+
+// Cycles per second
+const BOUNCE_SPEED: f32 = 1.2;
+const PULSE_SPEED: f32 = 1.5;
+// Radians each letter's bounce is behind the one before it, so a wave runs through the text
+const BOUNCE_LETTER_DELAY: f32 = 0.45;
+
+// See assets/glyphs.ron
+#[derive(Deserialize)]
+struct GlyphConfig {
+    #[serde(default)]
+    glyphs: BTreeMap<char, String>,
+    #[serde(default)]
+    fallback: Option<char>,
+}
+
+pub struct BitmapFont {
+    glyphs: HashMap<char, Handle<Image>>,
+    // Drawn for characters without a glyph, which are left out when there's none
+    fallback: Option<Handle<Image>>,
+}
+
+impl BitmapFont {
+    pub fn textures(&self) -> impl Iterator<Item = &Handle<Image>> {
+        self.glyphs.values().chain(self.fallback.iter())
+    }
+
+    // For glyphs that don't come from glyphs.ron, like ones drawn at runtime. Added before Loading is over, the
+    // game waits for them too.
+    pub fn insert_glyph(&mut self, c: char, texture: Handle<Image>) {
+        self.glyphs.insert(c, texture);
+    }
+
+    pub fn set_fallback(&mut self, texture: Option<Handle<Image>>) {
+        self.fallback = texture;
+    }
+
+    // Only its own glyphs, not the fallback
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.glyphs.contains_key(&c)
+    }
+
+    fn glyph(&self, c: char) -> Option<&Handle<Image>> {
+        self.glyphs.get(&c).or(self.fallback.as_ref())
+    }
+}
+
+// Widths of the bitmap font's glyphs, filled in as their images load, see font_metrics_system
+#[derive(Default)]
+pub struct FontMetrics {
+    widths: HashMap<HandleId, f32>,
+}
+
+impl FontMetrics {
+    fn width(&self, handle: &Handle<Image>) -> Option<f32> {
+        self.widths.get(&handle.id).copied()
+    }
+
+    // Whether all of a text's glyphs have been measured, characters without a glyph don't need to be
+    fn knows(&self, font: &BitmapFont, text: &str) -> bool {
+        text.chars()
+            .filter_map(|c| font.glyph(c))
+            .all(|handle| self.width(handle).is_some())
+    }
+
+    // How wide a single line of the text is drawn at its scale, once all of its glyphs have been measured
+    pub fn text_width(&self, font: &BitmapFont, text: &BitmapText) -> Option<f32> {
+        if !self.knows(font, &text.text) {
+            return None;
+        }
+
+        Some(line_width(font, &text.text, text.letter_spacing, self) * text.scale)
+    }
+}
+
+// Measures glyphs once their image is there, and again when it changes. Glyphs that failed to load take up no
+// room, like characters without a glyph.
+pub fn font_metrics_system(
+    mut metrics: ResMut<FontMetrics>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    assets: Res<GameAssets>,
+    images: Res<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+) {
+    for event in image_events.iter() {
+        if let AssetEvent::Modified { handle } | AssetEvent::Removed { handle } = event {
+            metrics.widths.remove(&handle.id);
+        }
+    }
+
+    for handle in assets.bitmap_font.textures() {
+        if metrics.widths.contains_key(&handle.id) {
+            continue;
+        }
+
+        let width = match images.get(handle) {
+            Some(image) => image.size().x,
+            None if asset_server.get_load_state(handle) == LoadState::Failed => 0.0,
+            None => continue,
+        };
+        metrics.widths.insert(handle.id, width);
+    }
+}
+
+// How the systems that write numbers into a BitmapText format them, see format_number
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum NumberFormat {
+    #[default]
+    Plain,
+    // 12,345
+    Grouped,
+    // Grouped, and from COMPACT_FROM on 123K, 1.2M and so on
+    Compact,
+}
+
+// Where each line is horizontally, relative to the text's position. Lines always go down from it.
 #[derive(Default)]
 pub enum TextAnchor {
     #[default]
     Left,
+    Center,
     Right,
 }
 
-#[derive(Component, Default)]
+#[derive(Component)]
 pub struct BitmapText {
+    // Each \n starts a new line below the last
     pub text: String,
     pub anchor: TextAnchor,
+    // TEXT_LINE_HEIGHT when not set
+    pub line_height: Option<f32>,
+    // Lines longer than this are wrapped between words. In the text's own units, before its scale.
+    pub max_width: Option<f32>,
+    // Applied to the glyphs, on top of whatever scale the text's transform has
+    pub scale: f32,
+    // Tints every glyph. Floating text still fades it out.
+    pub color: Color,
+    // Room between two glyphs, can be negative for tight text
+    pub letter_spacing: f32,
+    // For the numbers in the text, up to whatever writes it to use
+    pub number_format: NumberFormat,
+}
+
+impl Default for BitmapText {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            anchor: TextAnchor::default(),
+            line_height: None,
+            max_width: None,
+            scale: 1.0,
+            color: Color::WHITE,
+            letter_spacing: DEFAULT_LETTER_SPACING,
+            number_format: NumberFormat::default(),
+        }
+    }
 }
 
 #[derive(Bundle, Default)]
@@ -33,11 +198,15 @@ pub struct BitmapTextBundle {
 impl BitmapTextBundle {
     pub fn new(x: f32, y: f32) -> Self {
         Self {
-            transform: Transform::from_xyz(x, y, 10.0),
+            transform: Transform::from_xyz(x, y, Layer::Overlay.z()),
             ..default()
         }
     }
 
+    pub fn at(position: Vec2) -> Self {
+        Self::new(position.x, position.y)
+    }
+
     pub fn with_anchor(mut self, anchor: TextAnchor) -> Self {
         self.text.anchor = anchor;
         self
@@ -47,97 +216,491 @@ impl BitmapTextBundle {
         self.text.text = text;
         self
     }
+
+    pub fn with_line_height(mut self, line_height: f32) -> Self {
+        self.text.line_height = Some(line_height);
+        self
+    }
+
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.text.max_width = Some(max_width);
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.text.scale = scale;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.text.color = color;
+        self
+    }
+
+    pub fn with_letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.text.letter_spacing = letter_spacing;
+        self
+    }
+
+    pub fn with_number_format(mut self, number_format: NumberFormat) -> Self {
+        self.text.number_format = number_format;
+        self
+    }
 }
 
-pub fn create_bitmap_font(asset_server: Res<AssetServer>) -> BitmapFont {
+// Groups are separated by commas and compact numbers get a decimal point, as long as the font has glyphs for them.
+// Otherwise groups are separated by spaces and compact numbers are rounded down to a whole number.
+pub fn format_number(value: u64, format: NumberFormat, font: &BitmapFont) -> String {
+    if format == NumberFormat::Compact && value >= COMPACT_FROM {
+        let mut unit = 1000;
+        let mut suffix = 0;
+        while value / unit >= 1000 && suffix + 1 < COMPACT_SUFFIXES.len() {
+            unit *= 1000;
+            suffix += 1;
+        }
+
+        // Rounded down, so 999,999 doesn't come out as 1000K
+        let whole = value / unit;
+        let tenths = value % unit * 10 / unit;
+        return if whole < 100 && font.has_glyph('.') {
+            format!("{}.{}{}", whole, tenths, COMPACT_SUFFIXES[suffix])
+        } else {
+            format!("{}{}", whole, COMPACT_SUFFIXES[suffix])
+        };
+    }
+
+    let digits = value.to_string();
+    if format == NumberFormat::Plain {
+        return digits;
+    }
+
+    let separator = if font.has_glyph(',') { ',' } else { ' ' };
+    let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+
+    grouped
+}
+
+pub fn create_bitmap_font(asset_server: &AssetServer, pack: &AssetPack) -> BitmapFont {
     let mut map = HashMap::new();
 
     for c in '0'..='9' {
         let path = format!("Numbers/number{}.png", c);
-        map.insert(c, asset_server.load(&path));
+        map.insert(c, pack.load(asset_server, &path));
     }
 
     for c in 'A'..='Z' {
         let path = format!("Letters/letter{}.png", c);
-        let handle = asset_server.load(&path);
+        let handle = pack.load(asset_server, &path);
         map.insert(c, handle.clone());
         map.insert(c.to_ascii_lowercase(), handle);
     }
 
-    BitmapFont(map)
+    let config: GlyphConfig = ron::from_str(GLYPHS).expect("assets/glyphs.ron is invalid");
+    for (c, path) in config.glyphs {
+        map.insert(c, pack.load(asset_server, &path));
+    }
+
+    let fallback = config.fallback.and_then(|c| {
+        let glyph = map.get(&c).cloned();
+        if glyph.is_none() {
+            warn!("The fallback glyph {:?} is not in the bitmap font", c);
+        }
+        glyph
+    });
+
+    BitmapFont {
+        glyphs: map,
+        fallback,
+    }
+}
+
+// Text that drifts up from where something happened, fading and shrinking until it disappears
+#[derive(Component)]
+pub struct FloatingText {
+    scale: f32,
 }
 
+// Spawns a FloatingText starting at a position. A "+" is only drawn when glyphs.ron has a glyph for it.
+pub fn spawn_floating_text(commands: &mut Commands, position: Vec3, text: String, scale: f32) {
+    let mut bundle = BitmapTextBundle::new(position.x, position.y).with_text(text);
+    bundle.transform.scale = Vec3::new(scale, scale, 1.0);
+
+    commands
+        .spawn_bundle(bundle)
+        .insert(FloatingText { scale })
+        .insert(Lifetime::from_seconds(FLOATING_TEXT_TIME));
+}
+
+// Glyph sprites are children of their BitmapText. They're reused when the text changes and hidden when
+// there are too many, so once a text has had its longest string nothing gets spawned or despawned for it.
+#[derive(Component)]
+pub struct Glyph {
+    // The one it shows, so a slot that keeps its character isn't retextured
+    character: char,
+    // Where the layout put it, TextEffect moves it around from here
+    position: Vec3,
+    // False for the ones hidden because the text got shorter
+    in_use: bool,
+}
+
+struct GlyphLayout {
+    character: char,
+    texture: Handle<Image>,
+    x: f32,
+    y: f32,
+}
+
+// Only what differs from the last text is touched. The glyph in each slot is compared to the new character
+// for that slot, so adding a point to a score only retextures the digits that changed and leaves the rest
+// where they were.
 pub fn bitmap_font_system(
     mut commands: Commands,
-    font: Res<BitmapFont>,
-    texts: Query<(Entity, &BitmapText), Changed<BitmapText>>,
-    images: Res<Assets<Image>>,
+    assets: Res<GameAssets>,
+    texts: Query<(
+        Entity,
+        &BitmapText,
+        ChangeTrackers<BitmapText>,
+        Option<&TextEffect>,
+        Option<&Children>,
+    )>,
+    mut glyphs: Query<(
+        &mut Glyph,
+        &mut Handle<Image>,
+        &mut Transform,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+    metrics: Res<FontMetrics>,
+    save: Res<SaveData>,
+    mut reduced_motion: Local<bool>,
+    // Texts that changed before all of their glyphs were measured
+    mut waiting: Local<HashSet<Entity>>,
 ) {
-    for (entity, text) in texts.iter() {
-        let mut entity = commands.entity(entity);
+    // Every text is laid out again when the setting changes, so the tilt goes or comes back right away
+    let motion_changed = *reduced_motion != save.settings.reduced_motion;
+    *reduced_motion = save.settings.reduced_motion;
+    waiting.retain(|entity| texts.get(*entity).is_ok());
 
-        entity.despawn_descendants();
+    for (entity, text, tracker, effect, children) in texts.iter() {
+        if !tracker.is_changed() && !motion_changed && !waiting.contains(&entity) {
+            continue;
+        }
 
-        spawn_letters(&mut entity, &font, text, &images);
-    }
-}
+        // Laid out once every width is known instead of guessing, so text never shows up misaligned
+        if !metrics.knows(&assets.bitmap_font, &text.text) {
+            waiting.insert(entity);
+            continue;
+        }
+        waiting.remove(&entity);
 
-fn spawn_letters(
-    entity: &mut EntityCommands,
-    font: &Res<BitmapFont>,
-    text: &BitmapText,
-    images: &Res<Assets<Image>>,
-) {
-    // TODO: find a way to just store the result of chars() or chars().rev() into the third tuple item
-    let (sprite_anchor, direction, text_str) = match &text.anchor {
-        TextAnchor::Left => (Anchor::CenterLeft, 1.0, text.text.clone()),
-        TextAnchor::Right => (
-            Anchor::CenterRight,
-            -1.0,
-            text.text.chars().rev().collect::<String>(),
-        ),
-    };
-
-    let mut x: f32 = 0.0;
-
-    entity.with_children(|parent| {
-        let mut rng = thread_rng();
-
-        for c in text_str.chars() {
-            if c == ' ' {
-                x += 16.0;
-                continue;
-            }
+        let layout = layout_glyphs(&assets.bitmap_font, text, &metrics);
+        let layout_len = layout.len();
+
+        let pool: Vec<Entity> = children
+            .map(|children| {
+                children
+                    .iter()
+                    .filter(|child| glyphs.get(**child).is_ok())
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Laid out unscaled, so wrapping and line heights don't depend on the scale
+        let scale = Vec3::new(text.scale, text.scale, 1.0);
+        for (i, glyph) in layout.into_iter().enumerate() {
+            let translation = Vec3::new(glyph.x * text.scale, glyph.y * text.scale, 0.0);
+            let revealed = effect.map_or(true, |effect| effect.reveals(i));
+            let rotation = if *reduced_motion {
+                Quat::IDENTITY
+            } else {
+                Quat::from_rotation_z(glyph_tilt(entity, i))
+            };
 
-            let handle = font.0.get(&c);
-            if let Some(handle) = handle {
-                let width = if let Some(image) = images.get(&handle) {
-                    image.size().x
-                } else {
-                    // 64 is reasonably safe because it's more than the width of the widest character
-                    // Usually doesn't matter because we don't render proper pieces of text until the game over screen or the score reaches 10
-                    64.0
-                };
-
-
-                // This is a wacky game with wacky text
-                let mut text_angle = rng.gen_range(0.0..(PI / 24.0));
-                if rng.gen_bool(0.5) {
-                    text_angle = PI * 2.0 - text_angle;
+            let existing = pool.get(i).and_then(|child| glyphs.get_mut(*child).ok());
+            if let Some((mut shown, mut texture, mut transform, mut sprite, mut visibility)) =
+                existing
+            {
+                if shown.character != glyph.character {
+                    shown.character = glyph.character;
+                    *texture = glyph.texture;
+                }
+                if transform.rotation != rotation {
+                    transform.rotation = rotation;
+                }
+                if shown.position != translation || transform.scale != scale {
+                    shown.position = translation;
+                    transform.translation = translation;
+                    transform.scale = scale;
+                }
+                if sprite.color != text.color {
+                    sprite.color = text.color;
                 }
+                if visibility.is_visible != revealed {
+                    visibility.is_visible = revealed;
+                }
+                shown.in_use = true;
+                continue;
+            }
 
-                parent.spawn_bundle(SpriteBundle {
-                    texture: handle.clone(),
-                    transform: Transform::from_xyz(x, 0.0, 0.0).with_rotation(Quat::from_rotation_z(text_angle)),
+            let transform = Transform::from_translation(translation)
+                .with_rotation(rotation)
+                .with_scale(scale);
+            let child = commands
+                .spawn_bundle(SpriteBundle {
+                    texture: glyph.texture,
+                    transform,
                     sprite: Sprite {
-                        anchor: sprite_anchor.clone(),
+                        color: text.color,
+                        anchor: Anchor::CenterLeft,
                         ..default()
                     },
+                    visibility: Visibility {
+                        is_visible: revealed,
+                    },
                     ..default()
-                });
+                })
+                .insert(Glyph {
+                    character: glyph.character,
+                    position: translation,
+                    in_use: true,
+                })
+                .id();
+
+            commands.entity(entity).add_child(child);
+        }
 
-                x += (width + 1.0) * direction;
+        for &child in pool.iter().skip(layout_len) {
+            if let Ok((mut unused, _, _, _, mut visibility)) = glyphs.get_mut(child) {
+                unused.in_use = false;
+                if visibility.is_visible {
+                    visibility.is_visible = false;
+                }
             }
         }
-    });
+    }
+}
+
+// Moves the glyphs of its BitmapText around every frame. Effects can be combined, e.g. a title that types
+// itself out while the letters already there bounce.
+#[derive(Component, Default)]
+pub struct TextEffect {
+    // Letters per second, they show up one at a time in reading order
+    typewriter: Option<f32>,
+    // How far the letters go up and down, in the text's own units
+    bounce: Option<f32>,
+    // How much bigger the whole text gets at the top of a pulse, 0.1 is 10 percent
+    pulse: Option<f32>,
+    elapsed: f32,
+}
+
+impl TextEffect {
+    pub fn with_typewriter(mut self, letters_per_second: f32) -> Self {
+        self.typewriter = Some(letters_per_second);
+        self
+    }
+
+    pub fn with_bounce(mut self, height: f32) -> Self {
+        self.bounce = Some(height);
+        self
+    }
+
+    pub fn with_pulse(mut self, amount: f32) -> Self {
+        self.pulse = Some(amount);
+        self
+    }
+
+    fn reveals(&self, index: usize) -> bool {
+        self.typewriter
+            .map_or(true, |speed| index < (self.elapsed * speed) as usize)
+    }
+}
+
+// Runs after bitmap_font_system, which leaves letters the typewriter hasn't got to yet hidden. Uses real time so
+// titles keep moving while the game is paused.
+pub fn text_effect_system(
+    mut texts: Query<(&mut TextEffect, &BitmapText, &Children)>,
+    mut glyphs: Query<(&Glyph, &mut Transform, &mut Visibility)>,
+    time: Res<Time>,
+    save: Res<SaveData>,
+) {
+    // The typewriter still reveals letters with reduced motion, nothing moves though
+    let moving = !save.settings.reduced_motion;
+
+    for (mut effect, text, children) in texts.iter_mut() {
+        effect.elapsed += time.delta_seconds();
+
+        let pulse = effect.pulse.filter(|_| moving).map_or(1.0, |amount| {
+            1.0 + amount * (0.5 - 0.5 * (effect.elapsed * PULSE_SPEED * TAU).cos())
+        });
+
+        let in_use = children
+            .iter()
+            .filter_map(|child| glyphs.get(*child).ok().map(|(glyph, _, _)| (*child, glyph)))
+            .filter(|(_, glyph)| glyph.in_use)
+            .map(|(child, _)| child)
+            .collect::<Vec<Entity>>();
+
+        for (i, child) in in_use.into_iter().enumerate() {
+            let (glyph, mut transform, mut visibility) = match glyphs.get_mut(child) {
+                Ok(glyph) => glyph,
+                Err(_) => continue,
+            };
+
+            let revealed = effect.reveals(i);
+            if visibility.is_visible != revealed {
+                visibility.is_visible = revealed;
+            }
+
+            let bounce = effect.bounce.filter(|_| moving).map_or(0.0, |height| {
+                let phase = effect.elapsed * BOUNCE_SPEED * TAU - i as f32 * BOUNCE_LETTER_DELAY;
+                height * text.scale * phase.sin().max(0.0)
+            });
+
+            transform.translation = glyph.position * pulse + Vec3::new(0.0, bounce, 0.0);
+            transform.scale = Vec3::new(text.scale * pulse, text.scale * pulse, 1.0);
+        }
+    }
+}
+
+// This is a wacky game with wacky text. The tilt only depends on the text and the slot, so a score doesn't
+// wobble every time it goes up and the same text always leans the same way.
+fn glyph_tilt(text: Entity, index: usize) -> f32 {
+    // splitmix64, mixed well enough that neighbouring slots don't lean alike
+    let mut hash = text.to_bits() ^ (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    hash ^= hash >> 31;
+
+    // From -1 to 1
+    let unit = (hash >> 40) as f32 / (1u64 << 23) as f32 - 1.0;
+    unit * MAX_GLYPH_TILT
+}
+
+fn layout_glyphs(font: &BitmapFont, text: &BitmapText, metrics: &FontMetrics) -> Vec<GlyphLayout> {
+    let line_height = text.line_height.unwrap_or(TEXT_LINE_HEIGHT);
+    let lines: Vec<String> = text
+        .text
+        .split('\n')
+        .flat_map(|line| match text.max_width {
+            Some(max_width) => wrap_line(font, line, max_width, text.letter_spacing, metrics),
+            None => vec![line.to_string()],
+        })
+        .collect();
+
+    // Glyphs are laid out from the left of their line, which is moved over by its width for the other anchors
+    let mut layout = vec![];
+    for (line_index, line) in lines.iter().enumerate() {
+        let width = line_width(font, line, text.letter_spacing, metrics);
+        let x = match text.anchor {
+            TextAnchor::Left => 0.0,
+            TextAnchor::Center => width / -2.0,
+            TextAnchor::Right => -width,
+        };
+        let y = line_index as f32 * -line_height;
+
+        let start = Vec2::new(x, y);
+        layout_line(font, line, start, text.letter_spacing, metrics, &mut layout);
+    }
+
+    layout
+}
+
+// Words that don't fit on their own get a line to themselves, they're never split up
+fn wrap_line(
+    font: &BitmapFont,
+    line: &str,
+    max_width: f32,
+    spacing: f32,
+    metrics: &FontMetrics,
+) -> Vec<String> {
+    let mut lines = vec![];
+    let mut current = String::new();
+
+    for word in line.split(' ') {
+        if current.is_empty() {
+            current = word.to_string();
+            continue;
+        }
+
+        let joined = format!("{} {}", current, word);
+        if line_width(font, &joined, spacing, metrics) > max_width {
+            lines.push(std::mem::replace(&mut current, word.to_string()));
+        } else {
+            current = joined;
+        }
+    }
+
+    lines.push(current);
+    lines
+}
+
+fn line_width(font: &BitmapFont, line: &str, spacing: f32, metrics: &FontMetrics) -> f32 {
+    line.chars()
+        .map(|c| advance(font, c, spacing, metrics))
+        .sum()
+}
+
+// How far along the line a character moves the next one, characters without a glyph take up no room
+fn advance(font: &BitmapFont, c: char, spacing: f32, metrics: &FontMetrics) -> f32 {
+    if c == ' ' {
+        return SPACE_WIDTH;
+    }
+
+    font.glyph(c)
+        .map_or(0.0, |handle| metrics.width(handle).unwrap_or(0.0) + spacing)
+}
+
+fn layout_line(
+    font: &BitmapFont,
+    line: &str,
+    start: Vec2,
+    spacing: f32,
+    metrics: &FontMetrics,
+    layout: &mut Vec<GlyphLayout>,
+) {
+    let mut x = start.x;
+
+    for c in line.chars() {
+        if let Some(handle) = font.glyph(c).filter(|_| c != ' ') {
+            layout.push(GlyphLayout {
+                character: c,
+                texture: handle.clone(),
+                x,
+                y: start.y,
+            });
+        }
+
+        x += advance(font, c, spacing, metrics);
+    }
+}
+
+pub fn floating_text_system(
+    mut query: Query<(&FloatingText, &Lifetime, &mut Transform, Option<&Children>)>,
+    mut glyphs: Query<&mut Sprite, With<Glyph>>,
+    hud: Res<HudVisibility>,
+    simulation_time: Res<SimulationTime>,
+) {
+    for (floating, lifetime, mut transform, children) in query.iter_mut() {
+        let left = lifetime.percent_left();
+        let scale =
+            floating.scale * (FLOATING_TEXT_END_SCALE + (1.0 - FLOATING_TEXT_END_SCALE) * left);
+
+        transform.translation.y += FLOATING_TEXT_SPEED * simulation_time.delta_seconds();
+        transform.scale = Vec3::new(scale, scale, 1.0);
+
+        // Glyphs only exist from the frame after the text is spawned
+        for &child in children.into_iter().flatten() {
+            if let Ok(mut sprite) = glyphs.get_mut(child) {
+                let alpha = if hud.shows_extras() { left } else { 0.0 };
+                sprite.color.set_a(alpha);
+            }
+        }
+    }
 }
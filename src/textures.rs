@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+
+// Above this the game still runs, but it's worth finding out which textures are being kept around
+const TEXTURE_BUDGET: usize = 128 * 1024 * 1024;
+
+const MEGABYTE: f32 = 1024.0 * 1024.0;
+
+// An estimate from the pixel data of every loaded image. What the GPU actually holds can differ, e.g. with
+// padded rows or images that were never uploaded, but it's close enough to spot textures being kept around.
+// Textures are unloaded by Bevy once nothing holds a handle to them anymore. ThemeState only holds the
+// applied theme and the one that's loading, so inactive themes go away once their rocks are off screen.
+pub struct TextureMemory {
+    pub count: usize,
+    pub bytes: usize,
+    over_budget: bool,
+    timer: Timer,
+}
+
+impl Default for TextureMemory {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            bytes: 0,
+            over_budget: false,
+            timer: Timer::from_seconds(1.0, true),
+        }
+    }
+}
+
+impl TextureMemory {
+    pub fn megabytes(&self) -> f32 {
+        self.bytes as f32 / MEGABYTE
+    }
+
+    pub fn budget_megabytes(&self) -> f32 {
+        TEXTURE_BUDGET as f32 / MEGABYTE
+    }
+}
+
+pub fn texture_memory_system(
+    mut memory: ResMut<TextureMemory>,
+    images: Res<Assets<Image>>,
+    time: Res<Time>,
+) {
+    if !memory.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    memory.count = images.len();
+    memory.bytes = images.iter().map(|(_, image)| image.data.len()).sum();
+
+    let over_budget = memory.bytes > TEXTURE_BUDGET;
+    if over_budget && !memory.over_budget {
+        warn!(
+            "{} textures use about {:.1} MB, over the budget of {:.0} MB",
+            memory.count,
+            memory.megabytes(),
+            memory.budget_megabytes()
+        );
+    }
+    memory.over_budget = over_budget;
+}
@@ -0,0 +1,246 @@
+use bevy::asset::{HandleId, LoadState};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AssetPack, BaseColor, SaveData, ShowToast, SpriteAlpha, Tween, TweenCompleted, Weather,
+};
+
+const THEME_FADE_TIME: f32 = 0.4;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Theme {
+    Grass,
+    Ice,
+    Snow,
+    Rock,
+}
+
+const THEMES: &[Theme] = &[Theme::Grass, Theme::Ice, Theme::Snow, Theme::Rock];
+
+impl Theme {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Grass => "Grass",
+            Theme::Ice => "Ice",
+            Theme::Snow => "Snow",
+            Theme::Rock => "Rock",
+        }
+    }
+
+    pub fn ground(&self) -> &'static str {
+        match self {
+            Theme::Grass => "groundGrass.png",
+            Theme::Ice => "groundIce.png",
+            Theme::Snow => "groundSnow.png",
+            Theme::Rock => "groundRock.png",
+        }
+    }
+
+    pub fn ceiling(&self) -> &'static str {
+        match self {
+            Theme::Grass => "groundDirt.png",
+            _ => self.ground(),
+        }
+    }
+
+    pub fn rock_up(&self) -> &'static str {
+        match self {
+            Theme::Grass => "rockGrass.png",
+            Theme::Ice => "rockIce.png",
+            Theme::Snow => "rockSnow.png",
+            Theme::Rock => "rock.png",
+        }
+    }
+
+    pub fn rock_down(&self) -> &'static str {
+        match self {
+            Theme::Grass | Theme::Rock => "rockDown.png",
+            Theme::Ice => "rockIceDown.png",
+            Theme::Snow => "rockSnowDown.png",
+        }
+    }
+
+    pub fn weather(&self) -> Weather {
+        match self {
+            Theme::Grass => Weather::Rain,
+            Theme::Ice | Theme::Snow => Weather::Snow,
+            Theme::Rock => Weather::Clear,
+        }
+    }
+
+    fn load(&self, asset_server: &AssetServer, pack: &AssetPack) -> ThemeTextures {
+        ThemeTextures {
+            ground: pack.load(asset_server, self.ground()),
+            ceiling: pack.load(asset_server, self.ceiling()),
+            rock_up: pack.load(asset_server, self.rock_up()),
+            rock_down: pack.load(asset_server, self.rock_down()),
+        }
+    }
+
+    fn next(&self) -> Theme {
+        let index = THEMES.iter().position(|theme| theme == self).unwrap_or(0);
+        THEMES[(index + 1) % THEMES.len()]
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::Grass
+    }
+}
+
+pub struct ThemeTextures {
+    pub ground: Handle<Image>,
+    pub ceiling: Handle<Image>,
+    pub rock_up: Handle<Image>,
+    pub rock_down: Handle<Image>,
+}
+
+impl ThemeTextures {
+    pub fn ids(&self) -> impl Iterator<Item = HandleId> {
+        [&self.ground, &self.ceiling, &self.rock_up, &self.rock_down]
+            .map(|handle| handle.id)
+            .into_iter()
+    }
+}
+
+// The theme that's on screen can lag behind the one in the settings while its textures load
+pub struct ThemeState {
+    pub applied: Theme,
+    // Holding on to these keeps the applied theme loaded, so rocks spawned later don't pop in.
+    // Spawners take their textures from here rather than loading the applied theme's paths.
+    pub textures: ThemeTextures,
+    loading: Option<(Theme, ThemeTextures)>,
+}
+
+impl FromWorld for ThemeState {
+    fn from_world(world: &mut World) -> Self {
+        let applied = world.resource::<SaveData>().settings.theme;
+
+        Self {
+            applied,
+            textures: applied.load(
+                world.resource::<AssetServer>(),
+                world.resource::<AssetPack>(),
+            ),
+            loading: None,
+        }
+    }
+}
+
+// The ground and ceiling strips, which change texture with the theme
+#[derive(Component)]
+pub struct ThemedGround;
+
+// Fades in on top of a themed strip, then replaces its texture
+#[derive(Component)]
+pub struct ThemeFade;
+
+pub fn theme_select_system(
+    keys: Res<Input<KeyCode>>,
+    mut save: ResMut<SaveData>,
+    mut toasts: EventWriter<ShowToast>,
+) {
+    if keys.just_pressed(KeyCode::T) {
+        save.settings.theme = save.settings.theme.next();
+        toasts.send(ShowToast(format!("{} theme", save.settings.theme.name())));
+    }
+}
+
+// Loading happens in the background, the swap only starts once every texture is ready so nothing pops in
+pub fn theme_swap_system(
+    mut commands: Commands,
+    mut theme: ResMut<ThemeState>,
+    mut save: ResMut<SaveData>,
+    asset_server: Res<AssetServer>,
+    pack: Res<AssetPack>,
+    grounds: Query<(Entity, &Sprite), With<ThemedGround>>,
+    fades: Query<(), With<ThemeFade>>,
+) {
+    let wanted = save.settings.theme;
+    if wanted == theme.applied {
+        if theme.loading.is_some() {
+            theme.loading = None;
+        }
+        return;
+    }
+
+    if !matches!(&theme.loading, Some((loading, _)) if *loading == wanted) {
+        theme.loading = Some((wanted, wanted.load(&asset_server, &pack)));
+        return;
+    }
+
+    // Let a running crossfade finish first
+    if !fades.is_empty() {
+        return;
+    }
+
+    let load_state = match &theme.loading {
+        Some((_, textures)) => asset_server.get_group_load_state(textures.ids()),
+        None => return,
+    };
+
+    match load_state {
+        LoadState::Loaded => {}
+        LoadState::Failed => {
+            warn!("Could not load the {} theme", wanted.name());
+            save.settings.theme = theme.applied;
+            theme.loading = None;
+            return;
+        }
+        _ => return,
+    }
+
+    let textures = match theme.loading.take() {
+        Some((_, textures)) => textures,
+        None => return,
+    };
+
+    for (entity, sprite) in grounds.iter() {
+        let texture = if sprite.flip_y {
+            &textures.ceiling
+        } else {
+            &textures.ground
+        };
+
+        let fade = commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    flip_y: sprite.flip_y,
+                    color: Color::rgba(1.0, 1.0, 1.0, 0.0),
+                    ..default()
+                },
+                texture: texture.clone(),
+                transform: Transform::from_xyz(0.0, 0.0, 0.01),
+                ..default()
+            })
+            .insert(Tween::new(SpriteAlpha, 0.0, 1.0, THEME_FADE_TIME))
+            .insert(ThemeFade)
+            .insert(BaseColor(Color::WHITE))
+            .id();
+
+        commands.entity(entity).add_child(fade);
+    }
+
+    // New rocks use the new theme straight away, rocks already on screen keep theirs
+    theme.applied = wanted;
+    theme.textures = textures;
+}
+
+pub fn theme_fade_system(
+    mut commands: Commands,
+    mut completed: EventReader<TweenCompleted>,
+    fades: Query<(&Parent, &Handle<Image>), (With<ThemeFade>, Without<ThemedGround>)>,
+    mut grounds: Query<&mut Handle<Image>, With<ThemedGround>>,
+) {
+    for event in completed.iter() {
+        if let Ok((parent, texture)) = fades.get(event.entity) {
+            if let Ok(mut ground_texture) = grounds.get_mut(parent.get()) {
+                *ground_texture = texture.clone();
+            }
+
+            commands.entity(event.entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+
+use crate::{
+    BitmapText, BitmapTextBundle, DemoMode, GameMode, GameModes, GameOverSection, GameResult,
+    GameState, HudVisibility, RecordKey, RemoveAfterState, RunMode, SaveData, Score, ScreenAnchor,
+    PHYSICS_STEP,
+};
+
+const TIME_LIMIT: f32 = 60.0;
+
+// Most gates in a minute. Everything else that scores is left out, so the score is a count of gates passed.
+pub struct TimeTrialMode;
+
+impl GameMode for TimeTrialMode {
+    fn run_mode(&self) -> RunMode {
+        RunMode::TimeTrial
+    }
+
+    fn select_key(&self) -> Option<KeyCode> {
+        Some(KeyCode::C)
+    }
+
+    fn label(&self, save: &SaveData) -> String {
+        format!(
+            "Time trial most rocks in a minute best {}",
+            self.best_score(save)
+        )
+    }
+
+    fn setup(&self, commands: &mut Commands, _world: &World) {
+        commands.insert_resource(TimeTrialClock {
+            remaining: TIME_LIMIT,
+        });
+
+        let mut bundle = BitmapTextBundle::at(ScreenAnchor::Top.offset(-20.0, -110.0))
+            .with_text(clock_text(TIME_LIMIT));
+        bundle.transform.scale = Vec3::new(0.5, 0.5, 1.0);
+
+        commands
+            .spawn_bundle(bundle)
+            .insert(TimeTrialText)
+            .insert(RemoveAfterState);
+    }
+
+    fn record_key(&self) -> Option<RecordKey> {
+        Some(RecordKey::new(RunMode::TimeTrial, vec![], 0))
+    }
+
+    fn counts_only_gates(&self) -> bool {
+        true
+    }
+}
+
+// Seconds left in the current time trial. Left over after the run, the game over screen checks it to tell a
+// time trial that ran out from one that crashed.
+#[derive(Default)]
+pub struct TimeTrialClock {
+    remaining: f32,
+}
+
+impl TimeTrialClock {
+    pub fn ran_out(&self) -> bool {
+        self.remaining <= 0.0
+    }
+}
+
+#[derive(Component)]
+pub struct TimeTrialText;
+
+// Only counts down during physics steps, so the minute is the same at any frame rate and pausing stops it.
+// Runs after scoring, so a gate passed in the very last step still counts.
+pub fn time_trial_clock_system(
+    mut clock: ResMut<TimeTrialClock>,
+    mode: Res<RunMode>,
+    demo: Res<DemoMode>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    if *mode != RunMode::TimeTrial || clock.ran_out() {
+        return Ok(());
+    }
+
+    clock.remaining -= PHYSICS_STEP;
+    if !clock.ran_out() {
+        return Ok(());
+    }
+
+    // Like a crash, overwritten because more than one physics step can run in a frame
+    let next_state = if demo.active {
+        GameState::Menu
+    } else {
+        GameState::GameOver
+    };
+    state.overwrite_set(next_state)?;
+
+    Ok(())
+}
+
+pub fn time_trial_text_system(
+    clock: Res<TimeTrialClock>,
+    hud: Res<HudVisibility>,
+    mut query: Query<&mut BitmapText, With<TimeTrialText>>,
+) {
+    let text = hud.text(clock_text(clock.remaining));
+
+    for mut clock_text in query.iter_mut() {
+        if clock_text.text != text {
+            clock_text.text = text.clone();
+        }
+    }
+}
+
+// Above the score, it's what the round was about. A time trial that crashed gets the usual screen.
+pub fn time_trial_results_section(
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    clock: Res<TimeTrialClock>,
+    score: Res<Score>,
+    save: Res<SaveData>,
+    mut sections: EventWriter<GameOverSection>,
+) {
+    if *mode != RunMode::TimeTrial || !clock.ran_out() {
+        return;
+    }
+
+    // The record may or may not have been updated yet
+    let best = modes.get(*mode).best_score(&save).max(score.0);
+
+    sections.send(GameOverSection {
+        order: 50,
+        lines: vec![
+            format!("{} rocks in {} seconds", score.0, TIME_LIMIT),
+            format!("Best {}", best),
+        ],
+    });
+}
+
+fn clock_text(remaining: f32) -> String {
+    remaining.max(0.0).ceil().to_string()
+}
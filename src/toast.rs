@@ -0,0 +1,82 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    BitmapTextBundle, Ease, HudVisibility, ScreenAnchor, Translation, Tween, TweenCompleted,
+};
+
+// How long a toast is on screen in total, including sliding in and out
+const TOAST_TIME: f32 = 2.5;
+const TOAST_SLIDE_TIME: f32 = 0.3;
+
+const TOAST_X: f32 = ScreenAnchor::Left.offset(15.0, 0.0).x;
+const TOAST_Y: f32 = ScreenAnchor::Top.offset(0.0, -110.0).y;
+const TOAST_HIDDEN_Y: f32 = ScreenAnchor::Top.offset(0.0, 40.0).y;
+
+// Send this to briefly show a message at the top of the screen
+pub struct ShowToast(pub String);
+
+// Slides in, then slides back out once it's been read. Despawned by its tween after that.
+#[derive(Component)]
+pub struct Toast {
+    leaving: bool,
+}
+
+pub fn toast_system(
+    mut commands: Commands,
+    mut events: EventReader<ShowToast>,
+    mut completed: EventReader<TweenCompleted>,
+    mut queue: Local<VecDeque<String>>,
+    mut toasts: Query<(&mut Toast, &Transform)>,
+    hud: Res<HudVisibility>,
+) {
+    // Dropped rather than held back, they'd be out of date by the time the HUD comes back
+    for event in events.iter() {
+        if hud.shows_extras() {
+            queue.push_back(event.0.clone());
+        }
+    }
+
+    // Toasts are shown one at a time so they never overlap
+    if toasts.is_empty() {
+        if let Some(text) = queue.pop_front() {
+            let mut bundle = BitmapTextBundle::new(TOAST_X, TOAST_HIDDEN_Y).with_text(text);
+            bundle.transform.scale = Vec3::new(0.6, 0.6, 1.0);
+
+            let hidden = bundle.transform.translation;
+            let shown = Vec3::new(hidden.x, TOAST_Y, hidden.z);
+
+            commands
+                .spawn_bundle(bundle)
+                .insert(
+                    Tween::new(Translation, hidden, shown, TOAST_SLIDE_TIME)
+                        .with_ease(Ease::QuadOut),
+                )
+                .insert(Toast { leaving: false });
+        }
+
+        return;
+    }
+
+    for event in completed.iter() {
+        let (mut toast, transform) = match toasts.get_mut(event.entity) {
+            Ok(toast) => toast,
+            Err(_) => continue,
+        };
+        if toast.leaving {
+            continue;
+        }
+
+        let shown = transform.translation;
+        let hidden = Vec3::new(shown.x, TOAST_HIDDEN_Y, shown.z);
+
+        toast.leaving = true;
+        commands.entity(event.entity).insert(
+            Tween::new(Translation, shown, hidden, TOAST_SLIDE_TIME)
+                .with_ease(Ease::QuadIn)
+                .with_delay(TOAST_TIME - TOAST_SLIDE_TIME * 2.0)
+                .despawn_when_done(),
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+use crate::{
+    skin_trail_color, BatchedParticle, GameSpeed, GameState, Interpolate, Lifetime, Livery,
+    Particle, Player, SaveData, SimulationTime, SpeedZone, COURSE_VELOCITY,
+};
+
+const TRAIL_TIME: f32 = 0.35;
+const TRAIL_WIDTH: f32 = 3.0;
+// Where the ribbon starts, behind the plane's tail
+const TRAIL_OFFSET: Vec2 = Vec2::new(-22.0, 0.0);
+// How much wider, longer and closer to white the ribbon gets during a speed zone
+const ZONE_WIDTH: f32 = 1.5;
+const ZONE_LIFETIME: f32 = 0.6;
+const ZONE_WHITE: f32 = 0.5;
+// Anything longer is a plane that was moved rather than flown, like after a respawn
+const MAX_SEGMENT: f32 = 60.0;
+
+// A ribbon behind each plane, made of a short fading segment every frame. The segments drift back with the
+// course, so together they trace where the plane has been. Planes with a livery get its color, otherwise it's
+// the one of the picked skin.
+pub fn trail_system(
+    mut commands: Commands,
+    mut previous: Local<HashMap<Entity, Vec2>>,
+    planes: Query<(Entity, &GlobalTransform, Option<&Livery>), With<Player>>,
+    zone: Res<SpeedZone>,
+    game_speed: Res<GameSpeed>,
+    save: Res<SaveData>,
+    state: Res<State<GameState>>,
+    simulation_time: Res<SimulationTime>,
+) {
+    if state.current() != &GameState::Playing || save.settings.reduced_motion {
+        previous.clear();
+        return;
+    }
+
+    let delta = simulation_time.delta_seconds();
+    if delta == 0.0 {
+        return;
+    }
+
+    let velocity = Vec2::new(-COURSE_VELOCITY * game_speed.0, 0.0);
+    let intensity = zone.intensity();
+    let width = TRAIL_WIDTH * (1.0 + ZONE_WIDTH * intensity);
+    let lifetime = TRAIL_TIME + (ZONE_LIFETIME - TRAIL_TIME) * intensity;
+
+    let mut seen: HashMap<Entity, Vec2> = HashMap::default();
+
+    for (entity, transform, livery) in planes.iter() {
+        let position = transform.translation();
+        let tail = position.truncate() + TRAIL_OFFSET;
+        seen.insert(entity, tail);
+
+        // Where the end of the last segment has drifted to since the last frame
+        let start = match previous.get(&entity) {
+            Some(start) => *start + velocity * delta,
+            None => continue,
+        };
+
+        let segment = tail - start;
+        let length = segment.length();
+        if length < f32::EPSILON || length > MAX_SEGMENT {
+            continue;
+        }
+
+        // Fades out over its lifetime, see particle_system
+        let color = livery
+            .map_or_else(|| skin_trail_color(&save), |livery| livery.color)
+            .interpolate(Color::WHITE, ZONE_WHITE * intensity);
+
+        let center = (start + tail) / 2.0;
+        commands
+            .spawn()
+            .insert(
+                Transform::from_translation(center.extend(position.z - 0.06))
+                    .with_rotation(Quat::from_rotation_z(segment.y.atan2(segment.x))),
+            )
+            .insert(BatchedParticle {
+                color,
+                size: Vec2::new(length, width),
+            })
+            .insert(Particle::new(velocity, 0.0))
+            .insert(Lifetime::from_seconds(lifetime));
+    }
+
+    // Forgets planes that are gone
+    *previous = seen;
+}
@@ -0,0 +1,231 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    BitmapText, BitmapTextBundle, FlapEvent, GameSpeed, GatePassedEvent, GateSpawn, Layer,
+    Lifetime, Player, RemoveAfterState, Rock, RunMode, SaveData, ScreenAnchor,
+};
+
+// Speeds up to normal over the first few gates like any run does, see game_speed_system
+const TUTORIAL_SPEED: f32 = 0.7;
+// The smallest rocks there are, with the gap right in the middle
+const FIRST_GATE_SCALE: f32 = 0.5;
+const FLAPS_TO_LEARN: u32 = 3;
+
+const HINT_SCALE: f32 = 0.5;
+const FINISHED_HINT_TIME: f32 = 1.5;
+// Between the tip of an arrow and what it points at
+const ARROW_GAP: f32 = 45.0;
+
+// A guided first game, only exists while it's running. Crashing before the end shows it again next time.
+pub struct Tutorial {
+    step: TutorialStep,
+    flaps: u32,
+    first_gate_spawned: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TutorialStep {
+    Flap,
+    AvoidRocks,
+}
+
+impl TutorialStep {
+    fn hint(&self) -> &'static str {
+        match self {
+            TutorialStep::Flap => "Tap to flap",
+            TutorialStep::AvoidRocks => "Avoid the rocks",
+        }
+    }
+}
+
+impl Tutorial {
+    // Called by rock_spawn_system, after everything has been drawn from the course rng
+    pub fn shape_gate(&mut self, gate: &mut GateSpawn) {
+        if self.first_gate_spawned {
+            return;
+        }
+
+        self.first_gate_spawned = true;
+        gate.scale = FIRST_GATE_SCALE;
+        gate.rock_type = 2;
+        gate.offset = 0.0;
+        gate.oscillation = None;
+    }
+}
+
+#[derive(Component)]
+pub struct TutorialHint;
+
+#[derive(Component)]
+pub struct TutorialArrow;
+
+// Fresh saves get the tutorial, saves from before it existed only when they have no runs in them yet
+pub fn needs_tutorial(save: &SaveData) -> bool {
+    save.settings
+        .tutorial_done
+        .map_or(save.stats.runs == 0, |done| !done)
+}
+
+// Decided on the start screen, before the run counts towards the stats. Only endless gets it, the first button
+// on the menu.
+pub fn check_tutorial(mut commands: Commands, mode: Res<RunMode>, mut save: ResMut<SaveData>) {
+    if *mode != RunMode::Endless || !needs_tutorial(&save) {
+        return;
+    }
+
+    // Counts as started, so quitting halfway through brings it back
+    save.settings.tutorial_done = Some(false);
+    commands.insert_resource(Tutorial {
+        step: TutorialStep::Flap,
+        flaps: 0,
+        first_gate_spawned: false,
+    });
+}
+
+// The run ended before the first gate, the demo that may come next goes without it
+pub fn cleanup_tutorial(mut commands: Commands) {
+    commands.remove_resource::<Tutorial>();
+}
+
+pub fn setup_tutorial(
+    mut commands: Commands,
+    tutorial: Option<Res<Tutorial>>,
+    mut game_speed: ResMut<GameSpeed>,
+) {
+    let tutorial = match tutorial {
+        Some(tutorial) => tutorial,
+        None => return,
+    };
+
+    game_speed.0 = TUTORIAL_SPEED;
+
+    let mut hint = BitmapTextBundle::at(ScreenAnchor::Center.offset(-80.0, 120.0))
+        .with_text(tutorial.step.hint().to_string());
+    hint.transform.scale = Vec3::new(HINT_SCALE, HINT_SCALE, 1.0);
+    commands
+        .spawn_bundle(hint)
+        .insert(TutorialHint)
+        .insert(RemoveAfterState);
+
+    // Pointing right with the tip at the origin, it's turned towards whatever it points at
+    let arrow = shapes::Polygon {
+        points: vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(-16.0, 12.0),
+            Vec2::new(-16.0, 5.0),
+            Vec2::new(-40.0, 5.0),
+            Vec2::new(-40.0, -5.0),
+            Vec2::new(-16.0, -5.0),
+            Vec2::new(-16.0, -12.0),
+        ],
+        closed: true,
+    };
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &arrow,
+            DrawMode::Outlined {
+                fill_mode: FillMode::color(Color::WHITE),
+                outline_mode: StrokeMode::new(Color::BLACK, 2.0),
+            },
+            Transform::from_xyz(0.0, 0.0, Layer::Overlay.z()),
+        ))
+        .insert(TutorialArrow)
+        .insert(RemoveAfterState);
+}
+
+// Moves on after a few flaps and again after the first gate, which ends it
+pub fn tutorial_system(
+    mut commands: Commands,
+    tutorial: Option<ResMut<Tutorial>>,
+    mut save: ResMut<SaveData>,
+    mut flaps: EventReader<FlapEvent>,
+    mut gates: EventReader<GatePassedEvent>,
+    mut hints: Query<(Entity, &mut BitmapText), With<TutorialHint>>,
+    arrows: Query<Entity, With<TutorialArrow>>,
+) {
+    let flapped = flaps.iter().count() as u32;
+    let passed_gate = gates.iter().count() > 0;
+
+    let mut tutorial = match tutorial {
+        Some(tutorial) => tutorial,
+        None => return,
+    };
+
+    match tutorial.step {
+        TutorialStep::Flap => {
+            tutorial.flaps += flapped;
+            if tutorial.flaps < FLAPS_TO_LEARN {
+                return;
+            }
+
+            tutorial.step = TutorialStep::AvoidRocks;
+            for (_, mut hint) in hints.iter_mut() {
+                hint.text = tutorial.step.hint().to_string();
+            }
+        }
+        TutorialStep::AvoidRocks => {
+            if !passed_gate {
+                return;
+            }
+
+            save.settings.tutorial_done = Some(true);
+            commands.remove_resource::<Tutorial>();
+
+            // The hint stays a moment longer to say so
+            for (entity, mut hint) in hints.iter_mut() {
+                hint.text = "You got it".to_string();
+                commands
+                    .entity(entity)
+                    .insert(Lifetime::from_seconds(FINISHED_HINT_TIME));
+            }
+            for arrow in arrows.iter() {
+                commands.entity(arrow).despawn_recursive();
+            }
+        }
+    }
+}
+
+// Points down at the plane while learning to flap, then at the gap of the next rocks
+pub fn tutorial_arrow_system(
+    tutorial: Option<Res<Tutorial>>,
+    planes: Query<&Transform, (With<Player>, Without<TutorialArrow>)>,
+    rocks: Query<(&Transform, &Rock), Without<TutorialArrow>>,
+    mut arrows: Query<(&mut Transform, &mut Visibility), With<TutorialArrow>>,
+) {
+    let tutorial = match tutorial {
+        Some(tutorial) => tutorial,
+        None => return,
+    };
+
+    let target = match tutorial.step {
+        TutorialStep::Flap => planes
+            .iter()
+            .next()
+            .map(|plane| (plane.translation.truncate(), Vec2::NEG_Y)),
+        TutorialStep::AvoidRocks => {
+            let plane_x = planes
+                .iter()
+                .next()
+                .map_or(0.0, |plane| plane.translation.x);
+
+            rocks
+                .iter()
+                .filter(|(transform, _)| transform.translation.x > plane_x)
+                .min_by(|(a, _), (b, _)| a.translation.x.total_cmp(&b.translation.x))
+                .map(|(transform, rock)| {
+                    (Vec2::new(transform.translation.x, rock.gap_center), Vec2::X)
+                })
+        }
+    };
+
+    for (mut transform, mut visibility) in arrows.iter_mut() {
+        visibility.is_visible = target.is_some();
+
+        if let Some((position, direction)) = target {
+            let tip = position - direction * ARROW_GAP;
+            transform.translation = tip.extend(transform.translation.z);
+            transform.rotation = Quat::from_rotation_z(direction.y.atan2(direction.x));
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+
+use crate::{BitmapText, Ease, Interpolate, SimulationTime};
+
+// The part of a component a tween animates
+pub trait Lens: Send + Sync + 'static {
+    type Target: Component;
+    type Value: Interpolate + Send + Sync + 'static;
+
+    fn apply(&self, target: &mut Self::Target, value: Self::Value);
+}
+
+pub struct Translation;
+
+impl Lens for Translation {
+    type Target = Transform;
+    type Value = Vec3;
+
+    fn apply(&self, transform: &mut Transform, translation: Vec3) {
+        transform.translation = translation;
+    }
+}
+
+pub struct Scale;
+
+impl Lens for Scale {
+    type Target = Transform;
+    type Value = Vec3;
+
+    fn apply(&self, transform: &mut Transform, scale: Vec3) {
+        transform.scale = scale;
+    }
+}
+
+// An angle around z rather than a Quat, so a tween from 0 to TAU spins all the way round
+pub struct Rotation;
+
+impl Lens for Rotation {
+    type Target = Transform;
+    type Value = f32;
+
+    fn apply(&self, transform: &mut Transform, angle: f32) {
+        transform.rotation = Quat::from_rotation_z(angle);
+    }
+}
+
+pub struct SpriteColor;
+
+impl Lens for SpriteColor {
+    type Target = Sprite;
+    type Value = Color;
+
+    fn apply(&self, sprite: &mut Sprite, color: Color) {
+        sprite.color = color;
+    }
+}
+
+// Only the alpha, for sprites whose color is tinted by day_night_tint_system
+pub struct SpriteAlpha;
+
+impl Lens for SpriteAlpha {
+    type Target = Sprite;
+    type Value = f32;
+
+    fn apply(&self, sprite: &mut Sprite, alpha: f32) {
+        sprite.color.set_a(alpha);
+    }
+}
+
+// Lays the text out again every frame it changes, which is fine for a short fade
+pub struct TextAlpha;
+
+impl Lens for TextAlpha {
+    type Target = BitmapText;
+    type Value = f32;
+
+    fn apply(&self, text: &mut BitmapText, alpha: f32) {
+        text.color.set_a(alpha);
+    }
+}
+
+// Systems that react to TweenCompleted by inserting another tween run after this, or the finished tween
+// being removed could take the new one with it
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TweenSystem;
+
+// Sent when a tween is done. Tweens that repeat forever never are.
+pub struct TweenCompleted {
+    pub entity: Entity,
+}
+
+// Animates part of a component from one value to another. Removed once it's done, and the entity along with
+// it if it was only there for the animation. An entity can have one tween of each lens, inserting another one
+// replaces it.
+#[derive(Component)]
+pub struct Tween<L: Lens> {
+    lens: L,
+    from: L::Value,
+    to: L::Value,
+    duration: f32,
+    ease: Ease,
+    // Seconds left before it starts
+    delay: f32,
+    elapsed: f32,
+    repeat: bool,
+    // Goes back from to to from on every other run
+    mirrored: bool,
+    reversed: bool,
+    // Stops along with the simulation instead of going on in real time
+    simulated: bool,
+    despawn: bool,
+}
+
+impl<L: Lens> Tween<L> {
+    pub fn new(lens: L, from: L::Value, to: L::Value, seconds: f32) -> Self {
+        Self {
+            lens,
+            from,
+            to,
+            duration: seconds,
+            ease: Ease::Linear,
+            delay: 0.0,
+            elapsed: 0.0,
+            repeat: false,
+            mirrored: false,
+            reversed: false,
+            simulated: false,
+            despawn: false,
+        }
+    }
+
+    pub fn with_ease(mut self, ease: Ease) -> Self {
+        self.ease = ease;
+        self
+    }
+
+    pub fn with_delay(mut self, seconds: f32) -> Self {
+        self.delay = seconds;
+        self
+    }
+
+    pub fn repeat_forever(mut self) -> Self {
+        self.repeat = true;
+        self
+    }
+
+    // Back and forth instead of starting over, only makes a difference when it repeats
+    pub fn mirrored(mut self) -> Self {
+        self.mirrored = true;
+        self
+    }
+
+    // For things in the world, which shouldn't move on while the game is paused or in a hit-stop
+    pub fn on_simulation_time(mut self) -> Self {
+        self.simulated = true;
+        self
+    }
+
+    pub fn despawn_when_done(mut self) -> Self {
+        self.despawn = true;
+        self
+    }
+
+    fn value(&self) -> L::Value {
+        let t = if self.duration > 0.0 {
+            self.elapsed / self.duration
+        } else {
+            1.0
+        };
+        let t = if self.reversed { 1.0 - t } else { t };
+
+        // Exactly where it was asked to go, so it can be compared against afterwards
+        if t >= 1.0 {
+            return self.to;
+        }
+        if t <= 0.0 {
+            return self.from;
+        }
+
+        self.from.interpolate(self.to, self.ease.apply(t))
+    }
+
+    // True once it's done
+    fn tick(&mut self, seconds: f32) -> bool {
+        let mut seconds = seconds;
+        if self.delay > 0.0 {
+            self.delay -= seconds;
+            if self.delay > 0.0 {
+                return false;
+            }
+            seconds = -self.delay;
+        }
+
+        self.elapsed += seconds;
+        if self.elapsed < self.duration {
+            return false;
+        }
+
+        if !self.repeat || self.duration <= 0.0 {
+            self.elapsed = self.duration;
+            return true;
+        }
+
+        // Whatever went past the end carries over into the next run
+        while self.elapsed >= self.duration {
+            self.elapsed -= self.duration;
+            if self.mirrored {
+                self.reversed = !self.reversed;
+            }
+        }
+
+        false
+    }
+}
+
+// Added for every lens, see TweenPlugin
+pub fn tween_system<L: Lens>(
+    mut commands: Commands,
+    mut tweens: Query<(Entity, &mut Tween<L>, &mut L::Target)>,
+    mut completed: EventWriter<TweenCompleted>,
+    simulation_time: Res<SimulationTime>,
+    time: Res<Time>,
+) {
+    for (entity, mut tween, mut target) in tweens.iter_mut() {
+        let seconds = if tween.simulated {
+            simulation_time.delta_seconds()
+        } else {
+            time.delta_seconds()
+        };
+
+        let done = tween.tick(seconds);
+        if tween.delay > 0.0 {
+            continue;
+        }
+
+        tween.lens.apply(&mut target, tween.value());
+
+        if done {
+            completed.send(TweenCompleted { entity });
+
+            if tween.despawn {
+                commands.entity(entity).despawn_recursive();
+            } else {
+                commands.entity(entity).remove::<Tween<L>>();
+            }
+        }
+    }
+}
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TweenCompleted>()
+            .add_system(tween_system::<Translation>.label(TweenSystem))
+            .add_system(tween_system::<Scale>.label(TweenSystem))
+            .add_system(tween_system::<Rotation>.label(TweenSystem))
+            .add_system(tween_system::<SpriteColor>.label(TweenSystem))
+            .add_system(tween_system::<SpriteAlpha>.label(TweenSystem))
+            .add_system(tween_system::<TextAlpha>.label(TweenSystem));
+    }
+}
@@ -0,0 +1,106 @@
+use bevy::prelude::*;
+
+use crate::{
+    add_livery, add_player_outline, BitmapTextBundle, DemoMode, FlapInput, GameAssets, GameMode,
+    GameOverSection, Interpolated, Layer, Pilot, PlaneHulls, Player, RemoveAfterState, RunMode,
+    SaveData, ScreenAnchor,
+};
+
+// Starts a little below the first plane so they don't overlap
+const SECOND_PLANE_Y: f32 = -60.0;
+
+// Set when a versus run ends. No winner means both planes crashed at the same time.
+pub struct VersusResult {
+    pub winner: Option<Pilot>,
+}
+
+pub struct VersusMode;
+
+impl GameMode for VersusMode {
+    fn run_mode(&self) -> RunMode {
+        RunMode::Versus
+    }
+
+    fn select_key(&self) -> Option<KeyCode> {
+        Some(KeyCode::V)
+    }
+
+    fn label(&self, _save: &SaveData) -> String {
+        format!(
+            "Versus {} flaps with the mouse or space {} with up",
+            Pilot::One.name(),
+            Pilot::Two.name()
+        )
+    }
+
+    fn setup(&self, commands: &mut Commands, world: &World) {
+        commands.remove_resource::<VersusResult>();
+
+        // The autopilot only knows how to fly one plane
+        if !world.resource::<DemoMode>().active {
+            spawn_second_plane(
+                commands,
+                world.resource::<GameAssets>(),
+                world.resource::<PlaneHulls>(),
+            );
+        }
+    }
+
+    // Versus is about beating each other, not a score, so it keeps no records.
+    // It ends as soon as one plane crashes, so the other one wins. Crashing together is a draw.
+    fn end_run(&self, commands: &mut Commands, flying: &[Pilot]) {
+        commands.insert_resource(VersusResult {
+            winner: flying.first().copied(),
+        });
+    }
+
+    fn has_lives(&self) -> bool {
+        false
+    }
+
+    fn allows_continues(&self) -> bool {
+        false
+    }
+}
+
+fn spawn_second_plane(commands: &mut Commands, assets: &GameAssets, hulls: &PlaneHulls) {
+    let livery = Pilot::Two.livery();
+    let hull = hulls.get(livery.plane());
+
+    let mut player = commands.spawn_bundle(SpriteBundle {
+        texture: livery.texture(assets),
+        transform: Transform::from_xyz(-200.0, SECOND_PLANE_Y, Layer::Course.z())
+            .with_scale(Vec3::new(0.5, 0.5, 1.0)),
+        ..default()
+    });
+
+    player
+        .insert(Player::new(hull))
+        .insert(Pilot::Two)
+        .insert(FlapInput::default())
+        .insert(Interpolated::default())
+        .insert(RemoveAfterState);
+
+    add_player_outline(&mut player, hull);
+    add_livery(&mut player, livery, assets);
+}
+
+// A result left over from a versus demo is ignored in the other modes
+// Above the score, it's what the round was about
+pub fn versus_result_section(
+    mode: Res<RunMode>,
+    result: Option<Res<VersusResult>>,
+    mut sections: EventWriter<GameOverSection>,
+) {
+    let result = match result {
+        Some(result) if *mode == RunMode::Versus => result,
+        _ => return,
+    };
+
+    let text = match result.winner {
+        Some(pilot) => format!("{} wins", pilot.name()),
+        None => "Draw".to_string(),
+    };
+
+    sections.send(GameOverSection::line(0, text));
+}
@@ -0,0 +1,112 @@
+use bevy::asset::{HandleId, LoadState};
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+
+use crate::{
+    BatchedParticle, GameAssets, GameResult, GameState, Lifetime, Particle, Profiles,
+    QualityBenchmark, RemoveAfterState, ThemeState,
+};
+
+// The first frame that draws something new compiles its pipeline, the others make sure it has been shown
+const WARM_UP_FRAMES: u32 = 3;
+
+// One of everything that gets drawn later on, drawn invisibly while loading.
+// Otherwise compiling their shaders causes a hitch when the first rock or debug outline shows up.
+#[derive(Default)]
+pub struct WarmUp {
+    frames: u32,
+    done: bool,
+    // Everything in GameAssets and the applied theme, so nothing pops in the first time it's spawned
+    handles: Vec<HandleId>,
+}
+
+pub fn setup_warm_up(
+    mut commands: Commands,
+    mut warm_up: ResMut<WarmUp>,
+    assets: Res<GameAssets>,
+    theme: Res<ThemeState>,
+) {
+    let invisible = Color::rgba(1.0, 1.0, 1.0, 0.0);
+
+    *warm_up = WarmUp {
+        frames: 0,
+        done: false,
+        handles: assets.ids().chain(theme.textures.ids()).collect(),
+    };
+
+    // Textured sprites, like rocks and the bitmap font
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: invisible,
+                ..default()
+            },
+            texture: theme.textures.rock_up.clone(),
+            ..default()
+        })
+        .insert(RemoveAfterState);
+
+    // Lyon shapes, like the collision outlines
+    let shape = shapes::Rectangle {
+        extents: Vec2::new(10.0, 10.0),
+        origin: RectangleOrigin::Center,
+    };
+    commands
+        .spawn_bundle(GeometryBuilder::build_as(
+            &shape,
+            DrawMode::Fill(FillMode::color(invisible)),
+            Transform::default(),
+        ))
+        .insert(RemoveAfterState);
+
+    // The batched particle mesh
+    let mut particle = Particle::new(Vec2::ZERO, 0.0);
+    particle.fade = false;
+    commands
+        .spawn()
+        .insert(Transform::default())
+        .insert(BatchedParticle {
+            color: invisible,
+            size: Vec2::new(1.0, 1.0),
+        })
+        .insert(particle)
+        .insert(Lifetime::from_seconds(1.0))
+        .insert(RemoveAfterState);
+}
+
+impl WarmUp {
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+// Loading also waits for the quality benchmark on the first launch
+pub fn warm_up_system(
+    mut warm_up: ResMut<WarmUp>,
+    mut state: ResMut<State<GameState>>,
+    asset_server: Res<AssetServer>,
+    benchmark: Option<Res<QualityBenchmark>>,
+    profiles: Res<Profiles>,
+) -> GameResult {
+    warm_up.frames += 1;
+
+    // Nothing gets drawn with a texture that hasn't loaded yet
+    let load_state = asset_server.get_group_load_state(warm_up.handles.iter().copied());
+    let loaded = matches!(load_state, LoadState::Loaded | LoadState::Failed);
+
+    if loaded && warm_up.frames >= WARM_UP_FRAMES {
+        warm_up.done = true;
+    }
+
+    if warm_up.done && benchmark.is_none() {
+        // The benchmark already went into whichever profile was played last, switching brings its own settings
+        let next = if profiles.has_choice() {
+            GameState::Profiles
+        } else {
+            GameState::Menu
+        };
+        state.set(next)?;
+    }
+
+    Ok(())
+}
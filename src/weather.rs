@@ -0,0 +1,266 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    tick_spawn_timer, BatchedParticle, BitmapTextBundle, GameRng, Lifetime, Particle,
+    RemoveAfterState, SaveData, Score, ScreenAnchor, SimulationTime, SoundCue, ThemeState, HEIGHT,
+    PHYSICS_STEP, WIDTH,
+};
+
+// Drops per second at full effects intensity
+const RAIN_RATE: f32 = 120.0;
+const SNOW_RATE: f32 = 40.0;
+
+// Rain is blown to the left, against the direction the plane flies in
+const RAIN_VELOCITY: Vec2 = Vec2::new(-150.0, -700.0);
+// Wet wings, the plane falls a little faster in the rain
+const RAIN_GRAVITY_FACTOR: f32 = 1.1;
+
+const GUST_MIN_SCORE: u64 = 20;
+const GUST_WARNING: f32 = 1.0;
+const GUST_DURATION: f32 = 1.5;
+// Speed a headwind blows the plane back at, and the speed it flies back to its spot at afterwards
+const HEADWIND_SPEED: f32 = 60.0;
+const HEADWIND_RECOVERY_SPEED: f32 = 40.0;
+// Extra downward acceleration on top of gravity
+const DOWNDRAFT: f32 = 300.0;
+
+const INDICATOR_BLINK_RATE: f32 = 6.0;
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Snow,
+}
+
+// Purely cosmetic, drops have no collision shape. Rain only affects the plane through WeatherEffects.
+#[derive(Component)]
+pub struct WeatherDrop;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GustKind {
+    Headwind,
+    Downdraft,
+}
+
+#[derive(Clone, Copy)]
+pub enum GustState {
+    Calm,
+    // Shown on screen before it hits
+    Warning { kind: GustKind, remaining: f32 },
+    Blowing { kind: GustKind, remaining: f32 },
+}
+
+// The parts of the weather that push the plane around. Gusts are drawn from the course rng, and rain is
+// taken from the theme setting when the run starts, so a seeded course flies the same regardless of how
+// long the theme textures took to load.
+#[derive(Clone)]
+pub struct WeatherEffects {
+    raining: bool,
+    gust_timer: Timer,
+    gust: GustState,
+}
+
+impl Default for WeatherEffects {
+    fn default() -> Self {
+        Self {
+            raining: false,
+            gust_timer: Timer::from_seconds(0.0, false),
+            gust: GustState::Calm,
+        }
+    }
+}
+
+impl WeatherEffects {
+    pub fn gravity_factor(&self) -> f32 {
+        if self.raining {
+            RAIN_GRAVITY_FACTOR
+        } else {
+            1.0
+        }
+    }
+
+    fn blowing(&self) -> Option<GustKind> {
+        match self.gust {
+            GustState::Blowing { kind, .. } => Some(kind),
+            _ => None,
+        }
+    }
+
+    // Horizontal velocity the wind gives the plane. Negative while a headwind blows it back, positive
+    // afterwards so it flies back to its spot, player_system stops it there.
+    pub fn wind_velocity(&self) -> f32 {
+        match self.blowing() {
+            Some(GustKind::Headwind) => -HEADWIND_SPEED,
+            _ => HEADWIND_RECOVERY_SPEED,
+        }
+    }
+
+    // Downward acceleration on top of gravity
+    pub fn downdraft(&self) -> f32 {
+        match self.blowing() {
+            Some(GustKind::Downdraft) => DOWNDRAFT,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct GustIndicator;
+
+// Fractional drops carry over to the next frame, so low rates still spawn something
+#[derive(Default)]
+pub struct WeatherSpawner {
+    pending: f32,
+}
+
+pub fn weather_system(
+    mut commands: Commands,
+    mut spawner: ResMut<WeatherSpawner>,
+    theme: Res<ThemeState>,
+    save: Res<SaveData>,
+    simulation_time: Res<SimulationTime>,
+    mut rng: ResMut<GameRng>,
+) {
+    let rate = match theme.applied.weather() {
+        Weather::Clear => 0.0,
+        Weather::Rain => RAIN_RATE,
+        Weather::Snow => SNOW_RATE,
+    } * save.settings.effects_intensity.factor();
+
+    if rate == 0.0 {
+        spawner.pending = 0.0;
+        return;
+    }
+
+    spawner.pending += rate * simulation_time.delta_seconds();
+    let rng = &mut rng.cosmetic;
+
+    while spawner.pending >= 1.0 {
+        spawner.pending -= 1.0;
+
+        let (velocity, size, color) = match theme.applied.weather() {
+            Weather::Rain => (
+                RAIN_VELOCITY,
+                Vec2::new(2.0, 14.0),
+                Color::rgba(0.7, 0.8, 1.0, 0.5),
+            ),
+            _ => (
+                Vec2::new(rng.gen_range(-40.0..20.0), rng.gen_range(-90.0..-60.0)),
+                Vec2::new(3.0, 3.0),
+                Color::rgba(1.0, 1.0, 1.0, 0.8),
+            ),
+        };
+
+        // Drops start above the screen, spread out far enough to the right that slanted rain still covers it all
+        let fall_time = (HEIGHT + 20.0) / -velocity.y;
+        let drift = -velocity.x * fall_time;
+        let x = rng.gen_range(WIDTH / -2.0..WIDTH / 2.0 + drift.max(0.0));
+
+        let mut particle = Particle::new(velocity, 0.0);
+        particle.fade = false;
+
+        // Streaks line up with the direction they fall in
+        let angle = velocity.x.atan2(-velocity.y);
+
+        commands
+            .spawn()
+            .insert(
+                Transform::from_xyz(x, HEIGHT / 2.0 + 10.0, 0.0)
+                    .with_rotation(Quat::from_rotation_z(angle)),
+            )
+            .insert(BatchedParticle { color, size })
+            .insert(particle)
+            .insert(Lifetime::from_seconds(fall_time))
+            .insert(WeatherDrop);
+    }
+}
+
+pub fn reset_weather_effects(mut effects: ResMut<WeatherEffects>, save: Res<SaveData>) {
+    *effects = WeatherEffects {
+        raining: save.settings.theme.weather() == Weather::Rain,
+        ..default()
+    };
+}
+
+pub fn gust_system(
+    mut effects: ResMut<WeatherEffects>,
+    score: Res<Score>,
+    mut rng: ResMut<GameRng>,
+    mut cues: EventWriter<SoundCue>,
+) {
+    effects.gust = match effects.gust {
+        GustState::Warning { kind, remaining } if remaining > PHYSICS_STEP => GustState::Warning {
+            kind,
+            remaining: remaining - PHYSICS_STEP,
+        },
+        GustState::Warning { kind, .. } => GustState::Blowing {
+            kind,
+            remaining: GUST_DURATION,
+        },
+        GustState::Blowing { kind, remaining } if remaining > PHYSICS_STEP => GustState::Blowing {
+            kind,
+            remaining: remaining - PHYSICS_STEP,
+        },
+        _ => GustState::Calm,
+    };
+
+    if score.0 < GUST_MIN_SCORE {
+        return;
+    }
+
+    if !tick_spawn_timer(&mut effects.gust_timer, || rng.course.gen_range(8.0..14.0)) {
+        return;
+    }
+
+    let kind = if rng.course.gen_bool(0.5) {
+        GustKind::Headwind
+    } else {
+        GustKind::Downdraft
+    };
+    effects.gust = GustState::Warning {
+        kind,
+        remaining: GUST_WARNING,
+    };
+    cues.send(SoundCue("gust"));
+}
+
+// Blinks at the top of the screen while a gust is on its way
+pub fn gust_indicator_system(
+    mut commands: Commands,
+    effects: Res<WeatherEffects>,
+    mut indicators: Query<(Entity, &mut Visibility), With<GustIndicator>>,
+) {
+    let (kind, remaining) = match effects.gust {
+        GustState::Warning { kind, remaining } => (kind, remaining),
+        _ => {
+            for (entity, _) in indicators.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            return;
+        }
+    };
+
+    if indicators.is_empty() {
+        let text = match kind {
+            GustKind::Headwind => "Headwind",
+            GustKind::Downdraft => "Downdraft",
+        };
+
+        let mut bundle = BitmapTextBundle::at(ScreenAnchor::Top.offset(-90.0, -110.0))
+            .with_text(text.to_string());
+        bundle.transform.scale = Vec3::new(0.5, 0.5, 1.0);
+
+        commands
+            .spawn_bundle(bundle)
+            .insert(GustIndicator)
+            .insert(RemoveAfterState);
+        return;
+    }
+
+    let elapsed = GUST_WARNING - remaining;
+    for (_, mut visibility) in indicators.iter_mut() {
+        visibility.is_visible = (elapsed * INDICATOR_BLINK_RATE) as u32 % 2 == 0;
+    }
+}
@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    today, unix_seconds, GameMode, GameModes, Layer, Leaderboard, RecordKey, RemoveAfterState,
+    RunMode, SaveData, HEIGHT, SECONDS_PER_DAY, WIDTH,
+};
+
+const DAYS_PER_WEEK: u64 = 7;
+
+// The unix epoch was a Thursday, this makes weeks start on Monday
+const MONDAY_OFFSET: u64 = 3;
+
+const MUTATORS_PER_WEEK: usize = 2;
+
+const FOG_STRIPS: usize = 8;
+const FOG_START_X: f32 = 60.0;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Mutator {
+    // Hides the right side of the screen, so rocks show up late
+    Fog,
+    // The game speeds up twice as fast
+    FastRamp,
+    HeavyGravity,
+    BigRocks,
+}
+
+const MUTATORS: &[Mutator] = &[
+    Mutator::Fog,
+    Mutator::FastRamp,
+    Mutator::HeavyGravity,
+    Mutator::BigRocks,
+];
+
+impl Mutator {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mutator::Fog => "Fog",
+            Mutator::FastRamp => "Fast ramp",
+            Mutator::HeavyGravity => "Heavy gravity",
+            Mutator::BigRocks => "Big rocks",
+        }
+    }
+}
+
+// Mutators that apply to the current run
+#[derive(Default)]
+pub struct ActiveMutators(pub Vec<Mutator>);
+
+impl ActiveMutators {
+    pub fn has(&self, mutator: Mutator) -> bool {
+        self.0.contains(&mutator)
+    }
+
+    pub fn gravity_factor(&self) -> f32 {
+        if self.has(Mutator::HeavyGravity) {
+            1.25
+        } else {
+            1.0
+        }
+    }
+
+    pub fn speed_ramp_factor(&self) -> f32 {
+        if self.has(Mutator::FastRamp) {
+            2.0
+        } else {
+            1.0
+        }
+    }
+
+    pub fn rock_scale_factor(&self) -> f32 {
+        if self.has(Mutator::BigRocks) {
+            1.15
+        } else {
+            1.0
+        }
+    }
+}
+
+// The weekly leaderboard of the week it was set in, from saves before records were kept per mode
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WeeklyRecords {
+    pub week: u64,
+    pub leaderboard: Leaderboard,
+}
+
+#[derive(Component)]
+pub struct Fog;
+
+pub fn this_week() -> u64 {
+    (today() + MONDAY_OFFSET) / DAYS_PER_WEEK
+}
+
+pub fn seconds_until_next_week() -> u64 {
+    let next_monday = (this_week() + 1) * DAYS_PER_WEEK - MONDAY_OFFSET;
+    (next_monday * SECONDS_PER_DAY).saturating_sub(unix_seconds())
+}
+
+// Scrambled so weekly courses don't line up with the daily course of some day
+pub fn weekly_seed(week: u64) -> u64 {
+    week.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+pub fn weekly_mutators(week: u64) -> Vec<Mutator> {
+    let mut rng = StdRng::seed_from_u64(weekly_seed(week));
+    MUTATORS
+        .choose_multiple(&mut rng, MUTATORS_PER_WEEK)
+        .copied()
+        .collect()
+}
+
+pub struct WeeklyMode;
+
+impl GameMode for WeeklyMode {
+    fn run_mode(&self) -> RunMode {
+        RunMode::Weekly
+    }
+
+    fn select_key(&self) -> Option<KeyCode> {
+        Some(KeyCode::W)
+    }
+
+    fn label(&self, _save: &SaveData) -> String {
+        let mutators: Vec<&str> = weekly_mutators(this_week())
+            .iter()
+            .map(|mutator| mutator.name())
+            .collect();
+
+        format!(
+            "Weekly {} ends in {}",
+            mutators.join(" and "),
+            format_countdown(seconds_until_next_week())
+        )
+    }
+
+    fn course_seed(&self, _seed: Option<u64>) -> Option<u64> {
+        Some(weekly_seed(this_week()))
+    }
+
+    fn mutators(&self) -> Vec<Mutator> {
+        weekly_mutators(this_week())
+    }
+
+    fn record_key(&self) -> Option<RecordKey> {
+        let week = this_week();
+        Some(RecordKey::new(RunMode::Weekly, weekly_mutators(week), week))
+    }
+
+    fn shares_course(&self) -> bool {
+        true
+    }
+
+    fn uses_difficulty(&self) -> bool {
+        false
+    }
+
+    fn allows_continues(&self) -> bool {
+        false
+    }
+
+    fn has_leaderboard(&self) -> bool {
+        true
+    }
+}
+
+pub fn setup_mutators(
+    mut commands: Commands,
+    mode: Res<RunMode>,
+    modes: Res<GameModes>,
+    mut mutators: ResMut<ActiveMutators>,
+) {
+    mutators.0 = modes.get(*mode).mutators();
+
+    if !mutators.has(Mutator::Fog) {
+        return;
+    }
+
+    // Strips that get more opaque towards the right edge
+    let strip_width = (WIDTH / 2.0 - FOG_START_X) / FOG_STRIPS as f32;
+    for i in 0..FOG_STRIPS {
+        let alpha = (i + 1) as f32 / FOG_STRIPS as f32 * 0.9;
+
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(0.85, 0.87, 0.9, alpha),
+                    custom_size: Some(Vec2::new(strip_width, HEIGHT)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(
+                    FOG_START_X + (i as f32 + 0.5) * strip_width,
+                    0.0,
+                    Layer::Fog.z(),
+                ),
+                ..default()
+            })
+            .insert(Fog)
+            .insert(RemoveAfterState);
+    }
+}
+
+fn format_countdown(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    let hours = minutes / 60;
+    let days = hours / 24;
+
+    if days > 0 {
+        format!("{}d {}h", days, hours % 24)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    Action, ActionInput, BitmapTextBundle, GameAssets, GameResult, GameState, Layer,
+    RemoveAfterState, SaveData, TEXT_LINE_HEIGHT,
+};
+
+const CHANGELOG: &str = include_str!("../assets/changelog.ron");
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const LEFT_X: f32 = -340.0;
+const TOP_Y: f32 = 200.0;
+const SECTION_SCALE: f32 = 0.45;
+const ITEM_SCALE: f32 = 0.25;
+const ITEM_LINE_HEIGHT: f32 = TEXT_LINE_HEIGHT * ITEM_SCALE;
+const ITEM_INDENT: f32 = 25.0;
+const SECTION_GAP: f32 = 45.0;
+
+// See assets/changelog.ron
+#[derive(Deserialize)]
+struct ChangelogEntry {
+    version: String,
+    sections: Vec<ChangelogSection>,
+}
+
+#[derive(Deserialize)]
+struct ChangelogSection {
+    title: String,
+    items: Vec<String>,
+}
+
+fn changelog() -> Vec<ChangelogEntry> {
+    ron::from_str(CHANGELOG).expect("assets/changelog.ron is invalid")
+}
+
+// Brand new players have nothing to compare against, they only see it once there's been an update
+pub fn check_whats_new(
+    mut save: ResMut<SaveData>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    if save.seen_version == CURRENT_VERSION {
+        return Ok(());
+    }
+
+    if save.stats.runs == 0 {
+        save.seen_version = CURRENT_VERSION.to_string();
+        return Ok(());
+    }
+
+    state.overwrite_set(GameState::WhatsNew)?;
+    Ok(())
+}
+
+// N on the menu
+pub fn whats_new_shortcut_system(
+    keys: Res<Input<KeyCode>>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    if keys.just_pressed(KeyCode::N) {
+        state.set(GameState::WhatsNew)?;
+    }
+
+    Ok(())
+}
+
+// Each section is a title with its items as one multi-line text below it, a dot in front of every item
+pub fn setup_whats_new(mut commands: Commands, assets: Res<GameAssets>) {
+    let changelog = changelog();
+    let entry = changelog
+        .iter()
+        .find(|entry| entry.version == CURRENT_VERSION)
+        .or_else(|| changelog.first());
+
+    let mut title = BitmapTextBundle::new(LEFT_X, TOP_Y).with_text("Whats new".to_string());
+    title.transform.scale = Vec3::new(0.6, 0.6, 1.0);
+    commands.spawn_bundle(title).insert(RemoveAfterState);
+
+    let entry = match entry {
+        Some(entry) => entry,
+        None => return,
+    };
+
+    let mut y = TOP_Y - 55.0;
+    for section in &entry.sections {
+        let mut title = BitmapTextBundle::new(LEFT_X, y).with_text(section.title.clone());
+        title.transform.scale = Vec3::new(SECTION_SCALE, SECTION_SCALE, 1.0);
+        commands.spawn_bundle(title).insert(RemoveAfterState);
+        y -= 35.0;
+
+        let mut items =
+            BitmapTextBundle::new(LEFT_X + ITEM_INDENT, y).with_text(section.items.join("\n"));
+        items.transform.scale = Vec3::new(ITEM_SCALE, ITEM_SCALE, 1.0);
+        commands.spawn_bundle(items).insert(RemoveAfterState);
+
+        // The font has no bullet, so it's a puff like the ones behind the plane
+        for _ in &section.items {
+            commands
+                .spawn_bundle(SpriteBundle {
+                    texture: assets.puff.clone(),
+                    transform: Transform::from_xyz(LEFT_X + 10.0, y, Layer::Overlay.z())
+                        .with_scale(Vec3::new(0.12, 0.12, 1.0)),
+                    ..default()
+                })
+                .insert(RemoveAfterState);
+            y -= ITEM_LINE_HEIGHT;
+        }
+
+        y -= SECTION_GAP - ITEM_LINE_HEIGHT;
+    }
+}
+
+// Anything that confirms dismisses it, until the next update or N on the menu
+pub fn whats_new_system(
+    mut input: ActionInput,
+    mut save: ResMut<SaveData>,
+    mut state: ResMut<State<GameState>>,
+) -> GameResult {
+    let bindings = &save.settings.bindings;
+    let dismissed = input.keys.just_pressed(KeyCode::Escape)
+        || input.just_pressed(bindings, Action::Confirm)
+        || input.mouse.just_pressed(MouseButton::Left)
+        || input.tap().is_some();
+    if !dismissed {
+        return Ok(());
+    }
+
+    input.consume(bindings, Action::Confirm);
+    input.mouse.reset(MouseButton::Left);
+    input.consume_tap();
+
+    if save.seen_version != CURRENT_VERSION {
+        save.seen_version = CURRENT_VERSION.to_string();
+    }
+    state.set(GameState::Menu)?;
+
+    Ok(())
+}
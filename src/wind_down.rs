@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+
+use crate::{
+    Ease, GameSpeed, HorizontalVelocity, Pilot, Player, SimulationTime, Telegraph, TimeTrialClock,
+    VersusResult, COURSE_VELOCITY, GROUND_HEIGHT, HEIGHT,
+};
+
+// Seconds the course takes to roll to a stop once the run is over
+const WIND_DOWN_TIME: f32 = 2.5;
+// Part of the game speed the course is still going at when the run ends
+const WIND_DOWN_SPEED: f32 = 0.5;
+
+const WRECK_GRAVITY: f32 = 1200.0;
+// Part of the speed the wreck keeps going up with after hitting the ground
+const WRECK_BOUNCE: f32 = 0.35;
+// How quickly the ground drags the wreck along with it, per second
+const WRECK_GRIP: f32 = 4.0;
+// In radians per second while it's in the air
+const WRECK_SPIN: f32 = -6.0;
+// Roughly half the height of the plane, it rolls as if it was a ball this size
+const WRECK_RADIUS: f32 = 15.0;
+
+// After a run ends the course keeps scrolling for a moment, slowing down to a halt behind the game over screen.
+// The physics stage only runs while playing, so none of this collides, scores or spawns anything.
+#[derive(Default)]
+pub struct WindDown {
+    elapsed: f32,
+    // Game speed the course is scrolled at at the start, eased down to nothing
+    speed: f32,
+}
+
+impl WindDown {
+    fn speed(&self) -> f32 {
+        let t = self.elapsed / WIND_DOWN_TIME;
+        self.speed * (1.0 - Ease::SmoothStep.apply(t))
+    }
+}
+
+// A crashed plane on its way down, tumbling instead of flying. Velocities are relative to the screen.
+#[derive(Component)]
+pub struct Wreck {
+    velocity: Vec2,
+    spin: f32,
+}
+
+// Planes that didn't crash are left where they are: the winner of a versus run, or a time trial that ran out
+pub fn setup_wind_down(
+    mut commands: Commands,
+    mut wind_down: ResMut<WindDown>,
+    game_speed: Res<GameSpeed>,
+    planes: Query<(Entity, &Player, &Pilot)>,
+    versus: Option<Res<VersusResult>>,
+    clock: Res<TimeTrialClock>,
+) {
+    *wind_down = WindDown {
+        elapsed: 0.0,
+        speed: game_speed.0 * WIND_DOWN_SPEED,
+    };
+
+    if clock.ran_out() {
+        return;
+    }
+
+    let winner = versus.and_then(|versus| versus.winner);
+    for (entity, player, pilot) in planes.iter() {
+        if Some(*pilot) == winner {
+            continue;
+        }
+
+        commands.entity(entity).insert(Wreck {
+            velocity: Vec2::new(0.0, player.velocity),
+            spin: WRECK_SPIN,
+        });
+    }
+}
+
+pub fn wind_down_system(
+    mut wind_down: ResMut<WindDown>,
+    mut course: Query<(&mut Transform, &HorizontalVelocity, Option<&Telegraph>), Without<Wreck>>,
+    mut wrecks: Query<(&mut Wreck, &mut Transform)>,
+    simulation_time: Res<SimulationTime>,
+) {
+    let dt = simulation_time.delta_seconds();
+    wind_down.elapsed += dt;
+    let speed = wind_down.speed();
+
+    for (mut transform, velocity, telegraph) in course.iter_mut() {
+        if speed > 0.0 && !telegraph.map_or(false, |telegraph| telegraph.holds()) {
+            transform.translation.x -= dt * speed * velocity.0;
+        }
+    }
+
+    // The ground scrolls along with the course, so a wreck lying on it is dragged to the left until it stops
+    let ground = HEIGHT / -2.0 + GROUND_HEIGHT + WRECK_RADIUS;
+    let ground_velocity = -speed * COURSE_VELOCITY;
+
+    for (mut wreck, mut transform) in wrecks.iter_mut() {
+        wreck.velocity.y -= WRECK_GRAVITY * dt;
+        transform.translation += (wreck.velocity * dt).extend(0.0);
+
+        if transform.translation.y <= ground {
+            transform.translation.y = ground;
+            wreck.velocity.y = (-wreck.velocity.y * WRECK_BOUNCE).max(0.0);
+
+            let grip = (WRECK_GRIP * dt).min(1.0);
+            wreck.velocity.x += (ground_velocity - wreck.velocity.x) * grip;
+            // Rolling rather than spinning, as fast as it goes along the ground
+            wreck.spin = -wreck.velocity.x / WRECK_RADIUS;
+        }
+
+        transform.rotate(Quat::from_rotation_z(wreck.spin * dt));
+    }
+}
+
+pub fn clear_wrecks(mut commands: Commands, wrecks: Query<Entity, With<Wreck>>) {
+    for entity in wrecks.iter() {
+        commands.entity(entity).remove::<Wreck>();
+    }
+}